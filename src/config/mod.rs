@@ -2,6 +2,27 @@
 pub struct Config {
     pub logger: Logger,
     pub engine: Engine,
+
+    #[serde(default)]
+    pub slowlog: Slowlog,
+
+    #[serde(default)]
+    pub latency: Latency,
+
+    #[serde(default)]
+    pub network: Network,
+
+    #[serde(default)]
+    pub tls: Tls,
+
+    #[serde(default)]
+    pub security: Security,
+
+    #[serde(default)]
+    pub commands: Commands,
+
+    #[serde(default)]
+    pub limits: Limits,
 }
 
 impl Config {
@@ -12,16 +33,49 @@ impl Config {
     }
 }
 
-#[derive(Default, serde::Deserialize)]
+#[derive(serde::Deserialize)]
 pub struct Logger {
     #[serde(default = "default_level")]
     pub level: String,
+
+    /// Format of the log lines
+    #[serde(default)]
+    pub format: LogFormat,
+
+    /// Fraction of commands, between 0 and 1, whose debug logs and spans are emitted
+    #[serde(default = "default_sample_rate")]
+    pub sample_rate: f64,
+}
+
+impl Default for Logger {
+    fn default() -> Self {
+        Self {
+            level: default_level(),
+            format: LogFormat::default(),
+            sample_rate: default_sample_rate(),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LogFormat {
+    /// Human readable lines
+    #[default]
+    Text,
+
+    /// One JSON object per line, with the fields of the current spans
+    Json,
 }
 
 fn default_level() -> String {
     "info".to_string()
 }
 
+const fn default_sample_rate() -> f64 {
+    1.0
+}
+
 #[derive(Clone, Default, serde::Deserialize)]
 pub struct Engine {
     /// Maximum number of items in the cache, if None, the cache is unbounded.
@@ -51,11 +105,225 @@ fn default_file() -> String {
     "./tmp/umd/persistence.bin".to_string()
 }
 
+#[derive(Clone, serde::Deserialize)]
+pub struct Slowlog {
+    /// Enable or disable the slowlog
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+
+    /// Commands taking more than this are logged, 0 logs every command
+    #[serde(default = "default_log_slower_than", with = "humantime_serde")]
+    pub log_slower_than: std::time::Duration,
+
+    /// Maximum number of entries kept, the oldest are dropped first
+    #[serde(default = "default_slowlog_max_len")]
+    pub max_len: usize,
+}
+
+impl Default for Slowlog {
+    fn default() -> Self {
+        Self {
+            enabled: default_true(),
+            log_slower_than: default_log_slower_than(),
+            max_len: default_slowlog_max_len(),
+        }
+    }
+}
+
+#[derive(Clone, serde::Deserialize)]
+pub struct Latency {
+    /// Enable or disable the latency histograms per command
+    #[serde(default = "default_true")]
+    pub tracking: bool,
+
+    /// Events taking more than this are recorded by the latency monitor, 0 disables the monitor
+    #[serde(default, with = "humantime_serde")]
+    pub monitor_threshold: std::time::Duration,
+}
+
+impl Default for Latency {
+    fn default() -> Self {
+        Self {
+            tracking: default_true(),
+            monitor_threshold: std::time::Duration::ZERO,
+        }
+    }
+}
+
+#[derive(Clone, serde::Deserialize)]
+pub struct Network {
+    /// Connections without requests for this long are closed, 0 disables the timeout
+    #[serde(default, with = "humantime_serde")]
+    pub idle_timeout: std::time::Duration,
+
+    /// Idle time before TCP keepalive probes are sent to detect dead peers, 0 disables the probes
+    #[serde(default = "default_tcp_keepalive", with = "humantime_serde")]
+    pub tcp_keepalive: std::time::Duration,
+
+    /// Enable or disable the TCP listener, it can be disabled when the unix socket is used
+    #[serde(default = "default_true")]
+    pub tcp_enabled: bool,
+
+    /// Path of the unix socket to listen on, if None only TCP is used
+    pub unixsocket: Option<String>,
+
+    /// Permissions of the unix socket, e.g. 0o770, if None they depend on the umask
+    pub unixsocket_perm: Option<u32>,
+}
+
+impl Default for Network {
+    fn default() -> Self {
+        Self {
+            idle_timeout: std::time::Duration::ZERO,
+            tcp_keepalive: default_tcp_keepalive(),
+            tcp_enabled: default_true(),
+            unixsocket: None,
+            unixsocket_perm: None,
+        }
+    }
+}
+
+#[derive(Clone, serde::Deserialize)]
+pub struct Tls {
+    /// Enable or disable the TLS listener, it is served alongside the plain TCP one
+    #[serde(default)]
+    pub enabled: bool,
+
+    /// Address of the TLS listener
+    #[serde(default = "default_tls_addr")]
+    pub addr: String,
+
+    /// PEM file with the certificate chain of the server
+    #[serde(default)]
+    pub cert_file: String,
+
+    /// PEM file with the private key of the server
+    #[serde(default)]
+    pub key_file: String,
+
+    /// PEM file with the CAs used to verify the client certificates, if None clients are not verified
+    pub ca_file: Option<String>,
+
+    /// Require a client certificate when `ca_file` is set, otherwise it is only verified if sent
+    #[serde(default = "default_true")]
+    pub auth_clients: bool,
+}
+
+impl Default for Tls {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            addr: default_tls_addr(),
+            cert_file: String::new(),
+            key_file: String::new(),
+            ca_file: None,
+            auth_clients: default_true(),
+        }
+    }
+}
+
+#[derive(Clone, serde::Deserialize)]
+pub struct Security {
+    /// Password of the default user, if None connections don't need to authenticate
+    pub requirepass: Option<String>,
+
+    /// File with the ACL users, one `user <name> <rules>` per line
+    pub aclfile: Option<String>,
+
+    /// Accept only clients from the loopback interface while the default user does not need a password
+    #[serde(default = "default_true")]
+    pub protected_mode: bool,
+}
+
+impl Default for Security {
+    fn default() -> Self {
+        Self {
+            requirepass: None,
+            aclfile: None,
+            protected_mode: default_true(),
+        }
+    }
+}
+
+#[derive(Clone, Default, serde::Deserialize)]
+pub struct Commands {
+    /// New name of the commands, an empty one disables the command
+    pub rename: std::collections::HashMap<String, String>,
+}
+
+#[derive(Clone, serde::Deserialize)]
+pub struct Limits {
+    /// Commands per second of each client, 0 disables the limit
+    #[serde(default)]
+    pub max_commands_per_second: u64,
+
+    /// Commands per second of all the clients connected from the same address, 0 disables the limit
+    #[serde(default)]
+    pub max_commands_per_second_per_ip: u64,
+
+    /// Maximum length of a bulk string in a request, the body of HTTP requests included
+    #[serde(default = "default_proto_max_bulk_len")]
+    pub proto_max_bulk_len: usize,
+
+    /// Maximum number of arguments of a request
+    #[serde(default = "default_max_array_len")]
+    pub max_array_len: usize,
+
+    /// Maximum bytes buffered while reading a request, the client is disconnected when it is exceeded
+    #[serde(default = "default_max_query_buffer")]
+    pub max_query_buffer: usize,
+}
+
+impl Default for Limits {
+    fn default() -> Self {
+        Self {
+            max_commands_per_second: 0,
+            max_commands_per_second_per_ip: 0,
+            proto_max_bulk_len: default_proto_max_bulk_len(),
+            max_array_len: default_max_array_len(),
+            max_query_buffer: default_max_query_buffer(),
+        }
+    }
+}
+
+const fn default_proto_max_bulk_len() -> usize {
+    512 * 1024 * 1024
+}
+
+const fn default_max_array_len() -> usize {
+    1024 * 1024
+}
+
+const fn default_max_query_buffer() -> usize {
+    1024 * 1024 * 1024
+}
+
+fn default_tls_addr() -> String {
+    "127.0.0.1:6380".to_string()
+}
+
+const fn default_tcp_keepalive() -> std::time::Duration {
+    std::time::Duration::from_secs(300)
+}
+
+const fn default_true() -> bool {
+    true
+}
+
+const fn default_log_slower_than() -> std::time::Duration {
+    std::time::Duration::from_millis(10)
+}
+
+const fn default_slowlog_max_len() -> usize {
+    128
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
     #[test]
+    #[allow(clippy::bool_assert_comparison)]
     fn test_config() {
         let config_file = r#"
             [logger]
@@ -72,10 +340,219 @@ mod tests {
 
         let config = Config::new(config_file);
         assert_eq!(config.logger.level, "warn");
+        assert_eq!(config.logger.format, LogFormat::Text);
+        assert!((config.logger.sample_rate - 1.0).abs() < f64::EPSILON);
         assert_eq!(config.engine.max_items, Some(99));
         let p = config.engine.persistence.as_ref().unwrap();
         assert_eq!(p.enabled, true);
         assert_eq!(p.file, "/tmp/umd/persistence.bin");
         assert_eq!(p.flush_every_changes, 10);
+        assert!(config.slowlog.enabled);
+        assert_eq!(
+            config.slowlog.log_slower_than,
+            std::time::Duration::from_millis(10)
+        );
+        assert_eq!(config.slowlog.max_len, 128);
+    }
+
+    #[test]
+    fn test_config_slowlog() {
+        let config_file = r#"
+            [logger]
+            level = "warn"
+
+            [engine]
+
+            [slowlog]
+            log_slower_than = "500us"
+            max_len = 3
+        "#;
+
+        let config = Config::new(config_file);
+        assert!(config.slowlog.enabled);
+        assert_eq!(
+            config.slowlog.log_slower_than,
+            std::time::Duration::from_micros(500)
+        );
+        assert_eq!(config.slowlog.max_len, 3);
+        assert!(config.latency.tracking);
+        assert!(config.latency.monitor_threshold.is_zero());
+    }
+
+    #[test]
+    fn test_config_logger() {
+        let config_file = r#"
+            [logger]
+            level = "debug"
+            format = "json"
+            sample_rate = 0.25
+
+            [engine]
+        "#;
+
+        let config = Config::new(config_file);
+        assert_eq!(config.logger.format, LogFormat::Json);
+        assert!((config.logger.sample_rate - 0.25).abs() < f64::EPSILON);
+    }
+
+    #[test]
+    fn test_config_latency() {
+        let config_file = r#"
+            [logger]
+            level = "warn"
+
+            [engine]
+
+            [latency]
+            tracking = false
+            monitor_threshold = "5ms"
+        "#;
+
+        let config = Config::new(config_file);
+        assert!(!config.latency.tracking);
+        assert_eq!(
+            config.latency.monitor_threshold,
+            std::time::Duration::from_millis(5)
+        );
+    }
+
+    #[test]
+    fn test_config_network() {
+        let config_file = r#"
+            [logger]
+            level = "warn"
+
+            [engine]
+
+            [network]
+            idle_timeout = "5m"
+            tcp_keepalive = "0s"
+            tcp_enabled = false
+            unixsocket = "/tmp/umd.sock"
+            unixsocket_perm = 0o770
+        "#;
+
+        let config = Config::new(config_file);
+        assert_eq!(
+            config.network.idle_timeout,
+            std::time::Duration::from_secs(300)
+        );
+        assert!(config.network.tcp_keepalive.is_zero());
+        assert!(!config.network.tcp_enabled);
+        assert_eq!(config.network.unixsocket.as_deref(), Some("/tmp/umd.sock"));
+        assert_eq!(config.network.unixsocket_perm, Some(0o770));
+
+        let config = Config::new("[logger]\n[engine]\n");
+        assert!(config.network.idle_timeout.is_zero());
+        assert_eq!(
+            config.network.tcp_keepalive,
+            std::time::Duration::from_secs(300)
+        );
+        assert!(config.network.tcp_enabled);
+        assert!(config.network.unixsocket.is_none());
+    }
+
+    #[test]
+    fn test_config_tls() {
+        let config_file = r#"
+            [logger]
+            level = "warn"
+
+            [engine]
+
+            [tls]
+            enabled = true
+            cert_file = "server.pem"
+            key_file = "server.key"
+            ca_file = "ca.pem"
+        "#;
+
+        let config = Config::new(config_file);
+        assert!(config.tls.enabled);
+        assert_eq!(config.tls.addr, "127.0.0.1:6380");
+        assert_eq!(config.tls.cert_file, "server.pem");
+        assert_eq!(config.tls.key_file, "server.key");
+        assert_eq!(config.tls.ca_file.as_deref(), Some("ca.pem"));
+        assert!(config.tls.auth_clients);
+
+        let config = Config::new("[logger]\n[engine]\n");
+        assert!(!config.tls.enabled);
+    }
+
+    #[test]
+    fn test_config_security() {
+        let config_file = r#"
+            [logger]
+            level = "warn"
+
+            [engine]
+
+            [security]
+            requirepass = "secret"
+            aclfile = "configs/users.acl"
+            protected_mode = false
+        "#;
+
+        let config = Config::new(config_file);
+        assert_eq!(config.security.requirepass.as_deref(), Some("secret"));
+        assert_eq!(
+            config.security.aclfile.as_deref(),
+            Some("configs/users.acl")
+        );
+        assert!(!config.security.protected_mode);
+
+        let config = Config::new("[logger]\n[engine]\n");
+        assert!(config.security.requirepass.is_none());
+        assert!(config.security.aclfile.is_none());
+        assert!(config.security.protected_mode);
+    }
+
+    #[test]
+    fn test_config_commands() {
+        let config_file = r#"
+            [logger]
+            level = "warn"
+
+            [engine]
+
+            [commands]
+            rename = { flushdb = "flushdb-4f2a", config = "" }
+        "#;
+
+        let config = Config::new(config_file);
+        assert_eq!(
+            config.commands.rename.get("flushdb").map(String::as_str),
+            Some("flushdb-4f2a")
+        );
+        assert_eq!(
+            config.commands.rename.get("config").map(String::as_str),
+            Some("")
+        );
+
+        let config = Config::new("[logger]\n[engine]\n");
+        assert!(config.commands.rename.is_empty());
+    }
+
+    #[test]
+    fn test_config_limits() {
+        let config_file = r#"
+            [logger]
+            level = "warn"
+
+            [engine]
+
+            [limits]
+            max_commands_per_second = 1000
+            proto_max_bulk_len = 1024
+        "#;
+
+        let config = Config::new(config_file);
+        assert_eq!(config.limits.max_commands_per_second, 1000);
+        assert_eq!(config.limits.max_commands_per_second_per_ip, 0);
+        assert_eq!(config.limits.proto_max_bulk_len, 1024);
+        assert_eq!(config.limits.max_array_len, 1024 * 1024);
+
+        let config = Config::new("[logger]\n[engine]\n");
+        assert_eq!(config.limits.max_query_buffer, 1024 * 1024 * 1024);
     }
 }
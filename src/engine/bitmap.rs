@@ -0,0 +1,358 @@
+/// Highest bit offset of SETBIT and BITFIELD, like redis strings are limited to 512MB.
+pub const MAX_OFFSET: u64 = (512 << 23) - 1;
+
+/// Unit of the ranges of BITCOUNT and BITPOS.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Unit {
+    #[default]
+    Byte,
+    Bit,
+}
+
+/// Bitwise operation of BITOP, a missing string is a string of zeros.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Operation {
+    And,
+    Or,
+    Xor,
+    Not,
+}
+
+/// Integer of a BITFIELD: signed up to 64 bits, unsigned up to 63 bits.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Field {
+    pub signed: bool,
+    pub bits: u8,
+}
+
+/// Behaviour of the BITFIELD writes of a value which does not fit in the field.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Overflow {
+    #[default]
+    Wrap,
+    Sat,
+    Fail,
+}
+
+/// Operation of BITFIELD, the offset is in bits. OVERFLOW applies to the writes which follow it.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FieldOp {
+    Get(Field, u64),
+    Set(Field, u64, i64),
+    IncrBy(Field, u64, i64),
+    Overflow(Overflow),
+}
+
+impl Field {
+    /// Parse the type of a field, like `i8` or `u16`.
+    pub fn parse(s: &str) -> Option<Self> {
+        let (signed, bits) = match s.chars().next()? {
+            'i' | 'I' => (true, &s[1..]),
+            'u' | 'U' => (false, &s[1..]),
+            _ => return None,
+        };
+        let bits = bits.parse().ok()?;
+        let max = if signed { 64 } else { 63 };
+
+        (1..=max).contains(&bits).then_some(Self { signed, bits })
+    }
+
+    const fn min(self) -> i128 {
+        if self.signed {
+            -(1 << (self.bits - 1))
+        } else {
+            0
+        }
+    }
+
+    const fn max(self) -> i128 {
+        if self.signed {
+            (1 << (self.bits - 1)) - 1
+        } else {
+            (1 << self.bits) - 1
+        }
+    }
+
+    /// Value of the field read from its bits, the sign is extended for signed fields.
+    #[allow(clippy::cast_possible_wrap)]
+    const fn decode(self, raw: u64) -> i64 {
+        let shift = 64 - self.bits as u32;
+        if self.signed {
+            ((raw << shift) as i64) >> shift
+        } else {
+            raw as i64
+        }
+    }
+
+    /// Value stored in the field after an overflow, None if the write must fail.
+    #[allow(clippy::cast_possible_truncation)]
+    fn fit(self, value: i128, overflow: Overflow) -> Option<i64> {
+        if (self.min()..=self.max()).contains(&value) {
+            return Some(value as i64);
+        }
+
+        match overflow {
+            Overflow::Wrap => {
+                let wrapped = (value - self.min()).rem_euclid(1 << self.bits) + self.min();
+                Some(wrapped as i64)
+            }
+            Overflow::Sat => Some(value.clamp(self.min(), self.max()) as i64),
+            Overflow::Fail => None,
+        }
+    }
+}
+
+/// Bit at offset, the bits beyond the end of the string are cleared.
+pub fn get_bit(bytes: &[u8], offset: u64) -> bool {
+    usize::try_from(offset / 8)
+        .ok()
+        .and_then(|i| bytes.get(i))
+        .is_some_and(|b| b & (0x80 >> (offset % 8)) != 0)
+}
+
+/// Set or clear the bit at offset, the string grows with zeros to hold it. Returns the previous bit.
+#[allow(clippy::cast_possible_truncation)]
+pub fn set_bit(bytes: &mut Vec<u8>, offset: u64, bit: bool) -> bool {
+    let i = (offset / 8) as usize;
+    if i >= bytes.len() {
+        bytes.resize(i + 1, 0);
+    }
+
+    let mask = 0x80 >> (offset % 8);
+    let previous = bytes[i] & mask != 0;
+    if bit {
+        bytes[i] |= mask;
+    } else {
+        bytes[i] &= !mask;
+    }
+
+    previous
+}
+
+/// Bits between start and end included, the indexes may be negative to count from the end like redis. None if the
+/// range is empty.
+#[allow(clippy::cast_possible_wrap)]
+fn bit_range(len: usize, start: i64, end: i64, unit: Unit) -> Option<(u64, u64)> {
+    let len = match unit {
+        Unit::Byte => len as i64,
+        Unit::Bit => len as i64 * 8,
+    };
+    let normalize = |i: i64| if i < 0 { (len + i).max(0) } else { i };
+    let (start, end) = (normalize(start), normalize(end).min(len - 1));
+    if start > end {
+        return None;
+    }
+
+    let (start, end) = (start.unsigned_abs(), end.unsigned_abs());
+    Some(match unit {
+        Unit::Byte => (start * 8, end * 8 + 7),
+        Unit::Bit => (start, end),
+    })
+}
+
+/// Number of bits set, optionally between start and end included.
+#[allow(clippy::cast_possible_truncation)]
+pub fn count(bytes: &[u8], range: Option<(i64, i64)>, unit: Unit) -> u64 {
+    let (start, end) = range.unwrap_or((0, -1));
+    let Some((start, end)) = bit_range(bytes.len(), start, end, unit) else {
+        return 0;
+    };
+
+    let (first, last) = (start / 8, end / 8);
+    (first..=last)
+        .map(|i| {
+            let mut b = bytes[i as usize];
+            if i == first {
+                b &= 0xff >> (start % 8);
+            }
+            if i == last {
+                b &= 0xff << (7 - end % 8);
+            }
+            u64::from(b.count_ones())
+        })
+        .sum()
+}
+
+/// Position of the first bit set or cleared, -1 if there is none. Like redis a string made of ones has its first
+/// cleared bit right after its end, unless the end of the range is given.
+#[allow(clippy::cast_possible_truncation, clippy::cast_possible_wrap)]
+pub fn position(bytes: &[u8], bit: bool, start: Option<i64>, end: Option<i64>, unit: Unit) -> i64 {
+    if bytes.is_empty() {
+        return if bit { -1 } else { 0 };
+    }
+    let Some((start, last)) = bit_range(bytes.len(), start.unwrap_or(0), end.unwrap_or(-1), unit)
+    else {
+        return -1;
+    };
+
+    let skipped = if bit { 0 } else { 0xff };
+    let mut i = start;
+    while i <= last {
+        // NOTE: whole bytes without the bit are skipped at once.
+        if i % 8 == 0 && i + 7 <= last && bytes[(i / 8) as usize] == skipped {
+            i += 8;
+            continue;
+        }
+        if get_bit(bytes, i) == bit {
+            return i as i64;
+        }
+        i += 1;
+    }
+
+    if !bit && end.is_none() {
+        (last + 1) as i64
+    } else {
+        -1
+    }
+}
+
+/// Result of the bitwise operation, as long as the longest string.
+pub fn operation(op: Operation, sources: &[Vec<u8>]) -> Vec<u8> {
+    let len = sources.iter().map(Vec::len).max().unwrap_or(0);
+    (0..len)
+        .map(|i| {
+            let mut bytes = sources.iter().map(|s| s.get(i).copied().unwrap_or(0));
+            match op {
+                Operation::And => bytes.fold(0xff, |a, b| a & b),
+                Operation::Or => bytes.fold(0, |a, b| a | b),
+                Operation::Xor => bytes.fold(0, |a, b| a ^ b),
+                Operation::Not => !bytes.next().unwrap_or(0),
+            }
+        })
+        .collect()
+}
+
+/// Run the operations of BITFIELD in order. GET replies with the value, SET with the previous value and INCRBY with
+/// the new one, the writes failing with the FAIL overflow reply None. OVERFLOW does not reply.
+pub fn bitfield(bytes: &mut Vec<u8>, ops: &[FieldOp]) -> Vec<Option<i64>> {
+    let read = |bytes: &[u8], field: Field, offset: u64| {
+        let raw = (0..u64::from(field.bits))
+            .fold(0, |v, i| v << 1 | u64::from(get_bit(bytes, offset + i)));
+        field.decode(raw)
+    };
+    let write = |bytes: &mut Vec<u8>, field: Field, offset: u64, value: i64| {
+        let bits = u64::from(field.bits);
+        for i in 0..bits {
+            set_bit(bytes, offset + i, (value >> (bits - 1 - i)) & 1 == 1);
+        }
+    };
+
+    let mut overflow = Overflow::default();
+    let mut replies = vec![];
+    for op in ops {
+        match *op {
+            FieldOp::Get(field, offset) => replies.push(Some(read(bytes, field, offset))),
+            FieldOp::Set(field, offset, value) => {
+                let previous = read(bytes, field, offset);
+                let set = field.fit(i128::from(value), overflow);
+                if let Some(v) = set {
+                    write(bytes, field, offset, v);
+                }
+                replies.push(set.map(|_| previous));
+            }
+            FieldOp::IncrBy(field, offset, increment) => {
+                let value = i128::from(read(bytes, field, offset)) + i128::from(increment);
+                let set = field.fit(value, overflow);
+                if let Some(v) = set {
+                    write(bytes, field, offset, v);
+                }
+                replies.push(set);
+            }
+            FieldOp::Overflow(o) => overflow = o,
+        }
+    }
+
+    replies
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bits() {
+        let mut bytes = vec![];
+        assert!(!set_bit(&mut bytes, 7, true));
+        assert!(set_bit(&mut bytes, 7, true));
+        assert!(!set_bit(&mut bytes, 9, true));
+        assert_eq!(bytes, [0x01, 0x40]);
+        assert!(get_bit(&bytes, 9));
+        assert!(!get_bit(&bytes, 100));
+
+        let bytes = b"foobar";
+        assert_eq!(count(bytes, None, Unit::Byte), 26);
+        assert_eq!(count(bytes, Some((1, 1)), Unit::Byte), 6);
+        assert_eq!(count(bytes, Some((5, 30)), Unit::Bit), 17);
+        assert_eq!(count(bytes, Some((-2, -1)), Unit::Byte), 7);
+        assert_eq!(count(bytes, Some((4, 2)), Unit::Byte), 0);
+    }
+
+    #[test]
+    fn positions() {
+        let bytes = [0xff, 0xf0, 0x00];
+        assert_eq!(position(&bytes, false, None, None, Unit::Byte), 12);
+        assert_eq!(position(&bytes, true, Some(2), None, Unit::Byte), -1);
+        assert_eq!(position(&bytes, true, Some(7), Some(15), Unit::Bit), 7);
+        assert_eq!(position(&[0xff], false, None, None, Unit::Byte), 8);
+        assert_eq!(position(&[0xff], false, Some(0), Some(-1), Unit::Byte), -1);
+        assert_eq!(position(&[], false, None, None, Unit::Byte), 0);
+        assert_eq!(position(&[], true, None, None, Unit::Byte), -1);
+
+        let sources = [vec![0xf0, 0x0f], vec![0xff]];
+        assert_eq!(operation(Operation::And, &sources), [0xf0, 0x00]);
+        assert_eq!(operation(Operation::Or, &sources), [0xff, 0x0f]);
+        assert_eq!(operation(Operation::Xor, &sources), [0x0f, 0x0f]);
+        assert_eq!(operation(Operation::Not, &sources[..1]), [0x0f, 0xf0]);
+    }
+
+    #[test]
+    fn fields() {
+        assert_eq!(
+            Field::parse("i64"),
+            Some(Field {
+                signed: true,
+                bits: 64
+            })
+        );
+        assert_eq!(Field::parse("u64"), None);
+        assert_eq!(Field::parse("i0"), None);
+
+        let u8 = Field::parse("u8").unwrap();
+        let i5 = Field::parse("i5").unwrap();
+        let mut bytes = vec![];
+        let replies = bitfield(
+            &mut bytes,
+            &[
+                FieldOp::Set(u8, 0, 255),
+                FieldOp::Get(u8, 0),
+                FieldOp::IncrBy(u8, 0, 10),
+                FieldOp::Overflow(Overflow::Sat),
+                FieldOp::IncrBy(i5, 8, 100),
+                FieldOp::IncrBy(i5, 8, -100),
+                FieldOp::Overflow(Overflow::Fail),
+                FieldOp::IncrBy(i5, 8, -1),
+                FieldOp::Get(i5, 8),
+            ],
+        );
+        assert_eq!(
+            replies,
+            [
+                Some(0),
+                Some(255),
+                Some(9),
+                Some(15),
+                Some(-16),
+                None,
+                Some(-16)
+            ]
+        );
+        assert_eq!(bytes, [9, 0x80]);
+
+        let i64 = Field::parse("i64").unwrap();
+        let replies = bitfield(
+            &mut bytes,
+            &[FieldOp::Set(i64, 16, i64::MAX), FieldOp::IncrBy(i64, 16, 1)],
+        );
+        assert_eq!(replies, [Some(0), Some(i64::MIN)]);
+    }
+}
@@ -1,3 +1,4 @@
+use super::value::Value;
 use crate::config;
 
 use std::cell::RefCell;
@@ -5,6 +6,9 @@ use std::collections::HashMap;
 use std::ptr::NonNull;
 use std::rc::Rc;
 
+/// Prefix of the snapshots keeping the type of the values, older snapshots hold only strings.
+const SNAPSHOT_MAGIC: &[u8] = b"UMD\x01";
+
 #[allow(clippy::module_name_repetitions)]
 pub fn create_db(c: &config::Engine) -> Result<Rc<RefCell<HashMapDb>>, std::io::Error> {
     let db = Rc::new(RefCell::new(HashMapDb::new(c.clone())));
@@ -14,12 +18,11 @@ pub fn create_db(c: &config::Engine) -> Result<Rc<RefCell<HashMapDb>>, std::io::
             return Ok(db);
         }
 
-        let data = std::fs::read_to_string(&p.file);
-        if data.is_err() {
+        let Ok(data) = std::fs::read(&p.file) else {
             return Ok(db);
-        }
+        };
 
-        let d = bincode::deserialize::<HashMapDb>(data?.as_bytes()).map_err(|e| {
+        let d = decode_snapshot(&data).map_err(|e| {
             std::io::Error::new(
                 std::io::ErrorKind::InvalidData,
                 format!("error on deserializing db: {e}"),
@@ -30,9 +33,9 @@ pub fn create_db(c: &config::Engine) -> Result<Rc<RefCell<HashMapDb>>, std::io::
         db.borrow_mut().config.persistence = None;
 
         // fill db with data
-        d.data.iter().for_each(|(k, v)| {
-            db.borrow_mut().set(k, v.value.clone(), None);
-        });
+        for (k, v) in d {
+            db.borrow_mut().set(&k, v, None);
+        }
 
         // inject config
         db.borrow_mut().config = c.clone();
@@ -43,26 +46,65 @@ pub fn create_db(c: &config::Engine) -> Result<Rc<RefCell<HashMapDb>>, std::io::
     Ok(db)
 }
 
+/// Entry of the snapshots written before the values had a type.
+#[derive(serde::Deserialize)]
+struct LegacyEntry {
+    #[allow(dead_code)]
+    key: String,
+    value: String,
+}
+
+/// Keys and values of the snapshot, the snapshots without [`SNAPSHOT_MAGIC`] are read as strings.
+fn decode_snapshot(data: &[u8]) -> Result<Vec<(String, Value)>, bincode::Error> {
+    if let Some(data) = data.strip_prefix(SNAPSHOT_MAGIC) {
+        let d = bincode::deserialize::<HashMapDb>(data)?;
+        return Ok(d.data.into_iter().map(|(k, e)| (k, e.value)).collect());
+    }
+
+    let d = bincode::deserialize::<HashMap<String, LegacyEntry>>(data)?;
+    Ok(d.into_iter()
+        .map(|(k, e)| (k, Value::from(e.value)))
+        .collect())
+}
+
 /// Entry is a value that represents a key-value pair in the database. It also is a node of a linked list built
 /// while setting values in the database. The linked list is used to implement LRU cache.
 /// In this way we can have fast access to the most recently used values.
 #[derive(serde::Deserialize, serde::Serialize)]
 struct Entry {
     key: String,
-    value: String,
+    value: Value,
 
     #[serde(skip_serializing, skip_deserializing)]
     prev: Option<NonNull<Entry>>,
 
     #[serde(skip_serializing, skip_deserializing)]
     next: Option<NonNull<Entry>>,
+
+    /// Memory counted for the entry in [`HashMapDb::estimated_memory`], measured when it is stored or changed.
+    #[serde(skip_serializing, skip_deserializing)]
+    size: usize,
+}
+
+impl Entry {
+    /// Rough estimation of the memory used by the entry, its key in the map and its TTL if it has one.
+    fn measure(&self, ttl: bool) -> usize {
+        let ttl = if ttl {
+            self.key.len() + std::mem::size_of::<std::time::Instant>()
+        } else {
+            0
+        };
+
+        self.key.len() * 2 + self.value.estimated_memory() + std::mem::size_of::<Self>() + ttl
+    }
 }
 
 #[allow(clippy::module_name_repetitions)]
 #[allow(clippy::unsafe_derive_deserialize)]
 #[derive(Default, serde::Deserialize, serde::Serialize)]
 pub struct HashMapDb {
-    data: HashMap<String, Entry>,
+    // NOTE: entries are boxed so that the nodes of the linked list don't move when the map grows.
+    data: HashMap<String, Box<Entry>>,
 
     #[serde(skip_serializing, skip_deserializing)]
     head: Option<NonNull<Entry>>,
@@ -78,6 +120,42 @@ pub struct HashMapDb {
 
     #[serde(skip_serializing, skip_deserializing)]
     changes: u64,
+
+    /// Sum of the sizes of the entries, kept up to date so that it is read without walking the keyspace.
+    #[serde(skip_serializing, skip_deserializing)]
+    used_memory: usize,
+
+    /// Keys created by [`HashMapDb::get_or_insert_with`] for the running command, they are kept once it records a
+    /// change.
+    #[serde(skip_serializing, skip_deserializing)]
+    created: Vec<String>,
+
+    #[serde(skip_serializing, skip_deserializing)]
+    stats: Stats,
+}
+
+/// Counters about the keyspace and the persistence, they are not persisted and restart from zero.
+#[derive(Default)]
+pub struct Stats {
+    pub keyspace_hits: u64,
+    pub keyspace_misses: u64,
+    pub expired_keys: u64,
+    pub evicted_keys: u64,
+
+    /// Last time the db has been persisted to disk, None if it never happened.
+    pub last_save_time: Option<std::time::SystemTime>,
+
+    /// True if the last attempt to persist the db failed.
+    pub last_save_failed: bool,
+
+    /// Time spent by the last attempt to persist the db.
+    pub last_save_duration: std::time::Duration,
+
+    /// Number of attempts to persist the db that failed.
+    pub save_failures: u64,
+
+    /// Latency of the internal operations as (event, duration), waiting to be collected by the latency monitor.
+    latency_events: Vec<(&'static str, std::time::Duration)>,
 }
 
 impl HashMapDb {
@@ -93,10 +171,47 @@ impl HashMapDb {
         }
     }
 
+    pub const fn stats(&self) -> &Stats {
+        &self.stats
+    }
+
+    /// Take the latency of the internal operations done since the last call, like persistence or eviction.
+    pub fn take_latency_events(&mut self) -> Vec<(&'static str, std::time::Duration)> {
+        std::mem::take(&mut self.stats.latency_events)
+    }
+
+    pub const fn max_items(&self) -> Option<u64> {
+        self.config.max_items
+    }
+
+    /// Number of keys stored, expired keys not yet removed are counted as well.
+    pub fn keys_count(&self) -> usize {
+        self.data.len()
+    }
+
+    /// Number of keys with a TTL.
+    pub fn expires_count(&self) -> usize {
+        self.ttl.len()
+    }
+
+    /// Number of changes not yet persisted to disk.
+    pub const fn changes_since_last_save(&self) -> u64 {
+        self.changes
+    }
+
+    /// Rough estimation of the memory used by the stored data, it counts keys, values and the bookkeeping for
+    /// entries and TTLs but not the allocator overhead.
+    /// The values modified in place are measured again by [`HashMapDb::changed`].
+    pub const fn estimated_memory(&self) -> usize {
+        self.used_memory
+    }
+
     /// Check the command [`FlushDb`](protocol::commands::Command::FlushDb) for more details.
     pub fn flush(&mut self) {
         let c = self.config.clone();
+        let stats = std::mem::take(&mut self.stats);
         *self = Self::new(c);
+        self.stats = stats;
     }
 
     /// Check if we have persistence enabled and if we have it + we reach the threshold of changes,
@@ -111,16 +226,29 @@ impl HashMapDb {
         }
     }
 
-    fn persist(&self) {
+    fn persist(&mut self) {
         if let Some(persistence) = &self.config.persistence {
             if !persistence.enabled {
                 return;
             }
 
-            let s = bincode::serialize(&self).unwrap();
             tracing::info!("persisting db to {}", persistence.file);
-            std::fs::File::create(&persistence.file).unwrap();
-            std::fs::write(&persistence.file, s).unwrap();
+            let started = std::time::Instant::now();
+            let res = bincode::serialize(&*self)
+                .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))
+                .and_then(|s| std::fs::write(&persistence.file, [SNAPSHOT_MAGIC, &s].concat()));
+
+            if let Err(e) = &res {
+                tracing::error!("error on persisting db to {}: {}", persistence.file, e);
+                self.stats.save_failures += 1;
+            }
+
+            self.stats.last_save_duration = started.elapsed();
+            self.stats
+                .latency_events
+                .push(("snapshot-persistence", self.stats.last_save_duration));
+            self.stats.last_save_failed = res.is_err();
+            self.stats.last_save_time = Some(std::time::SystemTime::now());
         }
     }
 
@@ -128,118 +256,221 @@ impl HashMapDb {
         self.get(key, instant).is_some()
     }
 
-    pub fn get(&mut self, key: &str, now: std::time::Instant) -> Option<&String> {
-        let v = match self.ttl.get(key) {
-            Some(ttl) if *ttl <= now => {
-                self.del(key);
-
-                None
-            }
-            _ => self.data.get_mut(key),
-        }?;
+    pub fn get(&mut self, key: &str, now: std::time::Instant) -> Option<&Value> {
+        self.lookup(key, now).map(|e| &e.value)
+    }
 
-        if self.tail != self.head && self.tail != Some(v.into()) {
-            // adjust head to second node if head is the node to be removed
-            if self.head == Some(v.into()) {
-                self.head = v.next;
-            }
+    /// Value of the key to be modified in place, the TTL is kept. Check [`HashMapDb::changed`] to persist the change.
+    pub fn get_mut(&mut self, key: &str, now: std::time::Instant) -> Option<&mut Value> {
+        self.lookup(key, now).map(|e| &mut e.value)
+    }
 
-            // remove node from the list
-            if let Some(prev) = v.prev {
-                unsafe {
-                    (*prev.as_ptr()).next = v.next;
-                }
-            }
-            if let Some(next) = v.next {
-                unsafe {
-                    (*next.as_ptr()).prev = v.prev;
-                }
-            }
+    /// Value of the key to be modified in place, it is created with `default` if the key does not exist. A created
+    /// key is removed by [`HashMapDb::discard_created`] unless [`HashMapDb::changed`] is called for it.
+    pub fn get_or_insert_with(
+        &mut self,
+        key: &str,
+        now: std::time::Instant,
+        default: impl FnOnce() -> Value,
+    ) -> &mut Value {
+        if self.lookup(key, now).is_none() {
+            // NOTE: at max_items, another key is evicted only once the command stores something in this one.
+            self.insert(key, default(), None);
+            self.created.push(key.to_string());
+        }
 
-            // attach node to the tail
-            if let Some(tail) = self.tail {
-                unsafe {
-                    (*tail.as_ptr()).next = Some(v.into());
-                }
-            }
+        // NOTE: the key has just been looked up or set, it is the most recently used one and can't be evicted.
+        &mut self.data.get_mut(key).unwrap().value
+    }
 
-            v.prev = self.tail;
-            v.next = None;
+    /// Record a change done through [`HashMapDb::get_mut`], a value left empty is removed like redis does with
+    /// collections.
+    pub fn changed(&mut self, key: &str) {
+        let created = self
+            .created
+            .iter()
+            .position(|k| k == key)
+            .map(|i| self.created.swap_remove(i))
+            .is_some();
+        let ttl = self.ttl.contains_key(key);
+        if let Some(e) = self.data.get_mut(key) {
+            let size = e.measure(ttl);
+            self.used_memory = self.used_memory - e.size + size;
+            e.size = size;
+        }
+        let empty = self.data.get(key).is_some_and(|e| match &e.value {
+            Value::List(l) => l.is_empty(),
+            Value::Hash(h) => h.is_empty(),
+            Value::Set(s) => s.is_empty(),
+            Value::SortedSet(z) => z.is_empty(),
+            Value::String(_) | Value::Integer(_) | Value::Stream(_) | Value::Json(_) => false,
+        });
 
-            self.tail = Some(v.into());
+        if empty {
+            self.del(key);
+        } else {
+            let full = self
+                .config
+                .max_items
+                .is_some_and(|max| self.data.len() as u64 > max);
+            if created && full {
+                self.evict(key);
+            }
+            self.evaluate_update_persistence();
         }
+    }
 
-        Some(&v.value)
+    /// Remove the keys created for a command which failed or did not store anything in them.
+    pub fn discard_created(&mut self) {
+        for key in std::mem::take(&mut self.created) {
+            self.remove(&key);
+        }
     }
 
-    pub fn set(&mut self, key: &str, value: String, ttl: Option<std::time::Instant>) {
-        let entry = Entry {
-            key: key.to_string(),
-            value,
-            prev: self.tail,
-            next: None,
+    /// Entry of the key moved to the tail of the list as the most recently used, expired keys are removed.
+    fn lookup(&mut self, key: &str, now: std::time::Instant) -> Option<&mut Entry> {
+        if self.ttl.get(key).is_some_and(|ttl| *ttl <= now) {
+            let started = std::time::Instant::now();
+            self.del(key);
+            self.stats.expired_keys += 1;
+            self.stats
+                .latency_events
+                .push(("expire-cycle", started.elapsed()));
+        }
+
+        let Some(e) = self.data.get_mut(key) else {
+            self.stats.keyspace_misses += 1;
+            return None;
         };
+        self.stats.keyspace_hits += 1;
 
-        if let Some(max) = self.config.max_items {
-            if self.data.len() as u64 == max {
-                let h = self.head.as_ref().unwrap();
-                unsafe {
-                    self.data.remove(&(*h.as_ptr()).key);
-                }
-            }
+        let node = NonNull::from(e.as_mut());
+        if self.tail != Some(node) {
+            self.unlink(node);
+            self.push_back(node);
         }
 
-        self.data.insert(key.to_string(), entry);
-        let e = self.data.get(key).unwrap();
+        // SAFETY: the node is owned by the box in `data`, which is borrowed mutably by self.
+        Some(unsafe { &mut *node.as_ptr() })
+    }
 
-        if self.head.is_none() {
-            self.head = Some(e.into());
+    pub fn set(&mut self, key: &str, value: impl Into<Value>, ttl: Option<std::time::Instant>) {
+        if let Some(max) = self.config.max_items {
+            if self.data.len() as u64 >= max && !self.data.contains_key(key) {
+                self.evict(key);
+            }
         }
 
-        if let Some(t) = self.tail {
-            unsafe {
-                (*t.as_ptr()).next = Some(e.into());
+        self.insert(key, value.into(), ttl);
+        self.evaluate_update_persistence();
+    }
+
+    /// Remove the least recently used key other than `keep`, to make room for it.
+    fn evict(&mut self, keep: &str) {
+        let started = std::time::Instant::now();
+        let mut node = self.head;
+        // SAFETY: nodes are boxes owned by `data`, they are in the list as long as they are in the map.
+        let oldest = unsafe {
+            while node.is_some_and(|n| (*n.as_ptr()).key == keep) {
+                node = node.and_then(|n| (*n.as_ptr()).next);
             }
+            node.map(|n| (*n.as_ptr()).key.clone())
+        };
+        let Some(oldest) = oldest else {
+            return;
+        };
+
+        self.del(&oldest);
+        self.stats.evicted_keys += 1;
+        self.stats
+            .latency_events
+            .push(("eviction-cycle", started.elapsed()));
+    }
+
+    /// Store the value as the most recently used one, without eviction nor persistence.
+    fn insert(&mut self, key: &str, value: Value, ttl: Option<std::time::Instant>) {
+        // NOTE: the node of the overwritten value must leave the list before its box is dropped.
+        if let Some(old) = self.data.get_mut(key) {
+            let node = NonNull::from(old.as_mut());
+            self.used_memory -= old.size;
+            self.unlink(node);
         }
 
-        self.tail = Some(e.into());
+        let mut entry = Box::new(Entry {
+            key: key.to_string(),
+            value,
+            prev: None,
+            next: None,
+            size: 0,
+        });
+        entry.size = entry.measure(ttl.is_some());
+        self.used_memory += entry.size;
+        self.push_back(NonNull::from(entry.as_mut()));
+        self.data.insert(key.to_string(), entry);
+
+        match ttl {
+            Some(ttl) => self.ttl.insert(key.to_string(), ttl),
+            None => self.ttl.remove(key),
+        };
+    }
 
-        if let Some(ttl) = ttl {
-            self.ttl.insert(key.to_string(), ttl);
+    /// Remove the key, returns if it existed.
+    pub fn del(&mut self, key: &str) -> bool {
+        let removed = self.remove(key);
+        if removed {
+            self.evaluate_update_persistence();
         }
 
-        self.evaluate_update_persistence();
+        removed
     }
 
-    pub fn del(&mut self, key: &str) {
-        let e = self.data.get_mut(key).unwrap();
+    /// Remove the key without recording a change, returns if it existed.
+    fn remove(&mut self, key: &str) -> bool {
+        let Some(e) = self.data.get_mut(key) else {
+            return false;
+        };
 
-        // adjust head to second node if head is the node to be removed
-        if self.head == Some(e.into()) {
-            self.head = e.next;
-        }
+        let node = NonNull::from(e.as_mut());
+        self.used_memory -= e.size;
+        self.unlink(node);
+        self.data.remove(key);
+        self.ttl.remove(key);
 
-        // adjust tail to latest - 1 node if tail is the node to be removed
-        if self.tail == Some(e.into()) {
-            self.tail = e.prev;
-        }
+        true
+    }
 
-        // remove node from the list in case is not the head or tail
-        if let Some(prev) = e.prev {
-            unsafe {
-                (*prev.as_ptr()).next = e.next;
+    /// Detach the node from the list, adjusting head and tail if it is one of them.
+    fn unlink(&mut self, node: NonNull<Entry>) {
+        // SAFETY: nodes are boxes owned by `data`, they are in the list as long as they are in the map.
+        unsafe {
+            let (prev, next) = ((*node.as_ptr()).prev, (*node.as_ptr()).next);
+            match prev {
+                Some(p) => (*p.as_ptr()).next = next,
+                None if self.head == Some(node) => self.head = next,
+                None => (),
             }
-        }
-        if let Some(next) = e.next {
-            unsafe {
-                (*next.as_ptr()).prev = e.prev;
+            match next {
+                Some(n) => (*n.as_ptr()).prev = prev,
+                None if self.tail == Some(node) => self.tail = prev,
+                None => (),
             }
+            (*node.as_ptr()).prev = None;
+            (*node.as_ptr()).next = None;
         }
+    }
 
-        self.data.remove(key);
-        self.ttl.remove(key);
-
-        self.evaluate_update_persistence();
+    /// Attach the node to the tail of the list, as the most recently used.
+    fn push_back(&mut self, node: NonNull<Entry>) {
+        // SAFETY: see `unlink`.
+        unsafe {
+            (*node.as_ptr()).prev = self.tail;
+            (*node.as_ptr()).next = None;
+            match self.tail {
+                Some(t) => (*t.as_ptr()).next = Some(node),
+                None => self.head = Some(node),
+            }
+        }
+        self.tail = Some(node);
     }
 }
 
@@ -247,6 +478,10 @@ impl HashMapDb {
 mod tests {
     use super::*;
 
+    fn string(s: &str) -> Value {
+        Value::String(s.as_bytes().to_vec())
+    }
+
     #[test]
     fn flush() {
         let mut db = HashMapDb::new(config::Engine {
@@ -280,10 +515,101 @@ mod tests {
 
         assert_eq!(
             db.get("two", std::time::Instant::now()),
-            Some(&"two".to_string())
+            Some(&string("two"))
         );
     }
 
+    #[test]
+    fn overwrite_at_max_items() {
+        let mut db = HashMapDb::new(config::Engine {
+            max_items: Some(2),
+            ..Default::default()
+        });
+        let now = std::time::Instant::now();
+        db.set("one", "one".to_string(), None);
+        db.set("two", "two".to_string(), None);
+
+        // NOTE: overwriting a key doesn't add one, no other key has to be evicted for it.
+        db.set("two", "dos".to_string(), None);
+        assert_eq!(db.get("one", now), Some(&string("one")));
+        assert_eq!(db.get("two", now), Some(&string("dos")));
+        assert_eq!(db.stats().evicted_keys, 0);
+
+        db.set("three", "three".to_string(), None);
+        assert_eq!(db.get("one", now), None);
+        assert_eq!(db.keys_count(), 2);
+        assert_eq!(db.stats().evicted_keys, 1);
+    }
+
+    #[test]
+    fn create_at_max_items() {
+        let mut db = HashMapDb::new(config::Engine {
+            max_items: Some(2),
+            ..Default::default()
+        });
+        let now = std::time::Instant::now();
+        db.set("one", "one".to_string(), None);
+        db.set("two", "two".to_string(), None);
+
+        // NOTE: a command which fails or stores nothing in the key it created must not evict another key.
+        db.get_or_insert_with("list", now, || {
+            Value::List(std::collections::VecDeque::new())
+        });
+        db.discard_created();
+        db.get_or_insert_with("list", now, || {
+            Value::List(std::collections::VecDeque::new())
+        });
+        db.changed("list");
+        assert_eq!(db.get("list", now), None);
+        assert_eq!(db.keys_count(), 2);
+        assert_eq!(db.stats().evicted_keys, 0);
+
+        db.get_or_insert_with("list", now, || {
+            Value::List(std::collections::VecDeque::new())
+        })
+        .as_list_mut()
+        .unwrap()
+        .push_back("a".to_string());
+        db.changed("list");
+        db.discard_created();
+        assert_eq!(db.get("one", now), None);
+        assert!(db.get("list", now).is_some());
+        assert_eq!(db.keys_count(), 2);
+        assert_eq!(db.stats().evicted_keys, 1);
+    }
+
+    #[test]
+    fn stats() {
+        let mut db = HashMapDb::new(config::Engine {
+            max_items: Some(2),
+            ..Default::default()
+        });
+        let now = std::time::Instant::now();
+        db.set("one", "one".to_string(), None);
+        db.set("two", "two".to_string(), Some(now));
+        db.set("three", "three".to_string(), None);
+
+        assert_eq!(db.get("one", now), None);
+        assert_eq!(db.get("two", now), None);
+        assert_eq!(db.get("three", now), Some(&string("three")));
+
+        let s = db.stats();
+        assert_eq!(s.evicted_keys, 1);
+        assert_eq!(s.expired_keys, 1);
+        assert_eq!(s.keyspace_hits, 1);
+        assert_eq!(s.keyspace_misses, 2);
+        assert_eq!(db.keys_count(), 1);
+        assert!(db.estimated_memory() > "three".len() * 3);
+
+        let events = db
+            .take_latency_events()
+            .into_iter()
+            .map(|(e, _)| e)
+            .collect::<Vec<&str>>();
+        assert_eq!(events, vec!["eviction-cycle", "expire-cycle"]);
+        assert!(db.take_latency_events().is_empty());
+    }
+
     #[test]
     fn linked_list() {
         let mut db = HashMapDb::new(config::Engine::default());
@@ -292,7 +618,7 @@ mod tests {
         db.set("foo", "bar".to_string(), None);
         assert_eq!(
             db.get("foo", std::time::Instant::now()),
-            Some(&"bar".to_string())
+            Some(&string("bar"))
         );
         assert!(db.tail.is_some());
         assert!(db.head.is_some());
@@ -302,43 +628,43 @@ mod tests {
         db.set("foz", "baz".to_string(), None);
         assert_eq!(
             db.get("foz", std::time::Instant::now()),
-            Some(&"baz".to_string())
+            Some(&string("baz"))
         );
         assert!(db.tail.is_some());
         assert!(db.head.is_some());
         assert_ne!(db.tail, db.head);
         unsafe {
-            assert_eq!((*(db.tail.unwrap().as_ptr())).value, "baz".to_string());
-            assert_eq!((*(db.head.unwrap().as_ptr())).value, "bar".to_string());
+            assert_eq!((*(db.tail.unwrap().as_ptr())).value, string("baz"));
+            assert_eq!((*(db.head.unwrap().as_ptr())).value, string("bar"));
         }
 
         // get first key, it should be the most recently used now then moved to the tail
         let output = db.get("foo", std::time::Instant::now());
-        assert_eq!(output, Some(&"bar".to_string()));
+        assert_eq!(output, Some(&string("bar")));
         assert_ne!(db.tail, db.head);
         unsafe {
-            assert_eq!((*(db.tail.unwrap().as_ptr())).value, "bar".to_string());
-            assert_eq!((*(db.head.unwrap().as_ptr())).value, "baz".to_string());
+            assert_eq!((*(db.tail.unwrap().as_ptr())).value, string("bar"));
+            assert_eq!((*(db.head.unwrap().as_ptr())).value, string("baz"));
         }
 
         // set a third key
         db.set("fob", "bax".to_string(), None);
         assert_eq!(
             db.get("fob", std::time::Instant::now()),
-            Some(&"bax".to_string())
+            Some(&string("bax"))
         );
         assert_ne!(db.tail, db.head);
         unsafe {
-            assert_eq!((*(db.tail.unwrap().as_ptr())).value, "bax".to_string());
-            assert_eq!((*(db.head.unwrap().as_ptr())).value, "baz".to_string());
+            assert_eq!((*(db.tail.unwrap().as_ptr())).value, string("bax"));
+            assert_eq!((*(db.head.unwrap().as_ptr())).value, string("baz"));
         }
 
         // remove the first key
         db.del("foo");
         assert_ne!(db.tail, db.head);
         unsafe {
-            assert_eq!((*(db.tail.unwrap().as_ptr())).value, "bax".to_string());
-            assert_eq!((*(db.head.unwrap().as_ptr())).value, "baz".to_string());
+            assert_eq!((*(db.tail.unwrap().as_ptr())).value, string("bax"));
+            assert_eq!((*(db.head.unwrap().as_ptr())).value, string("baz"));
         }
 
         // another remove
@@ -351,6 +677,100 @@ mod tests {
         assert_eq!(db.head, None);
     }
 
+    #[test]
+    fn overwrite() {
+        let mut db = HashMapDb::new(config::Engine {
+            max_items: Some(2),
+            ..Default::default()
+        });
+        let now = std::time::Instant::now();
+        db.set("one", "one".to_string(), Some(now));
+        db.set("one", "uno".to_string(), None);
+        db.set("two", "two".to_string(), None);
+
+        // NOTE: the overwritten node must not stay in the list, eviction would follow it.
+        db.set("three", "three".to_string(), None);
+        assert_eq!(db.get("one", now), None);
+        assert_eq!(db.get("two", now), Some(&string("two")));
+        assert_eq!(db.expires_count(), 0);
+
+        assert!(db.del("two"));
+        assert!(!db.del("two"));
+        assert!(!db.del("missing"));
+    }
+
+    #[test]
+    fn growth() {
+        let mut db = HashMapDb::new(config::Engine::default());
+        let now = std::time::Instant::now();
+        for i in 0..1000 {
+            db.set(&i.to_string(), i, None);
+        }
+
+        // NOTE: the map has been reallocated many times, the list must still be walkable from both ends.
+        assert_eq!(db.get("0", now), Some(&Value::Integer(0)));
+        assert!(db.del("500"));
+        let mut count = 0;
+        let mut node = db.head;
+        while let Some(n) = node {
+            count += 1;
+            node = unsafe { (*n.as_ptr()).next };
+        }
+        assert_eq!(count, 999);
+        unsafe {
+            assert_eq!((*db.tail.unwrap().as_ptr()).key, "0");
+        }
+    }
+
+    #[test]
+    fn get_mut() {
+        let mut db = HashMapDb::new(config::Engine::default());
+        let now = std::time::Instant::now();
+        let later = now + std::time::Duration::from_secs(10);
+        db.set("list", Value::List(["a".to_string()].into()), Some(later));
+
+        if let Some(Value::List(l)) = db.get_mut("list", now) {
+            l.push_back("b".to_string());
+        }
+        db.changed("list");
+        assert_eq!(
+            db.get("list", now),
+            Some(&Value::List(["a".to_string(), "b".to_string()].into()))
+        );
+        assert_eq!(db.expires_count(), 1);
+
+        if let Some(Value::List(l)) = db.get_mut("list", now) {
+            l.clear();
+        }
+        db.changed("list");
+        assert_eq!(db.get("list", now), None);
+    }
+
+    #[test]
+    fn memory() {
+        let mut db = HashMapDb::new(config::Engine::default());
+        let now = std::time::Instant::now();
+        let later = now + std::time::Duration::from_secs(10);
+        db.set("one", "one".to_string(), None);
+        let one = db.estimated_memory();
+        db.set("list", Value::List(["a".to_string()].into()), Some(later));
+        let list = db.estimated_memory();
+        assert!(list > one);
+
+        if let Some(Value::List(l)) = db.get_mut("list", now) {
+            l.extend(std::iter::repeat("b".repeat(100)).take(10));
+        }
+        db.changed("list");
+        assert!(db.estimated_memory() >= list + 1000);
+
+        db.set("list", "short".to_string(), None);
+        assert!(db.estimated_memory() < list);
+        db.del("list");
+        assert_eq!(db.estimated_memory(), one);
+        db.del("one");
+        assert_eq!(db.estimated_memory(), 0);
+    }
+
     #[test]
     fn lazy_ttl() {
         let mut db = HashMapDb::new(config::Engine::default());
@@ -362,7 +782,7 @@ mod tests {
         );
         assert_eq!(
             db.get("foo", now + std::time::Duration::from_secs(1)),
-            Some(&"bar".to_string())
+            Some(&string("bar"))
         );
         assert_eq!(
             db.get("foo", now + std::time::Duration::from_secs(11)),
@@ -374,13 +794,14 @@ mod tests {
     fn serialize_entry() {
         let e = Entry {
             key: "foo".to_string(),
-            value: "bar".to_string(),
+            value: string("bar"),
             prev: None,
             next: None,
+            size: 0,
         };
 
         let s = bincode::serialize(&e).unwrap();
-        assert_eq!(s.len(), 22);
+        assert_eq!(s.len(), 26);
 
         let ee = bincode::deserialize::<Entry>(&s).unwrap();
         assert_eq!(e.key, ee.key);
@@ -394,20 +815,50 @@ mod tests {
         db.set("baz", "qux".to_string(), None);
 
         let s = bincode::serialize(&db).unwrap();
-        assert_eq!(s.len(), 74);
+        assert_eq!(s.len(), 82);
 
         let mut dd = bincode::deserialize::<HashMapDb>(&s).unwrap();
         assert_eq!(
             dd.get("foo", std::time::Instant::now()),
-            Some(&"bar".to_string())
+            Some(&string("bar"))
         );
         assert_eq!(
             dd.get("baz", std::time::Instant::now()),
-            Some(&"qux".to_string())
+            Some(&string("qux"))
+        );
+    }
+
+    #[test]
+    fn serialize_json() {
+        let mut db = HashMapDb::new(config::Engine::default());
+        let doc = Value::Json(serde_json::json!({"user": {"name": "ada", "age": 36.5}}));
+        db.set("doc", doc.clone(), None);
+
+        let s = bincode::serialize(&db).unwrap();
+        let mut dd = bincode::deserialize::<HashMapDb>(&s).unwrap();
+        assert_eq!(dd.get("doc", std::time::Instant::now()), Some(&doc));
+    }
+
+    #[test]
+    fn legacy_snapshot() {
+        let mut data = HashMap::new();
+        data.insert("foo", ("foo", "bar"));
+        data.insert("num", ("num", "42"));
+        let s = bincode::serialize(&data).unwrap();
+
+        let mut values = decode_snapshot(&s).unwrap();
+        values.sort_by(|a, b| a.0.cmp(&b.0));
+        assert_eq!(
+            values,
+            vec![
+                ("foo".to_string(), string("bar")),
+                ("num".to_string(), Value::Integer(42))
+            ]
         );
     }
 
     #[test]
+    #[allow(clippy::redundant_clone)]
     fn persist() {
         let file = tempfile::NamedTempFile::new().unwrap();
         let file_path = file.path().to_str().unwrap().to_string();
@@ -429,28 +880,28 @@ mod tests {
             let dd = create_db(&c).unwrap();
             assert_eq!(
                 dd.borrow_mut().get("one", std::time::Instant::now()),
-                Some(&"one".to_string())
+                Some(&string("one"))
             );
             assert_eq!(
                 dd.borrow_mut().get("two", std::time::Instant::now()),
-                Some(&"two".to_string())
+                Some(&string("two"))
             );
         }
         {
             // another 2 changes
             db.del("one");
-            db.set("three", "three".to_string(), None);
+            db.set("three", Value::Set(["a".to_string()].into()), None);
 
             let dd = create_db(&c).unwrap();
 
             assert_eq!(dd.borrow_mut().get("one", std::time::Instant::now()), None);
             assert_eq!(
                 dd.borrow_mut().get("two", std::time::Instant::now()),
-                Some(&"two".to_string())
+                Some(&string("two"))
             );
             assert_eq!(
                 dd.borrow_mut().get("three", std::time::Instant::now()),
-                Some(&"three".to_string())
+                Some(&Value::Set(["a".to_string()].into()))
             );
         }
     }
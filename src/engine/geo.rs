@@ -0,0 +1,236 @@
+/// Bits of the longitude and of the latitude in a geohash, like redis the 52 bits fit exactly in a score.
+const STEP: u32 = 26;
+const LON_MIN: f64 = -180.0;
+const LON_MAX: f64 = 180.0;
+/// Latitudes of the web mercator projection, the poles are left out.
+const LAT_MIN: f64 = -85.051_128_78;
+const LAT_MAX: f64 = 85.051_128_78;
+/// Radius of the earth used by redis for the distances.
+const EARTH_RADIUS: f64 = 6_372_797.560_856;
+
+/// Unit of the distances of the geo commands.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Unit {
+    #[default]
+    Meters,
+    Kilometers,
+    Feet,
+    Miles,
+}
+
+/// Area of GEOSEARCH around its center, in meters.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Shape {
+    Radius(f64),
+    Box { width: f64, height: f64 },
+}
+
+impl Unit {
+    pub fn parse(s: &str) -> Option<Self> {
+        match s.to_lowercase().as_str() {
+            "m" => Some(Self::Meters),
+            "km" => Some(Self::Kilometers),
+            "ft" => Some(Self::Feet),
+            "mi" => Some(Self::Miles),
+            _ => None,
+        }
+    }
+
+    /// Number of meters in the unit.
+    pub const fn meters(self) -> f64 {
+        match self {
+            Self::Meters => 1.0,
+            Self::Kilometers => 1000.0,
+            Self::Feet => 0.3048,
+            Self::Miles => 1609.34,
+        }
+    }
+}
+
+impl Shape {
+    /// Distance of the point from the center, None if it is out of the shape.
+    pub fn contains(self, (lon, lat): (f64, f64), point: (f64, f64)) -> Option<f64> {
+        match self {
+            Self::Radius(r) => Some(distance((lon, lat), point)).filter(|d| *d <= r),
+            Self::Box { width, height } => {
+                // NOTE: like redis the width is measured along the latitude of the point.
+                let inside = lat_distance(lat, point.1) <= height / 2.0
+                    && distance((lon, point.1), point) <= width / 2.0;
+                inside.then(|| distance((lon, lat), point))
+            }
+        }
+    }
+
+    /// Distance from the center to the farthest point of the shape.
+    fn radius(self) -> f64 {
+        match self {
+            Self::Radius(r) => r,
+            Self::Box { width, height } => (width / 2.0).hypot(height / 2.0),
+        }
+    }
+}
+
+/// Returns if the position can be indexed, the poles are out of the web mercator projection.
+pub fn is_valid((lon, lat): (f64, f64)) -> bool {
+    (LON_MIN..=LON_MAX).contains(&lon) && (LAT_MIN..=LAT_MAX).contains(&lat)
+}
+
+/// Geohash of the position on 52 bits, the bits of the latitude and of the longitude are interleaved.
+pub fn encode((lon, lat): (f64, f64)) -> u64 {
+    interleave(
+        cell(lat, LAT_MIN, LAT_MAX, STEP),
+        cell(lon, LON_MIN, LON_MAX, STEP),
+    )
+}
+
+/// Position at the center of the area of the geohash.
+#[allow(clippy::cast_precision_loss)]
+pub fn decode(hash: u64) -> (f64, f64) {
+    let (lat, lon) = deinterleave(hash);
+    let cells = (1_u64 << STEP) as f64;
+    let center = |i: u64, min: f64, max: f64| {
+        let bottom = (i as f64 / cells).mul_add(max - min, min);
+        let top = ((i + 1) as f64 / cells).mul_add(max - min, min);
+        ((bottom + top) / 2.0).clamp(min, max)
+    };
+
+    (center(lon, LON_MIN, LON_MAX), center(lat, LAT_MIN, LAT_MAX))
+}
+
+/// Great-circle distance in meters between the positions with the haversine formula.
+pub fn distance((lon1, lat1): (f64, f64), (lon2, lat2): (f64, f64)) -> f64 {
+    let (lat1, lat2) = (lat1.to_radians(), lat2.to_radians());
+    let u = ((lat2 - lat1) / 2.0).sin();
+    let v = ((lon2.to_radians() - lon1.to_radians()) / 2.0).sin();
+    2.0 * EARTH_RADIUS * lat1.cos().mul_add(lat2.cos() * v * v, u * u).sqrt().asin()
+}
+
+fn lat_distance(lat1: f64, lat2: f64) -> f64 {
+    EARTH_RADIUS * (lat2.to_radians() - lat1.to_radians()).abs()
+}
+
+/// Ranges of geohashes, start included and end excluded, which cover the shape around the center: the cell of the
+/// center and its 8 neighbours at the precision where a cell is bigger than the shape.
+#[allow(clippy::cast_precision_loss)]
+pub fn ranges((lon, lat): (f64, f64), shape: Shape) -> Vec<(u64, u64)> {
+    let everything = vec![(0, 1 << (2 * STEP))];
+    let r_lat = (shape.radius() / EARTH_RADIUS).to_degrees();
+    let farthest = lat.abs() + r_lat;
+    if farthest >= 90.0 {
+        return everything;
+    }
+    let r_lon = r_lat / farthest.to_radians().cos();
+
+    let size = |step: u32, min: f64, max: f64| (max - min) / (1_u64 << step) as f64;
+    let Some(step) = (1..=STEP)
+        .rev()
+        .find(|s| size(*s, LAT_MIN, LAT_MAX) >= r_lat && size(*s, LON_MIN, LON_MAX) >= r_lon)
+    else {
+        return everything;
+    };
+
+    let cells = 1_u64 << step;
+    let (lat_cell, lon_cell) = (
+        cell(lat, LAT_MIN, LAT_MAX, step),
+        cell(lon, LON_MIN, LON_MAX, step),
+    );
+    let lat_cells = [
+        lat_cell.checked_sub(1),
+        Some(lat_cell),
+        Some(lat_cell + 1).filter(|c| *c < cells),
+    ];
+    // NOTE: the longitudes wrap around the antimeridian.
+    let lon_cells = [
+        (lon_cell + cells - 1) % cells,
+        lon_cell,
+        (lon_cell + 1) % cells,
+    ];
+
+    let shift = 2 * (STEP - step);
+    let mut ranges = vec![];
+    for la in lat_cells.into_iter().flatten() {
+        for lo in lon_cells {
+            let hash = interleave(la, lo) << shift;
+            ranges.push((hash, hash + (1 << shift)));
+        }
+    }
+    ranges.sort_unstable();
+    ranges.dedup();
+
+    ranges
+}
+
+/// Index of the cell of the value among the `2^step` cells between min and max.
+#[allow(
+    clippy::cast_precision_loss,
+    clippy::cast_possible_truncation,
+    clippy::cast_sign_loss
+)]
+fn cell(value: f64, min: f64, max: f64, step: u32) -> u64 {
+    let cells = (1_u64 << step) as f64;
+    (((value - min) / (max - min)) * cells).clamp(0.0, cells - 1.0) as u64
+}
+
+/// Bits of x on the even positions and of y on the odd positions.
+fn interleave(x: u64, y: u64) -> u64 {
+    (0..STEP).fold(0, |hash, i| {
+        hash | ((x >> i) & 1) << (2 * i) | ((y >> i) & 1) << (2 * i + 1)
+    })
+}
+
+fn deinterleave(hash: u64) -> (u64, u64) {
+    (0..STEP).fold((0, 0), |(x, y), i| {
+        (
+            x | ((hash >> (2 * i)) & 1) << i,
+            y | ((hash >> (2 * i + 1)) & 1) << i,
+        )
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PALERMO: (f64, f64) = (13.361_389, 38.115_556);
+    const CATANIA: (f64, f64) = (15.087_269, 37.502_669);
+
+    #[test]
+    fn geohash() {
+        // NOTE: the scores of redis for the members of the GEOADD example.
+        assert_eq!(encode(PALERMO), 3_479_099_956_230_698);
+        assert_eq!(encode(CATANIA), 3_479_447_370_796_909);
+
+        let (lon, lat) = decode(encode(PALERMO));
+        assert!((lon - PALERMO.0).abs() < 1e-5 && (lat - PALERMO.1).abs() < 1e-5);
+        assert!(is_valid((180.0, -85.0)));
+        assert!(!is_valid((0.0, 86.0)));
+    }
+
+    #[test]
+    fn distances() {
+        let d = distance(PALERMO, CATANIA);
+        assert!((d - 166_274.151_6).abs() < 1.0, "{d}");
+
+        let center = (15.0, 37.0);
+        assert!(Shape::Radius(100_000.0).contains(center, PALERMO).is_none());
+        assert!(Shape::Radius(200_000.0).contains(center, PALERMO).is_some());
+        let rect = Shape::Box {
+            width: 400_000.0,
+            height: 400_000.0,
+        };
+        assert!(rect.contains(center, PALERMO).is_some());
+        assert!(rect.contains(center, CATANIA).is_some());
+
+        for (point, shape) in [
+            (PALERMO, Shape::Radius(3000.0)),
+            (CATANIA, rect),
+            ((179.999, 0.0), Shape::Radius(1000.0)),
+        ] {
+            let hash = encode(point);
+            let ranges = ranges(point, shape);
+            assert!(ranges.iter().any(|(s, e)| (*s..*e).contains(&hash)));
+            assert!(ranges.len() <= 9);
+        }
+        assert_eq!(ranges((0.0, 85.0), Shape::Radius(1_000_000.0)).len(), 1);
+    }
+}
@@ -0,0 +1,99 @@
+/// Returns if the string matches the glob-style pattern used by redis for keys: `*` matches any sequence, `?` any
+/// character, `[abc]`, `[^abc]` and `[a-z]` a set of characters and `\` escapes the next character.
+pub fn matches(pattern: &str, s: &str) -> bool {
+    let (p, s) = (pattern.as_bytes(), s.as_bytes());
+    let (mut pi, mut si) = (0, 0);
+
+    // NOTE: position of the last star and of the string when it was found, on mismatch the star eats one more byte.
+    let mut star: Option<(usize, usize)> = None;
+
+    while si < s.len() {
+        let next = match p.get(pi) {
+            Some(b'*') => {
+                star = Some((pi, si));
+                pi += 1;
+                continue;
+            }
+            Some(b'?') => Some(pi + 1),
+            Some(b'[') => match class(p, pi, s[si]) {
+                Some((matched, end)) => matched.then_some(end),
+                None => (s[si] == b'[').then_some(pi + 1),
+            },
+            Some(b'\\') if pi + 1 < p.len() => (p[pi + 1] == s[si]).then_some(pi + 2),
+            Some(&c) => (c == s[si]).then_some(pi + 1),
+            None => None,
+        };
+
+        match (next, star) {
+            (Some(next), _) => {
+                pi = next;
+                si += 1;
+            }
+            (None, Some((pattern_at, string_at))) => {
+                pi = pattern_at + 1;
+                si = string_at + 1;
+                star = Some((pattern_at, si));
+            }
+            (None, None) => return false,
+        }
+    }
+
+    p[pi..].iter().all(|&c| c == b'*')
+}
+
+/// Match the character against the set starting at `start`, returns the result and the position after the set or
+/// None if the set is not closed.
+fn class(p: &[u8], start: usize, c: u8) -> Option<(bool, usize)> {
+    let mut i = start + 1;
+    let negate = p.get(i) == Some(&b'^');
+    if negate {
+        i += 1;
+    }
+
+    let mut matched = false;
+    while i < p.len() && p[i] != b']' {
+        if p[i] == b'\\' && i + 1 < p.len() {
+            i += 1;
+            matched |= p[i] == c;
+        } else if i + 2 < p.len() && p[i + 1] == b'-' && p[i + 2] != b']' {
+            let (lo, hi) = if p[i] <= p[i + 2] {
+                (p[i], p[i + 2])
+            } else {
+                (p[i + 2], p[i])
+            };
+            matched |= (lo..=hi).contains(&c);
+            i += 2;
+        } else {
+            matched |= p[i] == c;
+        }
+        i += 1;
+    }
+
+    (i < p.len()).then_some((matched != negate, i + 1))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn glob() {
+        assert!(matches("*", ""));
+        assert!(matches("*", "anything"));
+        assert!(matches("session:*", "session:42"));
+        assert!(!matches("session:*", "user:42"));
+        assert!(matches("h?llo", "hello"));
+        assert!(!matches("h?llo", "hllo"));
+        assert!(matches("h*llo*", "heeeello world"));
+        assert!(matches("h[ae]llo", "hallo"));
+        assert!(!matches("h[ae]llo", "hillo"));
+        assert!(matches("h[^e]llo", "hallo"));
+        assert!(!matches("h[^e]llo", "hello"));
+        assert!(matches("h[a-c]llo", "hbllo"));
+        assert!(!matches("h[a-c]llo", "hdllo"));
+        assert!(matches("a\\*b", "a*b"));
+        assert!(!matches("a\\*b", "axb"));
+        assert!(matches("a[b", "a[b"));
+        assert!(!matches("a*b", "acbd"));
+    }
+}
@@ -0,0 +1,383 @@
+/// Number of bits of the hash selecting the register, the standard error is 1.04 / sqrt(2^P) = 0.81%.
+const P: u32 = 14;
+const REGISTERS: usize = 1 << P;
+/// Number of bits of the hash used to count the zeros.
+const Q: u32 = 64 - P;
+const HEADER_SIZE: usize = 16;
+const DENSE_SIZE: usize = HEADER_SIZE + (REGISTERS * 6 + 7) / 8;
+/// Like redis, a sparse `HyperLogLog` bigger than this, header included, is converted to the dense encoding.
+const SPARSE_MAX_BYTES: usize = 3000;
+/// Highest value of a register in the sparse encoding.
+const SPARSE_MAX_VALUE: u8 = 32;
+
+const MAGIC: &[u8; 4] = b"HYLL";
+const DENSE: u8 = 0;
+const SPARSE: u8 = 1;
+
+/// Error of the commands run against a string which is not a `HyperLogLog`.
+#[derive(thiserror::Error, Debug, PartialEq, Eq)]
+#[error("WRONGTYPE Key is not a valid HyperLogLog string value.")]
+pub struct InvalidHyperLogLog;
+
+/// Cardinality estimation with 16384 registers of 6 bits. It is stored in a string using the format of redis, so
+/// that `HyperLogLogs` can be migrated from and to redis:
+///
+/// - a header with the magic `HYLL`, the encoding, 3 unused bytes and the cached cardinality on 8 bytes in little
+///   endian, the most significant bit being set when the cache is not valid.
+/// - the registers, either dense with 6 bits per register from the least significant bit, or sparse with runs of
+///   registers: `00xxxxxx` for up to 64 zeros, `01xxxxxx yyyyyyyy` for up to 16384 zeros and `1vvvvvxx` for up to
+///   4 registers of value up to 32.
+///
+/// The registers are decoded in memory to be updated, and encoded back in the most compact encoding allowed.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct HyperLogLog {
+    registers: Vec<u8>,
+    dense: bool,
+    cached: Option<u64>,
+}
+
+impl Default for HyperLogLog {
+    fn default() -> Self {
+        Self {
+            registers: vec![0; REGISTERS],
+            dense: false,
+            cached: Some(0),
+        }
+    }
+}
+
+impl HyperLogLog {
+    pub fn decode(bytes: &[u8]) -> Result<Self, InvalidHyperLogLog> {
+        if bytes.len() < HEADER_SIZE || &bytes[..4] != MAGIC {
+            return Err(InvalidHyperLogLog);
+        }
+
+        let mut card = [0; 8];
+        card.copy_from_slice(&bytes[8..HEADER_SIZE]);
+        let cached = (card[7] & 0x80 == 0).then(|| u64::from_le_bytes(card));
+        let registers = match bytes[4] {
+            DENSE if bytes.len() == DENSE_SIZE => (0..REGISTERS)
+                .map(|i| dense_register(&bytes[HEADER_SIZE..], i))
+                .collect(),
+            SPARSE => sparse_registers(&bytes[HEADER_SIZE..]).ok_or(InvalidHyperLogLog)?,
+            _ => return Err(InvalidHyperLogLog),
+        };
+
+        Ok(Self {
+            registers,
+            dense: bytes[4] == DENSE,
+            cached,
+        })
+    }
+
+    /// Bytes of the `HyperLogLog`, sparse unless it does not fit or it has already been converted to dense.
+    pub fn encode(&mut self) -> Vec<u8> {
+        let sparse = if self.dense {
+            None
+        } else {
+            sparse_bytes(&self.registers).filter(|s| HEADER_SIZE + s.len() <= SPARSE_MAX_BYTES)
+        };
+        self.dense = sparse.is_none();
+
+        let mut bytes = Vec::with_capacity(DENSE_SIZE);
+        bytes.extend_from_slice(MAGIC);
+        bytes.extend_from_slice(&[if self.dense { DENSE } else { SPARSE }, 0, 0, 0]);
+        let mut card = self.cached.unwrap_or(0).to_le_bytes();
+        if self.cached.is_none() {
+            card[7] |= 0x80;
+        }
+        bytes.extend_from_slice(&card);
+
+        if let Some(s) = sparse {
+            bytes.extend_from_slice(&s);
+        } else {
+            bytes.resize(DENSE_SIZE, 0);
+            for (i, v) in self.registers.iter().enumerate() {
+                set_dense_register(&mut bytes[HEADER_SIZE..], i, *v);
+            }
+        }
+
+        bytes
+    }
+
+    /// Add the element, returns if a register changed and the estimation may have changed.
+    pub fn add(&mut self, element: &[u8]) -> bool {
+        let hash = murmur_hash64a(element, 0xadc8_3b19);
+        #[allow(clippy::cast_possible_truncation)]
+        let index = (hash & (REGISTERS as u64 - 1)) as usize;
+        // NOTE: the bit after the Q bits of the hash stops the count of the zeros.
+        let zeros = ((hash >> P) | (1 << Q)).trailing_zeros();
+        #[allow(clippy::cast_possible_truncation)]
+        let count = zeros as u8 + 1;
+
+        if self.registers[index] >= count {
+            return false;
+        }
+        self.registers[index] = count;
+        self.cached = None;
+
+        true
+    }
+
+    /// Union of the `HyperLogLogs`: the highest value of every register.
+    pub fn merge(&mut self, other: &Self) {
+        for (r, o) in self.registers.iter_mut().zip(&other.registers) {
+            if *o > *r {
+                *r = *o;
+                self.cached = None;
+            }
+        }
+        self.dense |= other.dense;
+    }
+
+    /// Estimated cardinality, cached until the next change. It uses the estimator of Otmar Ertl like redis.
+    #[allow(
+        clippy::cast_precision_loss,
+        clippy::cast_possible_truncation,
+        clippy::cast_sign_loss
+    )]
+    pub fn count(&mut self) -> u64 {
+        if let Some(card) = self.cached {
+            return card;
+        }
+
+        let m = REGISTERS as f64;
+        let mut histogram = [0_u32; 64];
+        for r in &self.registers {
+            histogram[usize::from(*r)] += 1;
+        }
+
+        let q = Q as usize;
+        let mut z = m * tau((m - f64::from(histogram[q + 1])) / m);
+        for h in histogram[1..=q].iter().rev() {
+            z += f64::from(*h);
+            z *= 0.5;
+        }
+        z += m * sigma(f64::from(histogram[0]) / m);
+
+        let card = (0.721_347_520_444_481_7 * m * m / z).round() as u64;
+        self.cached = Some(card);
+        card
+    }
+
+    /// Returns if the cardinality is cached, PFCOUNT stores it back with the `HyperLogLog` once computed.
+    pub const fn is_cache_valid(&self) -> bool {
+        self.cached.is_some()
+    }
+}
+
+#[allow(clippy::float_cmp)]
+fn sigma(mut x: f64) -> f64 {
+    if x == 1.0 {
+        return f64::INFINITY;
+    }
+
+    let mut y = 1.0;
+    let mut z = x;
+    loop {
+        x *= x;
+        let previous = z;
+        z += x * y;
+        y += y;
+        if previous == z {
+            return z;
+        }
+    }
+}
+
+#[allow(clippy::float_cmp)]
+fn tau(mut x: f64) -> f64 {
+    if x == 0.0 || x == 1.0 {
+        return 0.0;
+    }
+
+    let mut y = 1.0;
+    let mut z = 1.0 - x;
+    loop {
+        x = x.sqrt();
+        let previous = z;
+        y *= 0.5;
+        z -= (1.0 - x).powi(2) * y;
+        if previous == z {
+            return z / 3.0;
+        }
+    }
+}
+
+const fn dense_register(registers: &[u8], i: usize) -> u8 {
+    let (byte, bit) = (i * 6 / 8, i * 6 % 8);
+    let mut value = registers[byte] >> bit;
+    if bit > 2 {
+        value |= registers[byte + 1] << (8 - bit);
+    }
+    value & 0x3f
+}
+
+fn set_dense_register(registers: &mut [u8], i: usize, value: u8) {
+    let (byte, bit) = (i * 6 / 8, i * 6 % 8);
+    registers[byte] &= !(0x3f << bit);
+    registers[byte] |= value << bit;
+    if bit > 2 {
+        registers[byte + 1] &= !(0x3f >> (8 - bit));
+        registers[byte + 1] |= value >> (8 - bit);
+    }
+}
+
+/// Registers of the sparse encoding, None if the runs do not cover exactly every register.
+fn sparse_registers(bytes: &[u8]) -> Option<Vec<u8>> {
+    let mut registers = Vec::with_capacity(REGISTERS);
+    let mut bytes = bytes.iter();
+    while let Some(b) = bytes.next() {
+        let (value, run) = match b >> 6 {
+            0 => (0, usize::from(b & 0x3f) + 1),
+            1 => (
+                0,
+                (usize::from(b & 0x3f) << 8 | usize::from(*bytes.next()?)) + 1,
+            ),
+            _ => (((b >> 2) & 0x1f) + 1, usize::from(b & 0x03) + 1),
+        };
+        if registers.len() + run > REGISTERS {
+            return None;
+        }
+        registers.resize(registers.len() + run, value);
+    }
+
+    (registers.len() == REGISTERS).then_some(registers)
+}
+
+/// Sparse encoding of the registers, None if a register is too high for it.
+#[allow(clippy::cast_possible_truncation)]
+fn sparse_bytes(registers: &[u8]) -> Option<Vec<u8>> {
+    let mut bytes = vec![];
+    let mut i = 0;
+    while i < registers.len() {
+        let value = registers[i];
+        let mut run = registers[i..].iter().take_while(|r| **r == value).count();
+        i += run;
+
+        if value > SPARSE_MAX_VALUE {
+            return None;
+        }
+        while run > 0 {
+            let n = if value == 0 {
+                run.min(1 << 14)
+            } else {
+                run.min(4)
+            };
+            match value {
+                0 if n <= 64 => bytes.push((n - 1) as u8),
+                0 => bytes.extend_from_slice(&[0x40 | ((n - 1) >> 8) as u8, (n - 1) as u8]),
+                v => bytes.push(0x80 | ((v - 1) << 2) | (n - 1) as u8),
+            }
+            run -= n;
+        }
+    }
+
+    Some(bytes)
+}
+
+/// 64 bits version of the `MurmurHash2` of Austin Appleby, the hash of the elements in redis.
+fn murmur_hash64a(key: &[u8], seed: u64) -> u64 {
+    const M: u64 = 0xc6a4_a793_5bd1_e995;
+    const R: u32 = 47;
+
+    let mut h = seed ^ (key.len() as u64).wrapping_mul(M);
+    let mut chunks = key.chunks_exact(8);
+    for chunk in chunks.by_ref() {
+        let mut k = u64::from_le_bytes(chunk.try_into().unwrap_or_default());
+        k = k.wrapping_mul(M);
+        k ^= k >> R;
+        k = k.wrapping_mul(M);
+        h ^= k;
+        h = h.wrapping_mul(M);
+    }
+
+    let tail = chunks.remainder();
+    if !tail.is_empty() {
+        for (i, b) in tail.iter().enumerate() {
+            h ^= u64::from(*b) << (8 * i);
+        }
+        h = h.wrapping_mul(M);
+    }
+
+    h ^= h >> R;
+    h = h.wrapping_mul(M);
+    h ^= h >> R;
+    h
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn encodings() {
+        let mut hll = HyperLogLog::default();
+        let empty = hll.encode();
+        assert_eq!(
+            empty,
+            [b'H', b'Y', b'L', b'L', 1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0x7f, 0xff]
+        );
+
+        assert!(hll.add(b"a"));
+        assert!(!hll.add(b"a"));
+        let bytes = hll.encode();
+        assert_eq!(bytes[15], 0x80);
+        assert_eq!(HyperLogLog::decode(&bytes), Ok(hll.clone()));
+        assert_eq!(hll.count(), 1);
+
+        // NOTE: a register too high for the sparse encoding converts to dense for good.
+        hll.registers[REGISTERS - 1] = 40;
+        let bytes = hll.encode();
+        assert_eq!((bytes[4], bytes.len()), (DENSE, DENSE_SIZE));
+        let mut decoded = HyperLogLog::decode(&bytes).unwrap();
+        assert_eq!(decoded, hll);
+        decoded.registers[REGISTERS - 1] = 0;
+        assert_eq!(decoded.encode()[4], DENSE);
+
+        // NOTE: the header counts towards the size limit of the sparse encoding.
+        let mut hll = HyperLogLog::default();
+        let mut i = 1;
+        while sparse_bytes(&hll.registers).unwrap().len() <= SPARSE_MAX_BYTES - HEADER_SIZE {
+            hll.registers[i] = 1;
+            i += 2;
+        }
+        assert!(sparse_bytes(&hll.registers).unwrap().len() <= SPARSE_MAX_BYTES);
+        assert_eq!(hll.encode()[4], DENSE);
+        hll.registers[i - 2] = 0;
+        hll.dense = false;
+        let bytes = hll.encode();
+        assert_eq!(bytes[4], SPARSE);
+        assert!(bytes.len() <= SPARSE_MAX_BYTES);
+
+        assert_eq!(HyperLogLog::decode(b"foo"), Err(InvalidHyperLogLog));
+        let mut truncated = empty;
+        truncated.pop();
+        assert_eq!(HyperLogLog::decode(&truncated), Err(InvalidHyperLogLog));
+    }
+
+    #[test]
+    #[allow(clippy::cast_precision_loss)]
+    fn estimation() {
+        assert_eq!(murmur_hash64a(b"", 0), 0);
+        assert_ne!(murmur_hash64a(b"hello", 0), murmur_hash64a(b"hellp", 0));
+
+        for n in [10_u64, 1000, 100_000] {
+            let mut hll = HyperLogLog::default();
+            for i in 0..n {
+                hll.add(format!("element:{i}").as_bytes());
+            }
+            let error = (hll.count() as f64 - n as f64).abs() / n as f64;
+            assert!(error < 0.03, "{n} elements estimated as {}", hll.count());
+        }
+
+        let (mut a, mut b) = (HyperLogLog::default(), HyperLogLog::default());
+        for i in 0..1000 {
+            a.add(format!("{i}").as_bytes());
+            b.add(format!("{}", i + 500).as_bytes());
+        }
+        a.merge(&b);
+        let error = (a.count() as f64 - 1500.0).abs() / 1500.0;
+        assert!(error < 0.03, "union estimated as {}", a.count());
+    }
+}
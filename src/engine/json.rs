@@ -0,0 +1,384 @@
+use serde_json::Value;
+
+/// Error of the paths of the JSON commands.
+#[derive(thiserror::Error, Debug, PartialEq, Eq)]
+#[allow(clippy::module_name_repetitions)]
+pub enum JsonError {
+    #[error("ERR invalid path '{0}'")]
+    InvalidPath(String),
+}
+
+/// Path of the JSON commands. Like `RedisJSON`, a path starting with `$` is a `JSONPath` matching any number of values
+/// while the legacy paths, like `.user.name`, match a single value.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Path {
+    text: String,
+    segments: Vec<Segment>,
+    legacy: bool,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct Segment {
+    selector: Selector,
+
+    /// The selector applies to every descendant, like in `$..name`.
+    descendants: bool,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum Selector {
+    Key(String),
+    /// Index in an array, negative indexes count from the end.
+    Index(i64),
+    Wildcard,
+}
+
+/// Step from a value to one of its children, a location in a document is the list of the steps from its root.
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Step {
+    Key(String),
+    Index(usize),
+}
+
+impl Path {
+    #[allow(clippy::option_if_let_else)]
+    pub fn parse(text: &str) -> Result<Self, JsonError> {
+        let invalid = || JsonError::InvalidPath(text.to_string());
+        let (legacy, mut rest) = match text.strip_prefix('$') {
+            Some(rest) => (false, rest),
+            None if text == "." => (true, ""),
+            None => (true, text),
+        };
+
+        let mut segments = vec![];
+        while !rest.is_empty() {
+            // NOTE: the legacy paths may start with a name without its dot, like `user.name`.
+            let (descendants, selected) = if let Some(r) = rest.strip_prefix("..") {
+                (true, r.strip_prefix('[').map_or_else(|| name(r), bracket))
+            } else if let Some(r) = rest.strip_prefix('[') {
+                (false, bracket(r))
+            } else if let Some(r) = rest.strip_prefix('.') {
+                (false, name(r))
+            } else if legacy && segments.is_empty() {
+                (false, name(rest))
+            } else {
+                (false, None)
+            };
+            let (selector, tail) = selected.ok_or_else(invalid)?;
+            segments.push(Segment {
+                selector,
+                descendants,
+            });
+            rest = tail;
+        }
+
+        Ok(Self {
+            text: text.to_string(),
+            segments,
+            legacy,
+        })
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.text
+    }
+
+    pub const fn is_legacy(&self) -> bool {
+        self.legacy
+    }
+
+    pub fn is_root(&self) -> bool {
+        self.segments.is_empty()
+    }
+
+    /// Locations of the values matched in the document, in the order of the document.
+    pub fn locate(&self, doc: &Value) -> Vec<Vec<Step>> {
+        let mut matches = vec![(vec![], doc)];
+        for segment in &self.segments {
+            let mut next = vec![];
+            for (location, value) in matches {
+                if segment.descendants {
+                    for (location, value) in descendants(location, value) {
+                        select(&segment.selector, &location, value, &mut next);
+                    }
+                } else {
+                    select(&segment.selector, &location, value, &mut next);
+                }
+            }
+            matches = next;
+        }
+
+        matches.into_iter().map(|(location, _)| location).collect()
+    }
+
+    /// Path of the objects where JSON.SET creates the missing key, for the paths ending with a name.
+    pub fn parent(&self) -> Option<(Self, String)> {
+        let (last, segments) = self.segments.split_last()?;
+        match last {
+            Segment {
+                selector: Selector::Key(key),
+                descendants: false,
+            } => Some((
+                Self {
+                    text: self.text.clone(),
+                    segments: segments.to_vec(),
+                    legacy: self.legacy,
+                },
+                key.clone(),
+            )),
+            _ => None,
+        }
+    }
+}
+
+/// Selector of a dot notation, a name up to the next dot or bracket or the wildcard.
+fn name(s: &str) -> Option<(Selector, &str)> {
+    let end = s.find(['.', '[']).unwrap_or(s.len());
+    let (name, rest) = s.split_at(end);
+    match name {
+        "" => None,
+        "*" => Some((Selector::Wildcard, rest)),
+        _ => Some((Selector::Key(name.to_string()), rest)),
+    }
+}
+
+/// Selector of a bracket notation, a quoted name, an index or the wildcard.
+fn bracket(s: &str) -> Option<(Selector, &str)> {
+    if let Some(quote @ ('\'' | '"')) = s.chars().next() {
+        let end = s[1..].find(quote)? + 1;
+        let rest = s[end + 1..].strip_prefix(']')?;
+        return Some((Selector::Key(s[1..end].to_string()), rest));
+    }
+
+    let (inner, rest) = s.split_once(']')?;
+    let selector = match inner.trim() {
+        "*" => Selector::Wildcard,
+        i => Selector::Index(i.parse().ok()?),
+    };
+    Some((selector, rest))
+}
+
+/// The value and all the values it contains, depth first.
+fn descendants(location: Vec<Step>, value: &Value) -> Vec<(Vec<Step>, &Value)> {
+    let mut all = vec![];
+    let mut stack = vec![(location, value)];
+    while let Some((location, value)) = stack.pop() {
+        let mut children = vec![];
+        select(&Selector::Wildcard, &location, value, &mut children);
+        stack.extend(children.into_iter().rev());
+        all.push((location, value));
+    }
+    all
+}
+
+fn select<'a>(
+    selector: &Selector,
+    location: &[Step],
+    value: &'a Value,
+    matches: &mut Vec<(Vec<Step>, &'a Value)>,
+) {
+    let child = |step: Step| {
+        let mut location = location.to_vec();
+        location.push(step);
+        location
+    };
+    match (selector, value) {
+        (Selector::Key(key), Value::Object(o)) => {
+            if let Some(v) = o.get(key) {
+                matches.push((child(Step::Key(key.clone())), v));
+            }
+        }
+        (Selector::Index(i), Value::Array(a)) => {
+            let index = if *i < 0 {
+                a.len()
+                    .checked_sub(usize::try_from(i.unsigned_abs()).unwrap_or(usize::MAX))
+            } else {
+                usize::try_from(*i).ok()
+            };
+            if let Some((index, v)) = index.and_then(|i| a.get(i).map(|v| (i, v))) {
+                matches.push((child(Step::Index(index)), v));
+            }
+        }
+        (Selector::Wildcard, Value::Object(o)) => {
+            matches.extend(o.iter().map(|(k, v)| (child(Step::Key(k.clone())), v)));
+        }
+        (Selector::Wildcard, Value::Array(a)) => {
+            matches.extend(
+                a.iter()
+                    .enumerate()
+                    .map(|(i, v)| (child(Step::Index(i)), v)),
+            );
+        }
+        _ => {}
+    }
+}
+
+pub fn get<'a>(doc: &'a Value, location: &[Step]) -> Option<&'a Value> {
+    location
+        .iter()
+        .try_fold(doc, |value, step| match (step, value) {
+            (Step::Key(k), Value::Object(o)) => o.get(k),
+            (Step::Index(i), Value::Array(a)) => a.get(*i),
+            _ => None,
+        })
+}
+
+pub fn get_mut<'a>(doc: &'a mut Value, location: &[Step]) -> Option<&'a mut Value> {
+    location
+        .iter()
+        .try_fold(doc, |value, step| match (step, value) {
+            (Step::Key(k), Value::Object(o)) => o.get_mut(k),
+            (Step::Index(i), Value::Array(a)) => a.get_mut(*i),
+            _ => None,
+        })
+}
+
+/// Remove the values at the locations, the ones inside another removed value are skipped. Returns the number of
+/// removed values.
+pub fn remove(doc: &mut Value, mut locations: Vec<Vec<Step>>) -> usize {
+    locations.sort();
+    locations.dedup();
+    let mut outermost: Vec<Vec<Step>> = vec![];
+    for location in locations {
+        if !outermost.iter().any(|o| location.starts_with(o)) {
+            outermost.push(location);
+        }
+    }
+
+    // NOTE: the last locations are removed first so that the indexes of the others stay valid.
+    outermost
+        .iter()
+        .rev()
+        .filter(|location| {
+            let Some((step, parent)) = location.split_last() else {
+                return false;
+            };
+            match (step, get_mut(doc, parent)) {
+                (Step::Key(k), Some(Value::Object(o))) => o.remove(k).is_some(),
+                (Step::Index(i), Some(Value::Array(a))) if *i < a.len() => {
+                    a.remove(*i);
+                    true
+                }
+                _ => false,
+            }
+        })
+        .count()
+}
+
+/// Name of the type of the value as returned by JSON.TYPE.
+pub fn type_name(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "boolean",
+        Value::Number(n) if n.is_f64() => "number",
+        Value::Number(_) => "integer",
+        Value::String(_) => "string",
+        Value::Array(_) => "array",
+        Value::Object(_) => "object",
+    }
+}
+
+/// Sum of the numbers, integers stay integers unless they overflow. None if the result is not a finite number.
+pub fn add(a: &serde_json::Number, b: &serde_json::Number) -> Option<serde_json::Number> {
+    if let (Some(a), Some(b)) = (a.as_i64(), b.as_i64()) {
+        if let Some(sum) = a.checked_add(b) {
+            return Some(sum.into());
+        }
+    }
+    serde_json::Number::from_f64(a.as_f64()? + b.as_f64()?)
+}
+
+/// Serialization of the documents as JSON text, the snapshots can't store a `serde_json::Value` as is since bincode
+/// doesn't support self-describing formats.
+pub mod text {
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(value: &serde_json::Value, s: S) -> Result<S::Ok, S::Error> {
+        s.serialize_str(&value.to_string())
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(d: D) -> Result<serde_json::Value, D::Error> {
+        let text = String::deserialize(d)?;
+        serde_json::from_str(&text).map_err(serde::de::Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn doc() -> Value {
+        json!({
+            "user": {"name": "ada", "tags": ["a", "b", "c"]},
+            "admin": {"name": "bob", "age": 36},
+        })
+    }
+
+    fn values<'a>(doc: &'a Value, path: &str) -> Vec<&'a Value> {
+        let path = Path::parse(path).unwrap();
+        path.locate(doc)
+            .iter()
+            .map(|l| get(doc, l).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn parse() {
+        assert!(Path::parse("$").unwrap().is_root());
+        assert!(Path::parse(".").unwrap().is_root());
+        assert!(Path::parse(".").unwrap().is_legacy());
+        assert!(!Path::parse("$.user").unwrap().is_legacy());
+        for invalid in ["$user", "$.", "$[", "$['user]", "$[x]", ".."] {
+            assert_eq!(
+                Path::parse(invalid),
+                Err(JsonError::InvalidPath(invalid.to_string())),
+                "{invalid}"
+            );
+        }
+        assert_eq!(
+            Path::parse("user.name").unwrap().segments,
+            Path::parse("$['user'].name").unwrap().segments
+        );
+    }
+
+    #[test]
+    fn locate() {
+        let doc = doc();
+        assert_eq!(values(&doc, "$.user.name"), [&json!("ada")]);
+        assert_eq!(values(&doc, ".user.tags[-1]"), [&json!("c")]);
+        assert_eq!(values(&doc, "$.user.tags[*]").len(), 3);
+        assert_eq!(values(&doc, "$..name"), [&json!("bob"), &json!("ada")]);
+        assert_eq!(values(&doc, "$.*.age"), [&json!(36)]);
+        assert!(values(&doc, "$.user.tags[3]").is_empty());
+        assert!(values(&doc, "$.missing.name").is_empty());
+    }
+
+    #[test]
+    fn remove_nested() {
+        let mut doc = doc();
+        let path = Path::parse("$..tags[*]").unwrap();
+        let mut locations = path.locate(&doc);
+        locations.extend(Path::parse("$.user").unwrap().locate(&doc));
+        assert_eq!(remove(&mut doc, locations), 1);
+        assert_eq!(doc, json!({"admin": {"name": "bob", "age": 36}}));
+
+        let mut doc = json!([1, 2, 3, 4]);
+        let locations = Path::parse("$[*]").unwrap().locate(&doc);
+        assert_eq!(remove(&mut doc, locations[1..3].to_vec()), 2);
+        assert_eq!(doc, json!([1, 4]));
+    }
+
+    #[test]
+    fn numbers() {
+        let n = |v: Value| v.as_number().unwrap().clone();
+        assert_eq!(add(&n(json!(1)), &n(json!(2))), Some(n(json!(3))));
+        assert_eq!(add(&n(json!(1)), &n(json!(0.5))), Some(n(json!(1.5))));
+        assert_eq!(
+            add(&n(json!(i64::MAX)), &n(json!(1))),
+            Some(n(json!(9_223_372_036_854_775_808.0)))
+        );
+        assert_eq!(type_name(&json!(1.0)), "number");
+        assert_eq!(type_name(&json!(1)), "integer");
+    }
+}
@@ -1 +1,11 @@
+pub mod bitmap;
 pub mod db;
+pub mod geo;
+pub mod glob;
+pub mod hyperloglog;
+pub mod json;
+pub mod scan;
+pub mod skiplist;
+pub mod stream;
+pub mod value;
+pub mod zset;
@@ -0,0 +1,78 @@
+/// Page of the items starting at the cursor, with the cursor of the next page, 0 when the scan is complete.
+///
+/// Items are visited in the order of a hash of their name which is also the cursor, so that like redis an item
+/// present for the whole scan is returned at least once even if the collection changes between the calls. Items
+/// sharing the same hash are never split between two pages.
+pub fn scan<'a>(
+    items: impl Iterator<Item = &'a str>,
+    cursor: u64,
+    count: usize,
+    pattern: Option<&str>,
+) -> (u64, Vec<&'a str>) {
+    let mut page = items
+        .map(|i| (hash(i), i))
+        .filter(|(h, _)| *h >= cursor)
+        .collect::<Vec<(u64, &str)>>();
+    page.sort_unstable();
+
+    let next = page
+        .get(count..)
+        .and_then(|rest| rest.iter().find(|(h, _)| *h != page[count - 1].0))
+        .map_or(0, |(h, _)| *h);
+    let page = page
+        .into_iter()
+        .take_while(|(h, _)| next == 0 || *h < next)
+        .map(|(_, i)| i)
+        .filter(|i| pattern.map_or(true, |p| super::glob::matches(p, i)))
+        .collect();
+
+    (next, page)
+}
+
+/// FNV-1a, it is stable across runs so that cursors don't depend on the process. Zero is the start of the scan and
+/// never the hash of an item.
+fn hash(s: &str) -> u64 {
+    let h = s.bytes().fold(0xcbf2_9ce4_8422_2325_u64, |h, b| {
+        (h ^ u64::from(b)).wrapping_mul(0x0100_0000_01b3)
+    });
+
+    h.max(1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pages() {
+        let items = (0..25)
+            .map(|i| format!("field:{i}"))
+            .collect::<Vec<String>>();
+
+        let mut seen = vec![];
+        let mut cursor = 0;
+        loop {
+            let (next, page) = scan(items.iter().map(String::as_str), cursor, 10, None);
+            assert!(page.len() <= 10);
+            seen.extend(page);
+            if next == 0 {
+                break;
+            }
+            cursor = next;
+        }
+
+        seen.sort_unstable();
+        let mut expected = items.iter().map(String::as_str).collect::<Vec<&str>>();
+        expected.sort_unstable();
+        assert_eq!(seen, expected);
+    }
+
+    #[test]
+    fn pattern() {
+        let items = ["name", "age", "nick"];
+        let (next, page) = scan(items.into_iter(), 0, 10, Some("n*"));
+        assert_eq!(next, 0);
+        assert_eq!(page.len(), 2);
+        assert!(page.iter().all(|i| i.starts_with('n')));
+    }
+}
@@ -0,0 +1,368 @@
+/// Highest number of levels of a node, enough for 4^32 keys.
+const MAX_LEVEL: usize = 32;
+/// Index of the head node, it has every level and no key.
+const HEAD: usize = 0;
+
+/// Link of a node to the next one on a level, the span is the number of nodes it skips plus one.
+#[derive(Clone, Copy, Debug, Default)]
+struct Level {
+    forward: Option<usize>,
+    span: usize,
+}
+
+#[derive(Clone, Debug)]
+struct Node<K> {
+    key: Option<K>,
+    levels: Vec<Level>,
+    backward: Option<usize>,
+}
+
+/// Skip list whose links count the nodes they skip, like the one of the sorted sets of redis: the rank of a key and
+/// the key at a rank are found in logarithmic time, the nodes are walked in both directions one at a time.
+///
+/// Nodes are stored in a vector and linked by their index, the slots of the removed ones are reused.
+#[derive(Clone, Debug)]
+pub struct SkipList<K> {
+    nodes: Vec<Node<K>>,
+    free: Vec<usize>,
+    tail: Option<usize>,
+    level: usize,
+    len: usize,
+    seed: u64,
+}
+
+impl<K> Default for SkipList<K> {
+    fn default() -> Self {
+        Self {
+            nodes: vec![Node {
+                key: None,
+                levels: vec![Level::default(); MAX_LEVEL],
+                backward: None,
+            }],
+            free: vec![],
+            tail: None,
+            level: 1,
+            len: 0,
+            seed: 0x2545_f491_4f6c_dd1d,
+        }
+    }
+}
+
+impl<K: Ord> SkipList<K> {
+    /// Add the key, which must not be in the list already.
+    pub fn insert(&mut self, key: K) {
+        let mut update = [HEAD; MAX_LEVEL];
+        let mut rank = [0; MAX_LEVEL];
+        let mut x = HEAD;
+        for i in (0..self.level).rev() {
+            rank[i] = if i + 1 == self.level { 0 } else { rank[i + 1] };
+            while let Some(f) = self.forward(x, i).filter(|f| *self.key(*f) < key) {
+                rank[i] += self.nodes[x].levels[i].span;
+                x = f;
+            }
+            update[i] = x;
+        }
+
+        let level = self.random_level();
+        if level > self.level {
+            for i in self.level..level {
+                self.nodes[HEAD].levels[i].span = self.len;
+            }
+            self.level = level;
+        }
+
+        let n = self.alloc(key, level);
+        for i in 0..level {
+            let u = update[i];
+            let skipped = rank[0] - rank[i];
+            self.nodes[n].levels[i] = Level {
+                forward: self.nodes[u].levels[i].forward,
+                span: self.nodes[u].levels[i].span - skipped,
+            };
+            self.nodes[u].levels[i] = Level {
+                forward: Some(n),
+                span: skipped + 1,
+            };
+        }
+        for (i, u) in update.iter().enumerate().take(self.level).skip(level) {
+            self.nodes[*u].levels[i].span += 1;
+        }
+
+        self.nodes[n].backward = (update[0] != HEAD).then_some(update[0]);
+        match self.forward(n, 0) {
+            Some(f) => self.nodes[f].backward = Some(n),
+            None => self.tail = Some(n),
+        }
+        self.len += 1;
+    }
+
+    /// Remove the key, returns it if it was in the list.
+    pub fn remove(&mut self, key: &K) -> Option<K> {
+        self.unlink(|k| k < key, |k| k == key)
+    }
+
+    /// Remove and return the lowest key.
+    pub fn pop_first(&mut self) -> Option<K> {
+        self.unlink(|_| false, |_| true)
+    }
+
+    /// Position of the key from the lowest one, None if it is not in the list.
+    pub fn rank(&self, key: &K) -> Option<usize> {
+        let mut rank = 0;
+        let mut x = HEAD;
+        for i in (0..self.level).rev() {
+            while let Some(f) = self.forward(x, i).filter(|f| self.key(*f) <= key) {
+                rank += self.nodes[x].levels[i].span;
+                x = f;
+            }
+            if x != HEAD && self.key(x) == key {
+                return Some(rank - 1);
+            }
+        }
+
+        None
+    }
+
+    /// Keys from the lowest one.
+    pub fn iter(&self) -> Iter<'_, K> {
+        self.between(self.forward(HEAD, 0), self.tail)
+    }
+
+    /// Keys from the one at rank to the highest one.
+    pub fn iter_from_rank(&self, rank: usize) -> Iter<'_, K> {
+        self.between(self.select(rank), self.tail)
+    }
+
+    /// Keys from the lowest one to the one at rank.
+    pub fn iter_to_rank(&self, rank: usize) -> Iter<'_, K> {
+        self.between(self.forward(HEAD, 0), self.select(rank))
+    }
+
+    /// Keys from the first one which is not below, below must hold for the lowest keys only.
+    pub fn iter_from(&self, below: impl Fn(&K) -> bool) -> Iter<'_, K> {
+        let x = self.last_below(below);
+        self.between(self.forward(x, 0), self.tail)
+    }
+
+    /// Keys from the lowest one to the last one which is below, below must hold for the lowest keys only.
+    pub fn iter_to(&self, below: impl Fn(&K) -> bool) -> Iter<'_, K> {
+        let x = self.last_below(below);
+        self.between(self.forward(HEAD, 0), (x != HEAD).then_some(x))
+    }
+
+    /// Node at the rank from the lowest key.
+    fn select(&self, rank: usize) -> Option<usize> {
+        let target = rank + 1;
+        let mut traversed = 0;
+        let mut x = HEAD;
+        for i in (0..self.level).rev() {
+            while let Some(f) = self.forward(x, i) {
+                if traversed + self.nodes[x].levels[i].span > target {
+                    break;
+                }
+                traversed += self.nodes[x].levels[i].span;
+                x = f;
+            }
+            if traversed == target {
+                return Some(x);
+            }
+        }
+
+        None
+    }
+
+    /// Last node whose key is below, the head if there is none.
+    fn last_below(&self, below: impl Fn(&K) -> bool) -> usize {
+        let mut x = HEAD;
+        for i in (0..self.level).rev() {
+            while let Some(f) = self.forward(x, i).filter(|f| below(self.key(*f))) {
+                x = f;
+            }
+        }
+
+        x
+    }
+
+    /// Remove the first node which is not below if it matches.
+    fn unlink(
+        &mut self,
+        below: impl Fn(&K) -> bool,
+        matches: impl FnOnce(&K) -> bool,
+    ) -> Option<K> {
+        let mut update = [HEAD; MAX_LEVEL];
+        let mut x = HEAD;
+        for i in (0..self.level).rev() {
+            while let Some(f) = self.forward(x, i).filter(|f| below(self.key(*f))) {
+                x = f;
+            }
+            update[i] = x;
+        }
+        let n = self.forward(x, 0).filter(|n| matches(self.key(*n)))?;
+
+        for (i, u) in update.iter().enumerate().take(self.level) {
+            if self.nodes[*u].levels[i].forward == Some(n) {
+                self.nodes[*u].levels[i] = Level {
+                    forward: self.nodes[n].levels[i].forward,
+                    span: self.nodes[*u].levels[i].span + self.nodes[n].levels[i].span - 1,
+                };
+            } else {
+                self.nodes[*u].levels[i].span -= 1;
+            }
+        }
+        match self.forward(n, 0) {
+            Some(f) => self.nodes[f].backward = self.nodes[n].backward,
+            None => self.tail = self.nodes[n].backward,
+        }
+        while self.level > 1 && self.forward(HEAD, self.level - 1).is_none() {
+            self.level -= 1;
+        }
+        self.len -= 1;
+
+        self.nodes[n].levels.clear();
+        self.free.push(n);
+        self.nodes[n].key.take()
+    }
+}
+
+impl<K> SkipList<K> {
+    fn forward(&self, x: usize, level: usize) -> Option<usize> {
+        self.nodes[x].levels[level].forward
+    }
+
+    fn key(&self, x: usize) -> &K {
+        self.nodes[x].key.as_ref().expect("the head has no key")
+    }
+
+    const fn between(&self, front: Option<usize>, back: Option<usize>) -> Iter<'_, K> {
+        match (front, back) {
+            (Some(_), Some(_)) => Iter {
+                list: self,
+                front,
+                back,
+            },
+            _ => Iter {
+                list: self,
+                front: None,
+                back: None,
+            },
+        }
+    }
+
+    fn alloc(&mut self, key: K, level: usize) -> usize {
+        let node = Node {
+            key: Some(key),
+            levels: vec![Level::default(); level],
+            backward: None,
+        };
+        if let Some(n) = self.free.pop() {
+            self.nodes[n] = node;
+            n
+        } else {
+            self.nodes.push(node);
+            self.nodes.len() - 1
+        }
+    }
+
+    /// Level of a new node, every level is reached with a probability of 1/4 like in redis.
+    fn random_level(&mut self) -> usize {
+        // NOTE: xorshift is enough to balance the list, the levels don't need to be unpredictable.
+        self.seed ^= self.seed << 13;
+        self.seed ^= self.seed >> 7;
+        self.seed ^= self.seed << 17;
+
+        let level = (self.seed.trailing_zeros() / 2) as usize + 1;
+        level.min(MAX_LEVEL)
+    }
+}
+
+impl<K: Ord> PartialEq for SkipList<K> {
+    fn eq(&self, other: &Self) -> bool {
+        self.len == other.len && self.iter().eq(other.iter())
+    }
+}
+
+/// Keys between two nodes, walked from both ends.
+pub struct Iter<'a, K> {
+    list: &'a SkipList<K>,
+    front: Option<usize>,
+    back: Option<usize>,
+}
+
+impl<'a, K> Iterator for Iter<'a, K> {
+    type Item = &'a K;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let x = self.front?;
+        if self.front == self.back {
+            self.front = None;
+            self.back = None;
+        } else {
+            self.front = self.list.forward(x, 0);
+        }
+
+        Some(self.list.key(x))
+    }
+}
+
+impl<'a, K> DoubleEndedIterator for Iter<'a, K> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        let x = self.back?;
+        if self.front == self.back {
+            self.front = None;
+            self.back = None;
+        } else {
+            self.back = self.list.nodes[x].backward;
+        }
+
+        Some(self.list.key(x))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ranks() {
+        let mut l = SkipList::default();
+        for k in (0..1000).rev().step_by(2) {
+            l.insert(k);
+        }
+        assert_eq!(l.iter().count(), 500);
+        assert_eq!(l.rank(&0), None);
+        assert_eq!(l.rank(&1), Some(0));
+        assert_eq!(l.rank(&999), Some(499));
+        assert_eq!(l.iter_from_rank(10).next(), Some(&21));
+        assert_eq!(l.iter_to_rank(10).next_back(), Some(&21));
+        assert_eq!(l.iter_from_rank(500).next(), None);
+
+        for k in (1..1000).step_by(4) {
+            assert_eq!(l.remove(&k), Some(k));
+        }
+        assert_eq!(l.remove(&1), None);
+        assert_eq!(l.iter().count(), 250);
+        assert!(l
+            .iter()
+            .enumerate()
+            .all(|(i, k)| *k == i * 4 + 3 && l.rank(k) == Some(i)));
+        assert_eq!(l.pop_first(), Some(3));
+        assert_eq!(l.iter().next_back(), Some(&999));
+    }
+
+    #[test]
+    fn bounds() {
+        let mut l = SkipList::default();
+        for k in [5, 1, 3, 9, 7] {
+            l.insert(k);
+        }
+
+        assert_eq!(l.iter_from(|k| *k < 4).collect::<Vec<_>>(), [&5, &7, &9]);
+        assert_eq!(l.iter_to(|k| *k < 4).rev().collect::<Vec<_>>(), [&3, &1]);
+        assert_eq!(l.iter_from(|_| true).next(), None);
+        assert_eq!(l.iter_to(|_| false).next(), None);
+
+        let mut i = l.iter();
+        assert_eq!((i.next(), i.next_back()), (Some(&1), Some(&9)));
+        assert_eq!(i.collect::<Vec<_>>(), [&3, &5, &7]);
+    }
+}
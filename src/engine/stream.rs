@@ -0,0 +1,496 @@
+use std::collections::BTreeMap;
+
+/// Fields and values of a stream entry, in the order they were added.
+pub type Fields = Vec<(String, String)>;
+
+/// ID of a stream entry: the unix time in milliseconds of its creation, and a sequence number for the entries
+/// created in the same millisecond.
+#[derive(
+    Clone,
+    Copy,
+    Debug,
+    Default,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    serde::Deserialize,
+    serde::Serialize,
+)]
+#[allow(clippy::module_name_repetitions)]
+pub struct StreamId {
+    pub ms: u64,
+    pub seq: u64,
+}
+
+impl StreamId {
+    pub const MIN: Self = Self { ms: 0, seq: 0 };
+    pub const MAX: Self = Self {
+        ms: u64::MAX,
+        seq: u64::MAX,
+    };
+
+    /// Parse `<ms>-<seq>`, or `<ms>` alone which takes seq as sequence number.
+    pub fn parse(s: &str, seq: u64) -> Option<Self> {
+        let (ms, seq) = match s.split_once('-') {
+            Some((ms, seq)) => (ms.parse().ok()?, seq.parse().ok()?),
+            None => (s.parse().ok()?, seq),
+        };
+
+        Some(Self { ms, seq })
+    }
+
+    /// ID right after this one, None for the last possible ID.
+    pub fn next(self) -> Option<Self> {
+        match self.seq.checked_add(1) {
+            Some(seq) => Some(Self { ms: self.ms, seq }),
+            None => Some(Self {
+                ms: self.ms.checked_add(1)?,
+                seq: 0,
+            }),
+        }
+    }
+
+    /// ID right before this one, None for 0-0.
+    pub fn prev(self) -> Option<Self> {
+        match self.seq.checked_sub(1) {
+            Some(seq) => Some(Self { ms: self.ms, seq }),
+            None => Some(Self {
+                ms: self.ms.checked_sub(1)?,
+                seq: u64::MAX,
+            }),
+        }
+    }
+}
+
+impl std::fmt::Display for StreamId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}-{}", self.ms, self.seq)
+    }
+}
+
+/// ID of the entry added by XADD.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum NewId {
+    /// `*`: the current time, with the next sequence number if the last entry was added in the same millisecond.
+    Auto,
+
+    /// `<ms>-*`: the given time with the next sequence number.
+    Ms(u64),
+
+    /// `<ms>-<seq>`: exactly this ID.
+    Exact(StreamId),
+}
+
+/// How XADD and XTRIM trim the stream, from the oldest entry.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Trim {
+    /// Keep at most this number of entries.
+    MaxLen(usize),
+
+    /// Remove the entries with an ID lower than this one.
+    MinId(StreamId),
+}
+
+#[derive(thiserror::Error, Debug, PartialEq, Eq)]
+#[allow(clippy::module_name_repetitions)]
+pub enum StreamError {
+    #[error("ERR The ID specified in XADD is equal or smaller than the target stream top item")]
+    IdTooSmall,
+
+    #[error("ERR The ID specified in XADD must be greater than 0-0")]
+    ZeroId,
+
+    #[error("NOGROUP No such key or consumer group")]
+    NoGroup,
+
+    #[error("BUSYGROUP Consumer Group name already exists")]
+    BusyGroup,
+}
+
+/// Entry delivered to a consumer of a group and not acknowledged yet.
+#[derive(Clone, Debug, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
+pub struct Pending {
+    pub consumer: String,
+
+    /// Unix time in milliseconds of the last delivery.
+    pub delivered_at: u64,
+    pub deliveries: u64,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
+pub struct Group {
+    last_delivered: StreamId,
+    pending: BTreeMap<StreamId, Pending>,
+
+    /// Consumers with the unix time in milliseconds they were last seen at.
+    consumers: BTreeMap<String, u64>,
+}
+
+impl Group {
+    /// ID of the last entry delivered to the group, the next XREADGROUP with `>` starts after it.
+    pub const fn last_delivered(&self) -> StreamId {
+        self.last_delivered
+    }
+
+    /// Entries delivered and not acknowledged yet, by ID.
+    pub const fn pending(&self) -> &BTreeMap<StreamId, Pending> {
+        &self.pending
+    }
+
+    /// Consumers of the group with their number of pending entries and the time they were last seen at.
+    pub fn consumers(&self) -> impl Iterator<Item = (&str, usize, u64)> {
+        self.consumers.iter().map(|(name, seen_at)| {
+            let pending = self
+                .pending
+                .values()
+                .filter(|p| p.consumer == *name)
+                .count();
+            (name.as_str(), pending, *seen_at)
+        })
+    }
+}
+
+/// Append only log of entries ordered by ID, with the consumer groups reading it. Unlike the other collections a
+/// stream is kept when all its entries are removed, so that its last ID and its groups are not lost.
+#[derive(Clone, Debug, Default, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
+pub struct Stream {
+    entries: BTreeMap<StreamId, Fields>,
+    last_id: StreamId,
+
+    /// Number of entries added over the life of the stream, including the ones removed since.
+    entries_added: u64,
+    groups: BTreeMap<String, Group>,
+}
+
+impl Stream {
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// ID of the last entry added, even if it has been removed since.
+    pub const fn last_id(&self) -> StreamId {
+        self.last_id
+    }
+
+    pub const fn entries_added(&self) -> u64 {
+        self.entries_added
+    }
+
+    /// Add the entry, now is the unix time in milliseconds used by `NewId::Auto`.
+    pub fn add(&mut self, id: NewId, fields: Fields, now: u64) -> Result<StreamId, StreamError> {
+        let last = self.last_id;
+        let id = match id {
+            NewId::Auto if now > last.ms => StreamId { ms: now, seq: 0 },
+            NewId::Auto => last.next().ok_or(StreamError::IdTooSmall)?,
+            NewId::Ms(ms) if ms > last.ms => StreamId { ms, seq: 0 },
+            NewId::Ms(ms) if ms == last.ms => last.next().ok_or(StreamError::IdTooSmall)?,
+            NewId::Exact(StreamId::MIN) => return Err(StreamError::ZeroId),
+            NewId::Exact(id) if id > last => id,
+            NewId::Ms(_) | NewId::Exact(_) => return Err(StreamError::IdTooSmall),
+        };
+
+        self.entries.insert(id, fields);
+        self.last_id = id;
+        self.entries_added += 1;
+
+        Ok(id)
+    }
+
+    /// Remove the oldest entries, returns the number of entries removed.
+    pub fn trim(&mut self, trim: Trim) -> usize {
+        let before = self.entries.len();
+        match trim {
+            Trim::MaxLen(n) => {
+                while self.entries.len() > n {
+                    self.entries.pop_first();
+                }
+            }
+            Trim::MinId(id) => self.entries = self.entries.split_off(&id),
+        }
+
+        before - self.entries.len()
+    }
+
+    /// Remove the entries, returns the number of entries removed. They stay pending in the groups which
+    /// delivered them.
+    pub fn delete(&mut self, ids: &[StreamId]) -> usize {
+        ids.iter()
+            .filter(|id| self.entries.remove(id).is_some())
+            .count()
+    }
+
+    /// Entries between start and end included, from the oldest.
+    pub fn range(
+        &self,
+        start: StreamId,
+        end: StreamId,
+    ) -> impl DoubleEndedIterator<Item = (&StreamId, &Fields)> {
+        // NOTE: the range of the tree panics when start is after end, the entries of such a range are filtered out.
+        let valid = start <= end;
+        self.entries
+            .range(start..=end.max(start))
+            .filter(move |_| valid)
+    }
+
+    pub fn group(&self, name: &str) -> Option<&Group> {
+        self.groups.get(name)
+    }
+
+    pub fn groups(&self) -> impl Iterator<Item = (&str, &Group)> {
+        self.groups.iter().map(|(name, g)| (name.as_str(), g))
+    }
+
+    /// Create the group, it delivers the entries after last delivered.
+    pub fn create_group(
+        &mut self,
+        name: &str,
+        last_delivered: StreamId,
+    ) -> Result<(), StreamError> {
+        if self.groups.contains_key(name) {
+            return Err(StreamError::BusyGroup);
+        }
+
+        self.groups.insert(
+            name.to_string(),
+            Group {
+                last_delivered,
+                ..Group::default()
+            },
+        );
+        Ok(())
+    }
+
+    /// Remove the group with its pending entries, returns if it existed.
+    pub fn destroy_group(&mut self, name: &str) -> bool {
+        self.groups.remove(name).is_some()
+    }
+
+    /// Read for a consumer of the group, it is created if needed. With no ID the entries never delivered to the
+    /// group are returned and become pending for the consumer unless noack; with an ID the pending entries of the
+    /// consumer after it are returned again, None for the ones removed from the stream since.
+    pub fn read_group(
+        &mut self,
+        group: &str,
+        consumer: &str,
+        after: Option<StreamId>,
+        count: usize,
+        noack: bool,
+        now: u64,
+    ) -> Result<Vec<(StreamId, Option<Fields>)>, StreamError> {
+        let g = self.groups.get_mut(group).ok_or(StreamError::NoGroup)?;
+        g.consumers.insert(consumer.to_string(), now);
+
+        let Some(after) = after else {
+            let start = g.last_delivered.next().unwrap_or(StreamId::MAX);
+            let read = self
+                .entries
+                .range(start..)
+                .take(count)
+                .map(|(id, f)| (*id, Some(f.clone())))
+                .collect::<Vec<(StreamId, Option<Fields>)>>();
+
+            for (id, _) in &read {
+                g.last_delivered = *id;
+                if !noack {
+                    let pending = Pending {
+                        consumer: consumer.to_string(),
+                        delivered_at: now,
+                        deliveries: 1,
+                    };
+                    g.pending.insert(*id, pending);
+                }
+            }
+            return Ok(read);
+        };
+
+        Ok(g.pending
+            .range(after.next().unwrap_or(StreamId::MAX)..)
+            .filter(|(_, p)| p.consumer == consumer)
+            .take(count)
+            .map(|(id, _)| (*id, self.entries.get(id).cloned()))
+            .collect())
+    }
+
+    /// Acknowledge the entries for the group, returns the number of entries which were pending.
+    pub fn ack(&mut self, group: &str, ids: &[StreamId]) -> usize {
+        self.groups.get_mut(group).map_or(0, |g| {
+            ids.iter()
+                .filter(|id| g.pending.remove(id).is_some())
+                .count()
+        })
+    }
+
+    /// Give to the consumer the pending entries idle for at least min idle milliseconds, they count as delivered
+    /// again. Like redis, the entries removed from the stream are acknowledged instead of being claimed.
+    pub fn claim(
+        &mut self,
+        group: &str,
+        consumer: &str,
+        min_idle: u64,
+        ids: &[StreamId],
+        now: u64,
+    ) -> Result<Vec<(StreamId, Fields)>, StreamError> {
+        let g = self.groups.get_mut(group).ok_or(StreamError::NoGroup)?;
+        g.consumers.insert(consumer.to_string(), now);
+
+        let mut claimed = vec![];
+        for id in ids {
+            let Some(p) = g.pending.get_mut(id) else {
+                continue;
+            };
+            if now.saturating_sub(p.delivered_at) < min_idle {
+                continue;
+            }
+            let Some(fields) = self.entries.get(id) else {
+                g.pending.remove(id);
+                continue;
+            };
+
+            p.consumer = consumer.to_string();
+            p.delivered_at = now;
+            p.deliveries += 1;
+            claimed.push((*id, fields.clone()));
+        }
+
+        Ok(claimed)
+    }
+
+    /// Rough estimation of the memory used by the entries.
+    pub fn estimated_memory(&self) -> usize {
+        self.entries
+            .values()
+            .flatten()
+            .map(|(f, v)| f.len() + v.len())
+            .sum::<usize>()
+            + self.entries.len() * std::mem::size_of::<StreamId>()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fields(v: &str) -> Fields {
+        vec![("v".to_string(), v.to_string())]
+    }
+
+    const fn id(ms: u64, seq: u64) -> StreamId {
+        StreamId { ms, seq }
+    }
+
+    #[test]
+    fn ids() {
+        let mut s = Stream::default();
+        assert_eq!(s.add(NewId::Auto, fields("a"), 5), Ok(id(5, 0)));
+        assert_eq!(s.add(NewId::Auto, fields("b"), 5), Ok(id(5, 1)));
+        // NOTE: the clock going backward must not break the order of the IDs.
+        assert_eq!(s.add(NewId::Auto, fields("c"), 3), Ok(id(5, 2)));
+        assert_eq!(s.add(NewId::Ms(5), fields("d"), 0), Ok(id(5, 3)));
+        assert_eq!(
+            s.add(NewId::Ms(4), fields("e"), 0),
+            Err(StreamError::IdTooSmall)
+        );
+        assert_eq!(
+            s.add(NewId::Exact(id(5, 3)), fields("e"), 0),
+            Err(StreamError::IdTooSmall)
+        );
+        assert_eq!(s.add(NewId::Exact(id(7, 0)), fields("e"), 0), Ok(id(7, 0)));
+        assert_eq!(
+            Stream::default().add(NewId::Exact(StreamId::MIN), fields("a"), 0),
+            Err(StreamError::ZeroId)
+        );
+
+        assert_eq!(StreamId::parse("12-3", 0), Some(id(12, 3)));
+        assert_eq!(StreamId::parse("12", u64::MAX), Some(id(12, u64::MAX)));
+        assert_eq!(StreamId::parse("12-x", 0), None);
+        assert_eq!(id(1, u64::MAX).next(), Some(id(2, 0)));
+        assert_eq!(StreamId::MIN.prev(), None);
+        assert_eq!(id(3, 4).to_string(), "3-4");
+    }
+
+    #[test]
+    fn range_and_trim() {
+        let mut s = Stream::default();
+        for ms in 1..=5 {
+            s.add(NewId::Ms(ms), fields(&ms.to_string()), 0).unwrap();
+        }
+
+        let ids = |s: &Stream, start, end| {
+            s.range(start, end)
+                .map(|(id, _)| id.ms)
+                .collect::<Vec<u64>>()
+        };
+        assert_eq!(ids(&s, id(2, 0), id(4, 0)), [2, 3, 4]);
+        assert_eq!(ids(&s, id(4, 0), id(2, 0)), Vec::<u64>::new());
+        assert_eq!(ids(&s, StreamId::MIN, StreamId::MAX).len(), 5);
+
+        assert_eq!(s.trim(Trim::MaxLen(3)), 2);
+        assert_eq!(s.trim(Trim::MinId(id(4, 0))), 1);
+        assert_eq!(s.delete(&[id(4, 0), id(9, 0)]), 1);
+        assert_eq!(ids(&s, StreamId::MIN, StreamId::MAX), [5]);
+        assert_eq!(s.last_id(), id(5, 0));
+        assert_eq!(s.entries_added(), 5);
+    }
+
+    #[test]
+    fn groups() {
+        let mut s = Stream::default();
+        for ms in 1..=3 {
+            s.add(NewId::Ms(ms), fields(&ms.to_string()), 0).unwrap();
+        }
+        s.create_group("g", StreamId::MIN).unwrap();
+        assert_eq!(
+            s.create_group("g", StreamId::MIN),
+            Err(StreamError::BusyGroup)
+        );
+
+        let read = s.read_group("g", "alice", None, 2, false, 100).unwrap();
+        assert_eq!(
+            read,
+            [(id(1, 0), Some(fields("1"))), (id(2, 0), Some(fields("2")))]
+        );
+        let read = s.read_group("g", "bob", None, 10, false, 100).unwrap();
+        assert_eq!(read, [(id(3, 0), Some(fields("3")))]);
+        assert_eq!(s.read_group("g", "bob", None, 10, false, 100), Ok(vec![]));
+
+        // NOTE: the history of a consumer returns its pending entries, removed ones without their fields.
+        s.delete(&[id(1, 0)]);
+        let read = s.read_group("g", "alice", Some(StreamId::MIN), 10, false, 100);
+        assert_eq!(
+            read,
+            Ok(vec![(id(1, 0), None), (id(2, 0), Some(fields("2")))])
+        );
+
+        assert_eq!(s.ack("g", &[id(2, 0), id(2, 0), id(9, 0)]), 1);
+        assert_eq!(s.claim("g", "bob", 50, &[id(1, 0)], 120), Ok(vec![]));
+        assert_eq!(
+            s.claim("g", "bob", 50, &[id(1, 0), id(3, 0)], 200),
+            Ok(vec![(id(3, 0), fields("3"))])
+        );
+        let g = s.group("g").unwrap();
+        assert_eq!(g.pending().len(), 1);
+        assert_eq!(g.pending()[&id(3, 0)].deliveries, 2);
+        assert_eq!(
+            g.consumers().collect::<Vec<(&str, usize, u64)>>(),
+            [("alice", 0, 100), ("bob", 1, 200)]
+        );
+
+        assert_eq!(
+            s.read_group("missing", "bob", None, 1, false, 0),
+            Err(StreamError::NoGroup)
+        );
+        assert!(s.destroy_group("g"));
+    }
+
+    #[test]
+    fn serialize() {
+        let mut s = Stream::default();
+        s.add(NewId::Ms(1), fields("a"), 0).unwrap();
+        s.create_group("g", StreamId::MIN).unwrap();
+        s.read_group("g", "c", None, 1, false, 10).unwrap();
+
+        let b = bincode::serialize(&s).unwrap();
+        assert_eq!(bincode::deserialize::<Stream>(&b).unwrap(), s);
+    }
+}
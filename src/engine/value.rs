@@ -0,0 +1,219 @@
+use super::stream::Stream;
+use super::zset::SortedSet;
+use std::borrow::Cow;
+use std::collections::{HashMap, HashSet, VecDeque};
+
+/// Error of the commands run against a key holding another type, like GET on a list.
+#[derive(thiserror::Error, Debug, PartialEq, Eq)]
+#[error("WRONGTYPE Operation against a key holding the wrong kind of value")]
+pub struct WrongType;
+
+/// Value stored at a key. Like redis, strings holding an integer in its canonical form are kept as integers, they
+/// are still strings for the commands. Strings are binary safe, the bitmap commands store any byte.
+#[derive(Clone, Debug, PartialEq, serde::Deserialize, serde::Serialize)]
+pub enum Value {
+    String(Vec<u8>),
+    Integer(i64),
+    List(VecDeque<String>),
+    Hash(HashMap<String, String>),
+    Set(HashSet<String>),
+    SortedSet(SortedSet),
+    Stream(Stream),
+    Json(#[serde(with = "super::json::text")] serde_json::Value),
+}
+
+impl Value {
+    /// Name of the type as returned by the TYPE command.
+    pub const fn type_name(&self) -> &'static str {
+        match self {
+            Self::String(_) | Self::Integer(_) => "string",
+            Self::List(_) => "list",
+            Self::Hash(_) => "hash",
+            Self::Set(_) => "set",
+            Self::SortedSet(_) => "zset",
+            Self::Stream(_) => "stream",
+            // NOTE: the name of the JSON type of redis, some clients rely on it.
+            Self::Json(_) => "ReJSON-RL",
+        }
+    }
+
+    pub fn as_bytes(&self) -> Result<Cow<'_, [u8]>, WrongType> {
+        match self {
+            Self::String(s) => Ok(Cow::Borrowed(s)),
+            Self::Integer(i) => Ok(Cow::Owned(i.to_string().into_bytes())),
+            _ => Err(WrongType),
+        }
+    }
+
+    /// Bytes of the string to be modified in place, an integer becomes a plain string.
+    pub fn as_bytes_mut(&mut self) -> Result<&mut Vec<u8>, WrongType> {
+        if let Self::Integer(i) = self {
+            *self = Self::String(i.to_string().into_bytes());
+        }
+        match self {
+            Self::String(s) => Ok(s),
+            _ => Err(WrongType),
+        }
+    }
+
+    pub const fn as_hash(&self) -> Result<&HashMap<String, String>, WrongType> {
+        match self {
+            Self::Hash(h) => Ok(h),
+            _ => Err(WrongType),
+        }
+    }
+
+    pub fn as_hash_mut(&mut self) -> Result<&mut HashMap<String, String>, WrongType> {
+        match self {
+            Self::Hash(h) => Ok(h),
+            _ => Err(WrongType),
+        }
+    }
+
+    pub const fn as_list(&self) -> Result<&VecDeque<String>, WrongType> {
+        match self {
+            Self::List(l) => Ok(l),
+            _ => Err(WrongType),
+        }
+    }
+
+    pub fn as_list_mut(&mut self) -> Result<&mut VecDeque<String>, WrongType> {
+        match self {
+            Self::List(l) => Ok(l),
+            _ => Err(WrongType),
+        }
+    }
+
+    pub const fn as_set(&self) -> Result<&HashSet<String>, WrongType> {
+        match self {
+            Self::Set(s) => Ok(s),
+            _ => Err(WrongType),
+        }
+    }
+
+    pub fn as_set_mut(&mut self) -> Result<&mut HashSet<String>, WrongType> {
+        match self {
+            Self::Set(s) => Ok(s),
+            _ => Err(WrongType),
+        }
+    }
+
+    pub const fn as_sorted_set(&self) -> Result<&SortedSet, WrongType> {
+        match self {
+            Self::SortedSet(z) => Ok(z),
+            _ => Err(WrongType),
+        }
+    }
+
+    pub fn as_sorted_set_mut(&mut self) -> Result<&mut SortedSet, WrongType> {
+        match self {
+            Self::SortedSet(z) => Ok(z),
+            _ => Err(WrongType),
+        }
+    }
+
+    pub const fn as_stream(&self) -> Result<&Stream, WrongType> {
+        match self {
+            Self::Stream(s) => Ok(s),
+            _ => Err(WrongType),
+        }
+    }
+
+    pub fn as_stream_mut(&mut self) -> Result<&mut Stream, WrongType> {
+        match self {
+            Self::Stream(s) => Ok(s),
+            _ => Err(WrongType),
+        }
+    }
+
+    pub const fn as_json(&self) -> Result<&serde_json::Value, WrongType> {
+        match self {
+            Self::Json(j) => Ok(j),
+            _ => Err(WrongType),
+        }
+    }
+
+    pub fn as_json_mut(&mut self) -> Result<&mut serde_json::Value, WrongType> {
+        match self {
+            Self::Json(j) => Ok(j),
+            _ => Err(WrongType),
+        }
+    }
+
+    /// Rough estimation of the memory used by the value, without the allocator overhead.
+    pub fn estimated_memory(&self) -> usize {
+        match self {
+            Self::String(s) => s.len(),
+            Self::Integer(_) => 0,
+            Self::List(l) => l.iter().map(String::len).sum(),
+            Self::Hash(h) => h.iter().map(|(k, v)| k.len() + v.len()).sum(),
+            Self::Set(s) => s.iter().map(String::len).sum(),
+            Self::SortedSet(z) => z
+                .iter()
+                .map(|(m, _)| 2 * m.len() + 2 * std::mem::size_of::<f64>())
+                .sum(),
+            Self::Stream(s) => s.estimated_memory(),
+            Self::Json(j) => j.to_string().len(),
+        }
+    }
+}
+
+impl From<String> for Value {
+    fn from(s: String) -> Self {
+        match s.parse::<i64>() {
+            Ok(i) if i.to_string() == s => Self::Integer(i),
+            _ => Self::String(s.into_bytes()),
+        }
+    }
+}
+
+impl From<Vec<u8>> for Value {
+    fn from(b: Vec<u8>) -> Self {
+        match String::from_utf8(b) {
+            Ok(s) => s.into(),
+            Err(e) => Self::String(e.into_bytes()),
+        }
+    }
+}
+
+impl From<i64> for Value {
+    fn from(i: i64) -> Self {
+        Self::Integer(i)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn from_string() {
+        assert_eq!(Value::from("42".to_string()), Value::Integer(42));
+        assert_eq!(Value::from("-7".to_string()), Value::Integer(-7));
+        assert_eq!(
+            Value::from("007".to_string()),
+            Value::String(b"007".to_vec())
+        );
+        assert_eq!(
+            Value::from("1.5".to_string()),
+            Value::String(b"1.5".to_vec())
+        );
+        assert_eq!(
+            Value::from(b"\xff\r\n".to_vec()),
+            Value::String(b"\xff\r\n".to_vec())
+        );
+        assert_eq!(Value::from(b"42".to_vec()), Value::Integer(42));
+        assert_eq!(Value::Integer(42).as_bytes().unwrap().as_ref(), b"42");
+    }
+
+    #[test]
+    fn wrong_type() {
+        let v = Value::List(VecDeque::from(["a".to_string()]));
+        assert_eq!(v.type_name(), "list");
+        assert_eq!(v.as_bytes(), Err(WrongType));
+        assert_eq!(
+            WrongType.to_string(),
+            "WRONGTYPE Operation against a key holding the wrong kind of value"
+        );
+    }
+}
@@ -0,0 +1,272 @@
+use super::skiplist::SkipList;
+use std::collections::HashMap;
+use std::ops::Bound;
+
+/// Score of a member, NaN is never stored so the order is total.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Score(pub f64);
+
+impl Eq for Score {}
+
+impl PartialOrd for Score {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Score {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        self.0.total_cmp(&other.0)
+    }
+}
+
+/// Sorted set: members ordered by score and then lexicographically, like redis. The map gives the score of a member
+/// in constant time while the skip list keeps the order, ranges and ranks are found in logarithmic time.
+///
+/// Only the members with their scores are serialized, the skip list is rebuilt on load.
+#[derive(Clone, Debug, Default, PartialEq, serde::Deserialize, serde::Serialize)]
+#[serde(from = "Vec<(String, f64)>", into = "Vec<(String, f64)>")]
+pub struct SortedSet {
+    members: HashMap<String, f64>,
+    ordered: SkipList<(Score, String)>,
+}
+
+impl SortedSet {
+    pub fn len(&self) -> usize {
+        self.members.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.members.is_empty()
+    }
+
+    pub fn score(&self, member: &str) -> Option<f64> {
+        self.members.get(member).copied()
+    }
+
+    /// Add the member or update its score, returns the previous score if the member already existed.
+    pub fn insert(&mut self, member: &str, score: f64) -> Option<f64> {
+        let previous = self.members.insert(member.to_string(), score);
+        if let Some(p) = previous {
+            self.ordered.remove(&(Score(p), member.to_string()));
+        }
+        self.ordered.insert((Score(score), member.to_string()));
+
+        previous
+    }
+
+    /// Remove the member, returns its score if it existed.
+    pub fn remove(&mut self, member: &str) -> Option<f64> {
+        let score = self.members.remove(member)?;
+        self.ordered.remove(&(Score(score), member.to_string()));
+
+        Some(score)
+    }
+
+    /// Members with their scores from the lowest score to the highest.
+    pub fn iter(&self) -> impl DoubleEndedIterator<Item = (&str, f64)> {
+        self.ordered.iter().map(|(s, m)| (m.as_str(), s.0))
+    }
+
+    /// Position of the member from the lowest score.
+    pub fn rank(&self, member: &str) -> Option<usize> {
+        let score = self.score(member)?;
+
+        self.ordered.rank(&(Score(score), member.to_string()))
+    }
+
+    /// Members from the rank start, counted from the lowest score or from the highest if rev.
+    pub fn range_by_rank(
+        &self,
+        start: usize,
+        rev: bool,
+    ) -> Box<dyn Iterator<Item = (&str, f64)> + '_> {
+        if !rev {
+            return Box::new(
+                self.ordered
+                    .iter_from_rank(start)
+                    .map(|(s, m)| (m.as_str(), s.0)),
+            );
+        }
+
+        let Some(end) = self.len().checked_sub(start + 1) else {
+            return Box::new(std::iter::empty());
+        };
+        Box::new(
+            self.ordered
+                .iter_to_rank(end)
+                .rev()
+                .map(|(s, m)| (m.as_str(), s.0)),
+        )
+    }
+
+    /// Remove and return the member with the lowest score.
+    pub fn pop_min(&mut self) -> Option<(String, f64)> {
+        let (score, member) = self.ordered.pop_first()?;
+        self.members.remove(&member);
+
+        Some((member, score.0))
+    }
+
+    /// Remove and return the member with the highest score.
+    pub fn pop_max(&mut self) -> Option<(String, f64)> {
+        let last = self.ordered.iter().next_back()?.clone();
+        let (score, member) = self.ordered.remove(&last)?;
+        self.members.remove(&member);
+
+        Some((member, score.0))
+    }
+
+    /// Members with a score between min and max, from the lowest score or from the highest if rev. The first member
+    /// is found in logarithmic time.
+    pub fn range_by_score(
+        &self,
+        min: Bound<f64>,
+        max: Bound<f64>,
+        rev: bool,
+    ) -> Box<dyn Iterator<Item = (&str, f64)> + '_> {
+        if rev {
+            return Box::new(
+                self.ordered
+                    .iter_to(|(s, _)| before(&s.0, max.as_ref()))
+                    .rev()
+                    .map(|(s, m)| (m.as_str(), s.0))
+                    .take_while(move |(_, s)| after(s, min.as_ref())),
+            );
+        }
+
+        Box::new(
+            self.ordered
+                .iter_from(|(s, _)| !after(&s.0, min.as_ref()))
+                .map(|(s, m)| (m.as_str(), s.0))
+                .take_while(move |(_, s)| before(s, max.as_ref())),
+        )
+    }
+
+    /// Members between min and max in lexicographic order, from the lowest or from the highest if rev. Like redis,
+    /// the result is meaningful only when all the members have the same score.
+    pub fn range_by_lex<'a>(
+        &'a self,
+        min: Bound<&'a str>,
+        max: Bound<&'a str>,
+        rev: bool,
+    ) -> Box<dyn Iterator<Item = (&'a str, f64)> + 'a> {
+        if rev {
+            Box::new(
+                self.ordered
+                    .iter_to(|(_, m)| before(m.as_str(), max))
+                    .rev()
+                    .map(|(s, m)| (m.as_str(), s.0))
+                    .take_while(move |(m, _)| after(*m, min)),
+            )
+        } else {
+            Box::new(
+                self.ordered
+                    .iter_from(|(_, m)| !after(m.as_str(), min))
+                    .map(|(s, m)| (m.as_str(), s.0))
+                    .take_while(move |(m, _)| before(*m, max)),
+            )
+        }
+    }
+}
+
+/// Returns if the value is within the lower bound.
+fn after<T: PartialOrd + ?Sized>(value: &T, min: Bound<&T>) -> bool {
+    match min {
+        Bound::Included(m) => value >= m,
+        Bound::Excluded(m) => value > m,
+        Bound::Unbounded => true,
+    }
+}
+
+/// Returns if the value is within the upper bound.
+fn before<T: PartialOrd + ?Sized>(value: &T, max: Bound<&T>) -> bool {
+    match max {
+        Bound::Included(m) => value <= m,
+        Bound::Excluded(m) => value < m,
+        Bound::Unbounded => true,
+    }
+}
+
+impl From<Vec<(String, f64)>> for SortedSet {
+    fn from(members: Vec<(String, f64)>) -> Self {
+        let mut z = Self::default();
+        for (member, score) in members {
+            z.insert(&member, score);
+        }
+
+        z
+    }
+}
+
+impl From<SortedSet> for Vec<(String, f64)> {
+    fn from(z: SortedSet) -> Self {
+        z.members.into_iter().collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn order() {
+        let mut z = SortedSet::default();
+        assert_eq!(z.insert("b", 1.0), None);
+        assert_eq!(z.insert("a", 1.0), None);
+        assert_eq!(z.insert("c", -2.5), None);
+        assert_eq!(z.insert("c", 3.0), Some(-2.5));
+
+        let members = z.iter().map(|(m, _)| m).collect::<Vec<&str>>();
+        assert_eq!(members, vec!["a", "b", "c"]);
+        assert_eq!(z.score("c"), Some(3.0));
+
+        assert_eq!(z.remove("a"), Some(1.0));
+        assert_eq!(z.remove("a"), None);
+        assert_eq!(z.len(), 2);
+    }
+
+    #[test]
+    fn ranges() {
+        let mut z = SortedSet::default();
+        for (m, s) in [("a", 1.0), ("b", 2.0), ("c", 2.0), ("d", 3.0)] {
+            z.insert(m, s);
+        }
+        let members = |i: Box<dyn Iterator<Item = (&str, f64)> + '_>| {
+            i.map(|(m, _)| m.to_string()).collect::<Vec<String>>()
+        };
+
+        let r = z.range_by_score(Bound::Excluded(1.0), Bound::Included(2.0), false);
+        assert_eq!(members(r), ["b", "c"]);
+        let r = z.range_by_score(Bound::Unbounded, Bound::Excluded(3.0), true);
+        assert_eq!(members(r), ["c", "b", "a"]);
+        let r = z.range_by_score(Bound::Included(2.5), Bound::Included(2.0), false);
+        assert_eq!(members(r), Vec::<String>::new());
+        let r = z.range_by_lex(Bound::Included("b"), Bound::Excluded("d"), false);
+        assert_eq!(members(r), ["b", "c"]);
+        let r = z.range_by_lex(Bound::Excluded("a"), Bound::Unbounded, true);
+        assert_eq!(members(r), ["d", "c", "b"]);
+
+        assert_eq!(z.rank("c"), Some(2));
+        let r = z.range_by_rank(1, false);
+        assert_eq!(members(r), ["b", "c", "d"]);
+        let r = z.range_by_rank(1, true);
+        assert_eq!(members(r), ["c", "b", "a"]);
+        assert_eq!(z.range_by_rank(4, true).next(), None);
+        assert_eq!(z.rank("missing"), None);
+        assert_eq!(z.pop_min(), Some(("a".to_string(), 1.0)));
+        assert_eq!(z.pop_max(), Some(("d".to_string(), 3.0)));
+        assert_eq!(z.len(), 2);
+        assert_eq!(z.score("a"), None);
+    }
+
+    #[test]
+    fn serialize() {
+        let mut z = SortedSet::default();
+        z.insert("a", 1.0);
+        z.insert("b", 2.0);
+
+        let s = bincode::serialize(&z).unwrap();
+        assert_eq!(bincode::deserialize::<SortedSet>(&s).unwrap(), z);
+    }
+}
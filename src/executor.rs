@@ -1,18 +1,54 @@
 use crate::engine::db::HashMapDb;
+use crate::engine::value::{Value, WrongType};
 use crate::protocol;
+use crate::server::Server;
+
+mod bitmap;
+mod geo;
+mod hash;
+mod hyperloglog;
+mod json;
+mod list;
+mod set;
+mod stream;
+mod zset;
+
+pub use stream::resolve_last_ids;
 
 pub fn execute_command(
     cmd: protocol::commands::Command,
     db: &mut HashMapDb,
+    server: &mut Server,
+    client_id: u64,
+    now: std::time::Instant,
+) -> protocol::commands::CommandResponse {
+    if let Some(refused) = admit(&cmd, server, client_id, now) {
+        return refused;
+    }
+
+    run(cmd, db, server, client_id, now)
+}
+
+/// Error returned when the command must not run, because of the rate limit or the permissions of the client.
+pub fn admit(
+    cmd: &protocol::commands::Command,
+    server: &mut Server,
+    client_id: u64,
+    now: std::time::Instant,
+) -> Option<protocol::commands::CommandResponse> {
+    check_rate(server, client_id, now).or_else(|| check_permissions(cmd, server, client_id))
+}
+
+/// Run the command, it must have been admitted by [`admit`].
+pub fn run(
+    cmd: protocol::commands::Command,
+    db: &mut HashMapDb,
+    server: &mut Server,
+    client_id: u64,
     now: std::time::Instant,
 ) -> protocol::commands::CommandResponse {
     match cmd {
-        protocol::commands::Command::Get { key } => protocol::commands::CommandResponse::String {
-            value: db
-                .get(key.as_str(), std::time::Instant::now())
-                .map_or("not found", |v| v)
-                .to_owned(),
-        },
+        protocol::commands::Command::Get { key } => get(db, &key, now),
         protocol::commands::Command::Set { key, value, ttl } => {
             db.set(key.as_str(), value, ttl.map(|ttl| now + ttl));
             protocol::commands::CommandResponse::String {
@@ -34,31 +70,711 @@ pub fn execute_command(
         protocol::commands::Command::Docs => {
             protocol::commands::CommandResponse::Array { value: Vec::new() }
         }
-        protocol::commands::Command::Config => protocol::commands::CommandResponse::String {
-            value: "OK".to_owned(),
-        },
+        // NOTE: for MONITOR the connection subscribes to the monitors once the OK is written, see `handle_connection`.
+        protocol::commands::Command::Config | protocol::commands::Command::Monitor => {
+            protocol::commands::CommandResponse::String {
+                value: "OK".to_owned(),
+            }
+        }
         protocol::commands::Command::Ping => protocol::commands::CommandResponse::String {
             value: "PONG".to_owned(),
         },
-        protocol::commands::Command::Incr { key } => {
-            match db.get(&key, std::time::Instant::now()) {
-                Some(k) => {
-                    let k = k.parse::<u64>().unwrap();
-                    db.set(&key, (k + 1).to_string(), None);
-                }
-                None => db.set(&key, 1.to_string(), None),
+        protocol::commands::Command::Type { key } => protocol::commands::CommandResponse::String {
+            value: db
+                .get(&key, now)
+                .map_or("none", Value::type_name)
+                .to_owned(),
+        },
+        protocol::commands::Command::View { key, path } => view(db, &key, path.as_ref(), now),
+        protocol::commands::Command::Incr { key } => incr(db, &key, now),
+        protocol::commands::Command::FlushDb => {
+            db.flush();
+            protocol::commands::CommandResponse::String {
+                value: "OK".to_owned(),
             }
+        }
+        protocol::commands::Command::Info { sections } => {
+            protocol::commands::CommandResponse::BulkString {
+                value: crate::server::info::render(&sections, server, db, now),
+            }
+        }
+        protocol::commands::Command::Metrics => protocol::commands::CommandResponse::BulkString {
+            value: crate::server::metrics::render(server, db, now),
+        },
+        cmd @ (protocol::commands::Command::SlowlogGet { .. }
+        | protocol::commands::Command::SlowlogLen
+        | protocol::commands::Command::SlowlogReset) => slowlog(&cmd, server),
+        cmd @ (protocol::commands::Command::LatencyLatest
+        | protocol::commands::Command::LatencyHistogram { .. }
+        | protocol::commands::Command::LatencyHistory { .. }
+        | protocol::commands::Command::LatencyReset { .. }) => latency(&cmd, server),
+        cmd @ (protocol::commands::Command::ClientId
+        | protocol::commands::Command::ClientInfo
+        | protocol::commands::Command::ClientList { .. }
+        | protocol::commands::Command::ClientSetName { .. }
+        | protocol::commands::Command::ClientGetName
+        | protocol::commands::Command::ClientKill { .. }
+        | protocol::commands::Command::ClientPause { .. }
+        | protocol::commands::Command::ClientUnpause) => client(cmd, server, client_id, now),
+        protocol::commands::Command::Auth { username, password } => {
+            auth(server, client_id, username.as_deref(), &password)
+        }
+        protocol::commands::Command::Hello {
+            protover,
+            auth,
+            setname,
+        } => hello(server, client_id, protover, auth, setname),
+        cmd @ (protocol::commands::Command::AclSetUser { .. }
+        | protocol::commands::Command::AclGetUser { .. }
+        | protocol::commands::Command::AclDelUser { .. }
+        | protocol::commands::Command::AclList
+        | protocol::commands::Command::AclWhoAmI) => acl(cmd, server, client_id),
+        cmd => data_type(cmd, db, server, now),
+    }
+}
+
+/// Run again the read of a connection blocked on streams once an entry has been added to one of them, the checks
+/// of [`execute_command`] were done by its first run.
+pub fn retry_read(
+    cmd: protocol::commands::Command,
+    db: &mut HashMapDb,
+    server: &mut Server,
+    now: std::time::Instant,
+) -> protocol::commands::CommandResponse {
+    stream::stream(cmd, db, &mut server.blocking, now)
+}
+
+/// Execute the commands of the data types other than strings, each type has its own module.
+fn data_type(
+    cmd: protocol::commands::Command,
+    db: &mut HashMapDb,
+    server: &mut Server,
+    now: std::time::Instant,
+) -> protocol::commands::CommandResponse {
+    let response = match cmd {
+        cmd @ (protocol::commands::Command::HSet { .. }
+        | protocol::commands::Command::HGet { .. }
+        | protocol::commands::Command::HMGet { .. }
+        | protocol::commands::Command::HDel { .. }
+        | protocol::commands::Command::HGetAll { .. }
+        | protocol::commands::Command::HIncrBy { .. }
+        | protocol::commands::Command::HExists { .. }
+        | protocol::commands::Command::HLen { .. }
+        | protocol::commands::Command::HScan { .. }) => hash::hash(cmd, db, now),
+        cmd @ (protocol::commands::Command::LPush { .. }
+        | protocol::commands::Command::RPush { .. }
+        | protocol::commands::Command::LPop { .. }
+        | protocol::commands::Command::RPop { .. }
+        | protocol::commands::Command::LRange { .. }
+        | protocol::commands::Command::LIndex { .. }
+        | protocol::commands::Command::LLen { .. }
+        | protocol::commands::Command::BLPop { .. }
+        | protocol::commands::Command::BRPop { .. }) => {
+            list::list(cmd, db, &mut server.blocking, now)
+        }
+        cmd @ (protocol::commands::Command::SAdd { .. }
+        | protocol::commands::Command::SRem { .. }
+        | protocol::commands::Command::SMembers { .. }
+        | protocol::commands::Command::SIsMember { .. }
+        | protocol::commands::Command::SCard { .. }
+        | protocol::commands::Command::SInter { .. }
+        | protocol::commands::Command::SUnion { .. }
+        | protocol::commands::Command::SDiff { .. }
+        | protocol::commands::Command::SInterStore { .. }
+        | protocol::commands::Command::SUnionStore { .. }
+        | protocol::commands::Command::SDiffStore { .. }
+        | protocol::commands::Command::SPop { .. }
+        | protocol::commands::Command::SRandMember { .. }) => set::set(cmd, db, now),
+        cmd @ (protocol::commands::Command::ZAdd { .. }
+        | protocol::commands::Command::ZIncrBy { .. }
+        | protocol::commands::Command::ZRem { .. }
+        | protocol::commands::Command::ZRange { .. }
+        | protocol::commands::Command::ZRank { .. }
+        | protocol::commands::Command::ZScore { .. }
+        | protocol::commands::Command::ZCard { .. }
+        | protocol::commands::Command::ZPopMin { .. }
+        | protocol::commands::Command::ZPopMax { .. }
+        | protocol::commands::Command::ZUnionStore { .. }
+        | protocol::commands::Command::ZInterStore { .. }
+        | protocol::commands::Command::ZDiffStore { .. }) => zset::zset(cmd, db, now),
+        cmd @ (protocol::commands::Command::SetBit { .. }
+        | protocol::commands::Command::GetBit { .. }
+        | protocol::commands::Command::BitCount { .. }
+        | protocol::commands::Command::BitPos { .. }
+        | protocol::commands::Command::BitOp { .. }
+        | protocol::commands::Command::BitField { .. }) => bitmap::bitmap(cmd, db, now),
+        cmd @ (protocol::commands::Command::PfAdd { .. }
+        | protocol::commands::Command::PfCount { .. }
+        | protocol::commands::Command::PfMerge { .. }) => hyperloglog::hyperloglog(cmd, db, now),
+        cmd @ (protocol::commands::Command::GeoAdd { .. }
+        | protocol::commands::Command::GeoPos { .. }
+        | protocol::commands::Command::GeoDist { .. }
+        | protocol::commands::Command::GeoSearch { .. }) => geo::geo(cmd, db, now),
+        cmd @ (protocol::commands::Command::JsonSet { .. }
+        | protocol::commands::Command::JsonGet { .. }
+        | protocol::commands::Command::JsonDel { .. }
+        | protocol::commands::Command::JsonArrAppend { .. }
+        | protocol::commands::Command::JsonNumIncrBy { .. }
+        | protocol::commands::Command::JsonType { .. }) => json::json(cmd, db, now),
+        cmd @ (protocol::commands::Command::XAdd { .. }
+        | protocol::commands::Command::XLen { .. }
+        | protocol::commands::Command::XRange { .. }
+        | protocol::commands::Command::XRevRange { .. }
+        | protocol::commands::Command::XTrim { .. }
+        | protocol::commands::Command::XDel { .. }
+        | protocol::commands::Command::XRead { .. }
+        | protocol::commands::Command::XGroupCreate { .. }
+        | protocol::commands::Command::XGroupDestroy { .. }
+        | protocol::commands::Command::XReadGroup { .. }
+        | protocol::commands::Command::XAck { .. }
+        | protocol::commands::Command::XClaim { .. }
+        | protocol::commands::Command::XPending { .. }
+        | protocol::commands::Command::XInfoStream { .. }
+        | protocol::commands::Command::XInfoGroups { .. }
+        | protocol::commands::Command::XInfoConsumers { .. }) => {
+            stream::stream(cmd, db, &mut server.blocking, now)
+        }
+        cmd => unreachable!("{} is not a command of a data type", cmd.name()),
+    };
+    db.discard_created();
+
+    response
+}
+
+fn get(
+    db: &mut HashMapDb,
+    key: &str,
+    now: std::time::Instant,
+) -> protocol::commands::CommandResponse {
+    match db.get(key, now).map(Value::as_bytes) {
+        Some(Ok(v)) => protocol::commands::CommandResponse::BulkBytes {
+            value: v.into_owned(),
+        },
+        Some(Err(e)) => protocol::commands::CommandResponse::Error {
+            value: e.to_string(),
+        },
+        None => protocol::commands::CommandResponse::String {
+            value: "not found".to_owned(),
+        },
+    }
+}
+
+/// Value of key for HTTP, hashes are maps which are rendered as JSON objects.
+fn view(
+    db: &mut HashMapDb,
+    key: &str,
+    path: Option<&crate::engine::json::Path>,
+    now: std::time::Instant,
+) -> protocol::commands::CommandResponse {
+    match (db.get(key, now), path) {
+        (Some(Value::Hash(h)), None) => hash::entries(h),
+        (Some(Value::Json(doc)), path) => json::fragment(doc, path),
+        (Some(_), Some(_)) => protocol::commands::CommandResponse::Error {
+            value: WrongType.to_string(),
+        },
+        _ => get(db, key, now),
+    }
+}
 
+/// Increment the integer at key in place, so that its TTL is kept.
+fn incr(
+    db: &mut HashMapDb,
+    key: &str,
+    now: std::time::Instant,
+) -> protocol::commands::CommandResponse {
+    let incremented = match db.get_mut(key, now) {
+        None => {
+            db.set(key, 1, None);
+            return protocol::commands::CommandResponse::String {
+                value: "OK".to_owned(),
+            };
+        }
+        Some(Value::Integer(i)) => i
+            .checked_add(1)
+            .map(|n| *i = n)
+            .ok_or_else(|| "ERR increment or decrement would overflow".to_owned()),
+        Some(Value::String(_)) => Err("ERR value is not an integer or out of range".to_owned()),
+        Some(_) => Err(WrongType.to_string()),
+    };
+
+    match incremented {
+        Ok(()) => {
+            db.changed(key);
             protocol::commands::CommandResponse::String {
                 value: "OK".to_owned(),
             }
         }
-        protocol::commands::Command::FlushDb => {
-            db.flush();
+        Err(value) => protocol::commands::CommandResponse::Error { value },
+    }
+}
+
+fn latency(
+    cmd: &protocol::commands::Command,
+    server: &mut Server,
+) -> protocol::commands::CommandResponse {
+    match cmd {
+        protocol::commands::Command::LatencyLatest => latency_latest(server),
+        protocol::commands::Command::LatencyHistogram { commands } => {
+            latency_histogram(server, commands)
+        }
+        protocol::commands::Command::LatencyHistory { event } => latency_history(server, event),
+        protocol::commands::Command::LatencyReset { events } => {
+            protocol::commands::CommandResponse::Integer {
+                value: i64::try_from(server.latency.reset(events)).unwrap_or(i64::MAX),
+            }
+        }
+        _ => unreachable!("{} is not a latency command", cmd.name()),
+    }
+}
+
+fn slowlog(
+    cmd: &protocol::commands::Command,
+    server: &mut Server,
+) -> protocol::commands::CommandResponse {
+    match cmd {
+        protocol::commands::Command::SlowlogGet { count } => {
+            protocol::commands::CommandResponse::Array {
+                value: server
+                    .slowlog
+                    .get(*count)
+                    .map(slowlog_entry_response)
+                    .collect(),
+            }
+        }
+        protocol::commands::Command::SlowlogLen => protocol::commands::CommandResponse::Integer {
+            value: i64::try_from(server.slowlog.len()).unwrap_or(i64::MAX),
+        },
+        protocol::commands::Command::SlowlogReset => {
+            server.slowlog.reset();
             protocol::commands::CommandResponse::String {
                 value: "OK".to_owned(),
             }
         }
+        _ => unreachable!("{} is not a slowlog command", cmd.name()),
+    }
+}
+
+fn client(
+    cmd: protocol::commands::Command,
+    server: &mut Server,
+    client_id: u64,
+    now: std::time::Instant,
+) -> protocol::commands::CommandResponse {
+    let ok = protocol::commands::CommandResponse::String {
+        value: "OK".to_owned(),
+    };
+    let clients = &mut server.clients;
+
+    match cmd {
+        protocol::commands::Command::ClientId => to_integer(client_id),
+        protocol::commands::Command::ClientInfo => {
+            clients
+                .get(client_id)
+                .map_or(protocol::commands::CommandResponse::Null, |c| {
+                    protocol::commands::CommandResponse::BulkString {
+                        value: format!("{}\n", c.info(now)),
+                    }
+                })
+        }
+        protocol::commands::Command::ClientList { ids } => {
+            protocol::commands::CommandResponse::BulkString {
+                value: clients
+                    .iter()
+                    .filter(|c| ids.is_empty() || ids.contains(&c.id))
+                    .fold(String::new(), |mut out, c| {
+                        out.push_str(&c.info(now));
+                        out.push('\n');
+                        out
+                    }),
+            }
+        }
+        protocol::commands::Command::ClientSetName { name } => {
+            set_client_name(server, client_id, name)
+        }
+        protocol::commands::Command::ClientGetName => match clients.get(client_id) {
+            Some(c) if !c.name.is_empty() => protocol::commands::CommandResponse::BulkString {
+                value: c.name.clone(),
+            },
+            _ => protocol::commands::CommandResponse::Null,
+        },
+        protocol::commands::Command::ClientKill {
+            id,
+            addr,
+            user,
+            skip_me,
+            legacy,
+        } => {
+            let killed = clients.kill(&crate::server::clients::KillFilter {
+                id,
+                addr,
+                user,
+                me: client_id,
+                skip_me,
+            });
+            match (legacy, killed) {
+                (true, 0) => protocol::commands::CommandResponse::Error {
+                    value: "ERR No such client".to_owned(),
+                },
+                (true, _) => ok,
+                (false, n) => to_integer(n as u64),
+            }
+        }
+        protocol::commands::Command::ClientPause { timeout, all } => {
+            clients.pause(now + timeout, all);
+            ok
+        }
+        protocol::commands::Command::ClientUnpause => {
+            clients.unpause();
+            ok
+        }
+        _ => unreachable!("{} is not a client command", cmd.name()),
+    }
+}
+
+fn acl(
+    cmd: protocol::commands::Command,
+    server: &mut Server,
+    client_id: u64,
+) -> protocol::commands::CommandResponse {
+    let bulk = |v: &str| protocol::commands::CommandResponse::BulkString {
+        value: v.to_owned(),
+    };
+
+    match cmd {
+        protocol::commands::Command::AclSetUser { username, rules } => {
+            match server.acl.set_user(&username, &rules) {
+                Ok(()) => protocol::commands::CommandResponse::String {
+                    value: "OK".to_owned(),
+                },
+                Err(e) => protocol::commands::CommandResponse::Error {
+                    value: format!("ERR {e}"),
+                },
+            }
+        }
+        protocol::commands::Command::AclGetUser { username } => {
+            server
+                .acl
+                .get(&username)
+                .map_or(protocol::commands::CommandResponse::Null, |u| {
+                    protocol::commands::CommandResponse::Array {
+                        value: vec![
+                            bulk("flags"),
+                            protocol::commands::CommandResponse::Array {
+                                value: u.flags().into_iter().map(bulk).collect(),
+                            },
+                            bulk("passwords"),
+                            protocol::commands::CommandResponse::Array {
+                                value: u.passwords().iter().map(|p| bulk(p)).collect(),
+                            },
+                            bulk("commands"),
+                            bulk(&u.commands()),
+                            bulk("keys"),
+                            bulk(&u.keys()),
+                        ],
+                    }
+                })
+        }
+        protocol::commands::Command::AclDelUser { usernames } => {
+            match server.acl.del_users(&usernames) {
+                Ok(deleted) => {
+                    // NOTE: like redis, the clients authenticated as the users are disconnected.
+                    for name in &deleted {
+                        server.clients.kill(&crate::server::clients::KillFilter {
+                            user: Some(name.clone()),
+                            me: client_id,
+                            ..Default::default()
+                        });
+                    }
+                    to_integer(deleted.len() as u64)
+                }
+                Err(e) => protocol::commands::CommandResponse::Error {
+                    value: format!("ERR {e}"),
+                },
+            }
+        }
+        protocol::commands::Command::AclList => protocol::commands::CommandResponse::Array {
+            value: server.acl.users().map(|u| bulk(&u.to_string())).collect(),
+        },
+        protocol::commands::Command::AclWhoAmI => bulk(
+            server
+                .clients
+                .get(client_id)
+                .map_or("default", crate::server::clients::Client::username),
+        ),
+        _ => unreachable!("{} is not an acl command", cmd.name()),
+    }
+}
+
+/// Error returned when the client, or its address, runs too many commands per second.
+fn check_rate(
+    server: &mut Server,
+    client_id: u64,
+    now: std::time::Instant,
+) -> Option<protocol::commands::CommandResponse> {
+    let addr = server
+        .clients
+        .get(client_id)
+        .map_or("", |c| c.addr.as_str());
+    let err = server.rate_limiter.check(client_id, addr, now).err()?;
+    server.stats.limit_exceeded(err.name());
+
+    Some(protocol::commands::CommandResponse::Error {
+        value: format!("RATELIMIT {err}"),
+    })
+}
+
+/// Error returned when the client must authenticate or its user is not allowed to run the command.
+fn check_permissions(
+    cmd: &protocol::commands::Command,
+    server: &Server,
+    client_id: u64,
+) -> Option<protocol::commands::CommandResponse> {
+    if matches!(
+        cmd,
+        protocol::commands::Command::Auth { .. } | protocol::commands::Command::Hello { .. }
+    ) {
+        return None;
+    }
+
+    let Some(user) = current_user(server, client_id) else {
+        return (!matches!(cmd, protocol::commands::Command::Ping)).then(|| {
+            protocol::commands::CommandResponse::Error {
+                value: "NOAUTH Authentication required.".to_owned(),
+            }
+        });
+    };
+
+    user.check(cmd)
+        .err()
+        .map(|e| protocol::commands::CommandResponse::Error {
+            value: format!("NOPERM {e}"),
+        })
+}
+
+/// User of the client, the default one is used until the client authenticates if it does not require a password.
+/// None if the client must authenticate.
+fn current_user(server: &Server, client_id: u64) -> Option<&crate::server::acl::User> {
+    server
+        .clients
+        .get(client_id)
+        .and_then(|c| c.user.as_deref())
+        .map_or_else(
+            || server.acl.get("default").filter(|u| u.nopass),
+            |name| server.acl.get(name),
+        )
+        .filter(|u| u.enabled)
+}
+
+fn set_client_name(
+    server: &mut Server,
+    client_id: u64,
+    name: String,
+) -> protocol::commands::CommandResponse {
+    if name.chars().any(|c| !c.is_ascii_graphic()) {
+        return protocol::commands::CommandResponse::Error {
+            value: "ERR Client names cannot contain spaces, newlines or special characters."
+                .to_owned(),
+        };
+    }
+
+    if let Some(c) = server.clients.get_mut(client_id) {
+        c.name = name;
+    }
+    protocol::commands::CommandResponse::String {
+        value: "OK".to_owned(),
+    }
+}
+
+/// Authenticate the client as the user, `default` when it is not given.
+fn auth(
+    server: &mut Server,
+    client_id: u64,
+    username: Option<&str>,
+    password: &str,
+) -> protocol::commands::CommandResponse {
+    let default_nopass = server.acl.get("default").is_some_and(|u| u.nopass);
+    if username.is_none() && default_nopass {
+        return protocol::commands::CommandResponse::Error {
+            value: "ERR AUTH <password> called without any password configured for the default user. Are you sure your configuration is correct?".to_owned(),
+        };
+    }
+
+    let username = username.unwrap_or("default");
+    if server.acl.authenticate(username, password).is_none() {
+        return protocol::commands::CommandResponse::Error {
+            value: "WRONGPASS invalid username-password pair or user is disabled.".to_owned(),
+        };
+    }
+
+    if let Some(c) = server.clients.get_mut(client_id) {
+        c.user = Some(username.to_string());
+    }
+    protocol::commands::CommandResponse::String {
+        value: "OK".to_owned(),
+    }
+}
+
+fn hello(
+    server: &mut Server,
+    client_id: u64,
+    protover: Option<u8>,
+    credentials: Option<(String, String)>,
+    setname: Option<String>,
+) -> protocol::commands::CommandResponse {
+    if protover.is_some_and(|v| v != 2 && v != 3) {
+        return protocol::commands::CommandResponse::Error {
+            value: "NOPROTO unsupported protocol version".to_owned(),
+        };
+    }
+
+    if let Some((username, password)) = credentials {
+        let r = auth(server, client_id, Some(&username), &password);
+        if matches!(r, protocol::commands::CommandResponse::Error { .. }) {
+            return r;
+        }
+    }
+    if current_user(server, client_id).is_none() {
+        return protocol::commands::CommandResponse::Error {
+            value: "NOAUTH HELLO must be called with the client already authenticated, otherwise the HELLO <proto> AUTH <user> <pass> option can be used to authenticate the client and select the RESP protocol version at the same time".to_owned(),
+        };
+    }
+
+    if let Some(name) = setname {
+        let r = set_client_name(server, client_id, name);
+        if matches!(r, protocol::commands::CommandResponse::Error { .. }) {
+            return r;
+        }
+    }
+
+    let mut resp = 2;
+    if let Some(c) = server.clients.get_mut(client_id) {
+        c.resp = protover.unwrap_or(c.resp);
+        resp = c.resp;
+    }
+
+    let bulk = |v: &str| protocol::commands::CommandResponse::BulkString {
+        value: v.to_owned(),
+    };
+    protocol::commands::CommandResponse::Map {
+        value: vec![
+            (bulk("server"), bulk("redis")),
+            (bulk("version"), bulk(crate::server::info::REDIS_VERSION)),
+            (bulk("proto"), to_integer(u64::from(resp))),
+            (bulk("id"), to_integer(client_id)),
+            (bulk("mode"), bulk("standalone")),
+            (bulk("role"), bulk("master")),
+            (
+                bulk("modules"),
+                protocol::commands::CommandResponse::Array { value: vec![] },
+            ),
+        ],
+    }
+}
+
+fn latency_latest(server: &Server) -> protocol::commands::CommandResponse {
+    protocol::commands::CommandResponse::Array {
+        value: server
+            .latency
+            .events()
+            .filter_map(|(name, e)| {
+                let last = e.samples.back()?;
+                Some(protocol::commands::CommandResponse::Array {
+                    value: vec![
+                        protocol::commands::CommandResponse::BulkString {
+                            value: name.to_string(),
+                        },
+                        to_integer(last.timestamp),
+                        to_integer(as_millis(last.latency)),
+                        to_integer(as_millis(e.max)),
+                    ],
+                })
+            })
+            .collect(),
+    }
+}
+
+/// Same layout used by redis: for every command its name followed by the number of calls and the cumulative
+/// distribution of the latency in microseconds.
+fn latency_histogram(server: &Server, commands: &[String]) -> protocol::commands::CommandResponse {
+    protocol::commands::CommandResponse::Array {
+        value: server
+            .latency
+            .histograms(commands)
+            .flat_map(|(name, h)| {
+                [
+                    protocol::commands::CommandResponse::BulkString {
+                        value: name.to_string(),
+                    },
+                    protocol::commands::CommandResponse::Array {
+                        value: vec![
+                            protocol::commands::CommandResponse::BulkString {
+                                value: "calls".to_string(),
+                            },
+                            to_integer(h.count()),
+                            protocol::commands::CommandResponse::BulkString {
+                                value: "histogram_usec".to_string(),
+                            },
+                            protocol::commands::CommandResponse::Array {
+                                value: h
+                                    .cdf()
+                                    .into_iter()
+                                    .flat_map(|(usec, n)| [to_integer(usec), to_integer(n)])
+                                    .collect(),
+                            },
+                        ],
+                    },
+                ]
+            })
+            .collect(),
+    }
+}
+
+fn latency_history(server: &Server, event: &str) -> protocol::commands::CommandResponse {
+    protocol::commands::CommandResponse::Array {
+        value: server
+            .latency
+            .event(event)
+            .map(|e| {
+                e.samples
+                    .iter()
+                    .map(|s| protocol::commands::CommandResponse::Array {
+                        value: vec![to_integer(s.timestamp), to_integer(as_millis(s.latency))],
+                    })
+                    .collect()
+            })
+            .unwrap_or_default(),
+    }
+}
+
+fn to_integer(n: u64) -> protocol::commands::CommandResponse {
+    protocol::commands::CommandResponse::Integer {
+        value: i64::try_from(n).unwrap_or(i64::MAX),
+    }
+}
+
+fn as_millis(d: std::time::Duration) -> u64 {
+    u64::try_from(d.as_millis()).unwrap_or(u64::MAX)
+}
+
+/// Same layout used by redis: id, timestamp, duration in microseconds, arguments, client address and name.
+fn slowlog_entry_response(
+    e: &crate::server::slowlog::Entry,
+) -> protocol::commands::CommandResponse {
+    let to_string = |s: &str| protocol::commands::CommandResponse::BulkString {
+        value: s.to_string(),
+    };
+
+    protocol::commands::CommandResponse::Array {
+        value: vec![
+            to_integer(e.id),
+            to_integer(e.timestamp),
+            to_integer(u64::try_from(e.duration.as_micros()).unwrap_or(u64::MAX)),
+            protocol::commands::CommandResponse::Array {
+                value: e.args.iter().map(|a| to_string(a)).collect(),
+            },
+            to_string(&e.client_addr),
+            to_string(&e.client_name),
+        ],
     }
 }
 
@@ -75,15 +791,108 @@ mod tests {
         let cmd = protocol::commands::Command::Get {
             key: "key".to_string(),
         };
-        let res = execute_command(cmd, &mut db, std::time::Instant::now());
+        let res = execute_command(
+            cmd,
+            &mut db,
+            &mut Server::new(6379, &config::Config::default()),
+            0,
+            std::time::Instant::now(),
+        );
         assert_eq!(
             res,
-            protocol::commands::CommandResponse::String {
-                value: "value".to_owned()
+            protocol::commands::CommandResponse::BulkBytes {
+                value: b"value".to_vec()
             }
         );
     }
 
+    #[test]
+    fn exec_type() {
+        let mut db = HashMapDb::new(config::Engine::default());
+        let mut server = Server::new(6379, &config::Config::default());
+        let now = std::time::Instant::now();
+        db.set("string", "value".to_string(), None);
+        db.set("integer", 42, None);
+        db.set("list", Value::List(["a".to_string()].into()), None);
+
+        let mut exec = |cmd| execute_command(cmd, &mut db, &mut server, 0, now);
+        for (key, expected) in [
+            ("string", "string"),
+            ("integer", "string"),
+            ("list", "list"),
+            ("missing", "none"),
+        ] {
+            let res = exec(protocol::commands::Command::Type {
+                key: key.to_string(),
+            });
+            assert_eq!(
+                res,
+                protocol::commands::CommandResponse::String {
+                    value: expected.to_owned()
+                }
+            );
+        }
+
+        let wrong_type = protocol::commands::CommandResponse::Error {
+            value: WrongType.to_string(),
+        };
+        let res = exec(protocol::commands::Command::Get {
+            key: "list".to_string(),
+        });
+        assert_eq!(res, wrong_type);
+        let res = exec(protocol::commands::Command::Incr {
+            key: "list".to_string(),
+        });
+        assert_eq!(res, wrong_type);
+        let res = exec(protocol::commands::Command::Incr {
+            key: "string".to_string(),
+        });
+        assert_eq!(
+            res,
+            protocol::commands::CommandResponse::Error {
+                value: "ERR value is not an integer or out of range".to_owned()
+            }
+        );
+    }
+
+    #[test]
+    fn exec_slash_keys() {
+        let mut db = HashMapDb::new(config::Engine::default());
+        let mut server = Server::new(6379, &config::Config::default());
+        let now = std::time::Instant::now();
+        let mut exec = |kind, key, value: Option<&str>, options: &[&str]| {
+            let options = options.iter().map(ToString::to_string).collect::<Vec<_>>();
+            let cmd = protocol::commands::Command::new(
+                kind,
+                key,
+                value.map(ToString::to_string),
+                &options,
+            )
+            .unwrap();
+            execute_command(cmd, &mut db, &mut server, 0, now)
+        };
+
+        exec("xadd", "/s", Some("*"), &["f", "v"]);
+        exec("setbit", "/b", Some("7"), &["1"]);
+        for (key, kind) in [("/s", "stream"), ("/b", "string")] {
+            let res = exec("type", key, None, &[]);
+            assert_eq!(
+                res,
+                protocol::commands::CommandResponse::String {
+                    value: kind.to_owned()
+                }
+            );
+            exec("del", key, None, &[]);
+            let res = exec("type", key, None, &[]);
+            assert_eq!(
+                res,
+                protocol::commands::CommandResponse::String {
+                    value: "none".to_owned()
+                }
+            );
+        }
+    }
+
     #[test]
     fn exec_exists() {
         let mut db = HashMapDb::new(config::Engine::default());
@@ -92,11 +901,477 @@ mod tests {
         let cmd = protocol::commands::Command::Exists {
             key: "key".to_string(),
         };
-        let res = execute_command(cmd, &mut db, std::time::Instant::now());
+        let res = execute_command(
+            cmd,
+            &mut db,
+            &mut Server::new(6379, &config::Config::default()),
+            0,
+            std::time::Instant::now(),
+        );
+        assert_eq!(
+            res,
+            protocol::commands::CommandResponse::Integer { value: 1 }
+        );
+    }
+
+    #[test]
+    fn exec_info() {
+        let mut db = HashMapDb::new(config::Engine::default());
+
+        let cmd = protocol::commands::Command::Info {
+            sections: vec!["clients".to_string()],
+        };
+        let res = execute_command(
+            cmd,
+            &mut db,
+            &mut Server::new(6379, &config::Config::default()),
+            0,
+            std::time::Instant::now(),
+        );
+        assert_eq!(
+            res,
+            protocol::commands::CommandResponse::BulkString {
+                value: "# Clients\r\nconnected_clients:0\r\nblocked_clients:0\r\n".to_owned()
+            }
+        );
+    }
+
+    #[test]
+    fn exec_slowlog() {
+        let mut db = HashMapDb::new(config::Engine::default());
+        let mut server = Server::new(
+            6379,
+            &config::Config {
+                slowlog: config::Slowlog {
+                    log_slower_than: std::time::Duration::ZERO,
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+        );
+        server.slowlog.record(
+            &["get".to_string(), "key".to_string()],
+            std::time::Duration::from_micros(15),
+            "127.0.0.1:5000",
+            "",
+        );
+
+        let cmd = protocol::commands::Command::SlowlogLen;
+        let res = execute_command(cmd, &mut db, &mut server, 0, std::time::Instant::now());
+        assert_eq!(
+            res,
+            protocol::commands::CommandResponse::Integer { value: 1 }
+        );
+
+        let cmd = protocol::commands::Command::SlowlogGet { count: Some(10) };
+        let res = execute_command(cmd, &mut db, &mut server, 0, std::time::Instant::now());
+        let protocol::commands::CommandResponse::Array { value } = res else {
+            panic!("slowlog get should return an array");
+        };
+        let protocol::commands::CommandResponse::Array { value: entry } = &value[0] else {
+            panic!("slowlog entry should be an array");
+        };
+        assert_eq!(
+            entry[2],
+            protocol::commands::CommandResponse::Integer { value: 15 }
+        );
+        assert_eq!(
+            entry[4],
+            protocol::commands::CommandResponse::BulkString {
+                value: "127.0.0.1:5000".to_string()
+            }
+        );
+
+        let cmd = protocol::commands::Command::SlowlogReset;
+        execute_command(cmd, &mut db, &mut server, 0, std::time::Instant::now());
+        assert_eq!(server.slowlog.len(), 0);
+    }
+
+    #[test]
+    fn exec_latency() {
+        let mut db = HashMapDb::new(config::Engine::default());
+        let mut server = Server::new(
+            6379,
+            &config::Config {
+                latency: config::Latency {
+                    tracking: true,
+                    monitor_threshold: std::time::Duration::from_millis(1),
+                },
+                ..Default::default()
+            },
+        );
+        server
+            .latency
+            .record_command("get", std::time::Duration::from_micros(3));
+        server
+            .latency
+            .record_event("snapshot-persistence", std::time::Duration::from_millis(7));
+
+        let cmd = protocol::commands::Command::LatencyHistogram { commands: vec![] };
+        let res = execute_command(cmd, &mut db, &mut server, 0, std::time::Instant::now());
+        assert_eq!(
+            res,
+            protocol::commands::CommandResponse::Array {
+                value: vec![
+                    protocol::commands::CommandResponse::BulkString {
+                        value: "get".to_string()
+                    },
+                    protocol::commands::CommandResponse::Array {
+                        value: vec![
+                            protocol::commands::CommandResponse::BulkString {
+                                value: "calls".to_string()
+                            },
+                            protocol::commands::CommandResponse::Integer { value: 1 },
+                            protocol::commands::CommandResponse::BulkString {
+                                value: "histogram_usec".to_string()
+                            },
+                            protocol::commands::CommandResponse::Array {
+                                value: vec![
+                                    protocol::commands::CommandResponse::Integer { value: 4 },
+                                    protocol::commands::CommandResponse::Integer { value: 1 },
+                                ]
+                            },
+                        ]
+                    },
+                ]
+            }
+        );
+
+        let cmd = protocol::commands::Command::LatencyLatest;
+        let res = execute_command(cmd, &mut db, &mut server, 0, std::time::Instant::now());
+        let protocol::commands::CommandResponse::Array { value } = res else {
+            panic!("latency latest should return an array");
+        };
+        let protocol::commands::CommandResponse::Array { value: event } = &value[0] else {
+            panic!("latency event should be an array");
+        };
+        assert_eq!(
+            event[0],
+            protocol::commands::CommandResponse::BulkString {
+                value: "snapshot-persistence".to_string()
+            }
+        );
+        assert_eq!(
+            event[3],
+            protocol::commands::CommandResponse::Integer { value: 7 }
+        );
+
+        let cmd = protocol::commands::Command::LatencyReset { events: vec![] };
+        let res = execute_command(cmd, &mut db, &mut server, 0, std::time::Instant::now());
+        assert_eq!(
+            res,
+            protocol::commands::CommandResponse::Integer { value: 1 }
+        );
+    }
+
+    #[test]
+    fn exec_client() {
+        let mut db = HashMapDb::new(config::Engine::default());
+        let mut server = Server::new(6379, &config::Config::default());
+        let now = std::time::Instant::now();
+        let _rx1 = server.clients.register(1, "127.0.0.1:1".to_string(), now);
+        let _rx2 = server.clients.register(2, "127.0.0.1:2".to_string(), now);
+
+        let cmd = protocol::commands::Command::ClientGetName;
+        let res = execute_command(cmd, &mut db, &mut server, 1, now);
+        assert_eq!(res, protocol::commands::CommandResponse::Null);
+
+        let cmd = protocol::commands::Command::ClientSetName {
+            name: "worker".to_string(),
+        };
+        execute_command(cmd, &mut db, &mut server, 1, now);
+        assert_eq!(server.clients.get(1).unwrap().name, "worker");
+
+        let cmd = protocol::commands::Command::ClientList { ids: vec![1] };
+        let res = execute_command(cmd, &mut db, &mut server, 1, now);
+        let protocol::commands::CommandResponse::BulkString { value } = res else {
+            panic!("client list should return a bulk string");
+        };
+        assert!(value.starts_with("id=1 addr=127.0.0.1:1 name=worker "));
+        assert_eq!(value.lines().count(), 1);
+
+        let cmd = protocol::commands::Command::ClientKill {
+            id: None,
+            addr: Some("127.0.0.1:9".to_string()),
+            user: None,
+            skip_me: false,
+            legacy: true,
+        };
+        let res = execute_command(cmd, &mut db, &mut server, 1, now);
+        assert_eq!(
+            res,
+            protocol::commands::CommandResponse::Error {
+                value: "ERR No such client".to_string()
+            }
+        );
+
+        let cmd = protocol::commands::Command::ClientKill {
+            id: None,
+            addr: None,
+            user: None,
+            skip_me: true,
+            legacy: false,
+        };
+        let res = execute_command(cmd, &mut db, &mut server, 1, now);
         assert_eq!(
             res,
             protocol::commands::CommandResponse::Integer { value: 1 }
         );
+        assert!(server.clients.get(2).is_none());
+    }
+
+    #[test]
+    fn exec_auth() {
+        let mut db = HashMapDb::new(config::Engine::default());
+        let mut server = Server::new(
+            6379,
+            &config::Config {
+                security: config::Security {
+                    requirepass: Some("secret".to_string()),
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+        );
+        let now = std::time::Instant::now();
+        let _rx = server.clients.register(1, "127.0.0.1:1".to_string(), now);
+
+        let cmd = protocol::commands::Command::Exists {
+            key: "key".to_string(),
+        };
+        let res = execute_command(cmd, &mut db, &mut server, 1, now);
+        assert_eq!(
+            res,
+            protocol::commands::CommandResponse::Error {
+                value: "NOAUTH Authentication required.".to_string()
+            }
+        );
+
+        let res = execute_command(
+            protocol::commands::Command::Ping,
+            &mut db,
+            &mut server,
+            1,
+            now,
+        );
+        assert_eq!(
+            res,
+            protocol::commands::CommandResponse::String {
+                value: "PONG".to_string()
+            }
+        );
+
+        let cmd = protocol::commands::Command::Hello {
+            protover: None,
+            auth: None,
+            setname: None,
+        };
+        let res = execute_command(cmd, &mut db, &mut server, 1, now);
+        assert!(
+            matches!(res, protocol::commands::CommandResponse::Error { value } if value.starts_with("NOAUTH"))
+        );
+
+        let cmd = protocol::commands::Command::Auth {
+            username: Some("admin".to_string()),
+            password: "secret".to_string(),
+        };
+        let res = execute_command(cmd, &mut db, &mut server, 1, now);
+        assert!(
+            matches!(res, protocol::commands::CommandResponse::Error { value } if value.starts_with("WRONGPASS"))
+        );
+
+        let cmd = protocol::commands::Command::Hello {
+            protover: Some(2),
+            auth: Some(("default".to_string(), "secret".to_string())),
+            setname: Some("app".to_string()),
+        };
+        let res = execute_command(cmd, &mut db, &mut server, 1, now);
+        assert!(matches!(
+            res,
+            protocol::commands::CommandResponse::Map { .. }
+        ));
+        assert_eq!(
+            server.clients.get(1).unwrap().user.as_deref(),
+            Some("default")
+        );
+        assert_eq!(server.clients.get(1).unwrap().name, "app");
+        assert_eq!(server.clients.get(1).unwrap().resp, 2);
+
+        let cmd = protocol::commands::Command::Hello {
+            protover: Some(3),
+            auth: None,
+            setname: None,
+        };
+        execute_command(cmd, &mut db, &mut server, 1, now);
+        assert_eq!(server.clients.get(1).unwrap().resp, 3);
+        let cmd = protocol::commands::Command::Hello {
+            protover: Some(4),
+            auth: None,
+            setname: None,
+        };
+        let res = execute_command(cmd, &mut db, &mut server, 1, now);
+        assert!(
+            matches!(res, protocol::commands::CommandResponse::Error { value } if value.starts_with("NOPROTO"))
+        );
+
+        let cmd = protocol::commands::Command::Exists {
+            key: "key".to_string(),
+        };
+        let res = execute_command(cmd, &mut db, &mut server, 1, now);
+        assert_eq!(
+            res,
+            protocol::commands::CommandResponse::Integer { value: 0 }
+        );
+    }
+
+    #[test]
+    fn exec_auth_without_password() {
+        let mut db = HashMapDb::new(config::Engine::default());
+        let mut server = Server::new(6379, &config::Config::default());
+
+        let cmd = protocol::commands::Command::Auth {
+            username: None,
+            password: "secret".to_string(),
+        };
+        let res = execute_command(cmd, &mut db, &mut server, 1, std::time::Instant::now());
+        assert!(
+            matches!(res, protocol::commands::CommandResponse::Error { value } if value.starts_with("ERR AUTH"))
+        );
+
+        let cmd = protocol::commands::Command::Auth {
+            username: Some("default".to_string()),
+            password: "anything".to_string(),
+        };
+        let res = execute_command(cmd, &mut db, &mut server, 1, std::time::Instant::now());
+        assert_eq!(
+            res,
+            protocol::commands::CommandResponse::String {
+                value: "OK".to_string()
+            }
+        );
+    }
+
+    #[test]
+    fn exec_acl() {
+        let mut db = HashMapDb::new(config::Engine::default());
+        let mut server = Server::new(6379, &config::Config::default());
+        let now = std::time::Instant::now();
+        let rx = server.clients.register(1, "127.0.0.1:1".to_string(), now);
+        let mut exec = |cmd| execute_command(cmd, &mut db, &mut server, 1, now);
+
+        let cmd = protocol::commands::Command::AclSetUser {
+            username: "sessions".to_string(),
+            rules: ["on", ">secret", "~session:*", "+@read", "+set"]
+                .map(str::to_string)
+                .to_vec(),
+        };
+        assert_eq!(
+            exec(cmd),
+            protocol::commands::CommandResponse::String {
+                value: "OK".to_string()
+            }
+        );
+
+        assert_eq!(
+            exec(protocol::commands::Command::AclWhoAmI),
+            protocol::commands::CommandResponse::BulkString {
+                value: "default".to_string()
+            }
+        );
+
+        let cmd = protocol::commands::Command::Auth {
+            username: Some("sessions".to_string()),
+            password: "secret".to_string(),
+        };
+        assert_eq!(
+            exec(cmd),
+            protocol::commands::CommandResponse::String {
+                value: "OK".to_string()
+            }
+        );
+
+        let cmd = protocol::commands::Command::Exists {
+            key: "session:1".to_string(),
+        };
+        assert_eq!(
+            exec(cmd),
+            protocol::commands::CommandResponse::Integer { value: 0 }
+        );
+
+        let cmd = protocol::commands::Command::Exists {
+            key: "user:1".to_string(),
+        };
+        assert_eq!(
+            exec(cmd),
+            protocol::commands::CommandResponse::Error {
+                value: "NOPERM No permissions to access a key".to_string()
+            }
+        );
+        assert_eq!(
+            exec(protocol::commands::Command::FlushDb),
+            protocol::commands::CommandResponse::Error {
+                value: "NOPERM User sessions has no permissions to run the 'flushdb' command"
+                    .to_string()
+            }
+        );
+
+        let cmd = protocol::commands::Command::AclDelUser {
+            usernames: vec!["sessions".to_string()],
+        };
+        assert_eq!(
+            execute_command(cmd, &mut db, &mut server, 0, now),
+            protocol::commands::CommandResponse::Integer { value: 1 }
+        );
+        assert!(server.clients.get(1).is_none());
+        drop(rx);
+    }
+
+    #[test]
+    fn exec_rate_limit() {
+        let mut db = HashMapDb::new(config::Engine::default());
+        let mut server = Server::new(
+            6379,
+            &config::Config {
+                limits: config::Limits {
+                    max_commands_per_second: 1,
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+        );
+        let now = std::time::Instant::now();
+        let _rx = server.clients.register(1, "127.0.0.1:1".to_string(), now);
+
+        let res = execute_command(
+            protocol::commands::Command::Ping,
+            &mut db,
+            &mut server,
+            1,
+            now,
+        );
+        assert_eq!(
+            res,
+            protocol::commands::CommandResponse::String {
+                value: "PONG".to_string()
+            }
+        );
+        let res = execute_command(
+            protocol::commands::Command::Ping,
+            &mut db,
+            &mut server,
+            1,
+            now,
+        );
+        assert_eq!(
+            res,
+            protocol::commands::CommandResponse::Error {
+                value: "RATELIMIT max 1 commands per second per client".to_string()
+            }
+        );
+        assert_eq!(
+            server.stats.limits_exceeded.get("max_commands_per_second"),
+            Some(&1)
+        );
     }
 
     #[test]
@@ -108,7 +1383,13 @@ mod tests {
             key: "key".to_string(),
         };
 
-        let res = execute_command(cmd, &mut db, std::time::Instant::now());
+        let res = execute_command(
+            cmd,
+            &mut db,
+            &mut Server::new(6379, &config::Config::default()),
+            0,
+            std::time::Instant::now(),
+        );
         assert_eq!(
             res,
             protocol::commands::CommandResponse::String {
@@ -116,18 +1397,22 @@ mod tests {
             }
         );
 
-        let v = db
-            .get("key", std::time::Instant::now())
-            .unwrap()
-            .parse::<u64>()
-            .unwrap();
-        assert_eq!(v, 1);
+        assert_eq!(
+            db.get("key", std::time::Instant::now()),
+            Some(&Value::Integer(1))
+        );
 
         // incr with key
         let cmd = protocol::commands::Command::Incr {
             key: "key".to_string(),
         };
-        let res = execute_command(cmd, &mut db, std::time::Instant::now());
+        let res = execute_command(
+            cmd,
+            &mut db,
+            &mut Server::new(6379, &config::Config::default()),
+            0,
+            std::time::Instant::now(),
+        );
         assert_eq!(
             res,
             protocol::commands::CommandResponse::String {
@@ -135,11 +1420,9 @@ mod tests {
             }
         );
 
-        let v = db
-            .get("key", std::time::Instant::now())
-            .unwrap()
-            .parse::<u64>()
-            .unwrap();
-        assert_eq!(v, 2);
+        assert_eq!(
+            db.get("key", std::time::Instant::now()),
+            Some(&Value::Integer(2))
+        );
     }
 }
@@ -0,0 +1,224 @@
+use crate::engine::bitmap::{self, FieldOp};
+use crate::engine::db::HashMapDb;
+use crate::engine::value::Value;
+use crate::protocol::commands::{Command, CommandResponse};
+use std::borrow::Cow;
+
+/// Execute the bitmap commands, they work on the bytes of string values. A missing key is an empty string.
+pub fn bitmap(cmd: Command, db: &mut HashMapDb, now: std::time::Instant) -> CommandResponse {
+    let r = match cmd {
+        Command::SetBit { key, offset, value } => db
+            .get_or_insert_with(&key, now, || Value::String(vec![]))
+            .as_bytes_mut()
+            .map(|bytes| i64::from(bitmap::set_bit(bytes, offset, value)))
+            .map_err(|e| e.to_string())
+            .map(|previous| {
+                db.changed(&key);
+                CommandResponse::Integer { value: previous }
+            }),
+        Command::GetBit { key, offset } => read(db, &key, now).map(|b| CommandResponse::Integer {
+            value: i64::from(bitmap::get_bit(&b, offset)),
+        }),
+        Command::BitCount { key, range, unit } => {
+            read(db, &key, now).map(|b| super::to_integer(bitmap::count(&b, range, unit)))
+        }
+        Command::BitPos {
+            key,
+            bit,
+            start,
+            end,
+            unit,
+        } => read(db, &key, now).map(|b| CommandResponse::Integer {
+            value: bitmap::position(&b, bit, start, end, unit),
+        }),
+        Command::BitOp {
+            op,
+            destination,
+            keys,
+        } => keys
+            .iter()
+            .map(|k| read(db, k, now).map(Cow::into_owned))
+            .collect::<Result<Vec<Vec<u8>>, String>>()
+            .map(|sources| {
+                let result = bitmap::operation(op, &sources);
+                let len = result.len();
+                if result.is_empty() {
+                    db.del(&destination);
+                } else {
+                    db.set(&destination, Value::String(result), None);
+                }
+                super::to_integer(len as u64)
+            }),
+        Command::BitField { key, ops } => bitfield(db, &key, &ops, now),
+        _ => unreachable!("not a bitmap command"),
+    };
+
+    r.unwrap_or_else(|value| CommandResponse::Error { value })
+}
+
+/// Bytes of the string stored at key, empty if the key does not exist.
+fn read<'a>(
+    db: &'a mut HashMapDb,
+    key: &str,
+    now: std::time::Instant,
+) -> Result<Cow<'a, [u8]>, String> {
+    db.get(key, now)
+        .map_or(Ok(Cow::Borrowed(&[][..])), Value::as_bytes)
+        .map_err(|e| e.to_string())
+}
+
+/// BITFIELD only creates the string for its writes, a failed write is a nil reply.
+fn bitfield(
+    db: &mut HashMapDb,
+    key: &str,
+    ops: &[FieldOp],
+    now: std::time::Instant,
+) -> Result<CommandResponse, String> {
+    let writes = ops
+        .iter()
+        .any(|op| matches!(op, FieldOp::Set(..) | FieldOp::IncrBy(..)));
+    let replies = if writes {
+        let bytes = db
+            .get_or_insert_with(key, now, || Value::String(vec![]))
+            .as_bytes_mut()
+            .map_err(|e| e.to_string())?;
+        let replies = bitmap::bitfield(bytes, ops);
+        if bytes.is_empty() {
+            db.del(key);
+        } else {
+            db.changed(key);
+        }
+        replies
+    } else {
+        bitmap::bitfield(&mut read(db, key, now)?.into_owned(), ops)
+    };
+
+    Ok(CommandResponse::Array {
+        value: replies
+            .into_iter()
+            .map(|r| {
+                r.map_or(CommandResponse::Null, |value| CommandResponse::Integer {
+                    value,
+                })
+            })
+            .collect(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config;
+    use crate::engine::bitmap::{Field, Operation, Overflow, Unit};
+
+    #[test]
+    fn commands() {
+        let mut db = HashMapDb::new(config::Engine::default());
+        let now = std::time::Instant::now();
+        let mut exec = |cmd| bitmap(cmd, &mut db, now);
+        let setbit = |key: &str, offset| Command::SetBit {
+            key: key.to_string(),
+            offset,
+            value: true,
+        };
+
+        assert_eq!(exec(setbit("a", 0)), CommandResponse::Integer { value: 0 });
+        assert_eq!(exec(setbit("a", 0)), CommandResponse::Integer { value: 1 });
+        assert_eq!(exec(setbit("a", 15)), CommandResponse::Integer { value: 0 });
+        assert_eq!(exec(setbit("b", 1)), CommandResponse::Integer { value: 0 });
+
+        let res = exec(Command::BitCount {
+            key: "a".to_string(),
+            range: None,
+            unit: Unit::Byte,
+        });
+        assert_eq!(res, CommandResponse::Integer { value: 2 });
+        let res = exec(Command::BitPos {
+            key: "a".to_string(),
+            bit: true,
+            start: Some(1),
+            end: None,
+            unit: Unit::Byte,
+        });
+        assert_eq!(res, CommandResponse::Integer { value: 15 });
+
+        let res = exec(Command::BitOp {
+            op: Operation::Or,
+            destination: "c".to_string(),
+            keys: vec!["a".to_string(), "b".to_string(), "missing".to_string()],
+        });
+        assert_eq!(res, CommandResponse::Integer { value: 2 });
+        let res = exec(Command::GetBit {
+            key: "c".to_string(),
+            offset: 1,
+        });
+        assert_eq!(res, CommandResponse::Integer { value: 1 });
+        let res = exec(Command::BitOp {
+            op: Operation::Not,
+            destination: "c".to_string(),
+            keys: vec!["missing".to_string()],
+        });
+        assert_eq!(res, CommandResponse::Integer { value: 0 });
+        assert_eq!(db.get("c", now), None);
+
+        // NOTE: an integer is a string for the bitmap commands, "1" is 0x31.
+        db.set("n", "1".to_string(), None);
+        let res = bitmap(
+            Command::GetBit {
+                key: "n".to_string(),
+                offset: 7,
+            },
+            &mut db,
+            now,
+        );
+        assert_eq!(res, CommandResponse::Integer { value: 1 });
+        bitmap(setbit("n", 6), &mut db, now);
+        assert_eq!(db.get("n", now), Some(&Value::String(b"3".to_vec())));
+    }
+
+    #[test]
+    fn bitfield() {
+        let mut db = HashMapDb::new(config::Engine::default());
+        let now = std::time::Instant::now();
+        let u8 = Field::parse("u8").unwrap();
+
+        let res = bitmap(
+            Command::BitField {
+                key: "f".to_string(),
+                ops: vec![FieldOp::Get(u8, 0)],
+            },
+            &mut db,
+            now,
+        );
+        assert_eq!(
+            res,
+            CommandResponse::Array {
+                value: vec![CommandResponse::Integer { value: 0 }]
+            }
+        );
+        assert_eq!(db.get("f", now), None);
+
+        let res = bitmap(
+            Command::BitField {
+                key: "f".to_string(),
+                ops: vec![
+                    FieldOp::Overflow(Overflow::Fail),
+                    FieldOp::IncrBy(u8, 0, 300),
+                    FieldOp::IncrBy(u8, 0, 200),
+                ],
+            },
+            &mut db,
+            now,
+        );
+        assert_eq!(
+            res,
+            CommandResponse::Array {
+                value: vec![
+                    CommandResponse::Null,
+                    CommandResponse::Integer { value: 200 }
+                ]
+            }
+        );
+        assert_eq!(db.get("f", now), Some(&Value::String(vec![200])));
+    }
+}
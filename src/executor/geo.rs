@@ -0,0 +1,313 @@
+use super::zset;
+use crate::engine::db::HashMapDb;
+use crate::engine::geo::{self, Shape, Unit};
+use crate::engine::zset::SortedSet;
+use crate::protocol::commands::{Command, CommandResponse, GeoFrom, GeoSearchOptions};
+use std::ops::Bound;
+
+/// Execute the geo commands. The positions are members of a sorted set whose score is their geohash, so the
+/// sorted set commands work on them too.
+pub fn geo(cmd: Command, db: &mut HashMapDb, now: std::time::Instant) -> CommandResponse {
+    let r = match cmd {
+        Command::GeoAdd {
+            key,
+            members,
+            flags,
+        } => {
+            if let Some((lon, lat, _)) = members
+                .iter()
+                .find(|(lon, lat, _)| !geo::is_valid((*lon, *lat)))
+            {
+                Err(invalid_position(*lon, *lat))
+            } else {
+                let members = members
+                    .into_iter()
+                    .map(|(lon, lat, m)| (score(geo::encode((lon, lat))), m))
+                    .collect();
+                zset::zadd(db, &key, members, flags, now)
+            }
+        }
+        Command::GeoPos { key, members } => zset::read(db, &key, now).map(|z| {
+            let positions = members
+                .iter()
+                .map(|m| position(z, m).map_or(CommandResponse::Null, coordinates))
+                .collect();
+            CommandResponse::Array { value: positions }
+        }),
+        Command::GeoDist {
+            key,
+            from,
+            to,
+            unit,
+        } => zset::read(db, &key, now).map(|z| match (position(z, &from), position(z, &to)) {
+            (Some(a), Some(b)) => distance(geo::distance(a, b), unit),
+            _ => CommandResponse::Null,
+        }),
+        Command::GeoSearch {
+            key,
+            from,
+            shape,
+            unit,
+            options,
+        } => zset::read(db, &key, now).and_then(|z| {
+            z.map_or(Ok(CommandResponse::Array { value: vec![] }), |z| {
+                search(z, from, shape, unit, options)
+            })
+        }),
+        _ => unreachable!("not a geo command"),
+    };
+
+    r.unwrap_or_else(|value| CommandResponse::Error { value })
+}
+
+/// Members within the shape with their distance and geohash, found in the cells around the center and then sorted
+/// by distance.
+fn search(
+    z: &SortedSet,
+    from: GeoFrom,
+    shape: Shape,
+    unit: Unit,
+    options: GeoSearchOptions,
+) -> Result<CommandResponse, String> {
+    let center = match from {
+        GeoFrom::Position(lon, lat) if geo::is_valid((lon, lat)) => (lon, lat),
+        GeoFrom::Position(lon, lat) => return Err(invalid_position(lon, lat)),
+        GeoFrom::Member(m) => position(Some(z), &m)
+            .ok_or_else(|| "ERR could not decode requested zset member".to_owned())?,
+    };
+
+    let mut found = vec![];
+    // NOTE: with ANY the search stops at the first members found, they are sorted afterwards.
+    'cells: for (start, end) in geo::ranges(center, shape) {
+        let (start, end) = (Bound::Included(score(start)), Bound::Excluded(score(end)));
+        for (member, s) in z.range_by_score(start, end, false) {
+            let hash = hash(s);
+            if let Some(d) = shape.contains(center, geo::decode(hash)) {
+                found.push((member, d, hash));
+                if options.count == Some((found.len(), true)) {
+                    break 'cells;
+                }
+            }
+        }
+    }
+
+    found.sort_by(|a, b| a.1.total_cmp(&b.1));
+    if options.desc {
+        found.reverse();
+    }
+    if let Some((n, _)) = options.count {
+        found.truncate(n);
+    }
+
+    let with_anything = options.with_coord || options.with_dist || options.with_hash;
+    let results = found
+        .into_iter()
+        .map(|(member, d, hash)| {
+            if !with_anything {
+                return bulk(member);
+            }
+            let mut result = vec![bulk(member)];
+            if options.with_dist {
+                result.push(distance(d, unit));
+            }
+            if options.with_hash {
+                result.push(super::to_integer(hash));
+            }
+            if options.with_coord {
+                result.push(coordinates(geo::decode(hash)));
+            }
+            CommandResponse::Array { value: result }
+        })
+        .collect();
+
+    Ok(CommandResponse::Array { value: results })
+}
+
+fn position(z: Option<&SortedSet>, member: &str) -> Option<(f64, f64)> {
+    z.and_then(|z| z.score(member))
+        .map(|s| geo::decode(hash(s)))
+}
+
+#[allow(clippy::cast_precision_loss)]
+const fn score(hash: u64) -> f64 {
+    hash as f64
+}
+
+/// Geohash of the score, the 52 bits are stored exactly by a f64.
+#[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+const fn hash(score: f64) -> u64 {
+    score as u64
+}
+
+fn invalid_position(lon: f64, lat: f64) -> String {
+    format!("ERR invalid longitude,latitude pair {lon:.6},{lat:.6}")
+}
+
+fn coordinates((lon, lat): (f64, f64)) -> CommandResponse {
+    CommandResponse::Array {
+        value: vec![bulk(lon.to_string()), bulk(lat.to_string())],
+    }
+}
+
+/// Distance in the unit with 4 decimals like redis.
+fn distance(meters: f64, unit: Unit) -> CommandResponse {
+    bulk(format!("{:.4}", meters / unit.meters()))
+}
+
+fn bulk(value: impl Into<String>) -> CommandResponse {
+    CommandResponse::BulkString {
+        value: value.into(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config;
+    use crate::protocol::commands::ZAddFlags;
+
+    fn sicily(db: &mut HashMapDb, now: std::time::Instant) {
+        let res = geo(
+            Command::GeoAdd {
+                key: "sicily".to_string(),
+                members: vec![
+                    (13.361_389, 38.115_556, "Palermo".to_string()),
+                    (15.087_269, 37.502_669, "Catania".to_string()),
+                    (12.758_489, 38.788_135, "edge1".to_string()),
+                    (17.241_510, 38.788_135, "edge2".to_string()),
+                ],
+                flags: ZAddFlags::default(),
+            },
+            db,
+            now,
+        );
+        assert_eq!(res, CommandResponse::Integer { value: 4 });
+    }
+
+    #[test]
+    fn positions() {
+        let mut db = HashMapDb::new(config::Engine::default());
+        let now = std::time::Instant::now();
+        sicily(&mut db, now);
+
+        let res = geo(
+            Command::GeoDist {
+                key: "sicily".to_string(),
+                from: "Palermo".to_string(),
+                to: "Catania".to_string(),
+                unit: Unit::Kilometers,
+            },
+            &mut db,
+            now,
+        );
+        assert_eq!(res, bulk("166.2742"));
+
+        let res = geo(
+            Command::GeoPos {
+                key: "sicily".to_string(),
+                members: vec!["Palermo".to_string(), "missing".to_string()],
+            },
+            &mut db,
+            now,
+        );
+        let CommandResponse::Array { value } = res else {
+            panic!("unexpected response {res:?}");
+        };
+        assert_eq!(
+            value,
+            [
+                coordinates(geo::decode(geo::encode((13.361_389, 38.115_556)))),
+                CommandResponse::Null
+            ]
+        );
+
+        let res = geo(
+            Command::GeoAdd {
+                key: "sicily".to_string(),
+                members: vec![(0.0, 89.0, "pole".to_string())],
+                flags: ZAddFlags::default(),
+            },
+            &mut db,
+            now,
+        );
+        assert_eq!(
+            res,
+            CommandResponse::Error {
+                value: "ERR invalid longitude,latitude pair 0.000000,89.000000".to_string()
+            }
+        );
+    }
+
+    #[test]
+    fn search() {
+        let mut db = HashMapDb::new(config::Engine::default());
+        let now = std::time::Instant::now();
+        sicily(&mut db, now);
+        let mut search = |from, shape, options| {
+            geo(
+                Command::GeoSearch {
+                    key: "sicily".to_string(),
+                    from,
+                    shape,
+                    unit: Unit::Kilometers,
+                    options,
+                },
+                &mut db,
+                now,
+            )
+        };
+        let members = |v: &[&str]| CommandResponse::Array {
+            value: v.iter().map(|m| bulk(*m)).collect(),
+        };
+
+        // NOTE: the examples of the GEOSEARCH documentation of redis.
+        let res = search(
+            GeoFrom::Position(15.0, 37.0),
+            Shape::Radius(200_000.0),
+            GeoSearchOptions::default(),
+        );
+        assert_eq!(res, members(&["Catania", "Palermo"]));
+        let res = search(
+            GeoFrom::Position(15.0, 37.0),
+            Shape::Box {
+                width: 400_000.0,
+                height: 400_000.0,
+            },
+            GeoSearchOptions {
+                desc: true,
+                with_dist: true,
+                ..GeoSearchOptions::default()
+            },
+        );
+        let entry = |m: &str, d: &str| CommandResponse::Array {
+            value: vec![bulk(m), bulk(d)],
+        };
+        assert_eq!(
+            res,
+            CommandResponse::Array {
+                value: vec![
+                    entry("edge1", "279.7405"),
+                    entry("edge2", "279.7403"),
+                    entry("Palermo", "190.4424"),
+                    entry("Catania", "56.4413"),
+                ]
+            }
+        );
+
+        let res = search(
+            GeoFrom::Member("Palermo".to_string()),
+            Shape::Radius(3000.0),
+            GeoSearchOptions {
+                count: Some((1, false)),
+                ..GeoSearchOptions::default()
+            },
+        );
+        assert_eq!(res, members(&["Palermo"]));
+        let res = search(
+            GeoFrom::Member("missing".to_string()),
+            Shape::Radius(3000.0),
+            GeoSearchOptions::default(),
+        );
+        assert!(matches!(res, CommandResponse::Error { .. }));
+    }
+}
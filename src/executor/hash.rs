@@ -0,0 +1,312 @@
+use crate::engine::db::HashMapDb;
+use crate::engine::value::Value;
+use crate::protocol::commands::{Command, CommandResponse};
+use std::collections::HashMap;
+
+/// Execute the commands of the hash type, a hash left without fields is removed.
+pub fn hash(cmd: Command, db: &mut HashMapDb, now: std::time::Instant) -> CommandResponse {
+    let r = match cmd {
+        Command::HSet { key, fields } => hset(db, &key, fields, now),
+        Command::HGet { key, field } => read(db, &key, now).map(|h| {
+            h.and_then(|h| h.get(&field))
+                .map_or(CommandResponse::Null, bulk)
+        }),
+        Command::HMGet { key, fields } => read(db, &key, now).map(|h| CommandResponse::Array {
+            value: fields
+                .iter()
+                .map(|f| h.and_then(|h| h.get(f)).map_or(CommandResponse::Null, bulk))
+                .collect(),
+        }),
+        Command::HDel { key, fields } => hdel(db, &key, &fields, now),
+        Command::HGetAll { key } => {
+            read(db, &key, now).map(|h| h.map_or(CommandResponse::Map { value: vec![] }, entries))
+        }
+        Command::HIncrBy {
+            key,
+            field,
+            increment,
+        } => hincrby(db, &key, field, increment, now),
+        Command::HExists { key, field } => read(db, &key, now).map(|h| CommandResponse::Integer {
+            value: i64::from(h.is_some_and(|h| h.contains_key(&field))),
+        }),
+        Command::HLen { key } => {
+            read(db, &key, now).map(|h| super::to_integer(h.map_or(0, HashMap::len) as u64))
+        }
+        Command::HScan {
+            key,
+            cursor,
+            pattern,
+            count,
+        } => read(db, &key, now).map(|h| {
+            let empty = HashMap::new();
+            let h = h.unwrap_or(&empty);
+            let (next, fields) = crate::engine::scan::scan(
+                h.keys().map(String::as_str),
+                cursor,
+                count,
+                pattern.as_deref(),
+            );
+            CommandResponse::Array {
+                value: vec![
+                    bulk(next.to_string()),
+                    CommandResponse::Array {
+                        value: fields
+                            .into_iter()
+                            .flat_map(|f| [bulk(f), bulk(&h[f])])
+                            .collect(),
+                    },
+                ],
+            }
+        }),
+        _ => unreachable!("not a hash command"),
+    };
+
+    r.unwrap_or_else(|value| CommandResponse::Error { value })
+}
+
+/// Fields and values of the hash ordered by field, so that the output is stable.
+pub fn entries(h: &HashMap<String, String>) -> CommandResponse {
+    let mut fields = h.iter().collect::<Vec<(&String, &String)>>();
+    fields.sort_unstable();
+
+    CommandResponse::Map {
+        value: fields
+            .into_iter()
+            .map(|(f, v)| (bulk(f), bulk(v)))
+            .collect(),
+    }
+}
+
+/// Hash stored at key, None if the key does not exist.
+fn read<'a>(
+    db: &'a mut HashMapDb,
+    key: &str,
+    now: std::time::Instant,
+) -> Result<Option<&'a HashMap<String, String>>, String> {
+    db.get(key, now)
+        .map(Value::as_hash)
+        .transpose()
+        .map_err(|e| e.to_string())
+}
+
+fn hset(
+    db: &mut HashMapDb,
+    key: &str,
+    fields: Vec<(String, String)>,
+    now: std::time::Instant,
+) -> Result<CommandResponse, String> {
+    let h = db
+        .get_or_insert_with(key, now, || Value::Hash(HashMap::new()))
+        .as_hash_mut()
+        .map_err(|e| e.to_string())?;
+    let added = fields
+        .into_iter()
+        .filter(|(f, v)| h.insert(f.clone(), v.clone()).is_none())
+        .count();
+    db.changed(key);
+
+    Ok(super::to_integer(added as u64))
+}
+
+fn hdel(
+    db: &mut HashMapDb,
+    key: &str,
+    fields: &[String],
+    now: std::time::Instant,
+) -> Result<CommandResponse, String> {
+    let Some(v) = db.get_mut(key, now) else {
+        return Ok(super::to_integer(0));
+    };
+    let h = v.as_hash_mut().map_err(|e| e.to_string())?;
+    let removed = fields.iter().filter(|f| h.remove(*f).is_some()).count();
+    if removed > 0 {
+        db.changed(key);
+    }
+
+    Ok(super::to_integer(removed as u64))
+}
+
+fn hincrby(
+    db: &mut HashMapDb,
+    key: &str,
+    field: String,
+    increment: i64,
+    now: std::time::Instant,
+) -> Result<CommandResponse, String> {
+    // NOTE: the value is checked before the hash is created, so that a failure leaves no empty hash behind.
+    let current = read(db, key, now)?
+        .and_then(|h| h.get(&field))
+        .map_or(Ok(0), |v| v.parse::<i64>())
+        .map_err(|_| "ERR hash value is not an integer".to_owned())?;
+    let value = current
+        .checked_add(increment)
+        .ok_or_else(|| "ERR increment or decrement would overflow".to_owned())?;
+
+    db.get_or_insert_with(key, now, || Value::Hash(HashMap::new()))
+        .as_hash_mut()
+        .map_err(|e| e.to_string())?
+        .insert(field, value.to_string());
+    db.changed(key);
+
+    Ok(CommandResponse::Integer { value })
+}
+
+fn bulk(value: impl Into<String>) -> CommandResponse {
+    CommandResponse::BulkString {
+        value: value.into(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config;
+
+    #[test]
+    fn commands() {
+        let mut db = HashMapDb::new(config::Engine::default());
+        let now = std::time::Instant::now();
+        let mut exec = |cmd| hash(cmd, &mut db, now);
+        let key = || "user:1".to_string();
+
+        let res = exec(Command::HSet {
+            key: key(),
+            fields: vec![
+                ("name".to_string(), "ada".to_string()),
+                ("age".to_string(), "36".to_string()),
+            ],
+        });
+        assert_eq!(res, CommandResponse::Integer { value: 2 });
+        let res = exec(Command::HSet {
+            key: key(),
+            fields: vec![("age".to_string(), "37".to_string())],
+        });
+        assert_eq!(res, CommandResponse::Integer { value: 0 });
+
+        let res = exec(Command::HGet {
+            key: key(),
+            field: "age".to_string(),
+        });
+        assert_eq!(res, bulk("37"));
+        let res = exec(Command::HMGet {
+            key: key(),
+            fields: vec!["name".to_string(), "missing".to_string()],
+        });
+        assert_eq!(
+            res,
+            CommandResponse::Array {
+                value: vec![bulk("ada"), CommandResponse::Null]
+            }
+        );
+        let res = exec(Command::HGetAll { key: key() });
+        assert_eq!(
+            res,
+            CommandResponse::Map {
+                value: vec![(bulk("age"), bulk("37")), (bulk("name"), bulk("ada"))]
+            }
+        );
+
+        let res = exec(Command::HIncrBy {
+            key: key(),
+            field: "age".to_string(),
+            increment: 3,
+        });
+        assert_eq!(res, CommandResponse::Integer { value: 40 });
+        let res = exec(Command::HIncrBy {
+            key: key(),
+            field: "name".to_string(),
+            increment: 1,
+        });
+        assert_eq!(
+            res,
+            CommandResponse::Error {
+                value: "ERR hash value is not an integer".to_string()
+            }
+        );
+
+        let res = exec(Command::HExists {
+            key: key(),
+            field: "name".to_string(),
+        });
+        assert_eq!(res, CommandResponse::Integer { value: 1 });
+        let res = exec(Command::HDel {
+            key: key(),
+            fields: vec!["name".to_string(), "age".to_string(), "missing".to_string()],
+        });
+        assert_eq!(res, CommandResponse::Integer { value: 2 });
+
+        // NOTE: the hash is removed with its last field.
+        let res = exec(Command::HLen { key: key() });
+        assert_eq!(res, CommandResponse::Integer { value: 0 });
+        assert_eq!(db.get("user:1", now), None);
+    }
+
+    #[test]
+    fn wrong_type() {
+        let mut db = HashMapDb::new(config::Engine::default());
+        let now = std::time::Instant::now();
+        db.set("key", "value".to_string(), None);
+
+        let res = hash(
+            Command::HSet {
+                key: "key".to_string(),
+                fields: vec![("f".to_string(), "v".to_string())],
+            },
+            &mut db,
+            now,
+        );
+        assert_eq!(
+            res,
+            CommandResponse::Error {
+                value: crate::engine::value::WrongType.to_string()
+            }
+        );
+    }
+
+    #[test]
+    fn scan() {
+        let mut db = HashMapDb::new(config::Engine::default());
+        let now = std::time::Instant::now();
+        let fields = (0..30)
+            .map(|i| (format!("f{i}"), i.to_string()))
+            .collect::<Vec<(String, String)>>();
+        hash(
+            Command::HSet {
+                key: "key".to_string(),
+                fields,
+            },
+            &mut db,
+            now,
+        );
+
+        let mut seen = 0;
+        let mut cursor = 0;
+        loop {
+            let res = hash(
+                Command::HScan {
+                    key: "key".to_string(),
+                    cursor,
+                    pattern: None,
+                    count: 10,
+                },
+                &mut db,
+                now,
+            );
+            let CommandResponse::Array { value } = res else {
+                panic!("unexpected response {res:?}");
+            };
+            let [CommandResponse::BulkString { value: next }, CommandResponse::Array { value: page }] =
+                value.as_slice()
+            else {
+                panic!("unexpected response {value:?}");
+            };
+
+            seen += page.len() / 2;
+            cursor = next.parse().unwrap();
+            if cursor == 0 {
+                break;
+            }
+        }
+        assert_eq!(seen, 30);
+    }
+}
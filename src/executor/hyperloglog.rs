@@ -0,0 +1,180 @@
+use crate::engine::db::HashMapDb;
+use crate::engine::hyperloglog::HyperLogLog;
+use crate::engine::value::Value;
+use crate::protocol::commands::{Command, CommandResponse};
+
+/// Execute the `HyperLogLog` commands, like redis the `HyperLogLogs` are strings. A missing key is an empty
+/// `HyperLogLog`.
+pub fn hyperloglog(cmd: Command, db: &mut HashMapDb, now: std::time::Instant) -> CommandResponse {
+    let r = match cmd {
+        Command::PfAdd { key, elements } => read(db, &key, now).map(|hll| {
+            let created = hll.is_none();
+            let mut hll = hll.unwrap_or_default();
+            let changed = elements
+                .iter()
+                .fold(false, |changed, e| hll.add(e.as_bytes()) || changed);
+            if changed || created {
+                write(db, &key, &mut hll, now);
+            }
+            CommandResponse::Integer {
+                value: i64::from(changed || created),
+            }
+        }),
+        Command::PfCount { keys } => count(db, &keys, now).map(super::to_integer),
+        Command::PfMerge { destination, keys } => std::iter::once(&destination)
+            .chain(&keys)
+            .try_fold(HyperLogLog::default(), |mut union, key| {
+                if let Some(hll) = read(db, key, now)? {
+                    union.merge(&hll);
+                }
+                Ok(union)
+            })
+            .map(|mut union| {
+                write(db, &destination, &mut union, now);
+                CommandResponse::String {
+                    value: "OK".to_owned(),
+                }
+            }),
+        _ => unreachable!("not a HyperLogLog command"),
+    };
+
+    r.unwrap_or_else(|value| CommandResponse::Error { value })
+}
+
+/// `HyperLogLog` stored at key, None if the key does not exist.
+fn read(
+    db: &mut HashMapDb,
+    key: &str,
+    now: std::time::Instant,
+) -> Result<Option<HyperLogLog>, String> {
+    db.get(key, now)
+        .map(|v| {
+            let bytes = v.as_bytes().map_err(|e| e.to_string())?;
+            HyperLogLog::decode(&bytes).map_err(|e| e.to_string())
+        })
+        .transpose()
+}
+
+/// Store the `HyperLogLog` at key in place, so that its TTL is kept.
+fn write(db: &mut HashMapDb, key: &str, hll: &mut HyperLogLog, now: std::time::Instant) {
+    let value = db.get_or_insert_with(key, now, || Value::String(vec![]));
+    *value = Value::String(hll.encode());
+    db.changed(key);
+}
+
+/// Cardinality of the union of the `HyperLogLogs`. Like redis, the cardinality of a single `HyperLogLog` is cached in
+/// its header until the next change.
+fn count(db: &mut HashMapDb, keys: &[String], now: std::time::Instant) -> Result<u64, String> {
+    if let [key] = keys {
+        let Some(mut hll) = read(db, key, now)? else {
+            return Ok(0);
+        };
+        if hll.is_cache_valid() {
+            return Ok(hll.count());
+        }
+
+        let card = hll.count();
+        if let Some(v) = db.get_mut(key, now) {
+            *v = Value::String(hll.encode());
+        }
+        return Ok(card);
+    }
+
+    let mut union = HyperLogLog::default();
+    for key in keys {
+        if let Some(hll) = read(db, key, now)? {
+            union.merge(&hll);
+        }
+    }
+    Ok(union.count())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config;
+
+    #[test]
+    fn commands() {
+        let mut db = HashMapDb::new(config::Engine::default());
+        let now = std::time::Instant::now();
+        let mut exec = |cmd| hyperloglog(cmd, &mut db, now);
+        let pfadd = |key: &str, elements: &[&str]| Command::PfAdd {
+            key: key.to_string(),
+            elements: elements.iter().map(ToString::to_string).collect(),
+        };
+        let pfcount = |keys: &[&str]| Command::PfCount {
+            keys: keys.iter().map(ToString::to_string).collect(),
+        };
+
+        assert_eq!(exec(pfadd("a", &[])), CommandResponse::Integer { value: 1 });
+        assert_eq!(exec(pfadd("a", &[])), CommandResponse::Integer { value: 0 });
+        assert_eq!(
+            exec(pfadd("a", &["x", "y", "z"])),
+            CommandResponse::Integer { value: 1 }
+        );
+        assert_eq!(
+            exec(pfadd("a", &["x"])),
+            CommandResponse::Integer { value: 0 }
+        );
+        assert_eq!(
+            exec(pfadd("b", &["z", "w"])),
+            CommandResponse::Integer { value: 1 }
+        );
+
+        assert_eq!(exec(pfcount(&["a"])), CommandResponse::Integer { value: 3 });
+        assert_eq!(
+            exec(pfcount(&["a", "b", "missing"])),
+            CommandResponse::Integer { value: 4 }
+        );
+        let res = exec(Command::PfMerge {
+            destination: "c".to_string(),
+            keys: vec!["a".to_string(), "b".to_string()],
+        });
+        assert_eq!(
+            res,
+            CommandResponse::String {
+                value: "OK".to_string()
+            }
+        );
+        assert_eq!(exec(pfcount(&["c"])), CommandResponse::Integer { value: 4 });
+
+        db.set("s", "not a HyperLogLog".to_string(), None);
+        let res = hyperloglog(pfadd("s", &["x"]), &mut db, now);
+        assert_eq!(
+            res,
+            CommandResponse::Error {
+                value: "WRONGTYPE Key is not a valid HyperLogLog string value.".to_string()
+            }
+        );
+    }
+
+    #[test]
+    fn cached_count() {
+        let mut db = HashMapDb::new(config::Engine::default());
+        let now = std::time::Instant::now();
+        hyperloglog(
+            Command::PfAdd {
+                key: "a".to_string(),
+                elements: vec!["x".to_string()],
+            },
+            &mut db,
+            now,
+        );
+        let cached = |db: &mut HashMapDb| match db.get("a", now) {
+            Some(Value::String(bytes)) => bytes[15] & 0x80 == 0,
+            v => panic!("unexpected value {v:?}"),
+        };
+        assert!(!cached(&mut db));
+
+        let res = hyperloglog(
+            Command::PfCount {
+                keys: vec!["a".to_string()],
+            },
+            &mut db,
+            now,
+        );
+        assert_eq!(res, CommandResponse::Integer { value: 1 });
+        assert!(cached(&mut db));
+    }
+}
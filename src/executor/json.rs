@@ -0,0 +1,439 @@
+use crate::engine::db::HashMapDb;
+use crate::engine::json::{self, Path, Step};
+use crate::engine::value::Value;
+use crate::protocol::commands::{Command, CommandResponse};
+
+/// Execute the JSON commands. Like `RedisJSON`, the `JSONPaths` reply for each matched value while the legacy paths
+/// reply for the first one and fail when nothing matches.
+pub fn json(cmd: Command, db: &mut HashMapDb, now: std::time::Instant) -> CommandResponse {
+    let r = match cmd {
+        Command::JsonSet {
+            key,
+            path,
+            value,
+            exists,
+        } => set(db, &key, &path, value, exists, now),
+        Command::JsonGet { key, paths } => read(db, &key, now)
+            .and_then(|doc| doc.map_or(Ok(CommandResponse::Null), |doc| get(doc, &paths))),
+        Command::JsonDel { key, path } => del(db, &key, &path, now),
+        Command::JsonArrAppend { key, path, values } => {
+            let lengths = update(db, &key, &path, now, |v| match v {
+                serde_json::Value::Array(a) => {
+                    a.extend(values.iter().cloned());
+                    Ok(a.len() as u64)
+                }
+                v => Err(expected("array", v)),
+            });
+            lengths.map(|lengths| {
+                let mut lengths = lengths
+                    .into_iter()
+                    .map(|l| l.map_or(CommandResponse::Null, super::to_integer));
+                if path.is_legacy() {
+                    lengths.next().unwrap_or(CommandResponse::Null)
+                } else {
+                    CommandResponse::Array {
+                        value: lengths.collect(),
+                    }
+                }
+            })
+        }
+        Command::JsonNumIncrBy {
+            key,
+            path,
+            increment,
+        } => {
+            let numbers = update(db, &key, &path, now, |v| match v {
+                serde_json::Value::Number(n) => {
+                    *n = json::add(n, &increment)
+                        .ok_or_else(|| "ERR result is not a number".to_owned())?;
+                    Ok(serde_json::Value::Number(n.clone()))
+                }
+                v => Err(expected("number", v)),
+            });
+            // NOTE: like redis the new values are serialized as JSON, an array of them for a JSONPath.
+            numbers.map(|numbers| {
+                let mut numbers = numbers.into_iter().map(Option::unwrap_or_default);
+                if path.is_legacy() {
+                    bulk(numbers.next().unwrap_or_default().to_string())
+                } else {
+                    bulk(numbers.collect::<serde_json::Value>().to_string())
+                }
+            })
+        }
+        Command::JsonType { key, path } => read(db, &key, now).and_then(|doc| {
+            let Some(doc) = doc else {
+                return Ok(CommandResponse::Null);
+            };
+            let mut types = matches(doc, &path, path.is_legacy())?
+                .into_iter()
+                .map(json::type_name);
+            Ok(if path.is_legacy() {
+                types
+                    .next()
+                    .map_or(CommandResponse::Null, |t| CommandResponse::String {
+                        value: t.to_owned(),
+                    })
+            } else {
+                CommandResponse::Array {
+                    value: types.map(bulk).collect(),
+                }
+            })
+        }),
+        _ => unreachable!("not a JSON command"),
+    };
+
+    r.unwrap_or_else(|value| CommandResponse::Error { value })
+}
+
+/// Document, or its fragment at path, for `GET /key` on HTTP. A path matching nothing is a nil reply.
+pub fn fragment(doc: &serde_json::Value, path: Option<&Path>) -> CommandResponse {
+    let location = path.map_or_else(|| Some(vec![]), |p| p.locate(doc).into_iter().next());
+    location
+        .and_then(|l| json::get(doc, &l))
+        .map_or(CommandResponse::Null, |v| bulk(v.to_string()))
+}
+
+/// JSON document stored at key, None if the key does not exist.
+fn read<'a>(
+    db: &'a mut HashMapDb,
+    key: &str,
+    now: std::time::Instant,
+) -> Result<Option<&'a serde_json::Value>, String> {
+    db.get(key, now)
+        .map(Value::as_json)
+        .transpose()
+        .map_err(|e| e.to_string())
+}
+
+/// A new document must be set at the root. Otherwise the matched values are replaced, or the missing key at the end
+/// of the path is added to the objects matching its parent.
+fn set(
+    db: &mut HashMapDb,
+    key: &str,
+    path: &Path,
+    value: serde_json::Value,
+    exists: Option<bool>,
+    now: std::time::Instant,
+) -> Result<CommandResponse, String> {
+    let Some(doc) = db.get_mut(key, now) else {
+        if !path.is_root() {
+            return Err("ERR new objects must be created at the root".to_owned());
+        }
+        if exists == Some(true) {
+            return Ok(CommandResponse::Null);
+        }
+        db.set(key, Value::Json(value), None);
+        return Ok(ok());
+    };
+    let doc = doc.as_json_mut().map_err(|e| e.to_string())?;
+
+    let locations = path.locate(doc);
+    let changed = if locations.is_empty() {
+        exists != Some(true) && insert(doc, path, &value)
+    } else if exists == Some(false) {
+        false
+    } else {
+        for location in &locations {
+            if let Some(v) = json::get_mut(doc, location) {
+                *v = value.clone();
+            }
+        }
+        true
+    };
+
+    if !changed {
+        return Ok(CommandResponse::Null);
+    }
+    db.changed(key);
+    Ok(ok())
+}
+
+/// Add the last key of the path to the objects matching its parent, returns if an object was changed.
+fn insert(doc: &mut serde_json::Value, path: &Path, value: &serde_json::Value) -> bool {
+    let Some((parent, name)) = path.parent() else {
+        return false;
+    };
+
+    let mut inserted = false;
+    for location in parent.locate(doc) {
+        if let Some(serde_json::Value::Object(o)) = json::get_mut(doc, &location) {
+            o.insert(name.clone(), value.clone());
+            inserted = true;
+        }
+    }
+    inserted
+}
+
+/// The values at each path serialized as JSON. With several paths the result is an object of the values by path,
+/// the legacy paths are `JSONPaths` once a `JSONPath` is among them.
+fn get(doc: &serde_json::Value, paths: &[Path]) -> Result<CommandResponse, String> {
+    let legacy = paths.iter().all(Path::is_legacy);
+    let values = |path: &Path| -> Result<serde_json::Value, String> {
+        let mut matched = matches(doc, path, legacy)?.into_iter().cloned();
+        Ok(if legacy {
+            matched.next().unwrap_or_default()
+        } else {
+            matched.collect()
+        })
+    };
+
+    let result = match paths {
+        [] => doc.clone(),
+        [path] => values(path)?,
+        paths => paths
+            .iter()
+            .map(|p| Ok((p.as_str().to_owned(), values(p)?)))
+            .collect::<Result<serde_json::Map<String, serde_json::Value>, String>>()?
+            .into(),
+    };
+    Ok(bulk(result.to_string()))
+}
+
+fn del(
+    db: &mut HashMapDb,
+    key: &str,
+    path: &Path,
+    now: std::time::Instant,
+) -> Result<CommandResponse, String> {
+    let Some(value) = db.get_mut(key, now) else {
+        return Ok(CommandResponse::Integer { value: 0 });
+    };
+    let doc = value.as_json_mut().map_err(|e| e.to_string())?;
+    if path.is_root() {
+        db.del(key);
+        return Ok(CommandResponse::Integer { value: 1 });
+    }
+
+    let deleted = json::remove(doc, path.locate(doc));
+    if deleted > 0 {
+        db.changed(key);
+    }
+    Ok(super::to_integer(deleted as u64))
+}
+
+/// Change the values matched by the path in place, the values of another type are None. A legacy path changes the
+/// first value only and fails on a value of another type.
+fn update<T, F>(
+    db: &mut HashMapDb,
+    key: &str,
+    path: &Path,
+    now: std::time::Instant,
+    mut change: F,
+) -> Result<Vec<Option<T>>, String>
+where
+    F: FnMut(&mut serde_json::Value) -> Result<T, String>,
+{
+    let doc = db
+        .get_mut(key, now)
+        .ok_or_else(|| {
+            "ERR could not perform this operation on a key that doesn't exist".to_owned()
+        })?
+        .as_json_mut()
+        .map_err(|e| e.to_string())?;
+
+    let mut results = vec![];
+    for location in locations(doc, path, path.is_legacy())? {
+        let Some(v) = json::get_mut(doc, &location) else {
+            continue;
+        };
+        match change(v) {
+            Ok(r) => results.push(Some(r)),
+            Err(e) if path.is_legacy() => return Err(e),
+            Err(_) => results.push(None),
+        }
+    }
+
+    if results.iter().any(Option::is_some) {
+        db.changed(key);
+    }
+    Ok(results)
+}
+
+fn matches<'a>(
+    doc: &'a serde_json::Value,
+    path: &Path,
+    legacy: bool,
+) -> Result<Vec<&'a serde_json::Value>, String> {
+    Ok(locations(doc, path, legacy)?
+        .iter()
+        .filter_map(|l| json::get(doc, l))
+        .collect())
+}
+
+/// Locations matched by the path, the first one for a legacy path which fails when nothing matches.
+fn locations(doc: &serde_json::Value, path: &Path, legacy: bool) -> Result<Vec<Vec<Step>>, String> {
+    let mut locations = path.locate(doc);
+    if legacy {
+        if locations.is_empty() {
+            return Err(format!("ERR Path '{}' does not exist", path.as_str()));
+        }
+        locations.truncate(1);
+    }
+    Ok(locations)
+}
+
+fn expected(kind: &str, found: &serde_json::Value) -> String {
+    format!(
+        "ERR wrong type of path value - expected {kind} but found {}",
+        json::type_name(found)
+    )
+}
+
+fn ok() -> CommandResponse {
+    CommandResponse::String {
+        value: "OK".to_owned(),
+    }
+}
+
+fn bulk(value: impl Into<String>) -> CommandResponse {
+    CommandResponse::BulkString {
+        value: value.into(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config;
+    use serde_json::json;
+
+    fn path(p: &str) -> Path {
+        Path::parse(p).unwrap()
+    }
+
+    fn jsonset(path: &str, value: serde_json::Value, exists: Option<bool>) -> Command {
+        Command::JsonSet {
+            key: "doc".to_string(),
+            path: Path::parse(path).unwrap(),
+            value,
+            exists,
+        }
+    }
+
+    fn jsonget(paths: &[&str]) -> Command {
+        Command::JsonGet {
+            key: "doc".to_string(),
+            paths: paths.iter().map(|p| path(p)).collect(),
+        }
+    }
+
+    #[test]
+    fn set_and_get() {
+        let mut db = HashMapDb::new(config::Engine::default());
+        let now = std::time::Instant::now();
+        let mut exec = |cmd| json(cmd, &mut db, now);
+
+        let res = exec(jsonset("$.user", json!({}), None));
+        assert_eq!(
+            res,
+            CommandResponse::Error {
+                value: "ERR new objects must be created at the root".to_string()
+            }
+        );
+        let doc = json!({"user": {"name": "ada", "tags": ["a"]}});
+        assert_eq!(exec(jsonset("$", doc, None)), ok());
+        assert_eq!(
+            exec(jsonset("$.user.name", json!("bob"), Some(false))),
+            CommandResponse::Null
+        );
+        assert_eq!(exec(jsonset("$.user.age", json!(36), Some(false))), ok());
+        assert_eq!(
+            exec(jsonset("$.missing.age", json!(1), None)),
+            CommandResponse::Null
+        );
+
+        assert_eq!(exec(jsonget(&[".user.age"])), bulk("36"));
+        assert_eq!(exec(jsonget(&["$..name"])), bulk(r#"["ada"]"#));
+        assert_eq!(
+            exec(jsonget(&[".user.name", "$.user.tags"])),
+            bulk(r#"{"$.user.tags":[["a"]],".user.name":["ada"]}"#)
+        );
+        assert_eq!(
+            exec(jsonget(&[".missing"])),
+            CommandResponse::Error {
+                value: "ERR Path '.missing' does not exist".to_string()
+            }
+        );
+        assert_eq!(
+            exec(Command::JsonType {
+                key: "doc".to_string(),
+                path: path("$.user.*"),
+            }),
+            CommandResponse::Array {
+                value: vec![bulk("integer"), bulk("string"), bulk("array")]
+            }
+        );
+        assert_eq!(
+            exec(jsonget(&[])),
+            bulk(r#"{"user":{"age":36,"name":"ada","tags":["a"]}}"#)
+        );
+
+        db.set("s", "string".to_string(), None);
+        let res = json(
+            Command::JsonGet {
+                key: "s".to_string(),
+                paths: vec![],
+            },
+            &mut db,
+            now,
+        );
+        assert!(matches!(res, CommandResponse::Error { value } if value.starts_with("WRONGTYPE")));
+    }
+
+    #[test]
+    fn updates() {
+        let mut db = HashMapDb::new(config::Engine::default());
+        let now = std::time::Instant::now();
+        let mut exec = |cmd| json(cmd, &mut db, now);
+        let doc = json!({"a": {"n": 1, "l": [1]}, "b": {"n": 1.5, "l": "x"}});
+        exec(jsonset("$", doc, None));
+
+        let res = exec(Command::JsonArrAppend {
+            key: "doc".to_string(),
+            path: path("$..l"),
+            values: vec![json!(2), json!("three")],
+        });
+        assert_eq!(
+            res,
+            CommandResponse::Array {
+                value: vec![CommandResponse::Integer { value: 3 }, CommandResponse::Null]
+            }
+        );
+        let res = exec(Command::JsonArrAppend {
+            key: "doc".to_string(),
+            path: path(".b.l"),
+            values: vec![json!(2)],
+        });
+        assert_eq!(
+            res,
+            CommandResponse::Error {
+                value: "ERR wrong type of path value - expected array but found string".to_string()
+            }
+        );
+
+        let incr = |p: &str, n: serde_json::Value| Command::JsonNumIncrBy {
+            key: "doc".to_string(),
+            path: path(p),
+            increment: n.as_number().unwrap().clone(),
+        };
+        assert_eq!(exec(incr("$.*.n", json!(2))), bulk("[3,3.5]"));
+        assert_eq!(exec(incr(".a.n", json!(-4))), bulk("-1"));
+        assert_eq!(exec(incr("$.a.l", json!(1))), bulk("[null]"));
+
+        let res = exec(Command::JsonDel {
+            key: "doc".to_string(),
+            path: path("$..n"),
+        });
+        assert_eq!(res, CommandResponse::Integer { value: 2 });
+        assert_eq!(
+            exec(jsonget(&[])),
+            bulk(r#"{"a":{"l":[1,2,"three"]},"b":{"l":"x"}}"#)
+        );
+        let res = exec(Command::JsonDel {
+            key: "doc".to_string(),
+            path: path("$"),
+        });
+        assert_eq!(res, CommandResponse::Integer { value: 1 });
+        assert_eq!(db.get("doc", now), None);
+    }
+}
@@ -0,0 +1,278 @@
+use crate::engine::db::HashMapDb;
+use crate::engine::value::Value;
+use crate::protocol::commands::{Command, CommandResponse};
+use crate::server::blocking::Blocking;
+use std::collections::VecDeque;
+
+/// Execute the commands of the list type, a list left without elements is removed. Pushes serve the connections
+/// blocked on the key before replying, BLPOP and BRPOP reply Null when they would block, see `wait_blocked`.
+pub fn list(
+    cmd: Command,
+    db: &mut HashMapDb,
+    blocking: &mut Blocking,
+    now: std::time::Instant,
+) -> CommandResponse {
+    let r = match cmd {
+        Command::LPush { key, values } => push(db, blocking, &key, values, true, now),
+        Command::RPush { key, values } => push(db, blocking, &key, values, false, now),
+        Command::LPop { key, count } => pop(db, &key, count, true, now),
+        Command::RPop { key, count } => pop(db, &key, count, false, now),
+        Command::LRange { key, start, stop } => read(db, &key, now).map(|l| {
+            let l = l.map_or_else(Vec::new, |l| {
+                range(l.len(), start, stop)
+                    .map_or_else(Vec::new, |r| l.range(r).map(bulk).collect())
+            });
+            CommandResponse::Array { value: l }
+        }),
+        Command::LIndex { key, index } => read(db, &key, now).map(|l| {
+            l.and_then(|l| index_of(l.len(), index).and_then(|i| l.get(i)))
+                .map_or(CommandResponse::Null, bulk)
+        }),
+        Command::LLen { key } => {
+            read(db, &key, now).map(|l| super::to_integer(l.map_or(0, VecDeque::len) as u64))
+        }
+        Command::BLPop { keys, .. } => first_pop(db, &keys, true, now),
+        Command::BRPop { keys, .. } => first_pop(db, &keys, false, now),
+        _ => unreachable!("not a list command"),
+    };
+
+    r.unwrap_or_else(|value| CommandResponse::Error { value })
+}
+
+/// List stored at key, None if the key does not exist.
+fn read<'a>(
+    db: &'a mut HashMapDb,
+    key: &str,
+    now: std::time::Instant,
+) -> Result<Option<&'a VecDeque<String>>, String> {
+    db.get(key, now)
+        .map(Value::as_list)
+        .transpose()
+        .map_err(|e| e.to_string())
+}
+
+fn push(
+    db: &mut HashMapDb,
+    blocking: &mut Blocking,
+    key: &str,
+    values: Vec<String>,
+    left: bool,
+    now: std::time::Instant,
+) -> Result<CommandResponse, String> {
+    let l = db
+        .get_or_insert_with(key, now, || Value::List(VecDeque::new()))
+        .as_list_mut()
+        .map_err(|e| e.to_string())?;
+    for v in values {
+        if left {
+            l.push_front(v);
+        } else {
+            l.push_back(v);
+        }
+    }
+    let len = l.len();
+
+    // NOTE: like redis the reply is the length after the push, the blocked connections are served right after.
+    while let Some(w) = blocking.next(key) {
+        let Some(element) = (if w.left { l.pop_front() } else { l.pop_back() }) else {
+            break;
+        };
+        w.serve(key, element);
+    }
+    db.changed(key);
+
+    Ok(super::to_integer(len as u64))
+}
+
+fn pop(
+    db: &mut HashMapDb,
+    key: &str,
+    count: Option<usize>,
+    left: bool,
+    now: std::time::Instant,
+) -> Result<CommandResponse, String> {
+    let Some(v) = db.get_mut(key, now) else {
+        return Ok(CommandResponse::Null);
+    };
+    let l = v.as_list_mut().map_err(|e| e.to_string())?;
+
+    let n = count.unwrap_or(1).min(l.len());
+    let popped = if left {
+        l.drain(..n).collect::<Vec<String>>()
+    } else {
+        let start = l.len() - n;
+        l.drain(start..).rev().collect()
+    };
+    db.changed(key);
+
+    Ok(match count {
+        None => popped.first().map_or(CommandResponse::Null, bulk),
+        Some(_) => CommandResponse::Array {
+            value: popped.iter().map(bulk).collect(),
+        },
+    })
+}
+
+/// Pop from the first non empty list as `[key, element]`, Null if all of them are empty.
+fn first_pop(
+    db: &mut HashMapDb,
+    keys: &[String],
+    left: bool,
+    now: std::time::Instant,
+) -> Result<CommandResponse, String> {
+    for key in keys {
+        if let CommandResponse::BulkString { value } = pop(db, key, None, left, now)? {
+            return Ok(CommandResponse::Array {
+                value: vec![bulk(key), bulk(value)],
+            });
+        }
+    }
+
+    Ok(CommandResponse::Null)
+}
+
+/// Position of the index in a list of the given length, negative indexes count from the tail.
+fn index_of(len: usize, index: i64) -> Option<usize> {
+    let len = i64::try_from(len).ok()?;
+    let i = if index < 0 { len + index } else { index };
+    (0..len).contains(&i).then(|| usize::try_from(i).ok())?
+}
+
+/// Positions between start and stop included, clamped to the list like LRANGE and ZRANGE do, None if the range is empty.
+pub(super) fn range(len: usize, start: i64, stop: i64) -> Option<std::ops::RangeInclusive<usize>> {
+    let n = i64::try_from(len).ok()?;
+    let start = if start < 0 { (n + start).max(0) } else { start };
+    let stop = if stop < 0 { n + stop } else { stop.min(n - 1) };
+    if start > stop || start >= n {
+        return None;
+    }
+
+    Some(usize::try_from(start).ok()?..=usize::try_from(stop).ok()?)
+}
+
+fn bulk(value: impl Into<String>) -> CommandResponse {
+    CommandResponse::BulkString {
+        value: value.into(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config;
+    use crate::server::blocking::Pop;
+
+    fn bulks(values: &[&str]) -> CommandResponse {
+        CommandResponse::Array {
+            value: values.iter().map(|v| bulk(*v)).collect(),
+        }
+    }
+
+    #[test]
+    fn commands() {
+        let mut db = HashMapDb::new(config::Engine::default());
+        let mut blocking = Blocking::default();
+        let now = std::time::Instant::now();
+        let mut exec = |cmd| list(cmd, &mut db, &mut blocking, now);
+        let key = || "queue".to_string();
+        let values = |v: &[&str]| v.iter().map(ToString::to_string).collect::<Vec<String>>();
+
+        let res = exec(Command::RPush {
+            key: key(),
+            values: values(&["b", "c"]),
+        });
+        assert_eq!(res, CommandResponse::Integer { value: 2 });
+        let res = exec(Command::LPush {
+            key: key(),
+            values: values(&["a", "z"]),
+        });
+        assert_eq!(res, CommandResponse::Integer { value: 4 });
+
+        let res = exec(Command::LRange {
+            key: key(),
+            start: 0,
+            stop: -1,
+        });
+        assert_eq!(res, bulks(&["z", "a", "b", "c"]));
+        let res = exec(Command::LRange {
+            key: key(),
+            start: -2,
+            stop: 100,
+        });
+        assert_eq!(res, bulks(&["b", "c"]));
+        let res = exec(Command::LIndex {
+            key: key(),
+            index: -1,
+        });
+        assert_eq!(res, bulk("c"));
+
+        let res = exec(Command::LPop {
+            key: key(),
+            count: None,
+        });
+        assert_eq!(res, bulk("z"));
+        let res = exec(Command::RPop {
+            key: key(),
+            count: Some(2),
+        });
+        assert_eq!(res, bulks(&["c", "b"]));
+        let res = exec(Command::BLPop {
+            keys: vec!["empty".to_string(), key()],
+            timeout: std::time::Duration::ZERO,
+        });
+        assert_eq!(res, bulks(&["queue", "a"]));
+
+        // NOTE: the list is removed with its last element.
+        let res = exec(Command::LLen { key: key() });
+        assert_eq!(res, CommandResponse::Integer { value: 0 });
+        let res = exec(Command::BRPop {
+            keys: vec![key()],
+            timeout: std::time::Duration::ZERO,
+        });
+        assert_eq!(res, CommandResponse::Null);
+        assert_eq!(db.get("queue", now), None);
+    }
+
+    #[test]
+    fn serve_blocked() {
+        let mut db = HashMapDb::new(config::Engine::default());
+        let mut blocking = Blocking::default();
+        let now = std::time::Instant::now();
+        let pop = Pop {
+            keys: vec!["queue".to_string()],
+            left: true,
+            timeout: std::time::Duration::ZERO,
+        };
+        let first = blocking.block(&pop);
+        let second = blocking.block(&pop);
+
+        let res = list(
+            Command::RPush {
+                key: "queue".to_string(),
+                values: vec!["a".to_string()],
+            },
+            &mut db,
+            &mut blocking,
+            now,
+        );
+        assert_eq!(res, CommandResponse::Integer { value: 1 });
+        assert_eq!(
+            first.try_recv(),
+            Some(("queue".to_string(), "a".to_string()))
+        );
+        assert_eq!(second.try_recv(), None);
+        assert_eq!(db.get("queue", now), None);
+    }
+
+    #[test]
+    fn ranges() {
+        assert_eq!(range(5, 0, -1), Some(0..=4));
+        assert_eq!(range(5, -100, 1), Some(0..=1));
+        assert_eq!(range(5, 3, 1), None);
+        assert_eq!(range(5, 5, 10), None);
+        assert_eq!(range(0, 0, -1), None);
+        assert_eq!(index_of(3, -1), Some(2));
+        assert_eq!(index_of(3, 3), None);
+        assert_eq!(index_of(3, -4), None);
+    }
+}
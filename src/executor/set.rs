@@ -0,0 +1,483 @@
+use crate::engine::db::HashMapDb;
+use crate::engine::value::Value;
+use crate::protocol::commands::{Command, CommandResponse};
+use std::collections::HashSet;
+
+/// Largest number of members of SRANDMEMBER with a negative count, the members repeat so the reply is not bounded by
+/// the size of the set.
+const MAX_REPEATED_MEMBERS: u64 = 1024 * 1024;
+
+/// Operation combining the sets of SINTER, SUNION, SDIFF and their STORE variants.
+#[derive(Clone, Copy)]
+enum Op {
+    Inter,
+    Union,
+    Diff,
+}
+
+/// Execute the commands of the set type, a set left without members is removed.
+pub fn set(cmd: Command, db: &mut HashMapDb, now: std::time::Instant) -> CommandResponse {
+    let r = match cmd {
+        Command::SAdd { key, members } => sadd(db, &key, members, now),
+        Command::SRem { key, members } => srem(db, &key, &members, now),
+        Command::SMembers { key } => read(db, &key, now).map(|s| sorted(s.into_iter().flatten())),
+        Command::SIsMember { key, member } => {
+            read(db, &key, now).map(|s| CommandResponse::Integer {
+                value: i64::from(s.is_some_and(|s| s.contains(&member))),
+            })
+        }
+        Command::SCard { key } => {
+            read(db, &key, now).map(|s| super::to_integer(s.map_or(0, HashSet::len) as u64))
+        }
+        Command::SInter { keys } => combine(db, &keys, Op::Inter, now).map(|s| sorted(s.iter())),
+        Command::SUnion { keys } => combine(db, &keys, Op::Union, now).map(|s| sorted(s.iter())),
+        Command::SDiff { keys } => combine(db, &keys, Op::Diff, now).map(|s| sorted(s.iter())),
+        Command::SInterStore { destination, keys } => {
+            store(db, &destination, &keys, Op::Inter, now)
+        }
+        Command::SUnionStore { destination, keys } => {
+            store(db, &destination, &keys, Op::Union, now)
+        }
+        Command::SDiffStore { destination, keys } => store(db, &destination, &keys, Op::Diff, now),
+        Command::SPop { key, count } => spop(db, &key, count, now),
+        Command::SRandMember { key, count } => {
+            read(db, &key, now).and_then(|s| srandmember(s, count))
+        }
+        _ => unreachable!("not a set command"),
+    };
+
+    r.unwrap_or_else(|value| CommandResponse::Error { value })
+}
+
+/// Set stored at key, None if the key does not exist.
+fn read<'a>(
+    db: &'a mut HashMapDb,
+    key: &str,
+    now: std::time::Instant,
+) -> Result<Option<&'a HashSet<String>>, String> {
+    db.get(key, now)
+        .map(Value::as_set)
+        .transpose()
+        .map_err(|e| e.to_string())
+}
+
+fn sadd(
+    db: &mut HashMapDb,
+    key: &str,
+    members: Vec<String>,
+    now: std::time::Instant,
+) -> Result<CommandResponse, String> {
+    let s = db
+        .get_or_insert_with(key, now, || Value::Set(HashSet::new()))
+        .as_set_mut()
+        .map_err(|e| e.to_string())?;
+    let added = members.into_iter().filter(|m| s.insert(m.clone())).count();
+    db.changed(key);
+
+    Ok(super::to_integer(added as u64))
+}
+
+fn srem(
+    db: &mut HashMapDb,
+    key: &str,
+    members: &[String],
+    now: std::time::Instant,
+) -> Result<CommandResponse, String> {
+    let Some(v) = db.get_mut(key, now) else {
+        return Ok(super::to_integer(0));
+    };
+    let s = v.as_set_mut().map_err(|e| e.to_string())?;
+    let removed = members.iter().filter(|m| s.remove(*m)).count();
+    if removed > 0 {
+        db.changed(key);
+    }
+
+    Ok(super::to_integer(removed as u64))
+}
+
+/// Combine the sets stored at the keys from the first one, a missing key is an empty set. Like redis, every key
+/// must hold a set even when the result is already known.
+fn combine(
+    db: &mut HashMapDb,
+    keys: &[String],
+    op: Op,
+    now: std::time::Instant,
+) -> Result<HashSet<String>, String> {
+    let Some((first, others)) = keys.split_first() else {
+        return Ok(HashSet::new());
+    };
+
+    let mut combined = read(db, first, now)?.cloned().unwrap_or_default();
+    for key in others {
+        let s = read(db, key, now)?;
+        match op {
+            Op::Inter => combined.retain(|m| s.is_some_and(|s| s.contains(m))),
+            Op::Union => combined.extend(s.into_iter().flatten().cloned()),
+            Op::Diff => combined.retain(|m| !s.is_some_and(|s| s.contains(m))),
+        }
+    }
+
+    Ok(combined)
+}
+
+/// Store the combined sets at destination, which is removed if the result is empty. The reply is the number of
+/// members stored.
+fn store(
+    db: &mut HashMapDb,
+    destination: &str,
+    keys: &[String],
+    op: Op,
+    now: std::time::Instant,
+) -> Result<CommandResponse, String> {
+    let s = combine(db, keys, op, now)?;
+    let len = s.len();
+    if s.is_empty() {
+        db.del(destination);
+    } else {
+        db.set(destination, Value::Set(s), None);
+    }
+
+    Ok(super::to_integer(len as u64))
+}
+
+fn spop(
+    db: &mut HashMapDb,
+    key: &str,
+    count: Option<usize>,
+    now: std::time::Instant,
+) -> Result<CommandResponse, String> {
+    let Some(v) = db.get_mut(key, now) else {
+        return Ok(
+            count.map_or(CommandResponse::Null, |_| CommandResponse::Array {
+                value: vec![],
+            }),
+        );
+    };
+    let s = v.as_set_mut().map_err(|e| e.to_string())?;
+
+    let popped = sample(s, count.unwrap_or(1))
+        .into_iter()
+        .cloned()
+        .collect::<Vec<String>>();
+    for m in &popped {
+        s.remove(m);
+    }
+    db.changed(key);
+
+    Ok(match count {
+        None => popped.first().map_or(CommandResponse::Null, bulk),
+        Some(_) => CommandResponse::Array {
+            value: popped.iter().map(bulk).collect(),
+        },
+    })
+}
+
+/// Random members without removing them, like redis a negative count allows the same member more than once.
+fn srandmember(s: Option<&HashSet<String>>, count: Option<i64>) -> Result<CommandResponse, String> {
+    if count.is_some_and(|n| n < 0 && n.unsigned_abs() > MAX_REPEATED_MEMBERS) {
+        return Err("ERR value is out of range".to_owned());
+    }
+    let Some(s) = s.filter(|s| !s.is_empty()) else {
+        return Ok(
+            count.map_or(CommandResponse::Null, |_| CommandResponse::Array {
+                value: vec![],
+            }),
+        );
+    };
+
+    Ok(match count {
+        None => sample(s, 1)
+            .first()
+            .map_or(CommandResponse::Null, |m| bulk(*m)),
+        Some(n) if n >= 0 => CommandResponse::Array {
+            value: sample(s, usize::try_from(n).unwrap_or(usize::MAX))
+                .into_iter()
+                .map(bulk)
+                .collect(),
+        },
+        Some(n) => {
+            let mut rng = Rng::new();
+            let mut indexes = (0..n.unsigned_abs())
+                .map(|_| rng.below(s.len()))
+                .collect::<Vec<usize>>();
+            indexes.sort_unstable();
+            let mut members = members_at(s, &indexes);
+            shuffle(&mut members, &mut rng);
+
+            CommandResponse::Array {
+                value: members.into_iter().map(bulk).collect(),
+            }
+        }
+    })
+}
+
+/// Up to count distinct members picked at random, the set is walked once up to the last member picked.
+fn sample(s: &HashSet<String>, count: usize) -> Vec<&String> {
+    if count >= s.len() {
+        return s.iter().collect();
+    }
+
+    // NOTE: Floyd's algorithm picks count distinct indexes, without a permutation of the whole set.
+    let mut rng = Rng::new();
+    let mut picked = HashSet::with_capacity(count);
+    for j in s.len() - count..s.len() {
+        let i = rng.below(j + 1);
+        if !picked.insert(i) {
+            picked.insert(j);
+        }
+    }
+    let mut indexes = picked.into_iter().collect::<Vec<usize>>();
+    indexes.sort_unstable();
+    let mut members = members_at(s, &indexes);
+    shuffle(&mut members, &mut rng);
+
+    members
+}
+
+/// Members at the sorted indexes of the iteration order, an index may repeat.
+fn members_at<'a>(s: &'a HashSet<String>, indexes: &[usize]) -> Vec<&'a String> {
+    let mut members = s.iter();
+    let mut next = 0;
+    let mut member = None;
+
+    indexes
+        .iter()
+        .filter_map(|i| {
+            if *i >= next {
+                member = members.nth(i - next);
+                next = i + 1;
+            }
+            member
+        })
+        .collect()
+}
+
+/// Fisher-Yates shuffle, the members are picked in the iteration order of the set.
+fn shuffle<T>(items: &mut [T], rng: &mut Rng) {
+    for i in (1..items.len()).rev() {
+        items.swap(i, rng.below(i + 1));
+    }
+}
+
+/// Splitmix64 generator, seeded from the system random source or from the clock when it is not available.
+struct Rng(u64);
+
+impl Rng {
+    fn new() -> Self {
+        let seed = ring::rand::generate::<[u8; 8]>(&ring::rand::SystemRandom::new()).map_or_else(
+            |_| {
+                let now = std::time::SystemTime::now()
+                    .duration_since(std::time::UNIX_EPOCH)
+                    .unwrap_or_default();
+                // NOTE: only the low bits of the nanoseconds change between calls, the truncation is fine.
+                #[allow(clippy::cast_possible_truncation)]
+                let nanos = now.as_nanos() as u64;
+                nanos
+            },
+            |r| u64::from_le_bytes(r.expose()),
+        );
+
+        Self(seed)
+    }
+
+    /// Random number below n, which must not be zero.
+    fn below(&mut self, n: usize) -> usize {
+        self.0 = self.0.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^= z >> 31;
+
+        usize::try_from(z % n as u64).unwrap_or(0)
+    }
+}
+
+/// Members sorted, so that the output is stable.
+fn sorted<'a>(members: impl Iterator<Item = &'a String>) -> CommandResponse {
+    let mut members = members.collect::<Vec<&String>>();
+    members.sort_unstable();
+
+    CommandResponse::Array {
+        value: members.into_iter().map(bulk).collect(),
+    }
+}
+
+fn bulk(value: impl Into<String>) -> CommandResponse {
+    CommandResponse::BulkString {
+        value: value.into(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config;
+
+    fn strings(values: &[&str]) -> Vec<String> {
+        values.iter().map(ToString::to_string).collect()
+    }
+
+    fn bulks(values: &[&str]) -> CommandResponse {
+        CommandResponse::Array {
+            value: values.iter().map(|v| bulk(*v)).collect(),
+        }
+    }
+
+    #[test]
+    fn commands() {
+        let mut db = HashMapDb::new(config::Engine::default());
+        let now = std::time::Instant::now();
+        let mut exec = |cmd| set(cmd, &mut db, now);
+        let key = || "flags".to_string();
+
+        let res = exec(Command::SAdd {
+            key: key(),
+            members: strings(&["b", "a", "b"]),
+        });
+        assert_eq!(res, CommandResponse::Integer { value: 2 });
+        let res = exec(Command::SAdd {
+            key: key(),
+            members: strings(&["a", "c"]),
+        });
+        assert_eq!(res, CommandResponse::Integer { value: 1 });
+
+        let res = exec(Command::SMembers { key: key() });
+        assert_eq!(res, bulks(&["a", "b", "c"]));
+        let res = exec(Command::SIsMember {
+            key: key(),
+            member: "c".to_string(),
+        });
+        assert_eq!(res, CommandResponse::Integer { value: 1 });
+        let res = exec(Command::SCard { key: key() });
+        assert_eq!(res, CommandResponse::Integer { value: 3 });
+
+        let res = exec(Command::SRem {
+            key: key(),
+            members: strings(&["a", "missing"]),
+        });
+        assert_eq!(res, CommandResponse::Integer { value: 1 });
+        let res = exec(Command::SPop {
+            key: key(),
+            count: Some(5),
+        });
+        let CommandResponse::Array { value } = res else {
+            panic!("unexpected response {res:?}");
+        };
+        assert_eq!(value.len(), 2);
+
+        // NOTE: the set is removed with its last member.
+        let res = exec(Command::SCard { key: key() });
+        assert_eq!(res, CommandResponse::Integer { value: 0 });
+        let res = exec(Command::SPop {
+            key: key(),
+            count: None,
+        });
+        assert_eq!(res, CommandResponse::Null);
+        assert_eq!(db.get("flags", now), None);
+    }
+
+    #[test]
+    fn algebra() {
+        let mut db = HashMapDb::new(config::Engine::default());
+        let now = std::time::Instant::now();
+        let mut exec = |cmd| set(cmd, &mut db, now);
+        exec(Command::SAdd {
+            key: "a".to_string(),
+            members: strings(&["1", "2", "3"]),
+        });
+        exec(Command::SAdd {
+            key: "b".to_string(),
+            members: strings(&["2", "3", "4"]),
+        });
+
+        let res = exec(Command::SInter {
+            keys: strings(&["a", "b"]),
+        });
+        assert_eq!(res, bulks(&["2", "3"]));
+        let res = exec(Command::SUnion {
+            keys: strings(&["a", "b", "missing"]),
+        });
+        assert_eq!(res, bulks(&["1", "2", "3", "4"]));
+        let res = exec(Command::SDiff {
+            keys: strings(&["a", "b"]),
+        });
+        assert_eq!(res, bulks(&["1"]));
+        let res = exec(Command::SInter {
+            keys: strings(&["a", "missing"]),
+        });
+        assert_eq!(res, bulks(&[]));
+
+        let res = exec(Command::SUnionStore {
+            destination: "c".to_string(),
+            keys: strings(&["a", "b"]),
+        });
+        assert_eq!(res, CommandResponse::Integer { value: 4 });
+        let res = exec(Command::SMembers {
+            key: "c".to_string(),
+        });
+        assert_eq!(res, bulks(&["1", "2", "3", "4"]));
+
+        // NOTE: an empty result removes the destination.
+        let res = exec(Command::SDiffStore {
+            destination: "c".to_string(),
+            keys: strings(&["a", "a"]),
+        });
+        assert_eq!(res, CommandResponse::Integer { value: 0 });
+        assert_eq!(db.get("c", now), None);
+
+        db.set("s", "value".to_string(), None);
+        let res = set(
+            Command::SInterStore {
+                destination: "c".to_string(),
+                keys: strings(&["missing", "s"]),
+            },
+            &mut db,
+            now,
+        );
+        assert_eq!(
+            res,
+            CommandResponse::Error {
+                value: crate::engine::value::WrongType.to_string()
+            }
+        );
+    }
+
+    #[test]
+    fn random_members() {
+        let s = strings(&["a", "b", "c"])
+            .into_iter()
+            .collect::<HashSet<String>>();
+
+        let Ok(CommandResponse::Array { value }) = srandmember(Some(&s), Some(10)) else {
+            panic!("expected an array");
+        };
+        assert_eq!(value.len(), 3);
+        let Ok(CommandResponse::Array { value }) = srandmember(Some(&s), Some(-10)) else {
+            panic!("expected an array");
+        };
+        assert_eq!(value.len(), 10);
+        assert!(matches!(
+            srandmember(Some(&s), None),
+            Ok(CommandResponse::BulkString { .. })
+        ));
+        assert_eq!(srandmember(None, None), Ok(CommandResponse::Null));
+        assert_eq!(srandmember(None, Some(-3)), Ok(bulks(&[])));
+        assert_eq!(
+            srandmember(Some(&s), Some(i64::MIN)),
+            Err("ERR value is out of range".to_owned())
+        );
+
+        let mut picked = sample(&s, 3);
+        picked.sort_unstable();
+        assert_eq!(picked, ["a", "b", "c"]);
+        let picked = sample(&s, 2);
+        assert_eq!(picked.len(), 2);
+        assert_ne!(picked[0], picked[1]);
+        assert_eq!(members_at(&s, &[0, 0, 2]).len(), 3);
+
+        let mut rng = Rng::new();
+        let draws = (0..64).map(|_| rng.below(10)).collect::<HashSet<usize>>();
+        assert!(draws.iter().all(|d| *d < 10));
+        assert!(draws.len() > 1);
+    }
+}
@@ -0,0 +1,737 @@
+use crate::engine::db::HashMapDb;
+use crate::engine::stream::{Fields, NewId, Stream, StreamError, StreamId, Trim};
+use crate::engine::value::Value;
+use crate::protocol::commands::{Command, CommandResponse};
+use crate::server::blocking::Blocking;
+
+/// Execute the commands of the stream type, unlike the other collections a stream is kept without entries. XADD
+/// wakes up the connections blocked reading the stream, XREAD and XREADGROUP reply Null when they would block, see
+/// `wait_read`.
+pub fn stream(
+    cmd: Command,
+    db: &mut HashMapDb,
+    blocking: &mut Blocking,
+    now: std::time::Instant,
+) -> CommandResponse {
+    let r = match cmd {
+        Command::XAdd {
+            key,
+            id,
+            fields,
+            trim,
+            nomkstream,
+        } => match read(db, &key, now) {
+            Ok(None) if nomkstream => Ok(CommandResponse::Null),
+            _ => xadd(db, &key, id, fields, trim, now).inspect(|_| blocking.notify(&key)),
+        },
+        Command::XLen { key } => {
+            read(db, &key, now).map(|s| super::to_integer(s.map_or(0, Stream::len) as u64))
+        }
+        Command::XRange {
+            key,
+            start,
+            end,
+            count,
+        } => range(db, &key, (start, end), count, false, now),
+        Command::XRevRange {
+            key,
+            start,
+            end,
+            count,
+        } => range(db, &key, (start, end), count, true, now),
+        Command::XTrim { key, trim } => update(db, &key, now, |s| Ok(s.trim(trim))),
+        Command::XDel { key, ids } => update(db, &key, now, |s| Ok(s.delete(&ids))),
+        Command::XRead {
+            count, keys, ids, ..
+        } => xread(db, count, &keys, &ids, now),
+        Command::XGroupCreate {
+            key,
+            group,
+            id,
+            mkstream,
+        } => xgroup_create(db, &key, &group, id, mkstream, now),
+        Command::XGroupDestroy { key, group } => {
+            update(db, &key, now, |s| Ok(usize::from(s.destroy_group(&group))))
+        }
+        Command::XReadGroup {
+            group,
+            consumer,
+            count,
+            noack,
+            keys,
+            ids,
+            ..
+        } => xreadgroup(db, (&group, &consumer), count, noack, &keys, &ids, now),
+        Command::XAck { key, group, ids } => update(db, &key, now, |s| Ok(s.ack(&group, &ids))),
+        Command::XClaim {
+            key,
+            group,
+            consumer,
+            min_idle,
+            ids,
+        } => db
+            .get_mut(&key, now)
+            .ok_or(StreamError::NoGroup)
+            .map_err(|e| e.to_string())
+            .and_then(|v| v.as_stream_mut().map_err(|e| e.to_string()))
+            .and_then(|s| {
+                let at = unix_ms();
+                s.claim(&group, &consumer, super::as_millis(min_idle), &ids, at)
+                    .map_err(|e| e.to_string())
+            })
+            .map(|claimed| {
+                db.changed(&key);
+                CommandResponse::Array {
+                    value: claimed.iter().map(|(id, f)| entry(*id, Some(f))).collect(),
+                }
+            }),
+        Command::XPending {
+            key,
+            group,
+            range,
+            consumer,
+        } => read(db, &key, now).and_then(|s| {
+            let g = s
+                .and_then(|s| s.group(&group))
+                .ok_or_else(|| StreamError::NoGroup.to_string())?;
+            Ok(match range {
+                None => pending_summary(g),
+                Some((start, end, count)) => pending(g, start, end, count, consumer.as_deref()),
+            })
+        }),
+        cmd @ (Command::XInfoStream { .. }
+        | Command::XInfoGroups { .. }
+        | Command::XInfoConsumers { .. }) => xinfo(db, &cmd, now),
+        _ => unreachable!("not a stream command"),
+    };
+
+    r.unwrap_or_else(|value| CommandResponse::Error { value })
+}
+
+/// Replace the `$` of XREAD by the last ID of the streams, so that a blocked connection running the command again
+/// gets the entries added since the first run.
+pub fn resolve_last_ids(cmd: &mut Command, db: &mut HashMapDb, now: std::time::Instant) {
+    if let Command::XRead { keys, ids, .. } = cmd {
+        for (key, id) in keys.iter().zip(ids.iter_mut()) {
+            if id.is_none() {
+                let last = db
+                    .get(key, now)
+                    .and_then(|v| v.as_stream().ok())
+                    .map_or(StreamId::MIN, Stream::last_id);
+                *id = Some(last);
+            }
+        }
+    }
+}
+
+/// Stream stored at key, None if the key does not exist.
+fn read<'a>(
+    db: &'a mut HashMapDb,
+    key: &str,
+    now: std::time::Instant,
+) -> Result<Option<&'a Stream>, String> {
+    db.get(key, now)
+        .map(Value::as_stream)
+        .transpose()
+        .map_err(|e| e.to_string())
+}
+
+/// Apply the change to the stream stored at key, the reply is the number of items changed and 0 if the key does not
+/// exist.
+fn update(
+    db: &mut HashMapDb,
+    key: &str,
+    now: std::time::Instant,
+    f: impl FnOnce(&mut Stream) -> Result<usize, StreamError>,
+) -> Result<CommandResponse, String> {
+    let Some(v) = db.get_mut(key, now) else {
+        return Ok(super::to_integer(0));
+    };
+    let s = v.as_stream_mut().map_err(|e| e.to_string())?;
+    let n = f(s).map_err(|e| e.to_string())?;
+    if n > 0 {
+        db.changed(key);
+    }
+
+    Ok(super::to_integer(n as u64))
+}
+
+fn xadd(
+    db: &mut HashMapDb,
+    key: &str,
+    id: NewId,
+    fields: Fields,
+    trim: Option<Trim>,
+    now: std::time::Instant,
+) -> Result<CommandResponse, String> {
+    let created = read(db, key, now)?.is_none();
+    let s = db
+        .get_or_insert_with(key, now, || Value::Stream(Stream::default()))
+        .as_stream_mut()
+        .map_err(|e| e.to_string())?;
+
+    let added = s.add(id, fields, unix_ms());
+    if let (Ok(_), Some(trim)) = (&added, trim) {
+        s.trim(trim);
+    }
+    // NOTE: streams are kept without entries, the one created for an invalid ID must be removed here.
+    match added {
+        Ok(id) => {
+            db.changed(key);
+            Ok(bulk(id.to_string()))
+        }
+        Err(e) => {
+            if created {
+                db.del(key);
+            }
+            Err(e.to_string())
+        }
+    }
+}
+
+/// XRANGE and XREVRANGE, the count applies after the order of the entries.
+fn range(
+    db: &mut HashMapDb,
+    key: &str,
+    (start, end): (StreamId, StreamId),
+    count: Option<usize>,
+    rev: bool,
+    now: std::time::Instant,
+) -> Result<CommandResponse, String> {
+    let Some(s) = read(db, key, now)? else {
+        return Ok(CommandResponse::Array { value: vec![] });
+    };
+
+    let entries = s.range(start, end);
+    let entries: Box<dyn Iterator<Item = _>> = if rev {
+        Box::new(entries.rev())
+    } else {
+        Box::new(entries)
+    };
+    Ok(CommandResponse::Array {
+        value: entries
+            .take(count.unwrap_or(usize::MAX))
+            .map(|(id, f)| entry(*id, Some(f)))
+            .collect(),
+    })
+}
+
+fn xread(
+    db: &mut HashMapDb,
+    count: Option<usize>,
+    keys: &[String],
+    ids: &[Option<StreamId>],
+    now: std::time::Instant,
+) -> Result<CommandResponse, String> {
+    let mut streams = vec![];
+    for (key, id) in keys.iter().zip(ids) {
+        let Some(s) = read(db, key, now)? else {
+            continue;
+        };
+        let Some(start) = id.unwrap_or_else(|| s.last_id()).next() else {
+            continue;
+        };
+
+        let entries = s
+            .range(start, StreamId::MAX)
+            .take(count.unwrap_or(usize::MAX))
+            .map(|(id, f)| entry(*id, Some(f)))
+            .collect::<Vec<CommandResponse>>();
+        if !entries.is_empty() {
+            streams.push(keyed(key, entries));
+        }
+    }
+
+    Ok(if streams.is_empty() {
+        CommandResponse::Null
+    } else {
+        CommandResponse::Array { value: streams }
+    })
+}
+
+fn xgroup_create(
+    db: &mut HashMapDb,
+    key: &str,
+    group: &str,
+    id: Option<StreamId>,
+    mkstream: bool,
+    now: std::time::Instant,
+) -> Result<CommandResponse, String> {
+    if read(db, key, now)?.is_none() && !mkstream {
+        return Err("ERR The XGROUP subcommand requires the key to exist. Note that for CREATE you may want to use \
+                    the MKSTREAM option to create an empty stream automatically."
+            .to_owned());
+    }
+
+    let s = db
+        .get_or_insert_with(key, now, || Value::Stream(Stream::default()))
+        .as_stream_mut()
+        .map_err(|e| e.to_string())?;
+    let id = id.unwrap_or_else(|| s.last_id());
+    let created = s.create_group(group, id);
+    db.changed(key);
+
+    created
+        .map(|()| CommandResponse::String {
+            value: "OK".to_owned(),
+        })
+        .map_err(|e| e.to_string())
+}
+
+fn xreadgroup(
+    db: &mut HashMapDb,
+    (group, consumer): (&str, &str),
+    count: Option<usize>,
+    noack: bool,
+    keys: &[String],
+    ids: &[Option<StreamId>],
+    now: std::time::Instant,
+) -> Result<CommandResponse, String> {
+    let at = unix_ms();
+    let mut streams = vec![];
+    for (key, id) in keys.iter().zip(ids) {
+        let s = db
+            .get_mut(key, now)
+            .ok_or_else(|| StreamError::NoGroup.to_string())?
+            .as_stream_mut()
+            .map_err(|e| e.to_string())?;
+        let read = s
+            .read_group(group, consumer, *id, count.unwrap_or(usize::MAX), noack, at)
+            .map_err(|e| e.to_string())?;
+        db.changed(key);
+
+        // NOTE: like redis a stream without new entries is left out, the pending entries are always replied.
+        if id.is_none() && read.is_empty() {
+            continue;
+        }
+        let entries = read.iter().map(|(id, f)| entry(*id, f.as_ref())).collect();
+        streams.push(keyed(key, entries));
+    }
+
+    Ok(if streams.is_empty() {
+        CommandResponse::Null
+    } else {
+        CommandResponse::Array { value: streams }
+    })
+}
+
+/// Number of pending entries with the lowest and highest IDs, and the number of entries of every consumer.
+fn pending_summary(g: &crate::engine::stream::Group) -> CommandResponse {
+    let pending = g.pending();
+    let (Some((first, _)), Some((last, _))) = (pending.first_key_value(), pending.last_key_value())
+    else {
+        return CommandResponse::Array {
+            value: vec![
+                super::to_integer(0),
+                CommandResponse::Null,
+                CommandResponse::Null,
+                CommandResponse::Null,
+            ],
+        };
+    };
+
+    let consumers = g
+        .consumers()
+        .filter(|(_, n, _)| *n > 0)
+        .map(|(name, n, _)| CommandResponse::Array {
+            value: vec![bulk(name), bulk(n.to_string())],
+        })
+        .collect();
+    CommandResponse::Array {
+        value: vec![
+            super::to_integer(pending.len() as u64),
+            bulk(first.to_string()),
+            bulk(last.to_string()),
+            CommandResponse::Array { value: consumers },
+        ],
+    }
+}
+
+/// Pending entries between start and end as `[id, consumer, idle, deliveries]`.
+fn pending(
+    g: &crate::engine::stream::Group,
+    start: StreamId,
+    end: StreamId,
+    count: usize,
+    consumer: Option<&str>,
+) -> CommandResponse {
+    let at = unix_ms();
+    let entries = g
+        .pending()
+        .iter()
+        .skip_while(|(id, _)| **id < start)
+        .take_while(|(id, _)| **id <= end)
+        .filter(|(_, p)| consumer.map_or(true, |c| p.consumer == c))
+        .take(count)
+        .map(|(id, p)| CommandResponse::Array {
+            value: vec![
+                bulk(id.to_string()),
+                bulk(&p.consumer),
+                super::to_integer(at.saturating_sub(p.delivered_at)),
+                super::to_integer(p.deliveries),
+            ],
+        })
+        .collect();
+
+    CommandResponse::Array { value: entries }
+}
+
+fn xinfo(
+    db: &mut HashMapDb,
+    cmd: &Command,
+    now: std::time::Instant,
+) -> Result<CommandResponse, String> {
+    let key = cmd.keys()[0];
+    let s = read(db, key, now)?.ok_or_else(|| "ERR no such key".to_owned())?;
+    let at = unix_ms();
+    let map = |fields: Vec<(&str, CommandResponse)>| CommandResponse::Map {
+        value: fields.into_iter().map(|(k, v)| (bulk(k), v)).collect(),
+    };
+
+    Ok(match cmd {
+        Command::XInfoStream { .. } => {
+            let first = s.range(StreamId::MIN, StreamId::MAX).next();
+            let last = s.range(StreamId::MIN, StreamId::MAX).next_back();
+            let [first, last] = [first, last]
+                .map(|e| e.map_or(CommandResponse::Null, |(id, f)| entry(*id, Some(f))));
+            map(vec![
+                ("length", super::to_integer(s.len() as u64)),
+                ("last-generated-id", bulk(s.last_id().to_string())),
+                ("entries-added", super::to_integer(s.entries_added())),
+                ("groups", super::to_integer(s.groups().count() as u64)),
+                ("first-entry", first),
+                ("last-entry", last),
+            ])
+        }
+        Command::XInfoGroups { .. } => CommandResponse::Array {
+            value: s
+                .groups()
+                .map(|(name, g)| {
+                    map(vec![
+                        ("name", bulk(name)),
+                        ("consumers", super::to_integer(g.consumers().count() as u64)),
+                        ("pending", super::to_integer(g.pending().len() as u64)),
+                        ("last-delivered-id", bulk(g.last_delivered().to_string())),
+                    ])
+                })
+                .collect(),
+        },
+        Command::XInfoConsumers { group, .. } => {
+            let g = s
+                .group(group)
+                .ok_or_else(|| StreamError::NoGroup.to_string())?;
+            CommandResponse::Array {
+                value: g
+                    .consumers()
+                    .map(|(name, pending, seen_at)| {
+                        map(vec![
+                            ("name", bulk(name)),
+                            ("pending", super::to_integer(pending as u64)),
+                            ("idle", super::to_integer(at.saturating_sub(seen_at))),
+                        ])
+                    })
+                    .collect(),
+            }
+        }
+        _ => unreachable!("not a XINFO command"),
+    })
+}
+
+/// Unix time in milliseconds, the clock of the IDs and of the idle times.
+fn unix_ms() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map_or(0, super::as_millis)
+}
+
+/// Entry as `[id, [field, value, ...]]`, the fields are nil for an entry removed from the stream.
+fn entry(id: StreamId, fields: Option<&Fields>) -> CommandResponse {
+    CommandResponse::Array {
+        value: vec![
+            bulk(id.to_string()),
+            fields.map_or(CommandResponse::Null, |f| CommandResponse::Array {
+                value: f.iter().flat_map(|(f, v)| [bulk(f), bulk(v)]).collect(),
+            }),
+        ],
+    }
+}
+
+/// Entries of a stream read by XREAD and XREADGROUP as `[key, [entry, ...]]`.
+fn keyed(key: &str, entries: Vec<CommandResponse>) -> CommandResponse {
+    CommandResponse::Array {
+        value: vec![bulk(key), CommandResponse::Array { value: entries }],
+    }
+}
+
+fn bulk(value: impl Into<String>) -> CommandResponse {
+    CommandResponse::BulkString {
+        value: value.into(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config;
+
+    fn xadd(key: &str, id: NewId, value: &str) -> Command {
+        Command::XAdd {
+            key: key.to_string(),
+            id,
+            fields: vec![("v".to_string(), value.to_string())],
+            trim: None,
+            nomkstream: false,
+        }
+    }
+
+    fn ids(res: &CommandResponse) -> Vec<String> {
+        let CommandResponse::Array { value } = res else {
+            panic!("unexpected response {res:?}");
+        };
+        value
+            .iter()
+            .map(|e| match e {
+                CommandResponse::Array { value } => match &value[0] {
+                    CommandResponse::BulkString { value } => value.clone(),
+                    v => panic!("unexpected id {v:?}"),
+                },
+                e => panic!("unexpected entry {e:?}"),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn commands() {
+        let mut db = HashMapDb::new(config::Engine::default());
+        let mut blocking = Blocking::default();
+        let now = std::time::Instant::now();
+        let mut exec = |cmd| stream(cmd, &mut db, &mut blocking, now);
+
+        for ms in 1..=4 {
+            let res = exec(xadd("events", NewId::Ms(ms), "x"));
+            assert_eq!(res, bulk(format!("{ms}-0")));
+        }
+        let res = exec(xadd("events", NewId::Ms(1), "x"));
+        assert_eq!(
+            res,
+            CommandResponse::Error {
+                value: StreamError::IdTooSmall.to_string()
+            }
+        );
+
+        let res = exec(Command::XRange {
+            key: "events".to_string(),
+            start: StreamId { ms: 2, seq: 0 },
+            end: StreamId::MAX,
+            count: Some(2),
+        });
+        assert_eq!(ids(&res), ["2-0", "3-0"]);
+        let res = exec(Command::XRevRange {
+            key: "events".to_string(),
+            start: StreamId::MIN,
+            end: StreamId::MAX,
+            count: None,
+        });
+        assert_eq!(ids(&res), ["4-0", "3-0", "2-0", "1-0"]);
+
+        let res = exec(Command::XTrim {
+            key: "events".to_string(),
+            trim: Trim::MaxLen(1),
+        });
+        assert_eq!(res, CommandResponse::Integer { value: 3 });
+
+        // NOTE: the stream is kept without entries, with its last ID.
+        let res = exec(Command::XDel {
+            key: "events".to_string(),
+            ids: vec![StreamId { ms: 4, seq: 0 }],
+        });
+        assert_eq!(res, CommandResponse::Integer { value: 1 });
+        let res = exec(Command::XLen {
+            key: "events".to_string(),
+        });
+        assert_eq!(res, CommandResponse::Integer { value: 0 });
+        let res = exec(xadd("events", NewId::Ms(4), "x"));
+        assert_eq!(res, bulk("4-1"));
+
+        let res = exec(xadd("new", NewId::Exact(StreamId::MIN), "x"));
+        assert_eq!(
+            res,
+            CommandResponse::Error {
+                value: StreamError::ZeroId.to_string()
+            }
+        );
+        assert_eq!(db.get("new", now), None);
+    }
+
+    #[test]
+    fn read() {
+        let mut db = HashMapDb::new(config::Engine::default());
+        let mut blocking = Blocking::default();
+        let now = std::time::Instant::now();
+        for ms in 1..=3 {
+            stream(xadd("a", NewId::Ms(ms), "x"), &mut db, &mut blocking, now);
+        }
+
+        let mut cmd = Command::XRead {
+            count: Some(1),
+            block: None,
+            keys: vec!["a".to_string(), "missing".to_string()],
+            ids: vec![Some(StreamId { ms: 1, seq: 0 }), None],
+        };
+        let res = stream(cmd.clone(), &mut db, &mut blocking, now);
+        assert_eq!(
+            res,
+            CommandResponse::Array {
+                value: vec![keyed(
+                    "a",
+                    vec![entry(
+                        StreamId { ms: 2, seq: 0 },
+                        Some(&vec![("v".to_string(), "x".to_string())])
+                    )]
+                )]
+            }
+        );
+
+        // NOTE: a blocked read waits for the entries added after the last ID seen by its first run.
+        let Command::XRead { ids, .. } = &mut cmd else {
+            unreachable!();
+        };
+        ids[0] = None;
+        resolve_last_ids(&mut cmd, &mut db, now);
+        let Command::XRead { ids, .. } = &cmd else {
+            unreachable!();
+        };
+        assert_eq!(
+            ids,
+            &[Some(StreamId { ms: 3, seq: 0 }), Some(StreamId::MIN)]
+        );
+        assert_eq!(
+            stream(cmd.clone(), &mut db, &mut blocking, now),
+            CommandResponse::Null
+        );
+
+        let read = crate::server::blocking::Read {
+            keys: vec!["a".to_string()],
+            timeout: std::time::Duration::ZERO,
+            cmd: cmd.clone(),
+        };
+        let rx = blocking.watch(&read);
+        stream(xadd("a", NewId::Ms(4), "x"), &mut db, &mut blocking, now);
+        assert_eq!(rx.try_recv(), Some(()));
+        let res = stream(cmd, &mut db, &mut blocking, now);
+        let CommandResponse::Array { value } = res else {
+            panic!("unexpected response {res:?}");
+        };
+        assert_eq!(value.len(), 1);
+    }
+
+    #[test]
+    fn groups() {
+        let mut db = HashMapDb::new(config::Engine::default());
+        let mut blocking = Blocking::default();
+        let now = std::time::Instant::now();
+        let mut exec = |cmd| stream(cmd, &mut db, &mut blocking, now);
+        let create = |mkstream| Command::XGroupCreate {
+            key: "jobs".to_string(),
+            group: "workers".to_string(),
+            id: Some(StreamId::MIN),
+            mkstream,
+        };
+        let readgroup = |group: &str, consumer: &str, id| Command::XReadGroup {
+            group: group.to_string(),
+            consumer: consumer.to_string(),
+            count: None,
+            block: None,
+            noack: false,
+            keys: vec!["jobs".to_string()],
+            ids: vec![id],
+        };
+
+        assert!(matches!(exec(create(false)), CommandResponse::Error { .. }));
+        let ok = CommandResponse::String {
+            value: "OK".to_string(),
+        };
+        assert_eq!(exec(create(true)), ok);
+        assert_eq!(
+            exec(create(true)),
+            CommandResponse::Error {
+                value: StreamError::BusyGroup.to_string()
+            }
+        );
+
+        exec(xadd("jobs", NewId::Ms(1), "a"));
+        exec(xadd("jobs", NewId::Ms(2), "b"));
+        let res = exec(readgroup("workers", "alice", None));
+        let CommandResponse::Array { value } = &res else {
+            panic!("unexpected response {res:?}");
+        };
+        let CommandResponse::Array { value } = &value[0] else {
+            panic!("unexpected response {res:?}");
+        };
+        assert_eq!(ids(&value[1]), ["1-0", "2-0"]);
+        assert_eq!(
+            exec(readgroup("workers", "bob", None)),
+            CommandResponse::Null
+        );
+
+        let res = exec(Command::XPending {
+            key: "jobs".to_string(),
+            group: "workers".to_string(),
+            range: None,
+            consumer: None,
+        });
+        assert_eq!(
+            res,
+            CommandResponse::Array {
+                value: vec![
+                    CommandResponse::Integer { value: 2 },
+                    bulk("1-0"),
+                    bulk("2-0"),
+                    CommandResponse::Array {
+                        value: vec![CommandResponse::Array {
+                            value: vec![bulk("alice"), bulk("2")]
+                        }]
+                    }
+                ]
+            }
+        );
+
+        let res = exec(Command::XAck {
+            key: "jobs".to_string(),
+            group: "workers".to_string(),
+            ids: vec![StreamId { ms: 1, seq: 0 }],
+        });
+        assert_eq!(res, CommandResponse::Integer { value: 1 });
+        let res = exec(Command::XClaim {
+            key: "jobs".to_string(),
+            group: "workers".to_string(),
+            consumer: "bob".to_string(),
+            min_idle: std::time::Duration::ZERO,
+            ids: vec![StreamId { ms: 1, seq: 0 }, StreamId { ms: 2, seq: 0 }],
+        });
+        assert_eq!(ids(&res), ["2-0"]);
+
+        let res = exec(Command::XInfoGroups {
+            key: "jobs".to_string(),
+        });
+        let CommandResponse::Array { value } = res else {
+            panic!("unexpected response {res:?}");
+        };
+        let CommandResponse::Map { value } = &value[0] else {
+            panic!("unexpected group {value:?}");
+        };
+        assert_eq!(
+            value[2],
+            (bulk("pending"), CommandResponse::Integer { value: 1 })
+        );
+        assert_eq!(value[3], (bulk("last-delivered-id"), bulk("2-0")));
+
+        let res = exec(readgroup("missing", "bob", None));
+        assert_eq!(
+            res,
+            CommandResponse::Error {
+                value: StreamError::NoGroup.to_string()
+            }
+        );
+    }
+}
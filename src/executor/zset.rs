@@ -0,0 +1,555 @@
+use crate::engine::db::HashMapDb;
+use crate::engine::value::{Value, WrongType};
+use crate::engine::zset::SortedSet;
+use crate::protocol::commands::{Aggregate, Command, CommandResponse, ZAddFlags, ZRangeBy};
+use std::collections::HashMap;
+
+/// Operation combining the sets of ZUNIONSTORE, ZINTERSTORE and ZDIFFSTORE.
+#[derive(Clone, Copy)]
+enum Op {
+    Union,
+    Inter,
+    Diff,
+}
+
+/// Execute the commands of the sorted set type, a sorted set left without members is removed.
+pub fn zset(cmd: Command, db: &mut HashMapDb, now: std::time::Instant) -> CommandResponse {
+    let r = match cmd {
+        Command::ZAdd {
+            key,
+            members,
+            flags,
+        } => zadd(db, &key, members, flags, now),
+        Command::ZIncrBy {
+            key,
+            increment,
+            member,
+        } => {
+            let flags = ZAddFlags {
+                incr: true,
+                ..ZAddFlags::default()
+            };
+            zadd(db, &key, vec![(increment, member)], flags, now)
+        }
+        Command::ZRem { key, members } => zrem(db, &key, &members, now),
+        Command::ZRange {
+            key,
+            by,
+            rev,
+            limit,
+            with_scores,
+        } => read(db, &key, now).map(|z| {
+            let empty = SortedSet::default();
+            let members = range(z.unwrap_or(&empty), &by, rev, limit);
+            scored(members, with_scores)
+        }),
+        Command::ZRank { key, member } => read(db, &key, now).map(|z| {
+            z.and_then(|z| z.rank(&member))
+                .map_or(CommandResponse::Null, |r| super::to_integer(r as u64))
+        }),
+        Command::ZScore { key, member } => read(db, &key, now).map(|z| {
+            z.and_then(|z| z.score(&member))
+                .map_or(CommandResponse::Null, score)
+        }),
+        Command::ZCard { key } => {
+            read(db, &key, now).map(|z| super::to_integer(z.map_or(0, SortedSet::len) as u64))
+        }
+        Command::ZPopMin { key, count } => pop(db, &key, count, SortedSet::pop_min, now),
+        Command::ZPopMax { key, count } => pop(db, &key, count, SortedSet::pop_max, now),
+        Command::ZUnionStore {
+            destination,
+            keys,
+            weights,
+            aggregate,
+        } => store(db, &destination, &keys, &weights, aggregate, Op::Union, now),
+        Command::ZInterStore {
+            destination,
+            keys,
+            weights,
+            aggregate,
+        } => store(db, &destination, &keys, &weights, aggregate, Op::Inter, now),
+        Command::ZDiffStore { destination, keys } => {
+            store(db, &destination, &keys, &[], Aggregate::Sum, Op::Diff, now)
+        }
+        _ => unreachable!("not a sorted set command"),
+    };
+
+    r.unwrap_or_else(|value| CommandResponse::Error { value })
+}
+
+/// Sorted set stored at key, None if the key does not exist.
+pub(super) fn read<'a>(
+    db: &'a mut HashMapDb,
+    key: &str,
+    now: std::time::Instant,
+) -> Result<Option<&'a SortedSet>, String> {
+    db.get(key, now)
+        .map(Value::as_sorted_set)
+        .transpose()
+        .map_err(|e| e.to_string())
+}
+
+#[allow(clippy::float_cmp)]
+pub(super) fn zadd(
+    db: &mut HashMapDb,
+    key: &str,
+    members: Vec<(f64, String)>,
+    flags: ZAddFlags,
+    now: std::time::Instant,
+) -> Result<CommandResponse, String> {
+    let z = db
+        .get_or_insert_with(key, now, || Value::SortedSet(SortedSet::default()))
+        .as_sorted_set_mut()
+        .map_err(|e| e.to_string())?;
+
+    let (mut added, mut updated, mut last) = (0, 0, None);
+    let mut nan = false;
+    for (score, member) in members {
+        let current = z.score(&member);
+        if flags.exists.is_some_and(|e| e != current.is_some()) {
+            continue;
+        }
+        let score = if flags.incr {
+            current.unwrap_or(0.0) + score
+        } else {
+            score
+        };
+        if score.is_nan() {
+            nan = true;
+            break;
+        }
+        if let (Some(c), Some(o)) = (current, flags.compare) {
+            if score.partial_cmp(&c) != Some(o) {
+                continue;
+            }
+        }
+
+        match z.insert(&member, score) {
+            None => added += 1,
+            Some(p) if p != score => updated += 1,
+            Some(_) => {}
+        }
+        last = Some(score);
+    }
+    // NOTE: the sorted set is created before the checks, it is removed here if nothing was added.
+    db.changed(key);
+
+    if nan {
+        return Err("ERR resulting score is not a number (NaN)".to_owned());
+    }
+    Ok(if flags.incr {
+        last.map_or(CommandResponse::Null, score)
+    } else if flags.ch {
+        super::to_integer(added + updated)
+    } else {
+        super::to_integer(added)
+    })
+}
+
+fn zrem(
+    db: &mut HashMapDb,
+    key: &str,
+    members: &[String],
+    now: std::time::Instant,
+) -> Result<CommandResponse, String> {
+    let Some(v) = db.get_mut(key, now) else {
+        return Ok(super::to_integer(0));
+    };
+    let z = v.as_sorted_set_mut().map_err(|e| e.to_string())?;
+    let removed = members.iter().filter(|m| z.remove(m).is_some()).count();
+    if removed > 0 {
+        db.changed(key);
+    }
+
+    Ok(super::to_integer(removed as u64))
+}
+
+/// Members of ZRANGE, ranks are counted from the highest score with REV like redis does.
+fn range<'a>(
+    z: &'a SortedSet,
+    by: &'a ZRangeBy,
+    rev: bool,
+    limit: Option<(usize, usize)>,
+) -> Vec<(&'a str, f64)> {
+    let (offset, count) = limit.unwrap_or((0, usize::MAX));
+
+    match by {
+        ZRangeBy::Rank(start, stop) => {
+            let Some(r) = super::list::range(z.len(), *start, *stop) else {
+                return vec![];
+            };
+            let n = r.end() - r.start() + 1;
+            z.range_by_rank(*r.start(), rev).take(n).collect()
+        }
+        ZRangeBy::Score(min, max) => z
+            .range_by_score(*min, *max, rev)
+            .skip(offset)
+            .take(count)
+            .collect(),
+        ZRangeBy::Lex(min, max) => z
+            .range_by_lex(
+                min.as_ref().map(String::as_str),
+                max.as_ref().map(String::as_str),
+                rev,
+            )
+            .skip(offset)
+            .take(count)
+            .collect(),
+    }
+}
+
+fn pop(
+    db: &mut HashMapDb,
+    key: &str,
+    count: Option<usize>,
+    next: fn(&mut SortedSet) -> Option<(String, f64)>,
+    now: std::time::Instant,
+) -> Result<CommandResponse, String> {
+    let Some(v) = db.get_mut(key, now) else {
+        return Ok(CommandResponse::Array { value: vec![] });
+    };
+    let z = v.as_sorted_set_mut().map_err(|e| e.to_string())?;
+
+    let popped = std::iter::from_fn(|| next(z))
+        .take(count.unwrap_or(1))
+        .collect::<Vec<(String, f64)>>();
+    db.changed(key);
+
+    Ok(scored(
+        popped.iter().map(|(m, s)| (m.as_str(), *s)).collect(),
+        true,
+    ))
+}
+
+/// Scores of the members stored at key multiplied by the weight, a plain set counts as scores of 1 like redis.
+fn weighted(
+    db: &mut HashMapDb,
+    key: &str,
+    weight: f64,
+    now: std::time::Instant,
+) -> Result<HashMap<String, f64>, String> {
+    // NOTE: like redis a NaN from 0 * inf counts as 0.
+    let weigh = |s: f64| {
+        let s = s * weight;
+        if s.is_nan() {
+            0.0
+        } else {
+            s
+        }
+    };
+
+    match db.get(key, now) {
+        None => Ok(HashMap::new()),
+        Some(Value::SortedSet(z)) => Ok(z.iter().map(|(m, s)| (m.to_string(), weigh(s))).collect()),
+        Some(Value::Set(s)) => Ok(s.iter().map(|m| (m.clone(), weigh(1.0))).collect()),
+        Some(_) => Err(WrongType.to_string()),
+    }
+}
+
+fn aggregate(aggregate: Aggregate, a: f64, b: f64) -> f64 {
+    match aggregate {
+        Aggregate::Sum => {
+            let s = a + b;
+            if s.is_nan() {
+                0.0
+            } else {
+                s
+            }
+        }
+        Aggregate::Min => a.min(b),
+        Aggregate::Max => a.max(b),
+    }
+}
+
+/// Store the combined sets at destination, which is removed if the result is empty. The reply is the number of
+/// members stored.
+fn store(
+    db: &mut HashMapDb,
+    destination: &str,
+    keys: &[String],
+    weights: &[f64],
+    how: Aggregate,
+    op: Op,
+    now: std::time::Instant,
+) -> Result<CommandResponse, String> {
+    let mut combined: Option<HashMap<String, f64>> = None;
+    for (i, key) in keys.iter().enumerate() {
+        let scores = weighted(db, key, weights.get(i).copied().unwrap_or(1.0), now)?;
+        let Some(mut c) = combined else {
+            combined = Some(scores);
+            continue;
+        };
+
+        match op {
+            Op::Union => {
+                for (m, s) in scores {
+                    c.entry(m)
+                        .and_modify(|v| *v = aggregate(how, *v, s))
+                        .or_insert(s);
+                }
+            }
+            Op::Inter => {
+                c.retain(|m, _| scores.contains_key(m));
+                for (m, v) in &mut c {
+                    *v = aggregate(how, *v, scores[m]);
+                }
+            }
+            Op::Diff => c.retain(|m, _| !scores.contains_key(m)),
+        }
+        combined = Some(c);
+    }
+
+    let z = SortedSet::from(
+        combined
+            .unwrap_or_default()
+            .into_iter()
+            .collect::<Vec<(String, f64)>>(),
+    );
+    let len = z.len();
+    if z.is_empty() {
+        db.del(destination);
+    } else {
+        db.set(destination, Value::SortedSet(z), None);
+    }
+
+    Ok(super::to_integer(len as u64))
+}
+
+/// Members followed by their score if requested, as a flat array like RESP2 replies do.
+fn scored(members: Vec<(&str, f64)>, with_scores: bool) -> CommandResponse {
+    CommandResponse::Array {
+        value: members
+            .into_iter()
+            .flat_map(|(m, s)| std::iter::once(bulk(m)).chain(with_scores.then(|| score(s))))
+            .collect(),
+    }
+}
+
+/// Score as a string, infinities are `inf` and `-inf` like redis.
+fn score(s: f64) -> CommandResponse {
+    bulk(s.to_string())
+}
+
+fn bulk(value: impl Into<String>) -> CommandResponse {
+    CommandResponse::BulkString {
+        value: value.into(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config;
+    use std::ops::Bound;
+
+    fn bulks(values: &[&str]) -> CommandResponse {
+        CommandResponse::Array {
+            value: values.iter().map(|v| bulk(*v)).collect(),
+        }
+    }
+
+    fn zadd(members: &[(f64, &str)], flags: ZAddFlags) -> Command {
+        Command::ZAdd {
+            key: "board".to_string(),
+            members: members
+                .iter()
+                .map(|(s, m)| (*s, (*m).to_string()))
+                .collect(),
+            flags,
+        }
+    }
+
+    fn zrange(by: ZRangeBy, rev: bool, limit: Option<(usize, usize)>) -> Command {
+        Command::ZRange {
+            key: "board".to_string(),
+            by,
+            rev,
+            limit,
+            with_scores: false,
+        }
+    }
+
+    #[test]
+    fn add_flags() {
+        let mut db = HashMapDb::new(config::Engine::default());
+        let now = std::time::Instant::now();
+        let mut exec = |cmd| zset(cmd, &mut db, now);
+
+        let res = exec(zadd(&[(1.0, "a"), (2.0, "b")], ZAddFlags::default()));
+        assert_eq!(res, CommandResponse::Integer { value: 2 });
+
+        let xx = ZAddFlags {
+            exists: Some(true),
+            ch: true,
+            ..ZAddFlags::default()
+        };
+        let res = exec(zadd(&[(5.0, "a"), (1.0, "c")], xx));
+        assert_eq!(res, CommandResponse::Integer { value: 1 });
+
+        let gt = ZAddFlags {
+            compare: Some(std::cmp::Ordering::Greater),
+            ch: true,
+            ..ZAddFlags::default()
+        };
+        let res = exec(zadd(&[(3.0, "a"), (3.0, "b"), (0.0, "d")], gt));
+        assert_eq!(res, CommandResponse::Integer { value: 2 });
+
+        let nx_incr = ZAddFlags {
+            exists: Some(false),
+            incr: true,
+            ..ZAddFlags::default()
+        };
+        let res = exec(zadd(&[(1.0, "a")], nx_incr));
+        assert_eq!(res, CommandResponse::Null);
+        let res = exec(Command::ZIncrBy {
+            key: "board".to_string(),
+            increment: 0.5,
+            member: "b".to_string(),
+        });
+        assert_eq!(res, bulk("3.5"));
+        let res = exec(Command::ZIncrBy {
+            key: "board".to_string(),
+            increment: f64::NEG_INFINITY,
+            member: "e".to_string(),
+        });
+        assert_eq!(res, bulk("-inf"));
+        let res = exec(Command::ZIncrBy {
+            key: "board".to_string(),
+            increment: f64::INFINITY,
+            member: "e".to_string(),
+        });
+        assert_eq!(
+            res,
+            CommandResponse::Error {
+                value: "ERR resulting score is not a number (NaN)".to_string()
+            }
+        );
+
+        let res = exec(zrange(ZRangeBy::Rank(0, -1), false, None));
+        assert_eq!(res, bulks(&["e", "d", "b", "a"]));
+
+        // NOTE: XX on a missing key must not leave an empty sorted set behind.
+        let res = zset(
+            Command::ZAdd {
+                key: "missing".to_string(),
+                members: vec![(1.0, "a".to_string())],
+                flags: xx,
+            },
+            &mut db,
+            now,
+        );
+        assert_eq!(res, CommandResponse::Integer { value: 0 });
+        assert_eq!(db.get("missing", now), None);
+    }
+
+    #[test]
+    fn ranges() {
+        let mut db = HashMapDb::new(config::Engine::default());
+        let now = std::time::Instant::now();
+        let mut exec = |cmd| zset(cmd, &mut db, now);
+        exec(zadd(
+            &[(1.0, "a"), (2.0, "b"), (3.0, "c"), (4.0, "d")],
+            ZAddFlags::default(),
+        ));
+
+        let res = exec(zrange(ZRangeBy::Rank(0, 1), true, None));
+        assert_eq!(res, bulks(&["d", "c"]));
+        let by = ZRangeBy::Score(Bound::Excluded(1.0), Bound::Unbounded);
+        let res = exec(zrange(by, false, Some((1, 1))));
+        assert_eq!(res, bulks(&["c"]));
+        let by = ZRangeBy::Score(Bound::Included(2.0), Bound::Included(3.0));
+        let res = exec(Command::ZRange {
+            key: "board".to_string(),
+            by,
+            rev: true,
+            limit: None,
+            with_scores: true,
+        });
+        assert_eq!(res, bulks(&["c", "3", "b", "2"]));
+        let by = ZRangeBy::Lex(Bound::Unbounded, Bound::Excluded("c".to_string()));
+        let res = exec(zrange(by, false, None));
+        assert_eq!(res, bulks(&["a", "b"]));
+
+        let res = exec(Command::ZRank {
+            key: "board".to_string(),
+            member: "c".to_string(),
+        });
+        assert_eq!(res, CommandResponse::Integer { value: 2 });
+        let res = exec(Command::ZScore {
+            key: "board".to_string(),
+            member: "missing".to_string(),
+        });
+        assert_eq!(res, CommandResponse::Null);
+
+        let res = exec(Command::ZPopMin {
+            key: "board".to_string(),
+            count: None,
+        });
+        assert_eq!(res, bulks(&["a", "1"]));
+        let res = exec(Command::ZPopMax {
+            key: "board".to_string(),
+            count: Some(10),
+        });
+        assert_eq!(res, bulks(&["d", "4", "c", "3", "b", "2"]));
+        assert_eq!(db.get("board", now), None);
+    }
+
+    #[test]
+    fn store() {
+        let mut db = HashMapDb::new(config::Engine::default());
+        let now = std::time::Instant::now();
+        let mut z = SortedSet::default();
+        z.insert("a", 1.0);
+        z.insert("b", 2.0);
+        db.set("z", Value::SortedSet(z), None);
+        db.set(
+            "s",
+            Value::Set(["b".to_string(), "c".to_string()].into()),
+            None,
+        );
+        let mut exec = |cmd| zset(cmd, &mut db, now);
+        let keys = || vec!["z".to_string(), "s".to_string()];
+        let board = |by| Command::ZRange {
+            key: "dst".to_string(),
+            by,
+            rev: false,
+            limit: None,
+            with_scores: true,
+        };
+
+        let res = exec(Command::ZUnionStore {
+            destination: "dst".to_string(),
+            keys: keys(),
+            weights: vec![2.0, 10.0],
+            aggregate: Aggregate::Sum,
+        });
+        assert_eq!(res, CommandResponse::Integer { value: 3 });
+        let res = exec(board(ZRangeBy::Rank(0, -1)));
+        assert_eq!(res, bulks(&["a", "2", "c", "10", "b", "14"]));
+
+        let res = exec(Command::ZInterStore {
+            destination: "dst".to_string(),
+            keys: keys(),
+            weights: vec![],
+            aggregate: Aggregate::Max,
+        });
+        assert_eq!(res, CommandResponse::Integer { value: 1 });
+        let res = exec(board(ZRangeBy::Rank(0, -1)));
+        assert_eq!(res, bulks(&["b", "2"]));
+
+        let res = exec(Command::ZDiffStore {
+            destination: "dst".to_string(),
+            keys: vec!["s".to_string(), "z".to_string()],
+        });
+        assert_eq!(res, CommandResponse::Integer { value: 1 });
+        let res = exec(board(ZRangeBy::Rank(0, -1)));
+        assert_eq!(res, bulks(&["c", "1"]));
+
+        let res = exec(Command::ZDiffStore {
+            destination: "dst".to_string(),
+            keys: vec!["z".to_string(), "z".to_string()],
+        });
+        assert_eq!(res, CommandResponse::Integer { value: 0 });
+        assert_eq!(db.get("dst", now), None);
+    }
+}
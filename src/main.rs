@@ -6,122 +6,821 @@ mod engine;
 mod executor;
 mod parser;
 mod protocol;
+mod server;
 
-use monoio::io::{AsyncReadRent, AsyncWriteRentExt};
+use monoio::io::{AsyncReadRent, AsyncWriteRent, AsyncWriteRentExt};
+use tracing::Instrument;
 
-#[monoio::main]
+#[monoio::main(timer_enabled = true)]
 async fn main() {
     let config_file = std::fs::read_to_string("configs/local.toml");
     if config_file.is_err() {
         tracing::error!(
-            "error on reading config file: {}",
-            config_file.err().unwrap()
+            error = %config_file.err().unwrap(),
+            "error on reading config file"
         );
         return;
     }
     let c = config::Config::new(config_file.unwrap().as_str());
 
-    let subscriber = tracing_subscriber::FmtSubscriber::builder()
+    let builder = tracing_subscriber::FmtSubscriber::builder()
         .with_max_level(tracing::Level::from_str(&c.logger.level).unwrap())
-        .finish();
-    tracing::subscriber::set_global_default(subscriber).expect("setting default subscriber failed");
+        .with_span_events(tracing_subscriber::fmt::format::FmtSpan::CLOSE);
+    match c.logger.format {
+        config::LogFormat::Text => tracing::subscriber::set_global_default(builder.finish()),
+        config::LogFormat::Json => tracing::subscriber::set_global_default(builder.json().finish()),
+    }
+    .expect("setting default subscriber failed");
+
+    protocol::commands::rename(&c.commands.rename);
 
-    let addr = std::env::args()
-        .nth(1)
-        .unwrap_or_else(|| "127.0.0.1:6379".to_string());
-    let listener = monoio::net::TcpListener::bind(addr).unwrap();
-    tracing::info!("listening on {}", listener.local_addr().unwrap());
+    let tcp = if c.network.tcp_enabled {
+        let addr = std::env::args()
+            .nth(1)
+            .unwrap_or_else(|| "127.0.0.1:6379".to_string());
+        let listener = monoio::net::TcpListener::bind(addr).unwrap();
+        tracing::info!(addr = %listener.local_addr().unwrap(), "listening");
+        Some(listener)
+    } else {
+        None
+    };
 
     let db = engine::db::create_db(&c.engine).unwrap();
 
-    let number_of_connections = std::rc::Rc::new(std::cell::RefCell::new(0));
+    let mut server = server::Server::new(
+        tcp.as_ref().map_or(0, |l| l.local_addr().unwrap().port()),
+        &c,
+    );
+    if let Some(file) = &c.security.aclfile {
+        if let Err(e) = server.acl.load(file) {
+            tracing::error!(error = %e, "error on loading acl file");
+            return;
+        }
+        tracing::info!(file, users = server.acl.users().count(), "loaded acl file");
+    }
+    // NOTE: a wildcard or public bind address accepts remote clients, which protected mode is going to refuse.
+    let tcp_addr = tcp.as_ref().map(|l| l.local_addr().unwrap().to_string());
+    let tls_addr = c.tls.enabled.then(|| c.tls.addr.clone());
+    for addr in tcp_addr.iter().chain(tls_addr.iter()) {
+        if server.is_protected_from(addr) {
+            tracing::warn!(
+                addr,
+                "protected mode is enabled and no password is set, only loopback clients are accepted"
+            );
+        }
+    }
+    let server = std::rc::Rc::new(std::cell::RefCell::new(server));
 
-    loop {
-        let incoming = listener.accept().await;
-        let db = std::rc::Rc::clone(&db);
-        let number_of_connections = std::rc::Rc::clone(&number_of_connections);
+    if let Some(path) = &c.network.unixsocket {
+        let listener = bind_unix(path, c.network.unixsocket_perm).unwrap();
+        tracing::info!(path, "listening");
+        monoio::spawn(accept_unix(
+            listener,
+            path.clone(),
+            std::rc::Rc::clone(&db),
+            std::rc::Rc::clone(&server),
+        ));
+    }
 
-        monoio::spawn(async move {
-            match incoming {
-                Ok((mut stream, addr)) => {
-                    {
-                        let mut n = number_of_connections.borrow_mut();
-                        *n += 1;
-                        tracing::info!(
-                            "accepted a connection from {} (total concurrent {})",
-                            addr,
-                            *n
-                        );
-                    }
+    if c.tls.enabled {
+        let acceptor = match server::tls::acceptor(&c.tls) {
+            Ok(a) => a,
+            Err(e) => {
+                tracing::error!(error = %e, "error on loading tls config");
+                return;
+            }
+        };
+        let listener = monoio::net::TcpListener::bind(&c.tls.addr).unwrap();
+        tracing::info!(addr = %listener.local_addr().unwrap(), "listening with tls");
+        monoio::spawn(accept_tls(
+            listener,
+            acceptor,
+            std::rc::Rc::clone(&db),
+            std::rc::Rc::clone(&server),
+        ));
+    }
 
-                    loop {
-                        let buf = vec![0; 4096];
+    if tcp.is_none() && c.network.unixsocket.is_none() && !c.tls.enabled {
+        tracing::error!("no listener enabled, enable tcp, tls or set a unix socket");
+        return;
+    }
 
-                        let (res, buf) = stream.read(buf).await;
-                        if res.is_err() {
-                            tracing::error!("error on stream read: {}", res.err().unwrap());
-                            break;
-                        }
+    match tcp {
+        Some(listener) => accept_tcp(listener, db, server).await,
+        None => std::future::pending().await,
+    }
+}
 
-                        let content = String::from_utf8_lossy(&buf[..]);
-                        if content.is_empty() {
-                            tracing::debug!("content is empty, break...");
-                            break;
-                        }
+#[allow(clippy::future_not_send)]
+async fn accept_tcp(
+    listener: monoio::net::TcpListener,
+    db: std::rc::Rc<std::cell::RefCell<engine::db::HashMapDb>>,
+    server: std::rc::Rc<std::cell::RefCell<server::Server>>,
+) {
+    loop {
+        match listener.accept().await {
+            Ok((stream, addr)) => {
+                let keepalive = server.borrow().network.tcp_keepalive;
+                if let Err(e) = set_keepalive(&stream, keepalive) {
+                    tracing::warn!(error = %e, "error on setting tcp keepalive");
+                }
 
-                        tracing::debug!(content = content.as_ref(), "received");
-                        let request = match parser::parse_request(content.as_bytes()) {
-                            Ok(r) => {
-                                tracing::debug!(?r, "parsed request");
-                                r
-                            }
-                            Err(e) => {
-                                tracing::error!("error on parsing request: {}", e);
-                                let (r, _) = stream.write_all(b"-ERR unknown command\r\n").await;
-                                match r {
-                                    Ok(_) => (),
-                                    Err(e) => tracing::error!("error on stream write: {}", e),
-                                }
-                                break;
-                            }
-                        };
-
-                        let close_stream_after_response = request.kind == parser::RequestKind::Http;
-                        let response = {
-                            let mut db = db.borrow_mut();
-                            let n = std::time::Instant::now();
-                            let r = executor::execute_command(request.cmd, &mut db, n);
-                            drop(db);
-                            r
-                        };
-
-                        let answer = match request.kind {
-                            parser::RequestKind::Http => protocol::curl::Curl::encode(response),
-                            parser::RequestKind::RedisCLI => protocol::resp::Resp::encode(response),
-                        };
-                        let (res, _) = stream.write_all(answer).await;
-                        match res {
-                            Ok(_) => (),
-                            Err(e) => tracing::error!("error on stream write: {}", e),
-                        }
+                spawn_connection(stream, addr.to_string(), &db, &server);
+            }
+            Err(e) => tracing::error!(error = %e, "accepted connection failed"),
+        }
+    }
+}
 
-                        if close_stream_after_response {
-                            tracing::info!("request close stream");
-                            break;
+#[allow(clippy::future_not_send)]
+async fn accept_tls(
+    listener: monoio::net::TcpListener,
+    acceptor: monoio_rustls::TlsAcceptor,
+    db: std::rc::Rc<std::cell::RefCell<engine::db::HashMapDb>>,
+    server: std::rc::Rc<std::cell::RefCell<server::Server>>,
+) {
+    // NOTE: peers which never complete the handshake would keep their task forever.
+    const HANDSHAKE_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(10);
+
+    loop {
+        match listener.accept().await {
+            Ok((stream, addr)) => {
+                let keepalive = server.borrow().network.tcp_keepalive;
+                if let Err(e) = set_keepalive(&stream, keepalive) {
+                    tracing::warn!(error = %e, "error on setting tcp keepalive");
+                }
+
+                let acceptor = acceptor.clone();
+                let db = std::rc::Rc::clone(&db);
+                let server = std::rc::Rc::clone(&server);
+                monoio::spawn(async move {
+                    match monoio::time::timeout(HANDSHAKE_TIMEOUT, acceptor.accept(stream)).await {
+                        Ok(Ok(stream)) => spawn_connection(stream, addr.to_string(), &db, &server),
+                        Ok(Err(e)) => {
+                            tracing::warn!(error = %e, peer = %addr, "tls handshake failed");
                         }
+                        Err(_) => tracing::warn!(peer = %addr, "tls handshake timed out"),
                     }
+                });
+            }
+            Err(e) => tracing::error!(error = %e, "accepted connection failed"),
+        }
+    }
+}
+
+#[allow(clippy::future_not_send)]
+async fn accept_unix(
+    listener: monoio::net::UnixListener,
+    path: String,
+    db: std::rc::Rc<std::cell::RefCell<engine::db::HashMapDb>>,
+    server: std::rc::Rc<std::cell::RefCell<server::Server>>,
+) {
+    loop {
+        match listener.accept().await {
+            // NOTE: peers of a unix socket are usually unnamed, like redis they are reported as `path:0`.
+            Ok((stream, _)) => spawn_connection(stream, format!("{path}:0"), &db, &server),
+            Err(e) => tracing::error!(error = %e, "accepted connection failed"),
+        }
+    }
+}
+
+/// Bind the unix socket replacing the file left by a previous run, if any.
+fn bind_unix(path: &str, perm: Option<u32>) -> std::io::Result<monoio::net::UnixListener> {
+    use std::os::unix::fs::PermissionsExt;
+
+    match std::fs::remove_file(path) {
+        Err(e) if e.kind() != std::io::ErrorKind::NotFound => return Err(e),
+        _ => (),
+    }
+
+    // NOTE: the default options enable SO_REUSEPORT which is not supported by unix sockets.
+    let listener = monoio::net::UnixListener::bind_with_config(
+        path,
+        &monoio::net::ListenerOpts::default()
+            .reuse_port(false)
+            .reuse_addr(false),
+    )?;
+    if let Some(perm) = perm {
+        std::fs::set_permissions(path, std::fs::Permissions::from_mode(perm))?;
+    }
+
+    Ok(listener)
+}
+
+fn spawn_connection<S: AsyncReadRent + AsyncWriteRent + 'static>(
+    stream: S,
+    addr: String,
+    db: &std::rc::Rc<std::cell::RefCell<engine::db::HashMapDb>>,
+    server: &std::rc::Rc<std::cell::RefCell<server::Server>>,
+) {
+    let db = std::rc::Rc::clone(db);
+    let server = std::rc::Rc::clone(server);
+
+    monoio::spawn(async move {
+        if server.borrow().is_protected_from(&addr) {
+            reject_protected(stream, &addr, &server).await;
+            return;
+        }
+
+        let id = server.borrow_mut().stats.connection_opened();
+        let span = tracing::info_span!("connection", peer = %addr, client_id = id);
+        handle_connection(stream, addr, id, db, server)
+            .instrument(span)
+            .await;
+    });
+}
+
+/// Explain to the client why it is refused by protected mode and close the connection.
+#[allow(clippy::future_not_send)]
+async fn reject_protected<S: AsyncWriteRent>(
+    mut stream: S,
+    addr: &str,
+    server: &std::rc::Rc<std::cell::RefCell<server::Server>>,
+) {
+    tracing::warn!(peer = %addr, "connection refused by protected mode");
+    server.borrow_mut().stats.rejected_connections += 1;
+    write_error(&mut stream, PROTECTED_MODE_ERROR).await;
+    let _ = stream.shutdown().await;
+}
+
+const PROTECTED_MODE_ERROR: &str = "DENIED umd is running in protected mode because protected mode is enabled and no \
+password is set for the default user. In this mode connections are only accepted from the loopback interface. To \
+accept connections from other hosts set a password with security.requirepass or users with security.aclfile, or \
+disable protected mode setting security.protected_mode = false";
+
+/// Enable the TCP keepalive probes after `time` of inactivity, like redis the probes are sent every third of it and
+/// the peer is considered dead after 3 of them are lost.
+fn set_keepalive(
+    stream: &monoio::net::TcpStream,
+    time: std::time::Duration,
+) -> std::io::Result<()> {
+    if time.is_zero() {
+        return Ok(());
+    }
+
+    stream.set_tcp_keepalive(
+        Some(time),
+        Some((time / 3).max(std::time::Duration::from_secs(1))),
+        Some(3),
+    )
+}
+
+// NOTE: monoio is a thread-per-core runtime, connections never move between threads so they can share state with Rc.
+#[allow(clippy::future_not_send)]
+async fn handle_connection<S: AsyncReadRent + AsyncWriteRent>(
+    mut stream: S,
+    addr: String,
+    id: u64,
+    db: std::rc::Rc<std::cell::RefCell<engine::db::HashMapDb>>,
+    server: std::rc::Rc<std::cell::RefCell<server::Server>>,
+) {
+    tracing::info!(
+        connected_clients = server.borrow().stats.connected_clients,
+        "accepted a connection"
+    );
+    let killed = server
+        .borrow_mut()
+        .clients
+        .register(id, addr.clone(), std::time::Instant::now());
+    let mut pending = Vec::new();
+
+    loop {
+        let buf = match read_request(&mut stream, &mut pending, &killed, &server, id).await {
+            Some(Ok(buf)) => buf,
+            Some(Err(e)) => {
+                reject_request(&mut stream, &server, &e).await;
+                break;
+            }
+            None => break,
+        };
+
+        if buf.is_empty() {
+            tracing::debug!("content is empty, break...");
+            break;
+        }
 
-                    tracing::info!("close stream connection");
+        // NOTE: debug logs of every command are too many under load, only the sampled ones are emitted.
+        let sampled = server.borrow_mut().log_sampler.sample();
+        let received_at = std::time::Instant::now();
+        let request = match parser::parse_request(&buf) {
+            Ok(r) => {
+                // NOTE: requests carrying credentials are logged only through their redacted arguments.
+                if sampled && r.has_credentials() {
+                    tracing::debug!(args = ?r.args, "received");
+                } else if sampled {
+                    tracing::debug!(content = %String::from_utf8_lossy(&buf), "received");
+                    tracing::debug!(?r, "parsed request");
                 }
+                r
+            }
+            Err(e) => {
+                tracing::error!(error = %e, "error on parsing request");
+                write_error(&mut stream, "ERR unknown command").await;
+                break;
+            }
+        };
+
+        let close_stream_after_response = request.kind == parser::RequestKind::Http;
+        let monitor = request.cmd == protocol::commands::Command::Monitor;
+        let name = request.cmd.name();
+        let span = if sampled {
+            tracing::debug_span!(
+                "command",
+                command = name,
+                key = request.cmd.keys().first(),
+                duration_us = tracing::field::Empty,
+            )
+        } else {
+            tracing::Span::none()
+        };
+
+        wait_if_paused(&server, &request.cmd).await;
+
+        let kind = request.kind;
+        let response = execute_or_wait(request, &db, &server, &addr, id, &killed, &span).await;
+        // NOTE: MONITOR refused by the authentication, the ACL or the rate limit must not get the feed.
+        let monitor = monitor
+            && response
+                == protocol::commands::CommandResponse::String {
+                    value: "OK".to_owned(),
+                };
+
+        let answer = encode_response(kind, response, &server.borrow(), id);
+        if sampled {
+            tracing::debug!(response = %String::from_utf8_lossy(&answer), "response");
+        }
+        if let Some(c) = server.borrow_mut().clients.get_mut(id) {
+            c.last_command = name;
+            c.protocol = kind.name();
+            c.output_buffer = answer.len();
+        }
+        let (res, _) = stream.write_all(answer).await;
+        match res {
+            Ok(n) => server.borrow_mut().stats.total_net_output_bytes += n as u64,
+            Err(e) => tracing::error!(error = %e, "error on stream write"),
+        }
+
+        let elapsed = received_at.elapsed();
+        span.record(
+            "duration_us",
+            u64::try_from(elapsed.as_micros()).unwrap_or(u64::MAX),
+        );
+        drop(span);
+        server.borrow_mut().latency.record_command(name, elapsed);
+
+        if close_stream_after_response {
+            tracing::info!("request close stream");
+            break;
+        }
+
+        if monitor {
+            stream_monitor(&mut stream, &server, id, &killed).await;
+            break;
+        }
+    }
+
+    tracing::info!("close stream connection");
+
+    let mut server = server.borrow_mut();
+    server.clients.unregister(id);
+    server.stats.connection_closed();
+}
+
+/// Encode the response for the protocol of the request, RESP clients get maps only after switching to RESP3.
+fn encode_response(
+    kind: parser::RequestKind,
+    response: protocol::commands::CommandResponse,
+    server: &server::Server,
+    id: u64,
+) -> Vec<u8> {
+    match kind {
+        parser::RequestKind::Http => protocol::curl::Curl::encode(response),
+        parser::RequestKind::RedisCLI if server.clients.get(id).is_some_and(|c| c.resp == 3) => {
+            protocol::resp::Resp::encode(response)
+        }
+        parser::RequestKind::RedisCLI => protocol::resp::Resp::encode(response.into_resp2()),
+    }
+}
+
+/// Read the next request of the client, None is returned when the connection must be closed because of an error,
+/// because the client has been killed or because it has been idle for too long. The request is read in chunks until
+/// it is complete, the error is returned if it exceeds one of the limits. The bytes read after the request are kept in
+/// pending, pipelined requests are taken from there before reading the stream again.
+// NOTE: redundant_pub_crate is triggered by the code generated by select.
+#[allow(clippy::future_not_send, clippy::redundant_pub_crate)]
+async fn read_request<S: AsyncReadRent>(
+    stream: &mut S,
+    pending: &mut Vec<u8>,
+    killed: &server::channel::Receiver<()>,
+    server: &std::cell::RefCell<server::Server>,
+    id: u64,
+) -> Option<Result<Vec<u8>, protocol::ProtocolError>> {
+    let idle_timeout = server.borrow().network.idle_timeout;
+    let limits = server.borrow().limits.clone();
+
+    loop {
+        match parser::request_len(pending, &limits) {
+            Ok(Some(len)) => {
+                let rest = pending.split_off(len);
+                let request = std::mem::replace(pending, rest);
+                if let Some(c) = server.borrow_mut().clients.get_mut(id) {
+                    c.query_buffer = pending.len();
+                    c.query_buffer_capacity = pending.capacity();
+                }
+                return Some(Ok(request));
+            }
+            Ok(None) => {}
+            Err(e) => return Some(Err(e)),
+        }
+        if pending.len() >= limits.max_query_buffer {
+            return Some(Err(protocol::ProtocolError::LimitExceeded(
+                "max_query_buffer",
+            )));
+        }
+
+        let (res, buf) = monoio::select! {
+            r = stream.read(vec![0; 4096]) => r,
+            _ = killed.recv() => {
+                tracing::info!("client killed");
+                return None;
+            }
+            () = monoio::time::sleep(idle_timeout), if !idle_timeout.is_zero() => {
+                tracing::info!(?idle_timeout, "client idle timeout");
+                return None;
+            }
+        };
+        let n = match res {
+            Ok(n) => n,
+            Err(e) => {
+                tracing::error!(error = %e, "error on stream read");
+                return None;
+            }
+        };
+        // NOTE: on EOF the partial request is returned, the connection is closed once it fails to parse.
+        if n == 0 {
+            return Some(Ok(std::mem::take(pending)));
+        }
+        pending.extend_from_slice(&buf[..n]);
+
+        let mut server = server.borrow_mut();
+        server.stats.total_net_input_bytes += n as u64;
+        if let Some(c) = server.clients.get_mut(id) {
+            c.last_interaction = std::time::Instant::now();
+            c.query_buffer = pending.len();
+            c.query_buffer_capacity = pending.capacity();
+        }
+    }
+}
+
+/// Reply with the error to a request which can't be read, the connection is closed afterwards like redis does.
+#[allow(clippy::future_not_send)]
+async fn reject_request<S: AsyncWriteRent>(
+    stream: &mut S,
+    server: &std::cell::RefCell<server::Server>,
+    e: &protocol::ProtocolError,
+) {
+    tracing::warn!(error = %e, "request rejected");
+    if let protocol::ProtocolError::LimitExceeded(limit) = e {
+        server.borrow_mut().stats.limit_exceeded(limit);
+    }
+
+    write_error(stream, &format!("ERR {e}")).await;
+}
+
+#[allow(clippy::future_not_send)]
+async fn write_error<S: AsyncWriteRent>(stream: &mut S, message: &str) {
+    let (r, _) = stream
+        .write_all(format!("-{message}\r\n").into_bytes())
+        .await;
+    if let Err(e) = r {
+        tracing::error!(error = %e, "error on stream write");
+    }
+}
+
+/// Suspend the command while the clients are paused, CLIENT commands are never paused so that the pause can be
+/// removed.
+#[allow(clippy::future_not_send)]
+async fn wait_if_paused(
+    server: &std::cell::RefCell<server::Server>,
+    cmd: &protocol::commands::Command,
+) {
+    // NOTE: the pause is checked again periodically since it can be removed or extended meanwhile.
+    const CHECK_INTERVAL: std::time::Duration = std::time::Duration::from_millis(10);
+
+    if cmd.name() == "client" {
+        return;
+    }
+
+    loop {
+        let now = std::time::Instant::now();
+        let Some(until) = server.borrow().clients.paused_until(cmd.is_write(), now) else {
+            return;
+        };
+        monoio::time::sleep((until - now).min(CHECK_INTERVAL)).await;
+    }
+}
+
+/// Execute the request, when the lists of BLPOP or BRPOP are empty the connection waits for an element instead.
+#[allow(clippy::future_not_send)]
+async fn execute_or_wait(
+    request: parser::Request,
+    db: &std::cell::RefCell<engine::db::HashMapDb>,
+    server: &std::cell::RefCell<server::Server>,
+    addr: &str,
+    id: u64,
+    killed: &server::channel::Receiver<()>,
+    span: &tracing::Span,
+) -> protocol::commands::CommandResponse {
+    let pop = server::blocking::Pop::from_command(&request.cmd);
+    let read = server::blocking::Read::from_command(&request.cmd).map(|mut read| {
+        executor::resolve_last_ids(
+            &mut read.cmd,
+            &mut db.borrow_mut(),
+            std::time::Instant::now(),
+        );
+        read
+    });
+    let response = {
+        let _enter = span.enter();
+        execute_request(request, db, server, addr, id)
+    };
+
+    match (pop, read) {
+        (Some(pop), _) if response == protocol::commands::CommandResponse::Null => {
+            wait_blocked(&pop, server, killed).await
+        }
+        (_, Some(read)) if response == protocol::commands::CommandResponse::Null => {
+            wait_read(read, db, server, killed).await
+        }
+        _ => response,
+    }
+}
+
+/// Wait for an entry added to one of the streams of XREAD or XREADGROUP and run the command again, until it returns
+/// something. Null is returned on timeout or if the client is killed meanwhile.
+// NOTE: redundant_pub_crate is triggered by the code generated by select.
+#[allow(clippy::future_not_send, clippy::redundant_pub_crate)]
+async fn wait_read(
+    read: server::blocking::Read,
+    db: &std::cell::RefCell<engine::db::HashMapDb>,
+    server: &std::cell::RefCell<server::Server>,
+    killed: &server::channel::Receiver<()>,
+) -> protocol::commands::CommandResponse {
+    let deadline = std::time::Instant::now() + read.timeout;
+    loop {
+        let rx = server.borrow_mut().blocking.watch(&read);
+        let remaining = deadline.saturating_duration_since(std::time::Instant::now());
+        let woken = monoio::select! {
+            woken = rx.recv() => woken.is_some(),
+            _ = killed.recv() => false,
+            () = monoio::time::sleep(remaining), if !read.timeout.is_zero() => false,
+        };
+        drop(rx);
+        server.borrow_mut().blocking.unwatch(&read);
+        if !woken {
+            return protocol::commands::CommandResponse::Null;
+        }
+
+        // NOTE: another consumer of the group may have read the entries first, the wait goes on in that case.
+        let response = executor::retry_read(
+            read.cmd.clone(),
+            &mut db.borrow_mut(),
+            &mut server.borrow_mut(),
+            std::time::Instant::now(),
+        );
+        if response != protocol::commands::CommandResponse::Null {
+            return response;
+        }
+    }
+}
+
+/// Wait for an element pushed to one of the lists of BLPOP or BRPOP, Null is returned on timeout or if the client
+/// is killed meanwhile.
+// NOTE: redundant_pub_crate is triggered by the code generated by select.
+#[allow(clippy::future_not_send, clippy::redundant_pub_crate)]
+async fn wait_blocked(
+    pop: &server::blocking::Pop,
+    server: &std::cell::RefCell<server::Server>,
+    killed: &server::channel::Receiver<()>,
+) -> protocol::commands::CommandResponse {
+    let rx = server.borrow_mut().blocking.block(pop);
+    let popped = monoio::select! {
+        popped = rx.recv() => popped,
+        _ = killed.recv() => None,
+        () = monoio::time::sleep(pop.timeout), if !pop.timeout.is_zero() => None,
+    };
+
+    // NOTE: an element handed over while the wait was ending must not be lost with the receiver.
+    let popped = popped.or_else(|| rx.try_recv());
+    drop(rx);
+    server.borrow_mut().blocking.unblock(pop);
+
+    popped.map_or(
+        protocol::commands::CommandResponse::Null,
+        |(key, element)| protocol::commands::CommandResponse::Array {
+            value: vec![
+                protocol::commands::CommandResponse::BulkString { value: key },
+                protocol::commands::CommandResponse::BulkString { value: element },
+            ],
+        },
+    )
+}
+
+/// Push every command processed by the server to the client until it closes the connection, like redis any input
+/// is ignored.
+// NOTE: redundant_pub_crate is triggered by the code generated by select.
+#[allow(clippy::future_not_send, clippy::redundant_pub_crate)]
+async fn stream_monitor<S: AsyncReadRent + AsyncWriteRent>(
+    stream: &mut S,
+    server: &std::cell::RefCell<server::Server>,
+    id: u64,
+    killed: &server::channel::Receiver<()>,
+) {
+    tracing::info!("monitor started");
+    let rx = {
+        let mut server = server.borrow_mut();
+        if let Some(c) = server.clients.get_mut(id) {
+            c.monitor = true;
+        }
+        server.monitors.subscribe()
+    };
+
+    loop {
+        monoio::select! {
+            line = rx.recv() => {
+                let Some(line) = line else { break };
+                let (res, _) = stream.write_all(line).await;
+                match res {
+                    Ok(n) => server.borrow_mut().stats.total_net_output_bytes += n as u64,
+                    Err(e) => {
+                        tracing::error!(error = %e, "error on stream write");
+                        break;
+                    }
+                }
+            }
+            (res, _) = stream.read(vec![0; 1024]) => match res {
+                Ok(0) => break,
+                Ok(n) => server.borrow_mut().stats.total_net_input_bytes += n as u64,
                 Err(e) => {
-                    tracing::error!("accepted connection failed: {}", e);
+                    tracing::error!(error = %e, "error on stream read");
+                    break;
                 }
+            },
+            _ = killed.recv() => {
+                tracing::info!("client killed");
+                break;
             }
+        }
+    }
+}
 
-            {
-                let mut n = number_of_connections.borrow_mut();
-                *n -= 1;
+/// Execute the command of the request and record how long it took.
+fn execute_request(
+    request: parser::Request,
+    db: &std::cell::RefCell<engine::db::HashMapDb>,
+    server: &std::cell::RefCell<server::Server>,
+    addr: &str,
+    id: u64,
+) -> protocol::commands::CommandResponse {
+    if let Some(auth) = request.auth {
+        let now = std::time::Instant::now();
+        let r = executor::execute_command(
+            auth,
+            &mut db.borrow_mut(),
+            &mut server.borrow_mut(),
+            id,
+            now,
+        );
+        if matches!(r, protocol::commands::CommandResponse::Error { .. }) {
+            return r;
+        }
+    }
+
+    let name = request.cmd.name();
+    let n = std::time::Instant::now();
+    let (response, events) = {
+        let mut db = db.borrow_mut();
+        let mut server = server.borrow_mut();
+        let r = if let Some(refused) = executor::admit(&request.cmd, &mut server, id, n) {
+            refused
+        } else {
+            // NOTE: like redis, the monitors get the commands which passed the checks, before they run.
+            if request.cmd != protocol::commands::Command::Monitor {
+                server.monitors.publish(&request.args, addr);
             }
+            executor::run(request.cmd, &mut db, &mut server, id, n)
+        };
+        let events = db.take_latency_events();
+        drop(db);
+        (r, events)
+    };
+
+    let elapsed = n.elapsed();
+    let mut server = server.borrow_mut();
+    for (event, latency) in events {
+        server.latency.record_event(event, latency);
+    }
+    server
+        .stats
+        .record_command(name, request.kind.name(), elapsed);
+    let client_name = server
+        .clients
+        .get(id)
+        .map(|c| c.name.clone())
+        .unwrap_or_default();
+    server
+        .slowlog
+        .record(&request.args, elapsed, addr, &client_name);
+
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use monoio::io::AsyncReadRent;
+
+    /// Send the raw request to a connection handled by a new server, returns once the expected answer is read.
+    #[allow(clippy::future_not_send)]
+    async fn exchange(config: &config::Config, request: &'static [u8], expected: &[u8]) {
+        let listener = monoio::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        let db = std::rc::Rc::new(std::cell::RefCell::new(engine::db::HashMapDb::new(
+            config::Engine::default(),
+        )));
+        let server = std::rc::Rc::new(std::cell::RefCell::new(server::Server::new(0, config)));
+
+        monoio::spawn(async move {
+            let (stream, peer) = listener.accept().await.unwrap();
+            handle_connection(stream, peer.to_string(), 1, db, server).await;
         });
+
+        let mut stream = monoio::net::TcpStream::connect(addr).await.unwrap();
+        let (res, _) = stream.write_all(request).await;
+        res.unwrap();
+
+        let mut answer = vec![];
+        while answer.len() < expected.len() {
+            let read = stream.read(vec![0; 64]);
+            let (res, buf) = monoio::time::timeout(std::time::Duration::from_secs(5), read)
+                .await
+                .unwrap_or_else(|_| panic!("no answer after {answer:?}"));
+            let n = res.unwrap();
+            assert_ne!(n, 0, "connection closed after {answer:?}");
+            answer.extend_from_slice(&buf[..n]);
+        }
+        assert_eq!(answer, expected);
+    }
+
+    #[monoio::test(timer_enabled = true)]
+    async fn pipelined_requests() {
+        exchange(
+            &config::Config::default(),
+            b"*3\r\n$3\r\nSET\r\n$1\r\nk\r\n$2\r\nhi\r\n*2\r\n$3\r\nGET\r\n$1\r\nk\r\n",
+            b"+OK\r\n$2\r\nhi\r\n",
+        )
+        .await;
+    }
+
+    #[monoio::test(timer_enabled = true)]
+    async fn binary_values() {
+        exchange(
+            &config::Config::default(),
+            b"*3\r\n$3\r\nSET\r\n$1\r\nb\r\n$3\r\n\xff\r\n\r\n\
+              *4\r\n$6\r\nSETBIT\r\n$1\r\nb\r\n$1\r\n7\r\n$1\r\n0\r\n\
+              *2\r\n$3\r\nGET\r\n$1\r\nb\r\n",
+            b"+OK\r\n:1\r\n$3\r\n\xfe\r\n\r\n",
+        )
+        .await;
+    }
+
+    #[monoio::test(timer_enabled = true)]
+    async fn monitor_without_auth() {
+        let mut config = config::Config::default();
+        config.security.requirepass = Some("secret".to_string());
+
+        // NOTE: PING is answered only if the client did not switch to the feed of the monitors.
+        exchange(
+            &config,
+            b"MONITOR\r\nPING\r\n",
+            b"-NOAUTH Authentication required.\r\n+PONG\r\n",
+        )
+        .await;
+    }
+
+    #[test]
+    fn monitor_only_admitted_commands() {
+        let mut config = config::Config::default();
+        config.security.requirepass = Some("secret".to_string());
+        let db = std::cell::RefCell::new(engine::db::HashMapDb::new(config::Engine::default()));
+        let server = std::cell::RefCell::new(server::Server::new(0, &config));
+        let rx = server.borrow_mut().monitors.subscribe();
+
+        let request = |raw: &str| parser::parse_request(raw.as_bytes()).unwrap();
+        execute_request(request("GET key\r\n"), &db, &server, "127.0.0.1:1", 1);
+        assert!(rx.try_recv().is_none());
+
+        execute_request(request("PING\r\n"), &db, &server, "127.0.0.1:1", 1);
+        assert!(rx.try_recv().is_some());
     }
 }
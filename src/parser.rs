@@ -1,6 +1,6 @@
 use crate::protocol::Protocol;
 
-#[derive(Debug, Default, PartialEq, Eq)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
 pub enum RequestKind {
     /// Http kind is used to handle the HTTP requests using CURL.
     #[default]
@@ -10,10 +10,70 @@ pub enum RequestKind {
     RedisCLI,
 }
 
+impl RequestKind {
+    /// Name of the protocol, used as label for statistics.
+    pub const fn name(self) -> &'static str {
+        match self {
+            Self::Http => "http",
+            Self::RedisCLI => "resp",
+        }
+    }
+}
+
 #[derive(Debug)]
 pub struct Request {
     pub kind: RequestKind,
     pub cmd: crate::protocol::commands::Command,
+
+    /// Arguments as sent by the client, used to report the request in logs like the slowlog. Credentials are
+    /// redacted.
+    pub args: Vec<String>,
+
+    /// AUTH implied by the `Authorization` header of HTTP requests, it is executed before the command.
+    pub auth: Option<crate::protocol::commands::Command>,
+}
+
+impl Request {
+    /// Returns if the request carries credentials, its raw content must not be logged.
+    pub const fn has_credentials(&self) -> bool {
+        self.cmd.has_credentials() || self.auth.is_some()
+    }
+}
+
+/// Like redis, the arguments of commands carrying credentials are reported as `(redacted)`.
+fn redact(cmd: &crate::protocol::commands::Command, mut args: Vec<String>) -> Vec<String> {
+    if cmd.has_credentials() {
+        for a in args.iter_mut().skip(1) {
+            "(redacted)".clone_into(a);
+        }
+    }
+
+    args
+}
+
+/// Length of the request at the start of the buffer, None if more bytes must be read. The sizes declared by the
+/// request are checked against the limits before it is read.
+pub fn request_len(
+    raw: &[u8],
+    limits: &crate::config::Limits,
+) -> Result<Option<usize>, crate::protocol::ProtocolError> {
+    if raw.first() == Some(&b'*') {
+        return crate::protocol::resp::Resp::frame_len(
+            raw,
+            limits.proto_max_bulk_len,
+            limits.max_array_len,
+        );
+    }
+
+    let Some(end) = raw.windows(2).position(|w| w == b"\r\n") else {
+        return Ok(None);
+    };
+    // NOTE: anything which is not HTTP is an inline command, it is made of a single line.
+    if raw[..end].ends_with(b" HTTP/1.1") || raw[..end].ends_with(b" HTTP/1.0") {
+        crate::protocol::curl::Curl::frame_len(raw, limits.proto_max_bulk_len)
+    } else {
+        Ok(Some(end + 2))
+    }
 }
 
 pub fn parse_request(raw_request: &[u8]) -> Result<Request, String> {
@@ -21,22 +81,36 @@ pub fn parse_request(raw_request: &[u8]) -> Result<Request, String> {
     // http can be under feature flag in order to skip when we are stable.
     // Maybe filter if it start with GET/POST/PUT/DELETE or not.
     let http_cmd = crate::protocol::curl::Curl::decode(raw_request);
-    let redis_cli_cmd = crate::protocol::resp::Resp::decode(raw_request);
+    let redis_cli_args = crate::protocol::resp::Resp::decode_args(raw_request);
 
-    match (http_cmd, redis_cli_cmd) {
-        (Err(crate::protocol::ProtocolError::CurlProtocolDecodingError), r) => Ok(Request {
-            kind: RequestKind::RedisCLI,
-            cmd: r.map_err(|e| e.to_string())?,
-        }),
+    match (http_cmd, redis_cli_args) {
+        (Err(crate::protocol::ProtocolError::CurlProtocolDecodingError), r) => {
+            let args = r.map_err(|e| e.to_string())?;
+            let cmd =
+                crate::protocol::resp::Resp::command_from_args(&args).map_err(|e| e.to_string())?;
+            let args = args
+                .iter()
+                .map(|a| String::from_utf8_lossy(a).into_owned())
+                .collect();
+            Ok(Request {
+                kind: RequestKind::RedisCLI,
+                args: redact(&cmd, args),
+                cmd,
+                auth: None,
+            })
+        }
         (r, Err(crate::protocol::ProtocolError::RespProtocolDecodingError)) => Ok(Request {
             kind: RequestKind::Http,
             cmd: r.map_err(|e| e.to_string())?,
+            args: crate::protocol::curl::Curl::decode_args(raw_request),
+            auth: crate::protocol::curl::Curl::decode_auth(raw_request),
         }),
-        _ => panic!("all situations should be handled"),
+        _ => Err(crate::protocol::ProtocolError::AmbiguousRequest.to_string()),
     }
 }
 
 #[cfg(test)]
+#[allow(clippy::needless_raw_string_hashes)]
 mod tests {
     use super::*;
 
@@ -46,6 +120,7 @@ mod tests {
         let raw = "*2\r\n$7\r\nCOMMAND\r\n$4\r\nDOCS\r\n";
         let output = parse_request(raw.as_bytes()).unwrap();
         assert_eq!(output.kind, RequestKind::RedisCLI);
+        assert_eq!(output.args, vec!["COMMAND", "DOCS"]);
     }
 
     #[test]
@@ -58,6 +133,32 @@ Accept: */*
 
         let output = parse_request(raw.as_bytes()).unwrap();
         assert_eq!(output.kind, RequestKind::Http);
+        assert_eq!(output.args, vec!["GET", "/key"]);
+    }
+
+    #[test]
+    fn parse_http_with_ping() {
+        let raw = "GET /PING HTTP/1.1\r\nHost: 127.0.0.1\r\nAuthorization: Bearer PING\r\n\r\n";
+        let output = parse_request(raw.as_bytes()).unwrap();
+        assert_eq!(output.kind, RequestKind::Http);
+        assert_eq!(output.args, vec!["GET", "/PING"]);
+
+        let output = parse_request(b"PING\r\n").unwrap();
+        assert_eq!(output.kind, RequestKind::RedisCLI);
+        assert_eq!(output.cmd, crate::protocol::commands::Command::Ping);
+    }
+
+    #[test]
+    fn redacted_args() {
+        let raw = "*3\r\n$4\r\nAUTH\r\n$5\r\nadmin\r\n$6\r\nsecret\r\n";
+        let output = parse_request(raw.as_bytes()).unwrap();
+        assert_eq!(output.args, ["AUTH", "(redacted)", "(redacted)"]);
+        assert!(output.has_credentials());
+
+        let raw = "*2\r\n$3\r\nGET\r\n$3\r\nkey\r\n";
+        let output = parse_request(raw.as_bytes()).unwrap();
+        assert_eq!(output.args, ["GET", "key"]);
+        assert!(!output.has_credentials());
     }
 
     #[test]
@@ -67,9 +168,25 @@ Accept: */*
         assert!(output.is_err());
         assert_eq!(output.err().unwrap(), "resp protocol decoding error");
 
-        let raw = "*3\r\n$3\r\nNOTACOMMAND\r\n$1\r\nx\r\n$2\r\n11\r\n";
+        let raw = "*3\r\n$11\r\nNOTACOMMAND\r\n$1\r\nx\r\n$2\r\n11\r\n";
         let output = parse_request(raw.as_bytes());
         assert!(output.is_err());
         assert_eq!(output.err().unwrap(), "command not recognized notacommand");
     }
+
+    #[test]
+    fn request_len() {
+        let limits = crate::config::Limits::default();
+        assert_eq!(
+            super::request_len(b"*1\r\n$4\r\nPING\r\n", &limits),
+            Ok(Some(14))
+        );
+        assert_eq!(super::request_len(b"*1\r\n$4\r\nPI", &limits), Ok(None));
+        assert_eq!(super::request_len(b"PING\r\n", &limits), Ok(Some(6)));
+        assert_eq!(super::request_len(b"PING", &limits), Ok(None));
+        assert_eq!(
+            super::request_len(b"GET /key HTTP/1.1\r\nHost: localhost\r\n", &limits),
+            Ok(None)
+        );
+    }
 }
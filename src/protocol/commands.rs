@@ -1,6 +1,108 @@
 use super::ProtocolError;
+use crate::engine::bitmap::{Field, FieldOp, Operation, Overflow, Unit, MAX_OFFSET};
+use crate::engine::geo;
+use crate::engine::json;
+use crate::engine::stream::{Fields, NewId, StreamId, Trim};
+use std::cell::RefCell;
+use std::collections::{HashMap, HashSet};
 
-#[derive(Debug, PartialEq, Eq)]
+/// Commands renamed or disabled by the config, like `rename-command` in redis.
+#[derive(Default)]
+struct Renamed {
+    /// New name of the commands to the original one.
+    aliases: HashMap<String, String>,
+
+    /// Original names which can't be used anymore.
+    hidden: HashSet<String>,
+}
+
+impl Renamed {
+    /// Original name of the command, None if it is disabled.
+    fn resolve(&self, kind: String) -> Option<String> {
+        if let Some(original) = self.aliases.get(&kind) {
+            return Some(original.clone());
+        }
+
+        (!self.hidden.contains(&kind)).then_some(kind)
+    }
+}
+
+thread_local! {
+    // NOTE: every request is decoded on the thread running the connections, which is the one installing the table.
+    static RENAMED: RefCell<Renamed> = RefCell::new(Renamed::default());
+}
+
+/// Rename the commands, the original names are not recognized anymore and an empty new name disables the command.
+pub fn rename(commands: &HashMap<String, String>) {
+    let mut renamed = Renamed::default();
+    for (name, new_name) in commands {
+        renamed.hidden.insert(name.to_lowercase());
+        if !new_name.is_empty() {
+            renamed
+                .aliases
+                .insert(new_name.to_lowercase(), name.to_lowercase());
+        }
+    }
+
+    RENAMED.set(renamed);
+}
+
+/// Conditions of ZADD, set by its NX, XX, GT, LT, CH and INCR flags.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ZAddFlags {
+    /// Some(false) adds only new members (NX), Some(true) only updates the existing ones (XX).
+    pub exists: Option<bool>,
+
+    /// Updates only if the new score is greater (GT) or less (LT) than the current one.
+    pub compare: Option<std::cmp::Ordering>,
+
+    /// Returns the number of members added or updated, instead of only the added ones.
+    pub ch: bool,
+
+    /// Increments the score of the single member like `ZIncrBy`.
+    pub incr: bool,
+}
+
+/// Range of `ZRange`, the bounds are always from the lowest to the highest even with REV.
+#[derive(Clone, Debug, PartialEq)]
+pub enum ZRangeBy {
+    Rank(i64, i64),
+    Score(std::ops::Bound<f64>, std::ops::Bound<f64>),
+    Lex(std::ops::Bound<String>, std::ops::Bound<String>),
+}
+
+/// Center of `GeoSearch`: the position of a member, or a longitude and a latitude.
+#[derive(Clone, Debug, PartialEq)]
+pub enum GeoFrom {
+    Member(String),
+    Position(f64, f64),
+}
+
+/// Options of `GeoSearch`: the order by distance, the number of results and what comes with every member.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[allow(clippy::struct_excessive_bools)]
+pub struct GeoSearchOptions {
+    /// From the farthest member instead of the nearest.
+    pub desc: bool,
+
+    /// Returns at most count members. With ANY the first members found are returned, instead of the nearest.
+    pub count: Option<(usize, bool)>,
+
+    pub with_coord: bool,
+    pub with_dist: bool,
+    pub with_hash: bool,
+}
+
+/// How ZUNIONSTORE and ZINTERSTORE combine the scores of a member present in more than one set.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Aggregate {
+    #[default]
+    Sum,
+    Min,
+    Max,
+}
+
+#[derive(Clone, Debug, PartialEq)]
 pub enum Command {
     /// Get the value of key.
     /// If the key does not exist the special value nil is returned.
@@ -11,7 +113,7 @@ pub enum Command {
     /// If key already holds a value, it is overwritten, regardless of its type.
     Set {
         key: String,
-        value: String,
+        value: Vec<u8>,
         ttl: Option<std::time::Duration>,
     },
 
@@ -21,6 +123,417 @@ pub enum Command {
     /// Exists returns if key exists.
     Exists { key: String },
 
+    /// Returns the type of the value stored at key: string, list, hash, set, zset, stream or ReJSON-RL, none if the key
+    /// does not exist.
+    Type { key: String },
+
+    /// Returns the value of key for `GET /key` on HTTP: strings as they are, hashes and JSON documents as JSON. The
+    /// path of `GET /key?path=$.user.name` selects a fragment of a JSON document.
+    View {
+        key: String,
+        path: Option<json::Path>,
+    },
+
+    /// Sets the fields of the hash stored at key, returns the number of fields added.
+    HSet {
+        key: String,
+        fields: Vec<(String, String)>,
+    },
+
+    /// Returns the value of the field of the hash stored at key, nil if the field or the key does not exist.
+    HGet { key: String, field: String },
+
+    /// Returns the values of the fields of the hash stored at key, nil for the fields which do not exist.
+    HMGet { key: String, fields: Vec<String> },
+
+    /// Removes the fields from the hash stored at key, returns the number of fields removed.
+    HDel { key: String, fields: Vec<String> },
+
+    /// Returns all the fields and values of the hash stored at key.
+    HGetAll { key: String },
+
+    /// Increments the integer stored at the field of the hash by increment, a missing field is set to 0 first.
+    HIncrBy {
+        key: String,
+        field: String,
+        increment: i64,
+    },
+
+    /// Returns if the field exists in the hash stored at key.
+    HExists { key: String, field: String },
+
+    /// Returns the number of fields of the hash stored at key.
+    HLen { key: String },
+
+    /// Iterates the fields of the hash stored at key, the scan starts and ends with the cursor 0.
+    HScan {
+        key: String,
+        cursor: u64,
+        pattern: Option<String>,
+        count: usize,
+    },
+
+    /// Inserts the values at the head of the list stored at key, returns the length of the list.
+    LPush { key: String, values: Vec<String> },
+
+    /// Inserts the values at the tail of the list stored at key, returns the length of the list.
+    RPush { key: String, values: Vec<String> },
+
+    /// Removes and returns the first element of the list, or up to count elements if given.
+    LPop { key: String, count: Option<usize> },
+
+    /// Removes and returns the last element of the list, or up to count elements if given.
+    RPop { key: String, count: Option<usize> },
+
+    /// Returns the elements between start and stop included, negative indexes count from the tail.
+    LRange { key: String, start: i64, stop: i64 },
+
+    /// Returns the element at index, negative indexes count from the tail.
+    LIndex { key: String, index: i64 },
+
+    /// Returns the length of the list stored at key.
+    LLen { key: String },
+
+    /// Pops the first element of the first non empty list, waiting for a push up to timeout when all are empty.
+    BLPop {
+        keys: Vec<String>,
+        timeout: std::time::Duration,
+    },
+
+    /// Like `BLPop`, but from the tail of the lists.
+    BRPop {
+        keys: Vec<String>,
+        timeout: std::time::Duration,
+    },
+
+    /// Adds the members to the set stored at key, returns the number of members added.
+    SAdd { key: String, members: Vec<String> },
+
+    /// Removes the members from the set stored at key, returns the number of members removed.
+    SRem { key: String, members: Vec<String> },
+
+    /// Returns all the members of the set stored at key.
+    SMembers { key: String },
+
+    /// Returns if member belongs to the set stored at key.
+    SIsMember { key: String, member: String },
+
+    /// Returns the number of members of the set stored at key.
+    SCard { key: String },
+
+    /// Returns the members present in all the sets, a missing key is an empty set.
+    SInter { keys: Vec<String> },
+
+    /// Returns the members present in any of the sets.
+    SUnion { keys: Vec<String> },
+
+    /// Returns the members of the first set which are not in any of the others.
+    SDiff { keys: Vec<String> },
+
+    /// Like `SInter`, but the result is stored at destination and its size returned.
+    SInterStore {
+        destination: String,
+        keys: Vec<String>,
+    },
+
+    /// Like `SUnion`, but the result is stored at destination and its size returned.
+    SUnionStore {
+        destination: String,
+        keys: Vec<String>,
+    },
+
+    /// Like `SDiff`, but the result is stored at destination and its size returned.
+    SDiffStore {
+        destination: String,
+        keys: Vec<String>,
+    },
+
+    /// Removes and returns a random member of the set, or up to count members if given.
+    SPop { key: String, count: Option<usize> },
+
+    /// Returns a random member of the set, or up to count distinct members if given. A negative count returns
+    /// exactly -count members which can repeat.
+    SRandMember { key: String, count: Option<i64> },
+
+    /// Adds the members with their scores to the sorted set stored at key or updates their scores, returns the
+    /// number of members added. With INCR it returns the new score instead, nil if a condition prevented it.
+    ZAdd {
+        key: String,
+        members: Vec<(f64, String)>,
+        flags: ZAddFlags,
+    },
+
+    /// Increments the score of the member by increment, a missing member is added with increment as score.
+    ZIncrBy {
+        key: String,
+        increment: f64,
+        member: String,
+    },
+
+    /// Removes the members from the sorted set stored at key, returns the number of members removed.
+    ZRem { key: String, members: Vec<String> },
+
+    /// Returns the members within the range ordered by score, or by member with the same score. REV starts from the
+    /// highest score and LIMIT skips offset members and returns up to count of them.
+    ZRange {
+        key: String,
+        by: ZRangeBy,
+        rev: bool,
+        limit: Option<(usize, usize)>,
+        with_scores: bool,
+    },
+
+    /// Returns the position of the member from the lowest score, nil if it does not exist.
+    ZRank { key: String, member: String },
+
+    /// Returns the score of the member, nil if it does not exist.
+    ZScore { key: String, member: String },
+
+    /// Returns the number of members of the sorted set stored at key.
+    ZCard { key: String },
+
+    /// Removes and returns the member with the lowest score, or up to count members if given.
+    ZPopMin { key: String, count: Option<usize> },
+
+    /// Like `ZPopMin`, but from the highest score.
+    ZPopMax { key: String, count: Option<usize> },
+
+    /// Stores at destination the members of any of the sorted sets, plain sets count as scores of 1. Scores are
+    /// multiplied by the weight of their key, and combined by aggregate when a member is in more than one set.
+    ZUnionStore {
+        destination: String,
+        keys: Vec<String>,
+        weights: Vec<f64>,
+        aggregate: Aggregate,
+    },
+
+    /// Like `ZUnionStore`, but only with the members present in all the sets.
+    ZInterStore {
+        destination: String,
+        keys: Vec<String>,
+        weights: Vec<f64>,
+        aggregate: Aggregate,
+    },
+
+    /// Stores at destination the members of the first sorted set which are not in any of the others.
+    ZDiffStore {
+        destination: String,
+        keys: Vec<String>,
+    },
+
+    /// Appends the entry to the stream stored at key and returns its ID, then trims the stream if requested. With
+    /// nomkstream a missing stream is not created and nil is returned.
+    XAdd {
+        key: String,
+        id: NewId,
+        fields: Fields,
+        trim: Option<Trim>,
+        nomkstream: bool,
+    },
+
+    /// Returns the number of entries of the stream stored at key.
+    XLen { key: String },
+
+    /// Returns up to count entries with an ID between start and end included, from the oldest.
+    XRange {
+        key: String,
+        start: StreamId,
+        end: StreamId,
+        count: Option<usize>,
+    },
+
+    /// Like `XRange`, but from the newest. The bounds are still from the lowest to the highest.
+    XRevRange {
+        key: String,
+        start: StreamId,
+        end: StreamId,
+        count: Option<usize>,
+    },
+
+    /// Removes the oldest entries of the stream, returns the number of entries removed.
+    XTrim { key: String, trim: Trim },
+
+    /// Removes the entries from the stream, returns the number of entries removed.
+    XDel { key: String, ids: Vec<StreamId> },
+
+    /// Returns up to count entries after the ID of every stream, None being the last ID of the stream (`$`). With
+    /// block the connection waits up to the timeout for new entries when there are none, forever if zero.
+    XRead {
+        count: Option<usize>,
+        block: Option<std::time::Duration>,
+        keys: Vec<String>,
+        ids: Vec<Option<StreamId>>,
+    },
+
+    /// Creates the consumer group, it delivers the entries after the ID, None being the last ID of the stream
+    /// (`$`). With mkstream a missing stream is created empty.
+    XGroupCreate {
+        key: String,
+        group: String,
+        id: Option<StreamId>,
+        mkstream: bool,
+    },
+
+    /// Removes the consumer group with its pending entries, returns the number of groups removed.
+    XGroupDestroy { key: String, group: String },
+
+    /// Like `XRead` for a consumer of the group. None (`>`) returns the entries never delivered to the group which
+    /// become pending for the consumer unless noack, an ID returns again the pending entries of the consumer.
+    XReadGroup {
+        group: String,
+        consumer: String,
+        count: Option<usize>,
+        block: Option<std::time::Duration>,
+        noack: bool,
+        keys: Vec<String>,
+        ids: Vec<Option<StreamId>>,
+    },
+
+    /// Acknowledges the pending entries of the group, returns the number of entries acknowledged.
+    XAck {
+        key: String,
+        group: String,
+        ids: Vec<StreamId>,
+    },
+
+    /// Gives to the consumer the pending entries idle for at least min idle, returns the entries claimed.
+    XClaim {
+        key: String,
+        group: String,
+        consumer: String,
+        min_idle: std::time::Duration,
+        ids: Vec<StreamId>,
+    },
+
+    /// Returns the summary of the pending entries of the group or, with a range, up to count pending entries
+    /// between the IDs, only the ones of the consumer if given.
+    XPending {
+        key: String,
+        group: String,
+        range: Option<(StreamId, StreamId, usize)>,
+        consumer: Option<String>,
+    },
+
+    /// Returns the length, the IDs and the first and last entries of the stream.
+    XInfoStream { key: String },
+
+    /// Returns the consumer groups of the stream.
+    XInfoGroups { key: String },
+
+    /// Returns the consumers of the group.
+    XInfoConsumers { key: String, group: String },
+
+    /// Sets or clears the bit at offset of the string stored at key, the string grows with zeros. Returns the
+    /// previous bit.
+    SetBit {
+        key: String,
+        offset: u64,
+        value: bool,
+    },
+
+    /// Returns the bit at offset of the string stored at key.
+    GetBit { key: String, offset: u64 },
+
+    /// Returns the number of bits set in the string stored at key, optionally between start and end included.
+    BitCount {
+        key: String,
+        range: Option<(i64, i64)>,
+        unit: Unit,
+    },
+
+    /// Returns the position of the first bit set or cleared in the string stored at key, optionally from start to
+    /// end included.
+    BitPos {
+        key: String,
+        bit: bool,
+        start: Option<i64>,
+        end: Option<i64>,
+        unit: Unit,
+    },
+
+    /// Stores at destination the bitwise operation between the strings, returns the length of the result.
+    BitOp {
+        op: Operation,
+        destination: String,
+        keys: Vec<String>,
+    },
+
+    /// Reads and writes integers of any width in the string stored at key, returns a reply for every read and
+    /// write in order.
+    BitField { key: String, ops: Vec<FieldOp> },
+
+    /// Adds the members at their longitude and latitude to the sorted set stored at key, their score is their
+    /// geohash. Only NX, XX and CH of the flags are used.
+    GeoAdd {
+        key: String,
+        members: Vec<(f64, f64, String)>,
+        flags: ZAddFlags,
+    },
+
+    /// Returns the longitude and latitude of the members, nil for the missing members.
+    GeoPos { key: String, members: Vec<String> },
+
+    /// Returns the distance between the members in the unit, nil if one of them is missing.
+    GeoDist {
+        key: String,
+        from: String,
+        to: String,
+        unit: geo::Unit,
+    },
+
+    /// Returns the members within the shape around the center, the distances are in the unit.
+    GeoSearch {
+        key: String,
+        from: GeoFrom,
+        shape: geo::Shape,
+        unit: geo::Unit,
+        options: GeoSearchOptions,
+    },
+
+    /// Adds the elements to the `HyperLogLog` stored at key, returns 1 if its estimation changed.
+    PfAdd { key: String, elements: Vec<String> },
+
+    /// Returns the estimated number of distinct elements added to the `HyperLogLogs`.
+    PfCount { keys: Vec<String> },
+
+    /// Stores at destination the union of the `HyperLogLogs`, destination included.
+    PfMerge {
+        destination: String,
+        keys: Vec<String>,
+    },
+
+    /// Sets the value at path in the JSON document stored at key, a new document must be set at the root. NX and XX
+    /// set `exists` like for SET.
+    JsonSet {
+        key: String,
+        path: json::Path,
+        value: serde_json::Value,
+        exists: Option<bool>,
+    },
+
+    /// Returns the values at the paths of the JSON document stored at key, serialized as JSON.
+    JsonGet { key: String, paths: Vec<json::Path> },
+
+    /// Deletes the values at path in the JSON document stored at key, returns the number of deleted values.
+    JsonDel { key: String, path: json::Path },
+
+    /// Appends the values to the arrays at path, returns their new lengths.
+    JsonArrAppend {
+        key: String,
+        path: json::Path,
+        values: Vec<serde_json::Value>,
+    },
+
+    /// Increments the numbers at path, returns their new values.
+    JsonNumIncrBy {
+        key: String,
+        path: json::Path,
+        increment: serde_json::Number,
+    },
+
+    /// Returns the JSON types of the values at path.
+    JsonType { key: String, path: json::Path },
+
     /// Return documentary information about commands.
     Docs,
 
@@ -36,6 +549,103 @@ pub enum Command {
 
     /// Removes all keys from the current database.
     FlushDb,
+
+    /// Returns information and statistics about the server, optionally limited to the given sections.
+    Info { sections: Vec<String> },
+
+    /// Returns the metrics of the server in the prometheus text format, it is available only through HTTP.
+    Metrics,
+
+    /// Returns the newest entries of the slowlog, all of them if count is None.
+    SlowlogGet { count: Option<usize> },
+
+    /// Returns the number of entries in the slowlog.
+    SlowlogLen,
+
+    /// Removes all the entries of the slowlog.
+    SlowlogReset,
+
+    /// Returns the latest and the maximum latency of every event recorded by the latency monitor.
+    LatencyLatest,
+
+    /// Returns the latency histograms of the given commands, all of them if none is given.
+    LatencyHistogram { commands: Vec<String> },
+
+    /// Returns the samples recorded for the event.
+    LatencyHistory { event: String },
+
+    /// Removes the samples of the given events, all of them if none is given.
+    LatencyReset { events: Vec<String> },
+
+    /// Streams back every command processed by the server, the connection stays in this mode until it is closed.
+    Monitor,
+
+    /// Returns the id of the current client.
+    ClientId,
+
+    /// Returns the description of the current client.
+    ClientInfo,
+
+    /// Returns the description of the connected clients, only the ones with the given ids if any.
+    ClientList { ids: Vec<u64> },
+
+    /// Assigns a name to the current client.
+    ClientSetName { name: String },
+
+    /// Returns the name of the current client.
+    ClientGetName,
+
+    /// Closes the connections of the clients matching all the filters, the legacy form takes only the address and
+    /// fails if no client matches.
+    ClientKill {
+        id: Option<u64>,
+        addr: Option<String>,
+        user: Option<String>,
+        skip_me: bool,
+        legacy: bool,
+    },
+
+    /// Suspends the commands of every client for the timeout, only the writes if all is false.
+    ClientPause {
+        timeout: std::time::Duration,
+        all: bool,
+    },
+
+    /// Resumes the clients paused by `ClientPause`.
+    ClientUnpause,
+
+    /// Authenticates the connection, the username is `default` when it is not given.
+    Auth {
+        username: Option<String>,
+        password: String,
+    },
+
+    /// Handshake with the server, it optionally authenticates the connection and assigns it a name. It returns
+    /// the information about the server.
+    Hello {
+        protover: Option<u8>,
+        auth: Option<(String, String)>,
+        setname: Option<String>,
+    },
+
+    /// Creates the user if it does not exist and applies the rules in order, like `on`, `>password`, `~pattern`
+    /// and `+@category`.
+    AclSetUser {
+        username: String,
+        rules: Vec<String>,
+    },
+
+    /// Returns the flags, passwords and permissions of the user.
+    AclGetUser { username: String },
+
+    /// Removes the users, the clients authenticated as one of them are disconnected.
+    AclDelUser { usernames: Vec<String> },
+
+    /// Returns every user using the format of the ACL file.
+    AclList,
+
+    /// Returns the user of the current client.
+    AclWhoAmI,
 }
 
 impl Command {
@@ -45,8 +655,12 @@ impl Command {
         value: Option<String>,
         options: &[String],
     ) -> Result<Self, ProtocolError> {
+        // NOTE: the key is kept as sent for the commands where it is not a key, like the password of AUTH.
+        let raw_key = key;
         let key = key.trim_matches('/').to_string();
-        let kind = kind.to_lowercase();
+        let kind = RENAMED
+            .with_borrow(|r| r.resolve(kind.to_lowercase()))
+            .ok_or_else(|| ProtocolError::CommandNotRecognized(kind.to_lowercase()))?;
 
         let cmd = match kind.as_str() {
             "command" if key == "DOCS" => Self::Docs,
@@ -61,21 +675,371 @@ impl Command {
             },
             "del" => Self::Del { key },
             "exists" => Self::Exists { key },
+            "type" => Self::Type { key },
+            "hset" | "hget" | "hmget" | "hdel" | "hgetall" | "hincrby" | "hexists" | "hlen"
+            | "hscan" => make_hash(&kind, key, value, options)?,
+            "lpush" | "rpush" | "lpop" | "rpop" | "lrange" | "lindex" | "llen" | "blpop"
+            | "brpop" => make_list(&kind, key, value, options)?,
+            "sadd" | "srem" | "smembers" | "sismember" | "scard" | "sinter" | "sunion"
+            | "sdiff" | "sinterstore" | "sunionstore" | "sdiffstore" | "spop" | "srandmember" => {
+                make_set_type(&kind, key, value, options)?
+            }
+            "xadd" | "xlen" | "xrange" | "xrevrange" | "xtrim" | "xdel" | "xread" | "xgroup"
+            | "xreadgroup" | "xack" | "xclaim" | "xpending" | "xinfo" => {
+                let args = std::iter::once(key)
+                    .chain(value)
+                    .chain(options.iter().cloned())
+                    .collect::<Vec<String>>();
+                make_stream(&kind, &args)?
+            }
+            "setbit" | "getbit" | "bitcount" | "bitpos" | "bitop" | "bitfield" => {
+                let args = std::iter::once(key)
+                    .chain(value)
+                    .chain(options.iter().cloned())
+                    .collect::<Vec<String>>();
+                make_bitmap(&kind, &args)?
+            }
+            "zadd" | "zincrby" | "zrem" | "zrange" | "zrank" | "zscore" | "zcard" | "zpopmin"
+            | "zpopmax" | "zunionstore" | "zinterstore" | "zdiffstore" => {
+                make_zset(&kind, key, value, options)?
+            }
+            "geoadd" | "geopos" | "geodist" | "geosearch" => make_geo(&kind, key, value, options)?,
+            "json.set" | "json.get" | "json.del" | "json.arrappend" | "json.numincrby"
+            | "json.type" => make_json(&kind, key, value, options)?,
+            "pfadd" | "pfcount" | "pfmerge" => {
+                let args = value.into_iter().chain(options.iter().cloned());
+                match kind.as_str() {
+                    "pfadd" => Self::PfAdd {
+                        key,
+                        elements: args.collect(),
+                    },
+                    "pfcount" => Self::PfCount {
+                        keys: std::iter::once(key).chain(args).collect(),
+                    },
+                    _ => Self::PfMerge {
+                        destination: key,
+                        keys: args.collect(),
+                    },
+                }
+            }
             "config" => Self::Config,
             "ping" => Self::Ping,
             "incr" => Self::Incr { key },
             "flushdb" => Self::FlushDb,
+            "info" => Self::Info {
+                sections: std::iter::once(key)
+                    .chain(value)
+                    .chain(options.iter().cloned())
+                    .filter(|s| !s.is_empty())
+                    .map(|s| s.to_lowercase())
+                    .collect(),
+            },
+            "slowlog" => make_slowlog(&key, value.as_deref())?,
+            "latency" => make_latency(&key, value, options)?,
+            "monitor" => Self::Monitor,
+            "client" => make_client(&key, value, options)?,
+            "auth" => match (value, options) {
+                (None, []) if !raw_key.is_empty() => Self::Auth {
+                    username: None,
+                    password: raw_key.to_string(),
+                },
+                (Some(password), []) => Self::Auth {
+                    username: Some(raw_key.to_string()),
+                    password,
+                },
+                _ => return Err(ProtocolError::InvalidArgument("auth".to_string())),
+            },
+            "hello" => make_hello(raw_key, value, options)?,
+            "acl" => make_acl(&key, value, options)?,
             _ => return Err(ProtocolError::CommandNotRecognized(kind)),
         };
 
         Ok(cmd)
     }
+
+    /// Keys accessed by the command.
+    pub fn keys(&self) -> Vec<&str> {
+        match self {
+            Self::Get { key }
+            | Self::Set { key, .. }
+            | Self::Del { key }
+            | Self::Exists { key }
+            | Self::Type { key }
+            | Self::View { key, .. }
+            | Self::Incr { key }
+            | Self::HSet { key, .. }
+            | Self::HGet { key, .. }
+            | Self::HMGet { key, .. }
+            | Self::HDel { key, .. }
+            | Self::HGetAll { key }
+            | Self::HIncrBy { key, .. }
+            | Self::HExists { key, .. }
+            | Self::HLen { key }
+            | Self::HScan { key, .. }
+            | Self::LPush { key, .. }
+            | Self::RPush { key, .. }
+            | Self::LPop { key, .. }
+            | Self::RPop { key, .. }
+            | Self::LRange { key, .. }
+            | Self::LIndex { key, .. }
+            | Self::LLen { key }
+            | Self::SAdd { key, .. }
+            | Self::SRem { key, .. }
+            | Self::SMembers { key }
+            | Self::SIsMember { key, .. }
+            | Self::SCard { key }
+            | Self::SPop { key, .. }
+            | Self::SRandMember { key, .. }
+            | Self::ZAdd { key, .. }
+            | Self::ZIncrBy { key, .. }
+            | Self::ZRem { key, .. }
+            | Self::ZRange { key, .. }
+            | Self::ZRank { key, .. }
+            | Self::ZScore { key, .. }
+            | Self::ZCard { key }
+            | Self::ZPopMin { key, .. }
+            | Self::ZPopMax { key, .. }
+            | Self::XAdd { key, .. }
+            | Self::XLen { key }
+            | Self::XRange { key, .. }
+            | Self::XRevRange { key, .. }
+            | Self::XTrim { key, .. }
+            | Self::XDel { key, .. }
+            | Self::XGroupCreate { key, .. }
+            | Self::XGroupDestroy { key, .. }
+            | Self::XAck { key, .. }
+            | Self::XClaim { key, .. }
+            | Self::XPending { key, .. }
+            | Self::XInfoStream { key }
+            | Self::XInfoGroups { key }
+            | Self::XInfoConsumers { key, .. }
+            | Self::SetBit { key, .. }
+            | Self::GetBit { key, .. }
+            | Self::BitCount { key, .. }
+            | Self::BitPos { key, .. }
+            | Self::BitField { key, .. }
+            | Self::PfAdd { key, .. }
+            | Self::GeoAdd { key, .. }
+            | Self::GeoPos { key, .. }
+            | Self::GeoDist { key, .. }
+            | Self::GeoSearch { key, .. }
+            | Self::JsonSet { key, .. }
+            | Self::JsonGet { key, .. }
+            | Self::JsonDel { key, .. }
+            | Self::JsonArrAppend { key, .. }
+            | Self::JsonNumIncrBy { key, .. }
+            | Self::JsonType { key, .. } => vec![key.as_str()],
+            Self::BLPop { keys, .. }
+            | Self::BRPop { keys, .. }
+            | Self::SInter { keys }
+            | Self::SUnion { keys }
+            | Self::SDiff { keys }
+            | Self::XRead { keys, .. }
+            | Self::XReadGroup { keys, .. }
+            | Self::PfCount { keys } => keys.iter().map(String::as_str).collect(),
+            Self::SInterStore { destination, keys }
+            | Self::SUnionStore { destination, keys }
+            | Self::SDiffStore { destination, keys }
+            | Self::ZUnionStore {
+                destination, keys, ..
+            }
+            | Self::ZInterStore {
+                destination, keys, ..
+            }
+            | Self::ZDiffStore { destination, keys }
+            | Self::BitOp {
+                destination, keys, ..
+            }
+            | Self::PfMerge { destination, keys } => std::iter::once(destination)
+                .chain(keys)
+                .map(String::as_str)
+                .collect(),
+            _ => vec![],
+        }
+    }
+
+    /// Returns if the command modifies the data, writes are suspended by `ClientPause`.
+    pub const fn is_write(&self) -> bool {
+        matches!(
+            self,
+            Self::Set { .. }
+                | Self::Del { .. }
+                | Self::Incr { .. }
+                | Self::FlushDb
+                | Self::HSet { .. }
+                | Self::HDel { .. }
+                | Self::HIncrBy { .. }
+                | Self::LPush { .. }
+                | Self::RPush { .. }
+                | Self::LPop { .. }
+                | Self::RPop { .. }
+                | Self::BLPop { .. }
+                | Self::BRPop { .. }
+                | Self::SAdd { .. }
+                | Self::SRem { .. }
+                | Self::SInterStore { .. }
+                | Self::SUnionStore { .. }
+                | Self::SDiffStore { .. }
+                | Self::SPop { .. }
+                | Self::ZAdd { .. }
+                | Self::ZIncrBy { .. }
+                | Self::ZRem { .. }
+                | Self::ZPopMin { .. }
+                | Self::ZPopMax { .. }
+                | Self::ZUnionStore { .. }
+                | Self::ZInterStore { .. }
+                | Self::ZDiffStore { .. }
+                | Self::XAdd { .. }
+                | Self::XTrim { .. }
+                | Self::XDel { .. }
+                | Self::XGroupCreate { .. }
+                | Self::XGroupDestroy { .. }
+                | Self::XReadGroup { .. }
+                | Self::XAck { .. }
+                | Self::XClaim { .. }
+                | Self::SetBit { .. }
+                | Self::BitOp { .. }
+                | Self::BitField { .. }
+                | Self::PfAdd { .. }
+                | Self::PfMerge { .. }
+                | Self::GeoAdd { .. }
+                | Self::JsonSet { .. }
+                | Self::JsonDel { .. }
+                | Self::JsonArrAppend { .. }
+                | Self::JsonNumIncrBy { .. }
+        )
+    }
+
+    /// Returns if the command carries credentials, its arguments must not be logged.
+    pub const fn has_credentials(&self) -> bool {
+        matches!(
+            self,
+            Self::Auth { .. } | Self::Hello { auth: Some(_), .. } | Self::AclSetUser { .. }
+        )
+    }
+
+    /// Name of the command as known by redis, it is used to group statistics.
+    #[allow(clippy::too_many_lines)]
+    pub const fn name(&self) -> &'static str {
+        match self {
+            Self::Get { .. } | Self::View { .. } => "get",
+            Self::Set { .. } => "set",
+            Self::Del { .. } => "del",
+            Self::Exists { .. } => "exists",
+            Self::Type { .. } => "type",
+            Self::HSet { .. } => "hset",
+            Self::HGet { .. } => "hget",
+            Self::HMGet { .. } => "hmget",
+            Self::HDel { .. } => "hdel",
+            Self::HGetAll { .. } => "hgetall",
+            Self::HIncrBy { .. } => "hincrby",
+            Self::HExists { .. } => "hexists",
+            Self::HLen { .. } => "hlen",
+            Self::HScan { .. } => "hscan",
+            Self::LPush { .. } => "lpush",
+            Self::RPush { .. } => "rpush",
+            Self::LPop { .. } => "lpop",
+            Self::RPop { .. } => "rpop",
+            Self::LRange { .. } => "lrange",
+            Self::LIndex { .. } => "lindex",
+            Self::LLen { .. } => "llen",
+            Self::BLPop { .. } => "blpop",
+            Self::BRPop { .. } => "brpop",
+            Self::SAdd { .. } => "sadd",
+            Self::SRem { .. } => "srem",
+            Self::SMembers { .. } => "smembers",
+            Self::SIsMember { .. } => "sismember",
+            Self::SCard { .. } => "scard",
+            Self::SInter { .. } => "sinter",
+            Self::SUnion { .. } => "sunion",
+            Self::SDiff { .. } => "sdiff",
+            Self::SInterStore { .. } => "sinterstore",
+            Self::SUnionStore { .. } => "sunionstore",
+            Self::SDiffStore { .. } => "sdiffstore",
+            Self::SPop { .. } => "spop",
+            Self::SRandMember { .. } => "srandmember",
+            Self::ZAdd { .. } => "zadd",
+            Self::ZIncrBy { .. } => "zincrby",
+            Self::ZRem { .. } => "zrem",
+            Self::ZRange { .. } => "zrange",
+            Self::ZRank { .. } => "zrank",
+            Self::ZScore { .. } => "zscore",
+            Self::ZCard { .. } => "zcard",
+            Self::ZPopMin { .. } => "zpopmin",
+            Self::ZPopMax { .. } => "zpopmax",
+            Self::ZUnionStore { .. } => "zunionstore",
+            Self::ZInterStore { .. } => "zinterstore",
+            Self::ZDiffStore { .. } => "zdiffstore",
+            Self::XAdd { .. } => "xadd",
+            Self::XLen { .. } => "xlen",
+            Self::XRange { .. } => "xrange",
+            Self::XRevRange { .. } => "xrevrange",
+            Self::XTrim { .. } => "xtrim",
+            Self::XDel { .. } => "xdel",
+            Self::XRead { .. } => "xread",
+            Self::XGroupCreate { .. } | Self::XGroupDestroy { .. } => "xgroup",
+            Self::XReadGroup { .. } => "xreadgroup",
+            Self::XAck { .. } => "xack",
+            Self::XClaim { .. } => "xclaim",
+            Self::XPending { .. } => "xpending",
+            Self::XInfoStream { .. } | Self::XInfoGroups { .. } | Self::XInfoConsumers { .. } => {
+                "xinfo"
+            }
+            Self::SetBit { .. } => "setbit",
+            Self::GetBit { .. } => "getbit",
+            Self::BitCount { .. } => "bitcount",
+            Self::BitPos { .. } => "bitpos",
+            Self::BitOp { .. } => "bitop",
+            Self::BitField { .. } => "bitfield",
+            Self::PfAdd { .. } => "pfadd",
+            Self::PfCount { .. } => "pfcount",
+            Self::PfMerge { .. } => "pfmerge",
+            Self::GeoAdd { .. } => "geoadd",
+            Self::GeoPos { .. } => "geopos",
+            Self::GeoDist { .. } => "geodist",
+            Self::GeoSearch { .. } => "geosearch",
+            Self::JsonSet { .. } => "json.set",
+            Self::JsonGet { .. } => "json.get",
+            Self::JsonDel { .. } => "json.del",
+            Self::JsonArrAppend { .. } => "json.arrappend",
+            Self::JsonNumIncrBy { .. } => "json.numincrby",
+            Self::JsonType { .. } => "json.type",
+            Self::Docs => "command",
+            Self::Config => "config",
+            Self::Ping => "ping",
+            Self::Incr { .. } => "incr",
+            Self::FlushDb => "flushdb",
+            Self::Info { .. } => "info",
+            Self::Metrics => "metrics",
+            Self::Monitor => "monitor",
+            Self::ClientId
+            | Self::ClientInfo
+            | Self::ClientList { .. }
+            | Self::ClientSetName { .. }
+            | Self::ClientGetName
+            | Self::ClientKill { .. }
+            | Self::ClientPause { .. }
+            | Self::ClientUnpause => "client",
+            Self::Auth { .. } => "auth",
+            Self::Hello { .. } => "hello",
+            Self::AclSetUser { .. }
+            | Self::AclGetUser { .. }
+            | Self::AclDelUser { .. }
+            | Self::AclList
+            | Self::AclWhoAmI => "acl",
+            Self::SlowlogGet { .. } | Self::SlowlogLen | Self::SlowlogReset => "slowlog",
+            Self::LatencyLatest
+            | Self::LatencyHistogram { .. }
+            | Self::LatencyHistory { .. }
+            | Self::LatencyReset { .. } => "latency",
+        }
+    }
 }
 
 fn make_set(key: String, v: &str, options: &[String]) -> Command {
     Command::Set {
         key,
-        value: v.trim().to_string(),
+        value: v.trim().as_bytes().to_vec(),
         ttl: if options.is_empty() {
             None
         } else {
@@ -95,25 +1059,1942 @@ fn make_set(key: String, v: &str, options: &[String]) -> Command {
     }
 }
 
-#[derive(PartialEq, Debug)]
-pub enum CommandResponse {
-    String { value: String },
-    Integer { value: i64 },
-    Array { value: Vec<CommandResponse> },
+fn make_hash(
+    kind: &str,
+    key: String,
+    value: Option<String>,
+    options: &[String],
+) -> Result<Command, ProtocolError> {
+    let args = value
+        .into_iter()
+        .chain(options.iter().cloned())
+        .collect::<Vec<String>>();
+    let invalid = || ProtocolError::InvalidArgument(kind.to_string());
+
+    let cmd = match (kind, args.as_slice()) {
+        ("hset", fields) if !fields.is_empty() && fields.len() % 2 == 0 => Command::HSet {
+            key,
+            fields: fields
+                .chunks(2)
+                .map(|f| (f[0].clone(), f[1].clone()))
+                .collect(),
+        },
+        ("hget", [field]) => Command::HGet {
+            key,
+            field: field.clone(),
+        },
+        ("hmget", fields) if !fields.is_empty() => Command::HMGet {
+            key,
+            fields: fields.to_vec(),
+        },
+        ("hdel", fields) if !fields.is_empty() => Command::HDel {
+            key,
+            fields: fields.to_vec(),
+        },
+        ("hgetall", []) => Command::HGetAll { key },
+        ("hincrby", [field, increment]) => Command::HIncrBy {
+            key,
+            field: field.clone(),
+            increment: increment
+                .parse()
+                .map_err(|_| ProtocolError::InvalidArgument(increment.clone()))?,
+        },
+        ("hexists", [field]) => Command::HExists {
+            key,
+            field: field.clone(),
+        },
+        ("hlen", []) => Command::HLen { key },
+        ("hscan", [cursor, options @ ..]) => make_scan(key, cursor, options)?,
+        _ => return Err(invalid()),
+    };
+
+    Ok(cmd)
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+fn make_list(
+    kind: &str,
+    key: String,
+    value: Option<String>,
+    options: &[String],
+) -> Result<Command, ProtocolError> {
+    let args = value
+        .into_iter()
+        .chain(options.iter().cloned())
+        .collect::<Vec<String>>();
+    let int = |v: &String| {
+        v.parse::<i64>()
+            .map_err(|_| ProtocolError::InvalidArgument(v.clone()))
+    };
+    let count = |v: &String| {
+        v.parse::<usize>()
+            .map_err(|_| ProtocolError::InvalidArgument(v.clone()))
+    };
 
-    #[test]
-    fn test_new_command_ok() {
-        let cmd = Command::new("GET", "key", None, &[]);
-        assert_eq!(
-            cmd.unwrap(),
-            Command::Get {
-                key: "key".to_string()
+    let cmd = match (kind, args.as_slice()) {
+        ("lpush", values) if !values.is_empty() => Command::LPush {
+            key,
+            values: values.to_vec(),
+        },
+        ("rpush", values) if !values.is_empty() => Command::RPush {
+            key,
+            values: values.to_vec(),
+        },
+        ("lpop", []) => Command::LPop { key, count: None },
+        ("lpop", [n]) => Command::LPop {
+            key,
+            count: Some(count(n)?),
+        },
+        ("rpop", []) => Command::RPop { key, count: None },
+        ("rpop", [n]) => Command::RPop {
+            key,
+            count: Some(count(n)?),
+        },
+        ("lrange", [start, stop]) => Command::LRange {
+            key,
+            start: int(start)?,
+            stop: int(stop)?,
+        },
+        ("lindex", [index]) => Command::LIndex {
+            key,
+            index: int(index)?,
+        },
+        ("llen", []) => Command::LLen { key },
+        ("blpop" | "brpop", [keys @ .., timeout]) => {
+            // NOTE: like redis the timeout is in seconds and can have decimals, 0 blocks forever.
+            let timeout = timeout
+                .parse::<f64>()
+                .ok()
+                .and_then(|t| std::time::Duration::try_from_secs_f64(t).ok())
+                .ok_or_else(|| ProtocolError::InvalidArgument(timeout.clone()))?;
+            let keys = std::iter::once(key)
+                .chain(keys.iter().cloned())
+                .collect::<Vec<String>>();
+            if kind == "blpop" {
+                Command::BLPop { keys, timeout }
+            } else {
+                Command::BRPop { keys, timeout }
             }
+        }
+        _ => return Err(ProtocolError::InvalidArgument(kind.to_string())),
+    };
+
+    Ok(cmd)
+}
+
+fn make_set_type(
+    kind: &str,
+    key: String,
+    value: Option<String>,
+    options: &[String],
+) -> Result<Command, ProtocolError> {
+    let args = value
+        .into_iter()
+        .chain(options.iter().cloned())
+        .collect::<Vec<String>>();
+    let keys = || {
+        std::iter::once(key.clone())
+            .chain(args.iter().cloned())
+            .collect::<Vec<String>>()
+    };
+
+    let cmd = match (kind, args.as_slice()) {
+        ("sadd", members) if !members.is_empty() => Command::SAdd {
+            key,
+            members: members.to_vec(),
+        },
+        ("srem", members) if !members.is_empty() => Command::SRem {
+            key,
+            members: members.to_vec(),
+        },
+        ("smembers", []) => Command::SMembers { key },
+        ("sismember", [member]) => Command::SIsMember {
+            key,
+            member: member.clone(),
+        },
+        ("scard", []) => Command::SCard { key },
+        ("sinter", _) => Command::SInter { keys: keys() },
+        ("sunion", _) => Command::SUnion { keys: keys() },
+        ("sdiff", _) => Command::SDiff { keys: keys() },
+        ("sinterstore", [_, ..]) => Command::SInterStore {
+            destination: key,
+            keys: args,
+        },
+        ("sunionstore", [_, ..]) => Command::SUnionStore {
+            destination: key,
+            keys: args,
+        },
+        ("sdiffstore", [_, ..]) => Command::SDiffStore {
+            destination: key,
+            keys: args,
+        },
+        ("spop", []) => Command::SPop { key, count: None },
+        ("spop", [n]) => Command::SPop {
+            key,
+            count: Some(
+                n.parse()
+                    .map_err(|_| ProtocolError::InvalidArgument(n.clone()))?,
+            ),
+        },
+        ("srandmember", []) => Command::SRandMember { key, count: None },
+        ("srandmember", [n]) => Command::SRandMember {
+            key,
+            count: Some(
+                n.parse()
+                    .map_err(|_| ProtocolError::InvalidArgument(n.clone()))?,
+            ),
+        },
+        _ => return Err(ProtocolError::InvalidArgument(kind.to_string())),
+    };
+
+    Ok(cmd)
+}
+
+fn make_zset(
+    kind: &str,
+    key: String,
+    value: Option<String>,
+    options: &[String],
+) -> Result<Command, ProtocolError> {
+    let args = value
+        .into_iter()
+        .chain(options.iter().cloned())
+        .collect::<Vec<String>>();
+    let count = |v: &String| {
+        v.parse::<usize>()
+            .map_err(|_| ProtocolError::InvalidArgument(v.clone()))
+    };
+
+    let cmd = match (kind, args.as_slice()) {
+        ("zadd", args) => make_zadd(key, args)?,
+        ("zincrby", [increment, member]) => Command::ZIncrBy {
+            key,
+            increment: parse_score(increment)?,
+            member: member.clone(),
+        },
+        ("zrem", members) if !members.is_empty() => Command::ZRem {
+            key,
+            members: members.to_vec(),
+        },
+        ("zrange", [start, stop, options @ ..]) => make_zrange(key, start, stop, options)?,
+        ("zrank", [member]) => Command::ZRank {
+            key,
+            member: member.clone(),
+        },
+        ("zscore", [member]) => Command::ZScore {
+            key,
+            member: member.clone(),
+        },
+        ("zcard", []) => Command::ZCard { key },
+        ("zpopmin", []) => Command::ZPopMin { key, count: None },
+        ("zpopmin", [n]) => Command::ZPopMin {
+            key,
+            count: Some(count(n)?),
+        },
+        ("zpopmax", []) => Command::ZPopMax { key, count: None },
+        ("zpopmax", [n]) => Command::ZPopMax {
+            key,
+            count: Some(count(n)?),
+        },
+        ("zunionstore" | "zinterstore" | "zdiffstore", [numkeys, args @ ..]) => {
+            make_zstore(kind, key, numkeys, args)?
+        }
+        _ => return Err(ProtocolError::InvalidArgument(kind.to_string())),
+    };
+
+    Ok(cmd)
+}
+
+/// Score of the sorted sets, `inf`, `+inf` and `-inf` are accepted but not NaN.
+fn parse_score(v: &str) -> Result<f64, ProtocolError> {
+    v.parse::<f64>()
+        .ok()
+        .filter(|s| !s.is_nan())
+        .ok_or_else(|| ProtocolError::InvalidArgument(v.to_string()))
+}
+
+fn make_zadd(key: String, args: &[String]) -> Result<Command, ProtocolError> {
+    let invalid = || ProtocolError::InvalidArgument("zadd".to_string());
+
+    let mut flags = ZAddFlags::default();
+    let mut given = HashSet::new();
+    let mut args = args;
+    while let [flag, rest @ ..] = args {
+        let flag = flag.to_lowercase();
+        match flag.as_str() {
+            "nx" => flags.exists = Some(false),
+            "xx" => flags.exists = Some(true),
+            "gt" => flags.compare = Some(std::cmp::Ordering::Greater),
+            "lt" => flags.compare = Some(std::cmp::Ordering::Less),
+            "ch" => flags.ch = true,
+            "incr" => flags.incr = true,
+            _ => break,
+        }
+        given.insert(flag);
+        args = rest;
+    }
+
+    // NOTE: like redis the flags contradicting each other are rejected, instead of the last one winning.
+    let contradicting = [("nx", "xx"), ("gt", "lt"), ("nx", "gt"), ("nx", "lt")]
+        .iter()
+        .any(|(a, b)| given.contains(*a) && given.contains(*b));
+    if contradicting {
+        return Err(invalid());
+    }
+    if args.is_empty() || args.len() % 2 != 0 || (flags.incr && args.len() != 2) {
+        return Err(invalid());
+    }
+
+    Ok(Command::ZAdd {
+        key,
+        members: args
+            .chunks(2)
+            .map(|p| Ok((parse_score(&p[0])?, p[1].clone())))
+            .collect::<Result<Vec<(f64, String)>, ProtocolError>>()?,
+        flags,
+    })
+}
+
+fn make_zrange(
+    key: String,
+    start: &str,
+    stop: &str,
+    options: &[String],
+) -> Result<Command, ProtocolError> {
+    let invalid = |v: &str| ProtocolError::InvalidArgument(v.to_string());
+
+    let (mut by, mut rev, mut limit, mut with_scores) = ("rank", false, None, false);
+    let mut options = options;
+    while let [option, rest @ ..] = options {
+        options = rest;
+        match option.to_lowercase().as_str() {
+            "byscore" => by = "score",
+            "bylex" => by = "lex",
+            "rev" => rev = true,
+            "withscores" => with_scores = true,
+            "limit" => {
+                let [offset, count, rest @ ..] = options else {
+                    return Err(invalid(option));
+                };
+                options = rest;
+                let offset = offset.parse::<usize>().map_err(|_| invalid(offset))?;
+                // NOTE: like redis a negative count returns all the members after offset.
+                let count = count.parse::<i64>().map_err(|_| invalid(count))?;
+                limit = Some((offset, usize::try_from(count).unwrap_or(usize::MAX)));
+            }
+            _ => return Err(invalid(option)),
+        }
+    }
+
+    // NOTE: with REV the range is given from the highest, it is stored from the lowest like the other ranges.
+    let (min, max) = if rev && by != "rank" {
+        (stop, start)
+    } else {
+        (start, stop)
+    };
+    let by = match by {
+        "score" => ZRangeBy::Score(parse_score_bound(min)?, parse_score_bound(max)?),
+        "lex" if !with_scores => ZRangeBy::Lex(parse_lex_bound(min)?, parse_lex_bound(max)?),
+        "rank" if limit.is_none() => ZRangeBy::Rank(
+            min.parse().map_err(|_| invalid(min))?,
+            max.parse().map_err(|_| invalid(max))?,
+        ),
+        _ => return Err(invalid("zrange")),
+    };
+
+    Ok(Command::ZRange {
+        key,
+        by,
+        rev,
+        limit,
+        with_scores,
+    })
+}
+
+/// Bound of a score range, `(` makes it exclusive.
+fn parse_score_bound(v: &str) -> Result<std::ops::Bound<f64>, ProtocolError> {
+    v.strip_prefix('(').map_or_else(
+        || parse_score(v).map(std::ops::Bound::Included),
+        |s| parse_score(s).map(std::ops::Bound::Excluded),
+    )
+}
+
+/// Bound of a lex range: `[` makes it inclusive, `(` exclusive, `-` and `+` are the lowest and the highest.
+fn parse_lex_bound(v: &str) -> Result<std::ops::Bound<String>, ProtocolError> {
+    if v == "-" || v == "+" {
+        return Ok(std::ops::Bound::Unbounded);
+    }
+
+    match v.chars().next() {
+        Some('[') => Ok(std::ops::Bound::Included(v[1..].to_string())),
+        Some('(') => Ok(std::ops::Bound::Excluded(v[1..].to_string())),
+        _ => Err(ProtocolError::InvalidArgument(v.to_string())),
+    }
+}
+
+fn make_zstore(
+    kind: &str,
+    destination: String,
+    numkeys: &str,
+    args: &[String],
+) -> Result<Command, ProtocolError> {
+    let invalid = |v: &str| ProtocolError::InvalidArgument(v.to_string());
+
+    let n = numkeys
+        .parse::<usize>()
+        .ok()
+        .filter(|n| (1..=args.len()).contains(n))
+        .ok_or_else(|| invalid(numkeys))?;
+    let (keys, mut options) = args.split_at(n);
+
+    let (mut weights, mut aggregate) = (vec![], Aggregate::default());
+    while let [option, rest @ ..] = options {
+        match option.to_lowercase().as_str() {
+            "weights" if kind != "zdiffstore" && rest.len() >= n => {
+                weights = rest[..n]
+                    .iter()
+                    .map(|w| parse_score(w))
+                    .collect::<Result<Vec<f64>, ProtocolError>>()?;
+                options = &rest[n..];
+            }
+            "aggregate" if kind != "zdiffstore" => {
+                let [a, rest @ ..] = rest else {
+                    return Err(invalid(option));
+                };
+                aggregate = match a.to_lowercase().as_str() {
+                    "sum" => Aggregate::Sum,
+                    "min" => Aggregate::Min,
+                    "max" => Aggregate::Max,
+                    _ => return Err(invalid(a)),
+                };
+                options = rest;
+            }
+            _ => return Err(invalid(option)),
+        }
+    }
+
+    let keys = keys.to_vec();
+    Ok(match kind {
+        "zunionstore" => Command::ZUnionStore {
+            destination,
+            keys,
+            weights,
+            aggregate,
+        },
+        "zinterstore" => Command::ZInterStore {
+            destination,
+            keys,
+            weights,
+            aggregate,
+        },
+        _ => Command::ZDiffStore { destination, keys },
+    })
+}
+
+fn make_stream(kind: &str, args: &[String]) -> Result<Command, ProtocolError> {
+    let invalid = |v: &str| ProtocolError::InvalidArgument(v.to_string());
+    let id = |v: &String| StreamId::parse(v, 0).ok_or_else(|| invalid(v));
+    let ids = |v: &[String]| {
+        v.iter()
+            .map(id)
+            .collect::<Result<Vec<StreamId>, ProtocolError>>()
+    };
+
+    let cmd = match (kind, args) {
+        ("xadd", [key, args @ ..]) => make_xadd(key.clone(), args)?,
+        ("xlen", [key]) => Command::XLen { key: key.clone() },
+        ("xrange" | "xrevrange", [key, first, second, options @ ..]) => {
+            make_xrange(kind, key.clone(), (first, second), options)?
+        }
+        ("xtrim", [key, args @ ..]) => match parse_trim(args)? {
+            (Some(trim), []) => Command::XTrim {
+                key: key.clone(),
+                trim,
+            },
+            _ => return Err(invalid(kind)),
+        },
+        ("xdel", [key, v @ ..]) if !v.is_empty() => Command::XDel {
+            key: key.clone(),
+            ids: ids(v)?,
+        },
+        ("xread", args) => make_xread(None, args)?,
+        ("xreadgroup", [g, group, consumer, args @ ..]) if g.eq_ignore_ascii_case("group") => {
+            make_xread(Some((group.clone(), consumer.clone())), args)?
+        }
+        ("xgroup", [sub, args @ ..]) => match (sub.to_lowercase().as_str(), args) {
+            ("create", [key, group, last, mkstream @ ..]) => Command::XGroupCreate {
+                key: key.clone(),
+                group: group.clone(),
+                id: if last == "$" { None } else { Some(id(last)?) },
+                mkstream: match mkstream {
+                    [] => false,
+                    [m] if m.eq_ignore_ascii_case("mkstream") => true,
+                    _ => return Err(invalid(kind)),
+                },
+            },
+            ("destroy", [key, group]) => Command::XGroupDestroy {
+                key: key.clone(),
+                group: group.clone(),
+            },
+            _ => return Err(invalid(sub)),
+        },
+        ("xack", [key, group, v @ ..]) if !v.is_empty() => Command::XAck {
+            key: key.clone(),
+            group: group.clone(),
+            ids: ids(v)?,
+        },
+        ("xclaim", [key, group, consumer, min_idle, v @ ..]) if !v.is_empty() => Command::XClaim {
+            key: key.clone(),
+            group: group.clone(),
+            consumer: consumer.clone(),
+            min_idle: std::time::Duration::from_millis(
+                min_idle.parse().map_err(|_| invalid(min_idle))?,
+            ),
+            ids: ids(v)?,
+        },
+        ("xpending", [key, group, range @ ..]) => {
+            let (range, consumer) = match range {
+                [] => (None, None),
+                [start, end, count, consumer @ ..] if consumer.len() <= 1 => (
+                    Some((
+                        parse_range_id(start, false)?,
+                        parse_range_id(end, true)?,
+                        count.parse().map_err(|_| invalid(count))?,
+                    )),
+                    consumer.first().cloned(),
+                ),
+                _ => return Err(invalid(kind)),
+            };
+            Command::XPending {
+                key: key.clone(),
+                group: group.clone(),
+                range,
+                consumer,
+            }
+        }
+        ("xinfo", [sub, key, args @ ..]) => match (sub.to_lowercase().as_str(), args) {
+            ("stream", []) => Command::XInfoStream { key: key.clone() },
+            ("groups", []) => Command::XInfoGroups { key: key.clone() },
+            ("consumers", [group]) => Command::XInfoConsumers {
+                key: key.clone(),
+                group: group.clone(),
+            },
+            _ => return Err(invalid(sub)),
+        },
+        _ => return Err(invalid(kind)),
+    };
+
+    Ok(cmd)
+}
+
+fn make_bitmap(kind: &str, args: &[String]) -> Result<Command, ProtocolError> {
+    let invalid = |v: &str| ProtocolError::InvalidArgument(v.to_string());
+    let integer = |v: &String| v.parse::<i64>().map_err(|_| invalid(v));
+    let offset = |v: &String| {
+        v.parse::<u64>()
+            .ok()
+            .filter(|o| *o <= MAX_OFFSET)
+            .ok_or_else(|| invalid(v))
+    };
+    let bit = |v: &String| match v.as_str() {
+        "0" => Ok(false),
+        "1" => Ok(true),
+        _ => Err(invalid(v)),
+    };
+    let unit = |v: &[String]| match v {
+        [] => Ok(Unit::Byte),
+        [u] if u.eq_ignore_ascii_case("byte") => Ok(Unit::Byte),
+        [u] if u.eq_ignore_ascii_case("bit") => Ok(Unit::Bit),
+        _ => Err(invalid(kind)),
+    };
+
+    let cmd = match (kind, args) {
+        ("setbit", [key, o, v]) => Command::SetBit {
+            key: key.clone(),
+            offset: offset(o)?,
+            value: bit(v)?,
+        },
+        ("getbit", [key, o]) => Command::GetBit {
+            key: key.clone(),
+            offset: offset(o)?,
+        },
+        ("bitcount", [key]) => Command::BitCount {
+            key: key.clone(),
+            range: None,
+            unit: Unit::Byte,
+        },
+        ("bitcount", [key, start, end, u @ ..]) => Command::BitCount {
+            key: key.clone(),
+            range: Some((integer(start)?, integer(end)?)),
+            unit: unit(u)?,
+        },
+        ("bitpos", [key, b, range @ ..]) => {
+            let (start, end, unit) = match range {
+                [] => (None, None, Unit::Byte),
+                [start] => (Some(integer(start)?), None, Unit::Byte),
+                [start, end, u @ ..] => (Some(integer(start)?), Some(integer(end)?), unit(u)?),
+            };
+            Command::BitPos {
+                key: key.clone(),
+                bit: bit(b)?,
+                start,
+                end,
+                unit,
+            }
+        }
+        ("bitop", [op, destination, keys @ ..]) if !keys.is_empty() => Command::BitOp {
+            op: match op.to_lowercase().as_str() {
+                "and" => Operation::And,
+                "or" => Operation::Or,
+                "xor" => Operation::Xor,
+                "not" if keys.len() == 1 => Operation::Not,
+                _ => return Err(invalid(op)),
+            },
+            destination: destination.clone(),
+            keys: keys.to_vec(),
+        },
+        ("bitfield", [key, ops @ ..]) => Command::BitField {
+            key: key.clone(),
+            ops: make_bitfield(ops)?,
+        },
+        _ => return Err(invalid(kind)),
+    };
+
+    Ok(cmd)
+}
+
+/// Operations of BITFIELD, the offset of a field may be given in multiples of its width like `#2`.
+fn make_bitfield(mut args: &[String]) -> Result<Vec<FieldOp>, ProtocolError> {
+    let invalid = |v: &str| ProtocolError::InvalidArgument(v.to_string());
+    let field = |t: &String, o: &String| {
+        let field = Field::parse(t).ok_or_else(|| invalid(t))?;
+        let offset = o.strip_prefix('#').map_or_else(
+            || o.parse::<u64>().ok(),
+            |n| {
+                n.parse::<u64>()
+                    .ok()
+                    .and_then(|n| n.checked_mul(u64::from(field.bits)))
+            },
+        );
+        offset
+            .filter(|o| *o <= MAX_OFFSET + 1 - u64::from(field.bits))
+            .map(|o| (field, o))
+            .ok_or_else(|| invalid(o))
+    };
+    let integer = |v: &String| v.parse::<i64>().map_err(|_| invalid(v));
+
+    let mut ops = vec![];
+    loop {
+        let (op, rest) = match args {
+            [] => return Ok(ops),
+            [op, t, o, rest @ ..] if op.eq_ignore_ascii_case("get") => {
+                let (field, offset) = field(t, o)?;
+                (FieldOp::Get(field, offset), rest)
+            }
+            [op, t, o, v, rest @ ..] if op.eq_ignore_ascii_case("set") => {
+                let (field, offset) = field(t, o)?;
+                (FieldOp::Set(field, offset, integer(v)?), rest)
+            }
+            [op, t, o, v, rest @ ..] if op.eq_ignore_ascii_case("incrby") => {
+                let (field, offset) = field(t, o)?;
+                (FieldOp::IncrBy(field, offset, integer(v)?), rest)
+            }
+            [op, mode, rest @ ..] if op.eq_ignore_ascii_case("overflow") => {
+                let overflow = match mode.to_lowercase().as_str() {
+                    "wrap" => Overflow::Wrap,
+                    "sat" => Overflow::Sat,
+                    "fail" => Overflow::Fail,
+                    _ => return Err(invalid(mode)),
+                };
+                (FieldOp::Overflow(overflow), rest)
+            }
+            [op, ..] => return Err(invalid(op)),
+        };
+        ops.push(op);
+        args = rest;
+    }
+}
+
+fn make_geo(
+    kind: &str,
+    key: String,
+    value: Option<String>,
+    options: &[String],
+) -> Result<Command, ProtocolError> {
+    let args = value
+        .into_iter()
+        .chain(options.iter().cloned())
+        .collect::<Vec<String>>();
+    let invalid = |v: &str| ProtocolError::InvalidArgument(v.to_string());
+    let unit = |v: &String| geo::Unit::parse(v).ok_or_else(|| invalid(v));
+
+    let cmd = match (kind, args.as_slice()) {
+        ("geoadd", args) => {
+            let mut flags = ZAddFlags::default();
+            let mut args = args;
+            while let [flag, rest @ ..] = args {
+                match flag.to_lowercase().as_str() {
+                    "nx" if flags.exists.is_none() => flags.exists = Some(false),
+                    "xx" if flags.exists.is_none() => flags.exists = Some(true),
+                    "ch" => flags.ch = true,
+                    _ => break,
+                }
+                args = rest;
+            }
+            if args.is_empty() || args.len() % 3 != 0 {
+                return Err(invalid(kind));
+            }
+
+            let members = args
+                .chunks(3)
+                .map(|m| Ok((parse_score(&m[0])?, parse_score(&m[1])?, m[2].clone())))
+                .collect::<Result<Vec<(f64, f64, String)>, ProtocolError>>()?;
+            Command::GeoAdd {
+                key,
+                members,
+                flags,
+            }
+        }
+        ("geopos", members) => Command::GeoPos {
+            key,
+            members: members.to_vec(),
+        },
+        ("geodist", [from, to, u @ ..]) if u.len() <= 1 => Command::GeoDist {
+            key,
+            from: from.clone(),
+            to: to.clone(),
+            unit: u.first().map(unit).transpose()?.unwrap_or_default(),
+        },
+        ("geosearch", args) => make_geosearch(key, args)?,
+        _ => return Err(invalid(kind)),
+    };
+
+    Ok(cmd)
+}
+
+/// GEOSEARCH needs exactly one center, FROMMEMBER or FROMLONLAT, and one shape, BYRADIUS or BYBOX.
+fn make_geosearch(key: String, args: &[String]) -> Result<Command, ProtocolError> {
+    let invalid = |v: &str| ProtocolError::InvalidArgument(v.to_string());
+    let unit = |v: &String| geo::Unit::parse(v).ok_or_else(|| invalid(v));
+    let distance =
+        |v: &String| parse_score(v).and_then(|d| (d >= 0.0).then_some(d).ok_or_else(|| invalid(v)));
+
+    let (mut from, mut shape, mut options) = (None, None, GeoSearchOptions::default());
+    let mut args = args;
+    while !args.is_empty() {
+        args = match args {
+            [o, m, rest @ ..] if o.eq_ignore_ascii_case("frommember") && from.is_none() => {
+                from = Some(GeoFrom::Member(m.clone()));
+                rest
+            }
+            [o, lon, lat, rest @ ..] if o.eq_ignore_ascii_case("fromlonlat") && from.is_none() => {
+                from = Some(GeoFrom::Position(parse_score(lon)?, parse_score(lat)?));
+                rest
+            }
+            [o, r, u, rest @ ..] if o.eq_ignore_ascii_case("byradius") && shape.is_none() => {
+                let u = unit(u)?;
+                shape = Some((geo::Shape::Radius(distance(r)? * u.meters()), u));
+                rest
+            }
+            [o, w, h, u, rest @ ..] if o.eq_ignore_ascii_case("bybox") && shape.is_none() => {
+                let u = unit(u)?;
+                let (width, height) = (distance(w)? * u.meters(), distance(h)? * u.meters());
+                shape = Some((geo::Shape::Box { width, height }, u));
+                rest
+            }
+            [o, n, a, rest @ ..]
+                if o.eq_ignore_ascii_case("count") && a.eq_ignore_ascii_case("any") =>
+            {
+                options.count = Some((n.parse().map_err(|_| invalid(n))?, true));
+                rest
+            }
+            [o, n, rest @ ..] if o.eq_ignore_ascii_case("count") => {
+                options.count = Some((n.parse().map_err(|_| invalid(n))?, false));
+                rest
+            }
+            [o, rest @ ..] => {
+                match o.to_lowercase().as_str() {
+                    "asc" => options.desc = false,
+                    "desc" => options.desc = true,
+                    "withcoord" => options.with_coord = true,
+                    "withdist" => options.with_dist = true,
+                    "withhash" => options.with_hash = true,
+                    _ => return Err(invalid(o)),
+                }
+                rest
+            }
+            [] => unreachable!(),
+        };
+    }
+
+    let (Some(from), Some((shape, unit))) = (from, shape) else {
+        return Err(invalid("geosearch"));
+    };
+    if options.count.is_some_and(|(n, _)| n == 0) {
+        return Err(invalid("count"));
+    }
+    Ok(Command::GeoSearch {
+        key,
+        from,
+        shape,
+        unit,
+        options,
+    })
+}
+
+fn make_json(
+    kind: &str,
+    key: String,
+    value: Option<String>,
+    options: &[String],
+) -> Result<Command, ProtocolError> {
+    let args = value
+        .into_iter()
+        .chain(options.iter().cloned())
+        .collect::<Vec<String>>();
+    let invalid = |v: &str| ProtocolError::InvalidArgument(v.to_string());
+    let path = |v: &str| json::Path::parse(v).map_err(|_| invalid(v));
+    let document =
+        |v: &String| serde_json::from_str::<serde_json::Value>(v).map_err(|_| invalid(v));
+
+    let cmd = match (kind, args.as_slice()) {
+        ("json.set", [p, v, flag @ ..]) if flag.len() <= 1 => Command::JsonSet {
+            key,
+            path: path(p)?,
+            value: document(v)?,
+            exists: match flag.first().map(|f| f.to_lowercase()).as_deref() {
+                None => None,
+                Some("nx") => Some(false),
+                Some("xx") => Some(true),
+                Some(_) => return Err(invalid(&flag[0])),
+            },
+        },
+        // NOTE: without a path JSON.GET returns the whole document and JSON.TYPE its type, JSON.DEL deletes it.
+        ("json.get", paths) => Command::JsonGet {
+            key,
+            paths: paths
+                .iter()
+                .map(|p| path(p))
+                .collect::<Result<Vec<json::Path>, ProtocolError>>()?,
+        },
+        ("json.del", []) => Command::JsonDel {
+            key,
+            path: path("$")?,
+        },
+        ("json.del", [p]) => Command::JsonDel {
+            key,
+            path: path(p)?,
+        },
+        ("json.arrappend", [p, values @ ..]) if !values.is_empty() => Command::JsonArrAppend {
+            key,
+            path: path(p)?,
+            values: values
+                .iter()
+                .map(document)
+                .collect::<Result<Vec<serde_json::Value>, ProtocolError>>()?,
+        },
+        ("json.numincrby", [p, n]) => match document(n)? {
+            serde_json::Value::Number(increment) => Command::JsonNumIncrBy {
+                key,
+                path: path(p)?,
+                increment,
+            },
+            _ => return Err(invalid(n)),
+        },
+        ("json.type", []) => Command::JsonType {
+            key,
+            path: path(".")?,
+        },
+        ("json.type", [p]) => Command::JsonType {
+            key,
+            path: path(p)?,
+        },
+        _ => return Err(invalid(kind)),
+    };
+
+    Ok(cmd)
+}
+
+/// XRANGE and XREVRANGE, the bounds of the command are kept from the lowest to the highest ID.
+fn make_xrange(
+    kind: &str,
+    key: String,
+    (first, second): (&String, &String),
+    options: &[String],
+) -> Result<Command, ProtocolError> {
+    let (start, end) = if kind == "xrange" {
+        (first, second)
+    } else {
+        (second, first)
+    };
+    let count = match options {
+        [] => None,
+        [c, n] if c.eq_ignore_ascii_case("count") => Some(
+            n.parse()
+                .map_err(|_| ProtocolError::InvalidArgument(n.clone()))?,
+        ),
+        _ => return Err(ProtocolError::InvalidArgument(kind.to_string())),
+    };
+
+    let (start, end) = (parse_range_id(start, false)?, parse_range_id(end, true)?);
+    Ok(if kind == "xrange" {
+        Command::XRange {
+            key,
+            start,
+            end,
+            count,
+        }
+    } else {
+        Command::XRevRange {
+            key,
+            start,
+            end,
+            count,
+        }
+    })
+}
+
+/// ID of XRANGE and XPENDING: `-` and `+` are the lowest and the highest, `(` excludes the ID and a missing
+/// sequence number matches the whole millisecond.
+fn parse_range_id(v: &str, end: bool) -> Result<StreamId, ProtocolError> {
+    let invalid = || ProtocolError::InvalidArgument(v.to_string());
+
+    match v {
+        "-" => Ok(StreamId::MIN),
+        "+" => Ok(StreamId::MAX),
+        _ => match v.strip_prefix('(') {
+            Some(id) if end => StreamId::parse(id, 0).and_then(StreamId::prev),
+            Some(id) => StreamId::parse(id, u64::MAX).and_then(StreamId::next),
+            None => StreamId::parse(v, if end { u64::MAX } else { 0 }),
+        }
+        .ok_or_else(invalid),
+    }
+}
+
+/// `MAXLEN|MINID [=|~] threshold [LIMIT count]` at the start of the arguments, with the arguments after it. Like
+/// redis `~` allows the trimming to be approximate, here it is always exact and LIMIT is accepted but ignored.
+fn parse_trim(args: &[String]) -> Result<(Option<Trim>, &[String]), ProtocolError> {
+    let invalid = |v: &str| ProtocolError::InvalidArgument(v.to_string());
+
+    let [strategy, rest @ ..] = args else {
+        return Ok((None, args));
+    };
+    let strategy = strategy.to_lowercase();
+    if strategy != "maxlen" && strategy != "minid" {
+        return Ok((None, args));
+    }
+
+    let rest = match rest {
+        [op, rest @ ..] if op == "=" || op == "~" => rest,
+        _ => rest,
+    };
+    let [threshold, rest @ ..] = rest else {
+        return Err(invalid(&strategy));
+    };
+    let trim = if strategy == "maxlen" {
+        Trim::MaxLen(threshold.parse().map_err(|_| invalid(threshold))?)
+    } else {
+        Trim::MinId(StreamId::parse(threshold, 0).ok_or_else(|| invalid(threshold))?)
+    };
+    let rest = match rest {
+        [limit, n, rest @ ..] if limit.eq_ignore_ascii_case("limit") => {
+            n.parse::<usize>().map_err(|_| invalid(n))?;
+            rest
+        }
+        _ => rest,
+    };
+
+    Ok((Some(trim), rest))
+}
+
+fn make_xadd(key: String, args: &[String]) -> Result<Command, ProtocolError> {
+    let invalid = |v: &str| ProtocolError::InvalidArgument(v.to_string());
+
+    let (nomkstream, args) = match args {
+        [n, rest @ ..] if n.eq_ignore_ascii_case("nomkstream") => (true, rest),
+        _ => (false, args),
+    };
+    let (trim, args) = parse_trim(args)?;
+    let [id, fields @ ..] = args else {
+        return Err(invalid("xadd"));
+    };
+    if fields.is_empty() || fields.len() % 2 != 0 {
+        return Err(invalid("xadd"));
+    }
+
+    let id = match id.strip_suffix("-*") {
+        _ if id == "*" => NewId::Auto,
+        Some(ms) => NewId::Ms(ms.parse().map_err(|_| invalid(id))?),
+        None => NewId::Exact(StreamId::parse(id, 0).ok_or_else(|| invalid(id))?),
+    };
+
+    Ok(Command::XAdd {
+        key,
+        id,
+        fields: fields
+            .chunks(2)
+            .map(|f| (f[0].clone(), f[1].clone()))
+            .collect(),
+        trim,
+        nomkstream,
+    })
+}
+
+/// XREAD, or XREADGROUP for the group and consumer if given. The IDs follow the keys after STREAMS, `$` for XREAD
+/// and `>` for XREADGROUP are None.
+fn make_xread(group: Option<(String, String)>, args: &[String]) -> Result<Command, ProtocolError> {
+    let invalid = |v: &str| ProtocolError::InvalidArgument(v.to_string());
+
+    let (mut count, mut block, mut noack) = (None, None, false);
+    let mut args = args;
+    let streams = loop {
+        match args {
+            [option, n, rest @ ..] if option.eq_ignore_ascii_case("count") => {
+                count = Some(n.parse().map_err(|_| invalid(n))?);
+                args = rest;
+            }
+            [option, ms, rest @ ..] if option.eq_ignore_ascii_case("block") => {
+                block = Some(std::time::Duration::from_millis(
+                    ms.parse().map_err(|_| invalid(ms))?,
+                ));
+                args = rest;
+            }
+            [option, rest @ ..] if option.eq_ignore_ascii_case("noack") && group.is_some() => {
+                noack = true;
+                args = rest;
+            }
+            [option, rest @ ..] if option.eq_ignore_ascii_case("streams") => break rest,
+            [option, ..] => return Err(invalid(option)),
+            [] => return Err(invalid("streams")),
+        }
+    };
+    if streams.is_empty() || streams.len() % 2 != 0 {
+        return Err(invalid("streams"));
+    }
+
+    let (keys, ids) = streams.split_at(streams.len() / 2);
+    let last = if group.is_some() { ">" } else { "$" };
+    let ids = ids
+        .iter()
+        .map(|id| match id.as_str() {
+            v if v == last => Ok(None),
+            v => StreamId::parse(v, 0).map(Some).ok_or_else(|| invalid(v)),
+        })
+        .collect::<Result<Vec<Option<StreamId>>, ProtocolError>>()?;
+    let keys = keys.to_vec();
+
+    Ok(match group {
+        None => Command::XRead {
+            count,
+            block,
+            keys,
+            ids,
+        },
+        Some((group, consumer)) => Command::XReadGroup {
+            group,
+            consumer,
+            count,
+            block,
+            noack,
+            keys,
+            ids,
+        },
+    })
+}
+
+fn make_scan(key: String, cursor: &str, options: &[String]) -> Result<Command, ProtocolError> {
+    let mut cmd = (None, 10);
+    for option in options.chunks(2) {
+        match (option[0].to_lowercase().as_str(), option.get(1)) {
+            ("match", Some(pattern)) => cmd.0 = Some(pattern.clone()),
+            ("count", Some(count)) => {
+                cmd.1 = count
+                    .parse()
+                    .ok()
+                    .filter(|c| *c > 0)
+                    .ok_or_else(|| ProtocolError::InvalidArgument(count.clone()))?;
+            }
+            _ => return Err(ProtocolError::InvalidArgument(option[0].clone())),
+        }
+    }
+
+    Ok(Command::HScan {
+        key,
+        cursor: cursor
+            .parse()
+            .map_err(|_| ProtocolError::InvalidArgument(cursor.to_string()))?,
+        pattern: cmd.0,
+        count: cmd.1,
+    })
+}
+
+fn make_slowlog(subcommand: &str, value: Option<&str>) -> Result<Command, ProtocolError> {
+    match subcommand.to_lowercase().as_str() {
+        "get" => {
+            // NOTE: like redis, a negative count means all the entries.
+            let count = value
+                .map(|v| {
+                    v.parse::<i64>()
+                        .map_err(|_| ProtocolError::InvalidArgument(v.to_string()))
+                })
+                .transpose()?;
+            Ok(Command::SlowlogGet {
+                count: count.map_or(Some(10), |c| usize::try_from(c).ok()),
+            })
+        }
+        "len" => Ok(Command::SlowlogLen),
+        "reset" => Ok(Command::SlowlogReset),
+        _ => Err(ProtocolError::CommandNotRecognized(format!(
+            "slowlog {}",
+            subcommand.to_lowercase()
+        ))),
+    }
+}
+
+fn make_latency(
+    subcommand: &str,
+    value: Option<String>,
+    options: &[String],
+) -> Result<Command, ProtocolError> {
+    let args = value
+        .into_iter()
+        .chain(options.iter().cloned())
+        .collect::<Vec<String>>();
+
+    match subcommand.to_lowercase().as_str() {
+        "latest" => Ok(Command::LatencyLatest),
+        "histogram" => Ok(Command::LatencyHistogram {
+            commands: args.into_iter().map(|c| c.to_lowercase()).collect(),
+        }),
+        "history" => Ok(Command::LatencyHistory {
+            event: args
+                .into_iter()
+                .next()
+                .ok_or_else(|| ProtocolError::InvalidArgument("latency history".to_string()))?,
+        }),
+        "reset" => Ok(Command::LatencyReset { events: args }),
+        _ => Err(ProtocolError::CommandNotRecognized(format!(
+            "latency {}",
+            subcommand.to_lowercase()
+        ))),
+    }
+}
+
+fn make_client(
+    subcommand: &str,
+    value: Option<String>,
+    options: &[String],
+) -> Result<Command, ProtocolError> {
+    let args = value
+        .into_iter()
+        .chain(options.iter().cloned())
+        .collect::<Vec<String>>();
+    let parse_id = |v: &str| {
+        v.parse::<u64>()
+            .map_err(|_| ProtocolError::InvalidArgument(v.to_string()))
+    };
+
+    match (subcommand.to_lowercase().as_str(), args.as_slice()) {
+        ("id", []) => Ok(Command::ClientId),
+        ("info", []) => Ok(Command::ClientInfo),
+        ("list", []) => Ok(Command::ClientList { ids: vec![] }),
+        ("list", [filter, ids @ ..]) if filter.eq_ignore_ascii_case("id") && !ids.is_empty() => {
+            Ok(Command::ClientList {
+                ids: ids
+                    .iter()
+                    .map(|id| parse_id(id))
+                    .collect::<Result<_, _>>()?,
+            })
+        }
+        ("setname", [name]) => Ok(Command::ClientSetName { name: name.clone() }),
+        ("getname", []) => Ok(Command::ClientGetName),
+        ("kill", [addr]) => Ok(Command::ClientKill {
+            id: None,
+            addr: Some(addr.clone()),
+            user: None,
+            skip_me: false,
+            legacy: true,
+        }),
+        ("kill", filters) if !filters.is_empty() && filters.len() % 2 == 0 => {
+            let mut cmd = (None, None, None, true);
+            for f in filters.chunks(2) {
+                match (f[0].to_lowercase().as_str(), f[1].as_str()) {
+                    ("id", id) => cmd.0 = Some(parse_id(id)?),
+                    ("addr", addr) => cmd.1 = Some(addr.to_string()),
+                    ("user", user) => cmd.2 = Some(user.to_string()),
+                    ("skipme", yes) if yes.eq_ignore_ascii_case("yes") => cmd.3 = true,
+                    ("skipme", no) if no.eq_ignore_ascii_case("no") => cmd.3 = false,
+                    (name, _) => return Err(ProtocolError::InvalidArgument(name.to_string())),
+                }
+            }
+            Ok(Command::ClientKill {
+                id: cmd.0,
+                addr: cmd.1,
+                user: cmd.2,
+                skip_me: cmd.3,
+                legacy: false,
+            })
+        }
+        ("pause", [timeout, mode @ ..]) if mode.len() <= 1 => Ok(Command::ClientPause {
+            timeout: std::time::Duration::from_millis(
+                timeout
+                    .parse()
+                    .map_err(|_| ProtocolError::InvalidArgument(timeout.to_string()))?,
+            ),
+            all: match mode.first().map(|m| m.to_lowercase()).as_deref() {
+                None | Some("all") => true,
+                Some("write") => false,
+                Some(m) => return Err(ProtocolError::InvalidArgument(m.to_string())),
+            },
+        }),
+        ("unpause", []) => Ok(Command::ClientUnpause),
+        (subcommand, _) => Err(ProtocolError::CommandNotRecognized(format!(
+            "client {subcommand}"
+        ))),
+    }
+}
+
+fn make_hello(
+    protover: &str,
+    value: Option<String>,
+    options: &[String],
+) -> Result<Command, ProtocolError> {
+    let mut cmd = (None, None, None);
+    if !protover.is_empty() {
+        cmd.0 = Some(
+            protover
+                .parse()
+                .map_err(|_| ProtocolError::InvalidArgument(protover.to_string()))?,
+        );
+    }
+
+    let args = value
+        .into_iter()
+        .chain(options.iter().cloned())
+        .collect::<Vec<String>>();
+    let mut args = args.iter();
+    while let Some(option) = args.next() {
+        let mut next = || {
+            args.next()
+                .cloned()
+                .ok_or_else(|| ProtocolError::InvalidArgument(option.clone()))
+        };
+        match option.to_lowercase().as_str() {
+            "auth" => cmd.1 = Some((next()?, next()?)),
+            "setname" => cmd.2 = Some(next()?),
+            _ => return Err(ProtocolError::InvalidArgument(option.clone())),
+        }
+    }
+
+    Ok(Command::Hello {
+        protover: cmd.0,
+        auth: cmd.1,
+        setname: cmd.2,
+    })
+}
+
+fn make_acl(
+    subcommand: &str,
+    value: Option<String>,
+    options: &[String],
+) -> Result<Command, ProtocolError> {
+    match (subcommand.to_lowercase().as_str(), value) {
+        ("setuser", Some(username)) => Ok(Command::AclSetUser {
+            username,
+            rules: options.to_vec(),
+        }),
+        ("getuser", Some(username)) if options.is_empty() => Ok(Command::AclGetUser { username }),
+        ("deluser", Some(username)) => Ok(Command::AclDelUser {
+            usernames: std::iter::once(username)
+                .chain(options.iter().cloned())
+                .collect(),
+        }),
+        ("list", None) => Ok(Command::AclList),
+        ("whoami", None) => Ok(Command::AclWhoAmI),
+        (subcommand @ ("setuser" | "getuser" | "deluser" | "list" | "whoami"), _) => {
+            Err(ProtocolError::InvalidArgument(format!("acl {subcommand}")))
+        }
+        (subcommand, _) => Err(ProtocolError::CommandNotRecognized(format!(
+            "acl {subcommand}"
+        ))),
+    }
+}
+
+#[derive(PartialEq, Debug)]
+pub enum CommandResponse {
+    String {
+        value: String,
+    },
+    BulkString {
+        value: String,
+    },
+
+    /// Bulk string holding any byte, like the values of the binary safe strings.
+    BulkBytes {
+        value: Vec<u8>,
+    },
+    Integer {
+        value: i64,
+    },
+    Array {
+        value: Vec<CommandResponse>,
+    },
+
+    /// Pairs of keys and values, RESP2 clients receive them as a flat array.
+    Map {
+        value: Vec<(CommandResponse, CommandResponse)>,
+    },
+    Error {
+        value: String,
+    },
+    Null,
+}
+
+impl CommandResponse {
+    /// Response for the clients speaking RESP2, which has no maps.
+    pub fn into_resp2(self) -> Self {
+        match self {
+            Self::Map { value } => Self::Array {
+                value: value
+                    .into_iter()
+                    .flat_map(|(k, v)| [k.into_resp2(), v.into_resp2()])
+                    .collect(),
+            },
+            Self::Array { value } => Self::Array {
+                value: value.into_iter().map(Self::into_resp2).collect(),
+            },
+            r => r,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_new_command_ok() {
+        let cmd = Command::new("GET", "key", None, &[]);
+        assert_eq!(
+            cmd.unwrap(),
+            Command::Get {
+                key: "key".to_string()
+            }
+        );
+    }
+
+    #[test]
+    fn test_new_info() {
+        let cmd = Command::new("INFO", "", None, &[]);
+        assert_eq!(cmd.unwrap(), Command::Info { sections: vec![] });
+
+        let cmd = Command::new("info", "Server", Some("clients".to_string()), &[]);
+        assert_eq!(
+            cmd.unwrap(),
+            Command::Info {
+                sections: vec!["server".to_string(), "clients".to_string()]
+            }
+        );
+    }
+
+    #[test]
+    fn test_new_slowlog() {
+        let cmd = Command::new("SLOWLOG", "GET", None, &[]);
+        assert_eq!(cmd.unwrap(), Command::SlowlogGet { count: Some(10) });
+
+        let cmd = Command::new("slowlog", "get", Some("-1".to_string()), &[]);
+        assert_eq!(cmd.unwrap(), Command::SlowlogGet { count: None });
+
+        let cmd = Command::new("slowlog", "get", Some("x".to_string()), &[]);
+        assert_eq!(cmd, Err(ProtocolError::InvalidArgument("x".to_string())));
+
+        let cmd = Command::new("slowlog", "len", None, &[]);
+        assert_eq!(cmd.unwrap(), Command::SlowlogLen);
+
+        let cmd = Command::new("slowlog", "reset", None, &[]);
+        assert_eq!(cmd.unwrap(), Command::SlowlogReset);
+    }
+
+    #[test]
+    fn test_new_latency() {
+        let cmd = Command::new("LATENCY", "LATEST", None, &[]);
+        assert_eq!(cmd.unwrap(), Command::LatencyLatest);
+
+        let cmd = Command::new(
+            "latency",
+            "histogram",
+            Some("GET".to_string()),
+            &["set".to_string()],
+        );
+        assert_eq!(
+            cmd.unwrap(),
+            Command::LatencyHistogram {
+                commands: vec!["get".to_string(), "set".to_string()]
+            }
+        );
+
+        let cmd = Command::new("latency", "history", None, &[]);
+        assert!(cmd.is_err());
+
+        let cmd = Command::new("latency", "reset", None, &[]);
+        assert_eq!(cmd.unwrap(), Command::LatencyReset { events: vec![] });
+    }
+
+    #[test]
+    fn test_new_monitor() {
+        let cmd = Command::new("MONITOR", "", None, &[]);
+        assert_eq!(cmd.unwrap(), Command::Monitor);
+        assert!(Command::Monitor.keys().is_empty());
+    }
+
+    #[test]
+    fn test_new_client() {
+        let cmd = Command::new("CLIENT", "ID", None, &[]);
+        assert_eq!(cmd.unwrap(), Command::ClientId);
+
+        let cmd = Command::new(
+            "client",
+            "list",
+            Some("ID".to_string()),
+            &["3".to_string(), "5".to_string()],
+        );
+        assert_eq!(cmd.unwrap(), Command::ClientList { ids: vec![3, 5] });
+
+        let cmd = Command::new("client", "kill", Some("127.0.0.1:5".to_string()), &[]);
+        assert_eq!(
+            cmd.unwrap(),
+            Command::ClientKill {
+                id: None,
+                addr: Some("127.0.0.1:5".to_string()),
+                user: None,
+                skip_me: false,
+                legacy: true,
+            }
+        );
+
+        let cmd = Command::new(
+            "client",
+            "kill",
+            Some("id".to_string()),
+            &[
+                "3".to_string(),
+                "user".to_string(),
+                "alice".to_string(),
+                "skipme".to_string(),
+                "no".to_string(),
+            ],
+        );
+        assert_eq!(
+            cmd.unwrap(),
+            Command::ClientKill {
+                id: Some(3),
+                addr: None,
+                user: Some("alice".to_string()),
+                skip_me: false,
+                legacy: false,
+            }
+        );
+
+        let cmd = Command::new(
+            "client",
+            "pause",
+            Some("100".to_string()),
+            &["WRITE".to_string()],
+        );
+        assert_eq!(
+            cmd.unwrap(),
+            Command::ClientPause {
+                timeout: std::time::Duration::from_millis(100),
+                all: false,
+            }
+        );
+
+        let cmd = Command::new("client", "pause", Some("x".to_string()), &[]);
+        assert_eq!(cmd, Err(ProtocolError::InvalidArgument("x".to_string())));
+
+        let cmd = Command::new("client", "setname", None, &[]);
+        assert!(cmd.is_err());
+    }
+
+    #[test]
+    fn test_new_auth() {
+        let cmd = Command::new("AUTH", "/secret", None, &[]);
+        assert_eq!(
+            cmd.unwrap(),
+            Command::Auth {
+                username: None,
+                password: "/secret".to_string()
+            }
+        );
+
+        let cmd = Command::new("auth", "admin", Some("secret".to_string()), &[]).unwrap();
+        assert_eq!(
+            cmd,
+            Command::Auth {
+                username: Some("admin".to_string()),
+                password: "secret".to_string()
+            }
+        );
+        assert!(cmd.has_credentials());
+
+        assert!(Command::new("auth", "", None, &[]).is_err());
+    }
+
+    #[test]
+    fn test_new_hello() {
+        let cmd = Command::new("HELLO", "", None, &[]);
+        assert_eq!(
+            cmd.unwrap(),
+            Command::Hello {
+                protover: None,
+                auth: None,
+                setname: None
+            }
+        );
+
+        let cmd = Command::new(
+            "hello",
+            "2",
+            Some("AUTH".to_string()),
+            &[
+                "default".to_string(),
+                "secret".to_string(),
+                "SETNAME".to_string(),
+                "app".to_string(),
+            ],
+        )
+        .unwrap();
+        assert_eq!(
+            cmd,
+            Command::Hello {
+                protover: Some(2),
+                auth: Some(("default".to_string(), "secret".to_string())),
+                setname: Some("app".to_string())
+            }
+        );
+        assert!(cmd.has_credentials());
+
+        let cmd = Command::new("hello", "2", Some("AUTH".to_string()), &[]);
+        assert_eq!(cmd, Err(ProtocolError::InvalidArgument("AUTH".to_string())));
+    }
+
+    #[test]
+    fn test_new_acl() {
+        let cmd = Command::new(
+            "ACL",
+            "SETUSER",
+            Some("alice".to_string()),
+            &["on".to_string(), ">secret".to_string()],
+        )
+        .unwrap();
+        assert_eq!(
+            cmd,
+            Command::AclSetUser {
+                username: "alice".to_string(),
+                rules: vec!["on".to_string(), ">secret".to_string()]
+            }
+        );
+        assert!(cmd.has_credentials());
+
+        let cmd = Command::new("acl", "deluser", Some("a".to_string()), &["b".to_string()]);
+        assert_eq!(
+            cmd.unwrap(),
+            Command::AclDelUser {
+                usernames: vec!["a".to_string(), "b".to_string()]
+            }
+        );
+
+        let cmd = Command::new("acl", "whoami", None, &[]);
+        assert_eq!(cmd.unwrap(), Command::AclWhoAmI);
+
+        let cmd = Command::new("acl", "getuser", None, &[]);
+        assert_eq!(
+            cmd,
+            Err(ProtocolError::InvalidArgument("acl getuser".to_string()))
+        );
+
+        let cmd = Command::new("acl", "save", None, &[]);
+        assert_eq!(
+            cmd,
+            Err(ProtocolError::CommandNotRecognized("acl save".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_new_hash() {
+        let args = |v: &[&str]| v.iter().map(ToString::to_string).collect::<Vec<String>>();
+
+        let cmd = Command::new(
+            "HSET",
+            "key",
+            Some("f1".to_string()),
+            &args(&["v1", "f2", "v2"]),
+        );
+        assert_eq!(
+            cmd,
+            Ok(Command::HSet {
+                key: "key".to_string(),
+                fields: vec![
+                    ("f1".to_string(), "v1".to_string()),
+                    ("f2".to_string(), "v2".to_string())
+                ]
+            })
+        );
+        let cmd = Command::new("hset", "key", Some("f1".to_string()), &[]);
+        assert_eq!(cmd, Err(ProtocolError::InvalidArgument("hset".to_string())));
+
+        let cmd = Command::new("hincrby", "key", Some("f".to_string()), &args(&["-2"]));
+        assert_eq!(
+            cmd,
+            Ok(Command::HIncrBy {
+                key: "key".to_string(),
+                field: "f".to_string(),
+                increment: -2
+            })
+        );
+
+        let cmd = Command::new(
+            "hscan",
+            "key",
+            Some("0".to_string()),
+            &args(&["MATCH", "a*", "COUNT", "5"]),
+        );
+        assert_eq!(
+            cmd,
+            Ok(Command::HScan {
+                key: "key".to_string(),
+                cursor: 0,
+                pattern: Some("a*".to_string()),
+                count: 5
+            })
+        );
+        let cmd = Command::new("hscan", "key", Some("0".to_string()), &args(&["COUNT"]));
+        assert_eq!(
+            cmd,
+            Err(ProtocolError::InvalidArgument("COUNT".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_new_set() {
+        let args = |v: &[&str]| v.iter().map(ToString::to_string).collect::<Vec<String>>();
+
+        let cmd = Command::new("sinterstore", "dst", Some("a".to_string()), &args(&["b"]));
+        assert_eq!(
+            cmd,
+            Ok(Command::SInterStore {
+                destination: "dst".to_string(),
+                keys: args(&["a", "b"])
+            })
+        );
+        assert_eq!(cmd.unwrap().keys(), ["dst", "a", "b"]);
+        let cmd = Command::new("sunionstore", "dst", None, &[]);
+        assert_eq!(
+            cmd,
+            Err(ProtocolError::InvalidArgument("sunionstore".to_string()))
+        );
+
+        let cmd = Command::new("srandmember", "key", Some("-3".to_string()), &[]);
+        assert_eq!(
+            cmd,
+            Ok(Command::SRandMember {
+                key: "key".to_string(),
+                count: Some(-3)
+            })
+        );
+        let cmd = Command::new("spop", "key", Some("-3".to_string()), &[]);
+        assert_eq!(cmd, Err(ProtocolError::InvalidArgument("-3".to_string())));
+    }
+
+    #[test]
+    fn test_new_bitmap() {
+        let args = |v: &[&str]| v.iter().map(ToString::to_string).collect::<Vec<String>>();
+
+        let cmd = Command::new(
+            "bitpos",
+            "key",
+            Some("0".to_string()),
+            &args(&["2", "-1", "BIT"]),
+        );
+        assert_eq!(
+            cmd,
+            Ok(Command::BitPos {
+                key: "key".to_string(),
+                bit: false,
+                start: Some(2),
+                end: Some(-1),
+                unit: Unit::Bit,
+            })
+        );
+        let cmd = Command::new(
+            "setbit",
+            "key",
+            Some("4294967296".to_string()),
+            &args(&["1"]),
+        );
+        assert_eq!(
+            cmd,
+            Err(ProtocolError::InvalidArgument("4294967296".to_string()))
+        );
+        let cmd = Command::new("bitop", "not", Some("dest".to_string()), &args(&["a", "b"]));
+        assert_eq!(cmd, Err(ProtocolError::InvalidArgument("not".to_string())));
+
+        let cmd = Command::new(
+            "bitfield",
+            "key",
+            Some("SET".to_string()),
+            &args(&[
+                "i5", "#2", "-3", "OVERFLOW", "FAIL", "INCRBY", "u4", "0", "1",
+            ]),
+        );
+        let (i5, u4) = (Field::parse("i5").unwrap(), Field::parse("u4").unwrap());
+        assert_eq!(
+            cmd,
+            Ok(Command::BitField {
+                key: "key".to_string(),
+                ops: vec![
+                    FieldOp::Set(i5, 10, -3),
+                    FieldOp::Overflow(Overflow::Fail),
+                    FieldOp::IncrBy(u4, 0, 1),
+                ],
+            })
+        );
+        let cmd = Command::new(
+            "bitfield",
+            "key",
+            Some("GET".to_string()),
+            &args(&["u64", "0"]),
+        );
+        assert_eq!(cmd, Err(ProtocolError::InvalidArgument("u64".to_string())));
+    }
+
+    #[test]
+    fn test_new_geo() {
+        let args = |v: &[&str]| v.iter().map(ToString::to_string).collect::<Vec<String>>();
+
+        let cmd = Command::new(
+            "geosearch",
+            "key",
+            Some("FROMLONLAT".to_string()),
+            &args(&[
+                "15", "37", "BYBOX", "2", "1", "km", "DESC", "COUNT", "3", "ANY",
+            ]),
+        );
+        assert_eq!(
+            cmd,
+            Ok(Command::GeoSearch {
+                key: "key".to_string(),
+                from: GeoFrom::Position(15.0, 37.0),
+                shape: geo::Shape::Box {
+                    width: 2000.0,
+                    height: 1000.0
+                },
+                unit: geo::Unit::Kilometers,
+                options: GeoSearchOptions {
+                    desc: true,
+                    count: Some((3, true)),
+                    ..GeoSearchOptions::default()
+                },
+            })
+        );
+        let cmd = Command::new(
+            "geosearch",
+            "key",
+            Some("FROMMEMBER".to_string()),
+            &args(&["m", "WITHDIST"]),
+        );
+        assert_eq!(
+            cmd,
+            Err(ProtocolError::InvalidArgument("geosearch".to_string()))
+        );
+        let cmd = Command::new(
+            "geoadd",
+            "key",
+            Some("NX".to_string()),
+            &args(&["13.36", "38.11"]),
+        );
+        assert_eq!(
+            cmd,
+            Err(ProtocolError::InvalidArgument("geoadd".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_new_json() {
+        let args = |v: &[&str]| v.iter().map(ToString::to_string).collect::<Vec<String>>();
+
+        let cmd = Command::new(
+            "JSON.SET",
+            "doc",
+            Some("$.user".to_string()),
+            &args(&[r#"{"name": "ada"}"#, "nx"]),
+        );
+        assert_eq!(
+            cmd,
+            Ok(Command::JsonSet {
+                key: "doc".to_string(),
+                path: json::Path::parse("$.user").unwrap(),
+                value: serde_json::json!({"name": "ada"}),
+                exists: Some(false),
+            })
+        );
+        let cmd = Command::new("json.set", "doc", Some("$".to_string()), &args(&["{"]));
+        assert_eq!(cmd, Err(ProtocolError::InvalidArgument("{".to_string())));
+        let cmd = Command::new("json.get", "doc", Some("$.".to_string()), &[]);
+        assert_eq!(cmd, Err(ProtocolError::InvalidArgument("$.".to_string())));
+        let cmd = Command::new(
+            "json.numincrby",
+            "doc",
+            Some("$.n".to_string()),
+            &args(&["\"1\""]),
+        );
+        assert_eq!(
+            cmd,
+            Err(ProtocolError::InvalidArgument("\"1\"".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_new_zset() {
+        let args = |v: &[&str]| v.iter().map(ToString::to_string).collect::<Vec<String>>();
+
+        let cmd = Command::new(
+            "zadd",
+            "key",
+            Some("XX".to_string()),
+            &args(&["ch", "1", "a"]),
+        );
+        assert_eq!(
+            cmd,
+            Ok(Command::ZAdd {
+                key: "key".to_string(),
+                members: vec![(1.0, "a".to_string())],
+                flags: ZAddFlags {
+                    exists: Some(true),
+                    ch: true,
+                    ..ZAddFlags::default()
+                }
+            })
+        );
+        let cmd = Command::new(
+            "zadd",
+            "key",
+            Some("nx".to_string()),
+            &args(&["gt", "1", "a"]),
+        );
+        assert_eq!(cmd, Err(ProtocolError::InvalidArgument("zadd".to_string())));
+        let cmd = Command::new("zadd", "key", Some("nan".to_string()), &args(&["a"]));
+        assert_eq!(cmd, Err(ProtocolError::InvalidArgument("nan".to_string())));
+
+        let cmd = Command::new(
+            "zrange",
+            "key",
+            Some("+inf".to_string()),
+            &args(&["(1", "BYSCORE", "REV", "LIMIT", "0", "-1"]),
+        );
+        assert_eq!(
+            cmd,
+            Ok(Command::ZRange {
+                key: "key".to_string(),
+                by: ZRangeBy::Score(
+                    std::ops::Bound::Excluded(1.0),
+                    std::ops::Bound::Included(f64::INFINITY)
+                ),
+                rev: true,
+                limit: Some((0, usize::MAX)),
+                with_scores: false
+            })
+        );
+        let cmd = Command::new(
+            "zrange",
+            "key",
+            Some("[a".to_string()),
+            &args(&["+", "BYLEX"]),
+        );
+        assert_eq!(
+            cmd,
+            Ok(Command::ZRange {
+                key: "key".to_string(),
+                by: ZRangeBy::Lex(
+                    std::ops::Bound::Included("a".to_string()),
+                    std::ops::Bound::Unbounded
+                ),
+                rev: false,
+                limit: None,
+                with_scores: false
+            })
+        );
+        let cmd = Command::new(
+            "zrange",
+            "key",
+            Some("0".to_string()),
+            &args(&["-1", "LIMIT", "0", "1"]),
+        );
+        assert_eq!(
+            cmd,
+            Err(ProtocolError::InvalidArgument("zrange".to_string()))
+        );
+
+        let cmd = Command::new(
+            "zunionstore",
+            "dst",
+            Some("2".to_string()),
+            &args(&["a", "b", "WEIGHTS", "2", "3", "AGGREGATE", "max"]),
+        );
+        assert_eq!(
+            cmd,
+            Ok(Command::ZUnionStore {
+                destination: "dst".to_string(),
+                keys: args(&["a", "b"]),
+                weights: vec![2.0, 3.0],
+                aggregate: Aggregate::Max
+            })
+        );
+        let cmd = Command::new(
+            "zdiffstore",
+            "dst",
+            Some("3".to_string()),
+            &args(&["a", "b"]),
+        );
+        assert_eq!(cmd, Err(ProtocolError::InvalidArgument("3".to_string())));
+    }
+
+    #[test]
+    fn test_keys() {
+        let cmd = Command::new("set", "key", Some("value".to_string()), &[]).unwrap();
+        assert_eq!(cmd.keys(), vec!["key"]);
+        assert!(Command::Ping.keys().is_empty());
+
+        let cmd = Command::new("TYPE", "key", None, &[]).unwrap();
+        assert_eq!(
+            cmd,
+            Command::Type {
+                key: "key".to_string()
+            }
+        );
+        assert_eq!(cmd.keys(), vec!["key"]);
+    }
+
+    #[test]
+    fn test_rename() {
+        rename(&HashMap::from([
+            ("flushdb".to_string(), "FLUSHDB-secret".to_string()),
+            ("config".to_string(), String::new()),
+        ]));
+
+        let cmd = Command::new("FLUSHDB", "", None, &[]);
+        assert_eq!(
+            cmd,
+            Err(ProtocolError::CommandNotRecognized("flushdb".to_string()))
+        );
+        let cmd = Command::new("flushdb-secret", "", None, &[]);
+        assert_eq!(cmd.unwrap(), Command::FlushDb);
+        assert!(Command::new("config", "", None, &[]).is_err());
+        assert!(Command::new("ping", "", None, &[]).is_ok());
+
+        rename(&HashMap::new());
+        assert_eq!(
+            Command::new("flushdb", "", None, &[]).unwrap(),
+            Command::FlushDb
         );
     }
 
@@ -14,6 +14,14 @@ pub enum ProtocolError {
     /// Error for when a command is not recognized, but decoding was successful.
     CommandNotRecognized(String),
 
+    #[error("invalid argument {0}")]
+    /// Error for when the command is recognized but one of its arguments is not valid.
+    InvalidArgument(String),
+
+    #[error("Protocol error: {0} exceeded")]
+    /// Error for when the request is bigger than one of the limits of the config.
+    LimitExceeded(&'static str),
+
     #[error("curl protocol decoding error")]
     /// Generic error for curl protocol decoding, maybe it is just another protocol.
     CurlProtocolDecodingError,
@@ -21,4 +29,8 @@ pub enum ProtocolError {
     #[error("resp protocol decoding error")]
     /// Generic error for RESP protocol decoding, maybe it is just another protocol.
     RespProtocolDecodingError,
+
+    #[error("ambiguous request, it is both a HTTP and a RESP request")]
+    /// Error for when the request can be decoded by both protocols.
+    AmbiguousRequest,
 }
@@ -6,80 +6,101 @@ use super::{
 /// RESP is actually a serialization protocol that supports the following data types: Simple Strings, Errors, Integers, Bulk Strings, and Arrays.
 pub struct Resp {}
 
-struct RespDecoder<I: Iterator<Item = char>> {
-    chars: I,
+struct RespDecoder<'a> {
+    raw: &'a [u8],
+    pos: usize,
 }
 
-impl<I: Iterator<Item = char>> RespDecoder<I> {
-    const fn new(chars: I) -> Self {
-        Self { chars }
+impl<'a> RespDecoder<'a> {
+    const fn new(raw: &'a [u8]) -> Self {
+        Self { raw, pos: 0 }
     }
 
-    fn take_next_string(&mut self) -> String {
-        let s = self
-            .chars
-            .by_ref()
-            .take_while(|char| *char != '\r')
-            .collect();
+    /// Bytes up to the next CRLF, which is skipped.
+    fn take_line(&mut self) -> Result<&'a [u8], &'static str> {
+        let rest = &self.raw[self.pos..];
+        let end = rest
+            .windows(2)
+            .position(|w| w == b"\r\n")
+            .ok_or("Error while parsing RESP")?;
+        self.pos += end + 2;
 
-        self.chars.next(); // removing \n
+        Ok(&rest[..end])
+    }
+
+    fn take_next_string(&mut self) -> Result<String, &'static str> {
+        self.take_line()
+            .map(|line| String::from_utf8_lossy(line).into_owned())
+    }
 
-        s
+    /// Exactly len bytes followed by CRLF, they are taken as they are so they can hold a CRLF too.
+    fn take_bytes(&mut self, len: usize) -> Result<Vec<u8>, &'static str> {
+        let end = self.pos.saturating_add(len);
+        if self.raw.get(end..end.saturating_add(2)) != Some(b"\r\n") {
+            return Err("Error while parsing bulk string");
+        }
+        let bytes = self.raw[self.pos..end].to_vec();
+        self.pos = end + 2;
+
+        Ok(bytes)
     }
 
     fn decode_integer(&mut self) -> Result<i64, &'static str> {
-        self.take_next_string()
+        self.take_next_string()?
             .parse()
             .map_err(|_| "Error while parsing integer")
     }
 
     fn next_chunk(&mut self) -> Result<RespType, &'static str> {
-        match self.chars.next() {
-            Some(c) => match c {
-                '+' => Ok(RespType::SimpleString {
-                    value: self.take_next_string(),
-                }),
-                '-' => Ok(RespType::Error {
-                    value: self.take_next_string(),
-                }),
-                ':' => self
-                    .decode_integer()
-                    .map(|i| RespType::Integer { value: i }),
-                '$' => {
-                    let len = self.decode_integer()?;
-
-                    if len == -1 {
-                        return Ok(RespType::None);
-                    }
-
-                    Ok(RespType::BulkString {
-                        value: self.take_next_string(),
-                    })
+        let Some(c) = self.raw.get(self.pos) else {
+            return Err("Error while parsing RESP");
+        };
+        self.pos += 1;
+
+        match c {
+            b'+' => Ok(RespType::SimpleString {
+                value: self.take_next_string()?,
+            }),
+            b'-' => Ok(RespType::Error {
+                value: self.take_next_string()?,
+            }),
+            b':' => self
+                .decode_integer()
+                .map(|i| RespType::Integer { value: i }),
+            b'$' => {
+                let len = self.decode_integer()?;
+
+                if len == -1 {
+                    return Ok(RespType::None);
                 }
-                '*' => {
-                    let len = self.decode_integer()?;
 
-                    if len == -1 {
-                        return Ok(RespType::None);
-                    }
+                let len = usize::try_from(len).map_err(|_| "Error while parsing bulk string")?;
+                Ok(RespType::BulkString {
+                    value: self.take_bytes(len)?,
+                })
+            }
+            b'*' => {
+                let len = self.decode_integer()?;
 
-                    let mut items = Vec::with_capacity(
-                        usize::try_from(len).map_err(|_| "Error while parsing array")?,
-                    );
+                if len == -1 {
+                    return Ok(RespType::None);
+                }
 
-                    for _ in 0..len {
-                        if let Ok(rt) = self.next_chunk() {
-                            items.push(rt);
-                        } else {
-                            return Err("Error while parsing array");
-                        }
-                    }
+                let mut items = Vec::with_capacity(
+                    usize::try_from(len).map_err(|_| "Error while parsing array")?,
+                );
 
-                    Ok(RespType::Array { value: items })
+                for _ in 0..len {
+                    if let Ok(rt) = self.next_chunk() {
+                        items.push(rt);
+                    } else {
+                        return Err("Error while parsing array");
+                    }
                 }
-                _ => Err("Invalid first character in RESP"),
-            },
-            None => Err("Error while parsing RESP"),
+
+                Ok(RespType::Array { value: items })
+            }
+            _ => Err("Invalid first character in RESP"),
         }
     }
 }
@@ -89,7 +110,7 @@ enum RespType {
     SimpleString { value: String },
     Error { value: String },
     Integer { value: i64 },
-    BulkString { value: String },
+    BulkString { value: Vec<u8> },
     Array { value: Vec<RespType> },
     None,
 }
@@ -98,8 +119,7 @@ impl TryFrom<String> for RespType {
     type Error = &'static str;
 
     fn try_from(value: String) -> Result<Self, <Self as TryFrom<String>>::Error> {
-        let it = value.chars();
-        let mut rd = RespDecoder::new(it);
+        let mut rd = RespDecoder::new(value.as_bytes());
 
         rd.next_chunk()
     }
@@ -109,77 +129,149 @@ impl TryFrom<&str> for RespType {
     type Error = &'static str;
 
     fn try_from(value: &str) -> Result<Self, <Self as TryFrom<&str>>::Error> {
-        let it = value.chars();
-        let mut rd = RespDecoder::new(it);
+        let mut rd = RespDecoder::new(value.as_bytes());
 
         rd.next_chunk()
     }
 }
 
-impl Protocol for Resp {
-    fn decode(raw: &[u8]) -> Result<Command, ProtocolError> {
-        let s = String::from_utf8(raw.to_vec())
+impl Resp {
+    /// Decode the raw request as the list of arguments sent by the client, the first one is the command. The
+    /// arguments are binary safe, they are kept as the bytes sent.
+    pub fn decode_args(raw: &[u8]) -> Result<Vec<Vec<u8>>, ProtocolError> {
+        // NOTE: like redis, a request which is not RESP is an inline command made of a single line.
+        if !matches!(raw.first(), Some(b'*' | b'+' | b'-' | b':' | b'$')) {
+            return raw
+                .strip_suffix(b"\r\n")
+                .filter(|line| !line.contains(&b'\n'))
+                .map(|line| {
+                    line.split(u8::is_ascii_whitespace)
+                        .filter(|arg| !arg.is_empty())
+                        .map(<[u8]>::to_vec)
+                        .collect()
+                })
+                .filter(|args: &Vec<Vec<u8>>| !args.is_empty())
+                .ok_or(ProtocolError::RespProtocolDecodingError);
+        }
+
+        let rt = RespDecoder::new(raw)
+            .next_chunk()
             .map_err(|_| ProtocolError::RespProtocolDecodingError)?;
+        let RespType::Array { value } = rt else {
+            return Err(ProtocolError::RespProtocolDecodingError);
+        };
+
+        value
+            .into_iter()
+            .map(|v| match v {
+                RespType::SimpleString { value } => Ok(value.into_bytes()),
+                RespType::BulkString { value } => Ok(value),
+                _ => Err(ProtocolError::RespProtocolDecodingError),
+            })
+            .collect()
+    }
 
-        if s.contains("PING") {
-            return Ok(Command::Ping);
+    /// Length of the array of bulk strings at the start of the buffer, None if it is not complete yet. Lengths
+    /// are checked against the limits before the request is read.
+    pub fn frame_len(
+        raw: &[u8],
+        max_bulk_len: usize,
+        max_array_len: usize,
+    ) -> Result<Option<usize>, ProtocolError> {
+        let Some((n, mut pos)) = header(raw, 0, b'*')? else {
+            return Ok(None);
+        };
+        if n > max_array_len {
+            return Err(ProtocolError::LimitExceeded("max_array_len"));
         }
 
-        let rt = RespType::try_from(s).map_err(|_| ProtocolError::RespProtocolDecodingError)?;
-        match rt {
-            RespType::SimpleString { .. } => todo!(),
-            RespType::Error { .. } => todo!(),
-            RespType::Integer { .. } => todo!(),
-            RespType::BulkString { .. } => todo!(),
-            RespType::Array { value } => {
-                let mut it = value.into_iter();
-                let operation = it.next().map_or_else(
-                    || panic!(),
-                    |s| match s {
-                        RespType::SimpleString { value } | RespType::BulkString { value } => value,
-                        _ => panic!(),
-                    },
-                );
-
-                let key = if let Some(s) = it.next() {
-                    match s {
-                        RespType::SimpleString { value } | RespType::BulkString { value } => value,
-                        _ => panic!(),
-                    }
-                } else {
-                    // No key means, single command
-                    return Command::new(&operation, "", None, &[]);
-                };
-
-                let v = it.next().map(|v| match v {
-                    RespType::SimpleString { value } | RespType::BulkString { value } => value,
-                    _ => panic!("array can be only of strings"),
-                });
-
-                let options = it
-                    .map(|v| {
-                        if let RespType::BulkString { value } = v {
-                            value
-                        } else {
-                            panic!()
-                        }
-                    })
-                    .collect::<Vec<String>>();
+        for _ in 0..n {
+            let Some((len, start)) = header(raw, pos, b'$')? else {
+                return Ok(None);
+            };
+            if len > max_bulk_len {
+                return Err(ProtocolError::LimitExceeded("proto_max_bulk_len"));
+            }
 
-                Command::new(&operation, &key, v, &options)
+            pos = start + len + 2;
+            if pos > raw.len() {
+                return Ok(None);
             }
-            RespType::None => todo!(),
         }
+
+        Ok(Some(pos))
+    }
+
+    /// Build the command from the arguments decoded by [`Resp::decode_args`].
+    pub fn command_from_args(args: &[Vec<u8>]) -> Result<Command, ProtocolError> {
+        let text = args
+            .iter()
+            .map(|a| String::from_utf8_lossy(a).into_owned())
+            .collect::<Vec<String>>();
+        let cmd = match text.as_slice() {
+            [] => Err(ProtocolError::RespProtocolDecodingError),
+            // No key means, single command
+            [operation] => Command::new(operation, "", None, &[]),
+            [operation, key] => Command::new(operation, key, None, &[]),
+            [operation, key, v, options @ ..] => {
+                Command::new(operation, key, Some(v.clone()), options)
+            }
+        }?;
+
+        // NOTE: the other commands take text, the value of SET is binary safe so it is stored as it was sent.
+        Ok(match (cmd, args.get(2)) {
+            (Command::Set { key, ttl, .. }, Some(value)) => Command::Set {
+                key,
+                value: value.clone(),
+                ttl,
+            },
+            (cmd, _) => cmd,
+        })
+    }
+}
+
+/// Length in a line like `*3\r\n` at the given position, returns it with the position of the next line.
+fn header(raw: &[u8], pos: usize, prefix: u8) -> Result<Option<(usize, usize)>, ProtocolError> {
+    let Some(line) = raw.get(pos..) else {
+        return Ok(None);
+    };
+    match line.first() {
+        None => return Ok(None),
+        Some(c) if *c != prefix => return Err(ProtocolError::RespProtocolDecodingError),
+        Some(_) => (),
+    }
+    let Some(end) = line.windows(2).position(|w| w == b"\r\n") else {
+        return Ok(None);
+    };
+
+    let len = std::str::from_utf8(&line[1..end])
+        .ok()
+        .and_then(|n| n.parse().ok())
+        .ok_or(ProtocolError::RespProtocolDecodingError)?;
+    Ok(Some((len, pos + end + 2)))
+}
+
+impl Protocol for Resp {
+    fn decode(raw: &[u8]) -> Result<Command, ProtocolError> {
+        Self::command_from_args(&Self::decode_args(raw)?)
     }
 
     fn encode(response: CommandResponse) -> Vec<u8> {
         match response {
-            CommandResponse::String { value } => {
-                let s = format!("+{value}\r\n");
-                tracing::debug!(response = s, "resp");
-                s.as_bytes().to_vec()
+            CommandResponse::String { value } => format!("+{value}\r\n").into_bytes(),
+            CommandResponse::BulkString { value } => {
+                format!("${}\r\n{value}\r\n", value.len()).into_bytes()
+            }
+            CommandResponse::BulkBytes { value } => {
+                let mut s = format!("${}\r\n", value.len()).into_bytes();
+                s.extend_from_slice(&value);
+                s.extend_from_slice(b"\r\n");
+
+                s
             }
-            CommandResponse::Integer { value } => format!("*{value}\r\n").as_bytes().to_vec(),
+            CommandResponse::Integer { value } => format!(":{value}\r\n").into_bytes(),
+            CommandResponse::Error { value } => format!("-{value}\r\n").into_bytes(),
+            CommandResponse::Null => b"$-1\r\n".to_vec(),
             CommandResponse::Array { value } => {
                 let mut s = format!("*{}\r\n", value.len()).as_bytes().to_vec();
 
@@ -187,6 +279,16 @@ impl Protocol for Resp {
                     s.append(&mut Self::encode(el));
                 }
 
+                s
+            }
+            CommandResponse::Map { value } => {
+                let mut s = format!("%{}\r\n", value.len()).into_bytes();
+
+                for (k, v) in value {
+                    s.append(&mut Self::encode(k));
+                    s.append(&mut Self::encode(v));
+                }
+
                 s
             }
         }
@@ -194,6 +296,11 @@ impl Protocol for Resp {
 }
 
 #[cfg(test)]
+#[allow(
+    clippy::needless_raw_string_hashes,
+    clippy::semicolon_if_nothing_returned,
+    clippy::manual_string_new
+)]
 mod tests {
     use super::*;
 
@@ -247,7 +354,7 @@ mod tests {
         let s = ":-574\r\n".to_string();
         let rt = RespType::try_from(s);
 
-        assert_eq!(rt, Ok(RespType::Integer { value: -574 }))
+        assert_eq!(rt, Ok(RespType::Integer { value: -574 }));
     }
 
     #[test]
@@ -260,7 +367,7 @@ mod tests {
             assert_eq!(
                 rt,
                 Ok(RespType::BulkString {
-                    value: "hello".to_string()
+                    value: b"hello".to_vec()
                 })
             )
         }
@@ -272,7 +379,7 @@ mod tests {
             assert_eq!(
                 rt,
                 Ok(RespType::BulkString {
-                    value: "".to_string()
+                    value: b"".to_vec()
                 })
             )
         }
@@ -290,10 +397,10 @@ mod tests {
                 Ok(RespType::Array {
                     value: vec![
                         RespType::BulkString {
-                            value: "hello".to_string()
+                            value: b"hello".to_vec()
                         },
                         RespType::BulkString {
-                            value: "world".to_string()
+                            value: b"world".to_vec()
                         }
                     ]
                 })
@@ -329,7 +436,7 @@ mod tests {
                             value: "OK".to_string()
                         },
                         RespType::BulkString {
-                            value: "hello".to_string()
+                            value: b"hello".to_vec()
                         }
                     ]
                 })
@@ -366,6 +473,77 @@ mod tests {
         }
     }
 
+    #[test]
+    fn encode_bulk_string() {
+        let r = Resp::encode(CommandResponse::BulkString {
+            value: "a\r\nb".to_string(),
+        });
+        assert_eq!(r, b"$4\r\na\r\nb\r\n".to_vec());
+    }
+
+    #[test]
+    fn encode_integer() {
+        let r = Resp::encode(CommandResponse::Integer { value: -3 });
+        assert_eq!(r, b":-3\r\n".to_vec());
+    }
+
+    #[test]
+    fn encode_error_and_null() {
+        let r = Resp::encode(CommandResponse::Error {
+            value: "ERR No such client".to_string(),
+        });
+        assert_eq!(r, b"-ERR No such client\r\n".to_vec());
+        assert_eq!(Resp::encode(CommandResponse::Null), b"$-1\r\n".to_vec());
+    }
+
+    #[test]
+    fn encode_map() {
+        let map = CommandResponse::Map {
+            value: vec![(
+                CommandResponse::BulkString {
+                    value: "f".to_string(),
+                },
+                CommandResponse::Integer { value: 1 },
+            )],
+        };
+        assert_eq!(Resp::encode(map), b"%1\r\n$1\r\nf\r\n:1\r\n".to_vec());
+
+        let map = CommandResponse::Map {
+            value: vec![(
+                CommandResponse::BulkString {
+                    value: "f".to_string(),
+                },
+                CommandResponse::Integer { value: 1 },
+            )],
+        };
+        assert_eq!(
+            Resp::encode(map.into_resp2()),
+            b"*2\r\n$1\r\nf\r\n:1\r\n".to_vec()
+        );
+    }
+
+    #[test]
+    fn decode_args() {
+        let s = "*3\r\n$7\r\nslowlog\r\n$3\r\nget\r\n$1\r\n5\r\n";
+        let args = Resp::decode_args(s.as_bytes()).unwrap();
+        assert_eq!(
+            args,
+            vec![b"slowlog".to_vec(), b"get".to_vec(), b"5".to_vec()]
+        );
+
+        let s = b"*3\r\n$3\r\nset\r\n$1\r\nk\r\n$3\r\n\xff\r\n\r\n";
+        let args = Resp::decode_args(s).unwrap();
+        assert_eq!(args[2], b"\xff\r\n");
+        assert_eq!(
+            Resp::decode_args(&s[..s.len() - 1]),
+            Err(ProtocolError::RespProtocolDecodingError)
+        );
+
+        let s = ":5\r\n";
+        let args = Resp::decode_args(s.as_bytes());
+        assert_eq!(args, Err(ProtocolError::RespProtocolDecodingError));
+    }
+
     #[test]
     fn set_request() {
         let s = "*3\r\n$3\r\nset\r\n$4\r\nciao\r\n$4\r\ncome\r\n";
@@ -374,7 +552,7 @@ mod tests {
             cmd,
             Command::Set {
                 key: "ciao".to_string(),
-                value: "come".to_string(),
+                value: b"come".to_vec(),
                 ttl: None,
             }
         );
@@ -388,7 +566,7 @@ mod tests {
             cmd,
             Command::Set {
                 key: "ciao".to_string(),
-                value: "come".to_string(),
+                value: b"come".to_vec(),
                 ttl: Some(std::time::Duration::from_secs(10)),
             }
         );
@@ -401,7 +579,7 @@ mod tests {
         assert!(cmd.is_err());
         assert_eq!(cmd, Err(ProtocolError::RespProtocolDecodingError));
 
-        let payload = "*3\r\n$3\r\nNOTACOMMAND\r\n$1\r\nx\r\n$2\r\n11\r\n";
+        let payload = "*3\r\n$11\r\nNOTACOMMAND\r\n$1\r\nx\r\n$2\r\n11\r\n";
         let cmd = Resp::decode(payload.as_bytes());
         assert!(cmd.is_err());
         assert_eq!(
@@ -409,4 +587,24 @@ mod tests {
             Err(ProtocolError::CommandNotRecognized("notacommand".into()))
         );
     }
+
+    #[test]
+    fn frame_len() {
+        let raw = b"*2\r\n$3\r\nGET\r\n$3\r\nkey\r\n";
+        assert_eq!(Resp::frame_len(raw, 512, 16), Ok(Some(raw.len())));
+        assert_eq!(Resp::frame_len(&raw[..raw.len() - 1], 512, 16), Ok(None));
+        assert_eq!(Resp::frame_len(&raw[..3], 512, 16), Ok(None));
+        assert_eq!(
+            Resp::frame_len(raw, 2, 16),
+            Err(ProtocolError::LimitExceeded("proto_max_bulk_len"))
+        );
+        assert_eq!(
+            Resp::frame_len(raw, 512, 1),
+            Err(ProtocolError::LimitExceeded("max_array_len"))
+        );
+        assert_eq!(
+            Resp::frame_len(b"*1\r\n:3\r\n", 512, 16),
+            Err(ProtocolError::RespProtocolDecodingError)
+        );
+    }
 }
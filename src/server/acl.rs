@@ -0,0 +1,703 @@
+use crate::engine::glob;
+use crate::protocol::commands::Command;
+use std::collections::BTreeMap;
+use std::fmt::Write;
+
+/// Categories which can be used in the rules as `+@<category>`, `all` matches every command.
+const CATEGORIES: &[&str] = &[
+    "all",
+    "keyspace",
+    "read",
+    "write",
+    "string",
+    "hash",
+    "list",
+    "set",
+    "sortedset",
+    "stream",
+    "bitmap",
+    "hyperloglog",
+    "geo",
+    "json",
+    "blocking",
+    "fast",
+    "slow",
+    "admin",
+    "dangerous",
+    "connection",
+];
+
+/// Categories of every command, like the command table of redis.
+const COMMANDS: &[(&str, &[&str])] = &[
+    ("get", &["read", "string", "fast"]),
+    ("set", &["write", "string", "slow"]),
+    ("del", &["keyspace", "write", "slow"]),
+    ("exists", &["keyspace", "read", "fast"]),
+    ("type", &["keyspace", "read", "fast"]),
+    ("hset", &["write", "hash", "fast"]),
+    ("hget", &["read", "hash", "fast"]),
+    ("hmget", &["read", "hash", "fast"]),
+    ("hdel", &["write", "hash", "fast"]),
+    ("hgetall", &["read", "hash", "slow"]),
+    ("hincrby", &["write", "hash", "fast"]),
+    ("hexists", &["read", "hash", "fast"]),
+    ("hlen", &["read", "hash", "fast"]),
+    ("hscan", &["read", "hash", "slow"]),
+    ("lpush", &["write", "list", "fast"]),
+    ("rpush", &["write", "list", "fast"]),
+    ("lpop", &["write", "list", "fast"]),
+    ("rpop", &["write", "list", "fast"]),
+    ("lrange", &["read", "list", "slow"]),
+    ("lindex", &["read", "list", "slow"]),
+    ("llen", &["read", "list", "fast"]),
+    ("blpop", &["write", "list", "slow", "blocking"]),
+    ("brpop", &["write", "list", "slow", "blocking"]),
+    ("sadd", &["write", "set", "fast"]),
+    ("srem", &["write", "set", "fast"]),
+    ("smembers", &["read", "set", "slow"]),
+    ("sismember", &["read", "set", "fast"]),
+    ("scard", &["read", "set", "fast"]),
+    ("sinter", &["read", "set", "slow"]),
+    ("sunion", &["read", "set", "slow"]),
+    ("sdiff", &["read", "set", "slow"]),
+    ("sinterstore", &["write", "set", "slow"]),
+    ("sunionstore", &["write", "set", "slow"]),
+    ("sdiffstore", &["write", "set", "slow"]),
+    ("spop", &["write", "set", "fast"]),
+    ("srandmember", &["read", "set", "slow"]),
+    ("zadd", &["write", "sortedset", "fast"]),
+    ("zincrby", &["write", "sortedset", "fast"]),
+    ("zrem", &["write", "sortedset", "fast"]),
+    ("zrange", &["read", "sortedset", "slow"]),
+    ("zrank", &["read", "sortedset", "fast"]),
+    ("zscore", &["read", "sortedset", "fast"]),
+    ("zcard", &["read", "sortedset", "fast"]),
+    ("zpopmin", &["write", "sortedset", "fast"]),
+    ("zpopmax", &["write", "sortedset", "fast"]),
+    ("zunionstore", &["write", "sortedset", "slow"]),
+    ("zinterstore", &["write", "sortedset", "slow"]),
+    ("zdiffstore", &["write", "sortedset", "slow"]),
+    ("xadd", &["write", "stream", "fast"]),
+    ("xlen", &["read", "stream", "fast"]),
+    ("xrange", &["read", "stream", "slow"]),
+    ("xrevrange", &["read", "stream", "slow"]),
+    ("xtrim", &["write", "stream", "slow"]),
+    ("xdel", &["write", "stream", "fast"]),
+    ("xread", &["read", "stream", "slow", "blocking"]),
+    ("xgroup", &["write", "stream", "slow"]),
+    ("xreadgroup", &["write", "stream", "slow", "blocking"]),
+    ("xack", &["write", "stream", "fast"]),
+    ("xclaim", &["write", "stream", "fast"]),
+    ("xpending", &["read", "stream", "slow"]),
+    ("xinfo", &["read", "stream", "slow"]),
+    ("setbit", &["write", "bitmap", "slow"]),
+    ("getbit", &["read", "bitmap", "fast"]),
+    ("bitcount", &["read", "bitmap", "slow"]),
+    ("bitpos", &["read", "bitmap", "slow"]),
+    ("bitop", &["write", "bitmap", "slow"]),
+    ("bitfield", &["write", "bitmap", "slow"]),
+    ("pfadd", &["write", "hyperloglog", "fast"]),
+    ("pfcount", &["read", "hyperloglog", "slow"]),
+    ("pfmerge", &["write", "hyperloglog", "slow"]),
+    ("geoadd", &["write", "geo", "slow"]),
+    ("geopos", &["read", "geo", "slow"]),
+    ("geodist", &["read", "geo", "slow"]),
+    ("geosearch", &["read", "geo", "slow"]),
+    ("json.set", &["write", "json", "slow"]),
+    ("json.get", &["read", "json", "slow"]),
+    ("json.del", &["write", "json", "slow"]),
+    ("json.arrappend", &["write", "json", "slow"]),
+    ("json.numincrby", &["write", "json", "slow"]),
+    ("json.type", &["read", "json", "fast"]),
+    ("incr", &["write", "string", "fast"]),
+    ("flushdb", &["keyspace", "write", "slow", "dangerous"]),
+    ("command", &["slow", "connection"]),
+    ("ping", &["fast", "connection"]),
+    ("auth", &["fast", "connection"]),
+    ("hello", &["fast", "connection"]),
+    ("config", &["admin", "slow", "dangerous"]),
+    ("info", &["slow", "dangerous"]),
+    ("metrics", &["slow", "dangerous"]),
+    ("slowlog", &["admin", "slow", "dangerous"]),
+    ("latency", &["admin", "slow", "dangerous"]),
+    ("monitor", &["admin", "slow", "dangerous"]),
+    ("client", &["admin", "slow", "dangerous", "connection"]),
+    ("acl", &["admin", "slow", "dangerous"]),
+];
+
+#[allow(clippy::module_name_repetitions)]
+#[derive(thiserror::Error, Debug)]
+pub enum AclError {
+    #[error("Error in ACL SETUSER modifier '{rule}': {reason}")]
+    Rule { rule: String, reason: &'static str },
+
+    #[error("The 'default' user cannot be removed")]
+    DefaultUser,
+
+    #[error("error on reading {0}: {1}")]
+    Io(String, std::io::Error),
+
+    #[error("{file}:{line}: {error}")]
+    File {
+        file: String,
+        line: usize,
+        error: Box<AclError>,
+    },
+}
+
+/// Reason why a command is refused, the messages are the ones of redis.
+#[derive(Debug, PartialEq, Eq)]
+pub enum Denied {
+    Command { user: String, command: &'static str },
+    Key,
+}
+
+impl std::fmt::Display for Denied {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Command { user, command } => write!(
+                f,
+                "User {user} has no permissions to run the '{command}' command"
+            ),
+            Self::Key => write!(f, "No permissions to access a key"),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum Target {
+    All,
+    Category(String),
+    Command(String),
+}
+
+/// Rule allowing, or denying, a command or a category.
+#[derive(Clone, Debug)]
+struct CommandRule {
+    allow: bool,
+    target: Target,
+}
+
+impl CommandRule {
+    fn matches(&self, command: &str) -> bool {
+        match &self.target {
+            Target::All => true,
+            Target::Category(c) => categories(command).contains(&c.as_str()),
+            Target::Command(c) => c == command,
+        }
+    }
+}
+
+impl std::fmt::Display for CommandRule {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let sign = if self.allow { '+' } else { '-' };
+        match &self.target {
+            Target::All => write!(f, "{sign}@all"),
+            Target::Category(c) => write!(f, "{sign}@{c}"),
+            Target::Command(c) => write!(f, "{sign}{c}"),
+        }
+    }
+}
+
+fn categories(command: &str) -> &'static [&'static str] {
+    COMMANDS
+        .iter()
+        .find(|(name, _)| *name == command)
+        .map_or(&[], |(_, c)| c)
+}
+
+#[derive(Clone, Debug)]
+pub struct User {
+    pub name: String,
+    pub enabled: bool,
+
+    /// Any password is accepted.
+    pub nopass: bool,
+
+    /// SHA-256 of the passwords, hex encoded like in the ACL file.
+    passwords: Vec<String>,
+
+    /// Rules in the order they were given, the last one matching a command decides if it is allowed.
+    commands: Vec<CommandRule>,
+
+    /// Patterns of the keys which can be accessed.
+    keys: Vec<String>,
+}
+
+impl User {
+    /// New users are disabled and they can't run any command, like in redis.
+    fn new(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            enabled: false,
+            nopass: false,
+            passwords: vec![],
+            commands: vec![],
+            keys: vec![],
+        }
+    }
+
+    /// Apply the rule, it has the same syntax of `ACL SETUSER`.
+    pub fn apply(&mut self, rule: &str) -> Result<(), AclError> {
+        let err = |reason| AclError::Rule {
+            rule: rule.to_string(),
+            reason,
+        };
+
+        match rule.to_lowercase().as_str() {
+            "on" => self.enabled = true,
+            "off" => self.enabled = false,
+            "nopass" => {
+                self.nopass = true;
+                self.passwords.clear();
+            }
+            "resetpass" => {
+                self.nopass = false;
+                self.passwords.clear();
+            }
+            "allkeys" => self.keys = vec!["*".to_string()],
+            "resetkeys" => self.keys.clear(),
+            "allcommands" => self.apply_command_rule(true, Target::All),
+            "nocommands" => self.apply_command_rule(false, Target::All),
+            "reset" => *self = Self::new(&self.name),
+            _ => match rule.split_at(rule.chars().next().map_or(0, char::len_utf8)) {
+                (">", password) => self.add_password(hash(password)),
+                ("<", password) => self
+                    .remove_password(&hash(password))
+                    .ok_or_else(|| err("no such password"))?,
+                ("#", h) if is_hash(h) => self.add_password(h.to_lowercase()),
+                ("!", h) if is_hash(h) => self
+                    .remove_password(&h.to_lowercase())
+                    .ok_or_else(|| err("no such password"))?,
+                ("#" | "!", _) => return Err(err("the hash must be 64 hexadecimal characters")),
+                ("~", pattern) => self.keys.push(pattern.to_string()),
+                (sign @ ("+" | "-"), name) => {
+                    let target = match name.to_lowercase().strip_prefix('@') {
+                        Some("all") => Target::All,
+                        Some(c) if CATEGORIES.contains(&c) => Target::Category(c.to_string()),
+                        None if !categories(&name.to_lowercase()).is_empty() => {
+                            Target::Command(name.to_lowercase())
+                        }
+                        _ => return Err(err("Unknown command or category name in ACL")),
+                    };
+                    self.apply_command_rule(sign == "+", target);
+                }
+                _ => return Err(err("Syntax error")),
+            },
+        }
+
+        Ok(())
+    }
+
+    fn add_password(&mut self, hash: String) {
+        self.nopass = false;
+        if !self.passwords.contains(&hash) {
+            self.passwords.push(hash);
+        }
+    }
+
+    fn remove_password(&mut self, hash: &str) -> Option<()> {
+        let i = self.passwords.iter().position(|p| p == hash)?;
+        self.passwords.remove(i);
+        Some(())
+    }
+
+    fn apply_command_rule(&mut self, allow: bool, target: Target) {
+        // NOTE: rules for all the commands override the previous ones, the same happens for a repeated target.
+        if target == Target::All {
+            self.commands.clear();
+        }
+        self.commands.retain(|r| r.target != target);
+        self.commands.push(CommandRule { allow, target });
+    }
+
+    pub fn check_password(&self, password: &str) -> bool {
+        let h = hash(password);
+        self.nopass
+            || self
+                .passwords
+                .iter()
+                .any(|p| constant_time_eq(p.as_bytes(), h.as_bytes()))
+    }
+
+    /// Returns if the user can run the command and access all its keys.
+    pub fn check(&self, cmd: &Command) -> Result<(), Denied> {
+        let name = cmd.name();
+        let allowed = self
+            .commands
+            .iter()
+            .rev()
+            .find(|r| r.matches(name))
+            .is_some_and(|r| r.allow);
+        if !allowed {
+            return Err(Denied::Command {
+                user: self.name.clone(),
+                command: name,
+            });
+        }
+
+        let all_keys = cmd
+            .keys()
+            .iter()
+            .all(|k| self.keys.iter().any(|p| glob::matches(p, k)));
+        if !all_keys {
+            return Err(Denied::Key);
+        }
+
+        Ok(())
+    }
+
+    /// Flags of `ACL GETUSER`.
+    pub fn flags(&self) -> Vec<&'static str> {
+        let mut flags = vec![if self.enabled { "on" } else { "off" }];
+        if self.nopass {
+            flags.push("nopass");
+        }
+        flags
+    }
+
+    pub fn passwords(&self) -> &[String] {
+        &self.passwords
+    }
+
+    /// Command rules, they always start from all the commands allowed or denied.
+    pub fn commands(&self) -> String {
+        let mut out = match self.commands.first() {
+            Some(r) if r.target == Target::All => String::new(),
+            _ => "-@all".to_string(),
+        };
+        for r in &self.commands {
+            if !out.is_empty() {
+                out.push(' ');
+            }
+            // NOTE: writing on a String never fails.
+            let _ = write!(out, "{r}");
+        }
+        out
+    }
+
+    /// Key patterns, each one prefixed by `~`.
+    pub fn keys(&self) -> String {
+        self.keys.iter().fold(String::new(), |mut out, k| {
+            if !out.is_empty() {
+                out.push(' ');
+            }
+            out.push('~');
+            out.push_str(k);
+            out
+        })
+    }
+}
+
+/// Line of `ACL LIST`, it can be used as is in the ACL file.
+impl std::fmt::Display for User {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "user {}", self.name)?;
+        for flag in self.flags() {
+            write!(f, " {flag}")?;
+        }
+        for p in &self.passwords {
+            write!(f, " #{p}")?;
+        }
+        if !self.keys.is_empty() {
+            write!(f, " {}", self.keys())?;
+        }
+        write!(f, " {}", self.commands())
+    }
+}
+
+/// Users allowed to connect, there is always the `default` one which is used by the clients until they
+/// authenticate.
+pub struct Acl {
+    users: BTreeMap<String, User>,
+}
+
+impl Acl {
+    /// Only the default user, it can run every command and it requires the password if given.
+    pub fn new(requirepass: Option<&str>) -> Self {
+        let mut default = User::new("default");
+        for rule in ["on", "allkeys", "allcommands"] {
+            // NOTE: the rules are valid.
+            let _ = default.apply(rule);
+        }
+        match requirepass {
+            Some(p) => default.add_password(hash(p)),
+            None => default.nopass = true,
+        }
+
+        Self {
+            users: BTreeMap::from([(default.name.clone(), default)]),
+        }
+    }
+
+    /// Replace the users with the ones of the ACL file, the default user is kept if the file does not define it.
+    pub fn load(&mut self, file: &str) -> Result<(), AclError> {
+        let content =
+            std::fs::read_to_string(file).map_err(|e| AclError::Io(file.to_string(), e))?;
+
+        let mut users = BTreeMap::new();
+        for (i, line) in content.lines().enumerate() {
+            let err = |error| AclError::File {
+                file: file.to_string(),
+                line: i + 1,
+                error: Box::new(error),
+            };
+
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let mut words = line.split_whitespace();
+            let (Some("user"), Some(name)) = (words.next(), words.next()) else {
+                return Err(err(AclError::Rule {
+                    rule: line.to_string(),
+                    reason: "lines must start with 'user <name>'",
+                }));
+            };
+            if users.contains_key(name) {
+                return Err(err(AclError::Rule {
+                    rule: name.to_string(),
+                    reason: "duplicate user",
+                }));
+            }
+
+            let mut user = User::new(name);
+            for rule in words {
+                user.apply(rule).map_err(err)?;
+            }
+            users.insert(name.to_string(), user);
+        }
+
+        if !users.contains_key("default") {
+            if let Some(default) = self.users.remove("default") {
+                users.insert(default.name.clone(), default);
+            }
+        }
+        self.users = users;
+
+        Ok(())
+    }
+
+    /// Returns if clients can run commands without authenticating, the default user does not need a password.
+    pub fn is_open(&self) -> bool {
+        self.users
+            .get("default")
+            .is_some_and(|u| u.enabled && u.nopass)
+    }
+
+    pub fn get(&self, name: &str) -> Option<&User> {
+        self.users.get(name)
+    }
+
+    pub fn users(&self) -> impl Iterator<Item = &User> {
+        self.users.values()
+    }
+
+    /// Create or modify the user, nothing is changed if any rule is invalid.
+    pub fn set_user(&mut self, name: &str, rules: &[String]) -> Result<(), AclError> {
+        let mut user = self
+            .users
+            .get(name)
+            .cloned()
+            .unwrap_or_else(|| User::new(name));
+        for rule in rules {
+            user.apply(rule)?;
+        }
+
+        self.users.insert(name.to_string(), user);
+        Ok(())
+    }
+
+    /// Remove the users, returns the ones which existed. Nothing is removed if one of them is the default user.
+    pub fn del_users(&mut self, names: &[String]) -> Result<Vec<String>, AclError> {
+        if names.iter().any(|n| n == "default") {
+            return Err(AclError::DefaultUser);
+        }
+
+        Ok(names
+            .iter()
+            .filter(|n| self.users.remove(*n).is_some())
+            .cloned()
+            .collect())
+    }
+
+    /// Returns the user if it is enabled and the password is valid.
+    pub fn authenticate(&self, name: &str, password: &str) -> Option<&User> {
+        self.users
+            .get(name)
+            .filter(|u| u.enabled && u.check_password(password))
+    }
+}
+
+fn hash(password: &str) -> String {
+    ring::digest::digest(&ring::digest::SHA256, password.as_bytes())
+        .as_ref()
+        .iter()
+        .fold(String::with_capacity(64), |mut out, b| {
+            // NOTE: writing on a String never fails.
+            let _ = write!(out, "{b:02x}");
+            out
+        })
+}
+
+fn is_hash(h: &str) -> bool {
+    h.len() == 64 && h.bytes().all(|b| b.is_ascii_hexdigit())
+}
+
+/// Compare the secrets in a time which does not depend on their content, it does not leak the length of the
+/// common prefix.
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn get(key: &str) -> Command {
+        Command::Get {
+            key: key.to_string(),
+        }
+    }
+
+    fn rules(rules: &str) -> Vec<String> {
+        rules.split_whitespace().map(str::to_string).collect()
+    }
+
+    #[test]
+    fn default_user() {
+        let acl = Acl::new(None);
+        let default = acl.get("default").unwrap();
+        assert!(default.nopass);
+        assert_eq!(default.check(&Command::FlushDb), Ok(()));
+        assert_eq!(default.to_string(), "user default on nopass ~* +@all");
+
+        let acl = Acl::new(Some("secret"));
+        assert!(acl.authenticate("default", "secret").is_some());
+        assert!(acl.authenticate("default", "wrong").is_none());
+        assert!(acl.authenticate("alice", "secret").is_none());
+    }
+
+    #[test]
+    fn set_user() {
+        let mut acl = Acl::new(None);
+        acl.set_user("alice", &rules(">secret ~session:* +@read +set"))
+            .unwrap();
+        let alice = acl.get("alice").unwrap();
+        assert!(!alice.enabled);
+        assert_eq!(alice.commands(), "-@all +@read +set");
+        assert_eq!(alice.keys(), "~session:*");
+        assert!(alice.check_password("secret"));
+        assert!(acl.authenticate("alice", "secret").is_none());
+
+        acl.set_user("alice", &rules("on -set")).unwrap();
+        let alice = acl.authenticate("alice", "secret").unwrap();
+        assert_eq!(alice.check(&get("session:1")), Ok(()));
+        assert_eq!(alice.check(&get("user:1")), Err(Denied::Key));
+        assert_eq!(
+            alice
+                .check(&Command::Set {
+                    key: "session:1".to_string(),
+                    value: b"v".to_vec(),
+                    ttl: None
+                })
+                .unwrap_err()
+                .to_string(),
+            "User alice has no permissions to run the 'set' command"
+        );
+
+        let err = acl.set_user("alice", &rules("off +nope")).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Error in ACL SETUSER modifier '+nope': Unknown command or category name in ACL"
+        );
+        assert!(acl.get("alice").unwrap().enabled);
+
+        let names = ["alice".to_string(), "default".to_string()];
+        assert!(matches!(acl.del_users(&names), Err(AclError::DefaultUser)));
+        assert_eq!(acl.del_users(&names[..1]).unwrap(), ["alice"]);
+        assert!(acl.del_users(&names[..1]).unwrap().is_empty());
+    }
+
+    #[test]
+    fn command_rules() {
+        let mut user = User::new("u");
+        for r in ["+@all", "-flushdb", "-@dangerous", "+info", "allkeys"] {
+            user.apply(r).unwrap();
+        }
+        assert_eq!(user.commands(), "+@all -flushdb -@dangerous +info");
+        assert!(user.check(&Command::FlushDb).is_err());
+        assert!(user.check(&Command::Config).is_err());
+        assert_eq!(user.check(&Command::Info { sections: vec![] }), Ok(()));
+        assert_eq!(user.check(&get("key")), Ok(()));
+
+        user.apply("nocommands").unwrap();
+        assert_eq!(user.commands(), "-@all");
+        assert!(user.check(&Command::Ping).is_err());
+
+        user.apply("reset").unwrap();
+        assert_eq!(user.to_string(), "user u off -@all");
+    }
+
+    #[test]
+    fn passwords() {
+        let mut user = User::new("u");
+        user.apply(">a").unwrap();
+        user.apply(">b").unwrap();
+        user.apply("<a").unwrap();
+        assert!(user.apply("<a").is_err());
+        assert!(!user.check_password("a"));
+        assert!(user.check_password("b"));
+
+        let h = user.passwords()[0].clone();
+        let mut copy = User::new("u");
+        copy.apply(&format!("#{h}")).unwrap();
+        assert!(copy.check_password("b"));
+        assert!(copy.apply("#123").is_err());
+
+        user.apply("nopass").unwrap();
+        assert!(user.check_password("anything"));
+        user.apply("resetpass").unwrap();
+        assert!(!user.check_password("anything"));
+    }
+
+    #[test]
+    fn load() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("users.acl").to_string_lossy().to_string();
+        std::fs::write(
+            &file,
+            "# dashboards\nuser dashboard on >pass ~* -@all +@read\n\nuser admin on >pass ~* +@all\n",
+        )
+        .unwrap();
+
+        let mut acl = Acl::new(Some("secret"));
+        acl.load(&file).unwrap();
+        assert_eq!(acl.users().count(), 3);
+        assert!(acl.authenticate("default", "secret").is_some());
+
+        let dashboard = acl.authenticate("dashboard", "pass").unwrap();
+        assert_eq!(dashboard.check(&get("key")), Ok(()));
+        assert!(dashboard.check(&Command::FlushDb).is_err());
+        assert_eq!(acl.get("admin").unwrap().check(&Command::FlushDb), Ok(()));
+
+        std::fs::write(&file, "user a on\nuser b on +nope\n").unwrap();
+        let err = acl.load(&file).unwrap_err();
+        let AclError::File { line, error, .. } = err else {
+            panic!("unexpected error {err}");
+        };
+        assert_eq!(line, 2);
+        assert!(matches!(*error, AclError::Rule { .. }));
+        assert!(acl.get("dashboard").is_some());
+    }
+
+    #[test]
+    fn commands_categories() {
+        for (name, categories) in COMMANDS {
+            for c in *categories {
+                assert!(CATEGORIES.contains(c), "{name}: {c}");
+            }
+        }
+    }
+}
@@ -0,0 +1,250 @@
+use super::channel;
+use crate::protocol::commands::Command;
+use std::collections::{HashMap, VecDeque};
+use std::rc::Rc;
+
+/// Pop requested by BLPOP or BRPOP, the connection waits for it when the lists are empty.
+#[derive(Debug, PartialEq, Eq)]
+pub struct Pop {
+    pub keys: Vec<String>,
+    pub left: bool,
+
+    /// Zero means forever.
+    pub timeout: std::time::Duration,
+}
+
+impl Pop {
+    pub fn from_command(cmd: &Command) -> Option<Self> {
+        match cmd {
+            Command::BLPop { keys, timeout } | Command::BRPop { keys, timeout } => Some(Self {
+                keys: keys.clone(),
+                left: matches!(cmd, Command::BLPop { .. }),
+                timeout: *timeout,
+            }),
+            _ => None,
+        }
+    }
+}
+
+/// Read of XREAD or XREADGROUP with BLOCK, the connection runs the command again whenever an entry is added to one
+/// of the streams until it returns something.
+#[derive(Debug, PartialEq)]
+pub struct Read {
+    pub keys: Vec<String>,
+
+    /// Zero means forever.
+    pub timeout: std::time::Duration,
+
+    /// Command run again, the last IDs of XREAD (`$`) must be resolved before the first run.
+    pub cmd: Command,
+}
+
+impl Read {
+    pub fn from_command(cmd: &Command) -> Option<Self> {
+        match cmd {
+            Command::XRead {
+                keys,
+                block: Some(timeout),
+                ..
+            }
+            | Command::XReadGroup {
+                keys,
+                block: Some(timeout),
+                ..
+            } => Some(Self {
+                keys: keys.clone(),
+                timeout: *timeout,
+                cmd: cmd.clone(),
+            }),
+            _ => None,
+        }
+    }
+}
+
+/// Connection blocked on one or more lists, it is handed the first element pushed to any of them.
+pub struct Waiter {
+    pub left: bool,
+    tx: channel::Sender<(String, String)>,
+}
+
+impl Waiter {
+    /// Returns if the waiter can still be served, it is gone once served, timed out or killed.
+    pub fn is_waiting(&self) -> bool {
+        self.tx.is_ready()
+    }
+
+    pub fn serve(&self, key: &str, element: String) {
+        // NOTE: `is_waiting` is checked before popping the element, between the two nothing else can run.
+        let _ = self.tx.send((key.to_string(), element));
+    }
+}
+
+/// Wait queue of every key with blocked connections, the oldest waiter of a key is served first.
+#[derive(Default)]
+pub struct Blocking {
+    queues: HashMap<String, VecDeque<Rc<Waiter>>>,
+
+    /// Connections waiting for an entry to be added to the streams, they are all woken up at once.
+    readers: HashMap<String, Vec<Rc<channel::Sender<()>>>>,
+    blocked: usize,
+}
+
+impl Blocking {
+    /// Queue the connection on the keys, the receiver gets the key and the element popped for it.
+    pub fn block(&mut self, pop: &Pop) -> channel::Receiver<(String, String)> {
+        let (tx, rx) = channel::channel(1);
+        let waiter = Rc::new(Waiter { left: pop.left, tx });
+        for key in &pop.keys {
+            self.queues
+                .entry(key.clone())
+                .or_default()
+                .push_back(Rc::clone(&waiter));
+        }
+        self.blocked += 1;
+
+        rx
+    }
+
+    /// Remove the waiters of the keys which are not waiting anymore, it is called once the connection stops waiting.
+    pub fn unblock(&mut self, pop: &Pop) {
+        for key in &pop.keys {
+            if let Some(q) = self.queues.get_mut(key) {
+                q.retain(|w| w.is_waiting());
+                if q.is_empty() {
+                    self.queues.remove(key);
+                }
+            }
+        }
+        self.blocked = self.blocked.saturating_sub(1);
+    }
+
+    /// Oldest connection still waiting for the key.
+    pub fn next(&mut self, key: &str) -> Option<Rc<Waiter>> {
+        let q = self.queues.get_mut(key)?;
+        while let Some(w) = q.front() {
+            if w.is_waiting() {
+                return Some(Rc::clone(w));
+            }
+            q.pop_front();
+        }
+
+        self.queues.remove(key);
+        None
+    }
+
+    /// Watch the streams of the read, the receiver gets a value once an entry is added to one of them.
+    pub fn watch(&mut self, read: &Read) -> channel::Receiver<()> {
+        let (tx, rx) = channel::channel(1);
+        let tx = Rc::new(tx);
+        for key in &read.keys {
+            self.readers
+                .entry(key.clone())
+                .or_default()
+                .push(Rc::clone(&tx));
+        }
+        self.blocked += 1;
+
+        rx
+    }
+
+    /// Stop watching the streams of the read, it is called once the receiver is dropped.
+    pub fn unwatch(&mut self, read: &Read) {
+        for key in &read.keys {
+            if let Some(readers) = self.readers.get_mut(key) {
+                readers.retain(|tx| !tx.is_closed());
+                if readers.is_empty() {
+                    self.readers.remove(key);
+                }
+            }
+        }
+        self.blocked = self.blocked.saturating_sub(1);
+    }
+
+    /// Wake up the connections watching the stream.
+    pub fn notify(&mut self, key: &str) {
+        for tx in self.readers.remove(key).into_iter().flatten() {
+            let _ = tx.send(());
+        }
+    }
+
+    /// Number of connections blocked, reported by INFO.
+    pub const fn blocked_clients(&self) -> usize {
+        self.blocked
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pop(keys: &[&str], left: bool) -> Pop {
+        Pop {
+            keys: keys.iter().map(ToString::to_string).collect(),
+            left,
+            timeout: std::time::Duration::ZERO,
+        }
+    }
+
+    #[test]
+    fn oldest_first() {
+        let mut b = Blocking::default();
+        let first = b.block(&pop(&["a", "b"], true));
+        let second = b.block(&pop(&["b"], false));
+        assert_eq!(b.blocked_clients(), 2);
+
+        let w = b.next("b").unwrap();
+        assert!(w.left);
+        w.serve("b", "x".to_string());
+        assert_eq!(first.try_recv(), Some(("b".to_string(), "x".to_string())));
+
+        // NOTE: the first waiter has been served, it is skipped on the other keys too.
+        drop(first);
+        assert!(b.next("a").is_none());
+        let w = b.next("b").unwrap();
+        assert!(!w.left);
+        w.serve("b", "y".to_string());
+        assert_eq!(second.try_recv(), Some(("b".to_string(), "y".to_string())));
+    }
+
+    fn read(keys: &[&str]) -> Read {
+        Read {
+            keys: keys.iter().map(ToString::to_string).collect(),
+            timeout: std::time::Duration::ZERO,
+            cmd: Command::Ping,
+        }
+    }
+
+    #[test]
+    fn notify() {
+        let mut b = Blocking::default();
+        let (first, second) = (read(&["a", "b"]), read(&["b"]));
+        let rx = b.watch(&first);
+        let other = b.watch(&second);
+        assert_eq!(b.blocked_clients(), 2);
+
+        b.notify("a");
+        b.notify("c");
+        assert_eq!(rx.try_recv(), Some(()));
+        assert_eq!(other.try_recv(), None);
+
+        // NOTE: the first reader has been woken up, it is removed from the other streams once it stops watching.
+        drop(rx);
+        b.unwatch(&first);
+        assert_eq!(b.blocked_clients(), 1);
+        b.notify("b");
+        assert_eq!(other.try_recv(), Some(()));
+        assert!(b.readers.is_empty());
+    }
+
+    #[test]
+    fn unblock() {
+        let mut b = Blocking::default();
+        let p = pop(&["a"], true);
+        let rx = b.block(&p);
+        drop(rx);
+        b.unblock(&p);
+
+        assert_eq!(b.blocked_clients(), 0);
+        assert!(b.queues.is_empty());
+    }
+}
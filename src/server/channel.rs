@@ -0,0 +1,160 @@
+use std::cell::RefCell;
+use std::collections::VecDeque;
+use std::rc::Rc;
+use std::task::{Poll, Waker};
+
+/// Bounded channel between tasks running on the same thread, it is used to push data to connections waiting for
+/// it, like the ones in MONITOR mode.
+pub fn channel<T>(capacity: usize) -> (Sender<T>, Receiver<T>) {
+    let shared = Rc::new(RefCell::new(Shared {
+        queue: VecDeque::new(),
+        capacity,
+        waker: None,
+        sender_alive: true,
+        receiver_alive: true,
+    }));
+
+    (Sender(Rc::clone(&shared)), Receiver(shared))
+}
+
+struct Shared<T> {
+    queue: VecDeque<T>,
+    capacity: usize,
+    waker: Option<Waker>,
+    sender_alive: bool,
+    receiver_alive: bool,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum SendError {
+    /// The receiver has been dropped.
+    Closed,
+
+    /// The receiver is not keeping up and the queue reached its capacity.
+    Full,
+}
+
+pub struct Sender<T>(Rc<RefCell<Shared<T>>>);
+
+impl<T> Sender<T> {
+    pub fn send(&self, v: T) -> Result<(), SendError> {
+        let mut shared = self.0.borrow_mut();
+        if !shared.receiver_alive {
+            return Err(SendError::Closed);
+        }
+        if shared.queue.len() >= shared.capacity {
+            return Err(SendError::Full);
+        }
+
+        shared.queue.push_back(v);
+        if let Some(w) = shared.waker.take() {
+            w.wake();
+        }
+
+        Ok(())
+    }
+
+    pub fn is_closed(&self) -> bool {
+        !self.0.borrow().receiver_alive
+    }
+
+    /// Returns if a value can be sent right now, the receiver is alive and the queue has room for it.
+    pub fn is_ready(&self) -> bool {
+        let shared = self.0.borrow();
+        shared.receiver_alive && shared.queue.len() < shared.capacity
+    }
+}
+
+impl<T> Drop for Sender<T> {
+    fn drop(&mut self) {
+        let mut shared = self.0.borrow_mut();
+        shared.sender_alive = false;
+        if let Some(w) = shared.waker.take() {
+            w.wake();
+        }
+    }
+}
+
+pub struct Receiver<T>(Rc<RefCell<Shared<T>>>);
+
+impl<T> Receiver<T> {
+    /// Take the next value if there is one already, without waiting.
+    pub fn try_recv(&self) -> Option<T> {
+        self.0.borrow_mut().queue.pop_front()
+    }
+
+    /// Wait for the next value, None is returned once the sender is dropped and the queue is empty.
+    // NOTE: the channel never leaves the thread, like the connections of monoio.
+    #[allow(clippy::future_not_send)]
+    pub async fn recv(&self) -> Option<T> {
+        std::future::poll_fn(|cx| {
+            let mut shared = self.0.borrow_mut();
+            if let Some(v) = shared.queue.pop_front() {
+                return Poll::Ready(Some(v));
+            }
+            if !shared.sender_alive {
+                return Poll::Ready(None);
+            }
+
+            shared.waker = Some(cx.waker().clone());
+            Poll::Pending
+        })
+        .await
+    }
+}
+
+impl<T> Drop for Receiver<T> {
+    fn drop(&mut self) {
+        let mut shared = self.0.borrow_mut();
+        shared.receiver_alive = false;
+        shared.queue.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[monoio::test]
+    async fn send_recv() {
+        let (tx, rx) = channel(2);
+        tx.send(1).unwrap();
+        tx.send(2).unwrap();
+        assert_eq!(tx.send(3), Err(SendError::Full));
+
+        assert_eq!(rx.recv().await, Some(1));
+        assert_eq!(rx.recv().await, Some(2));
+
+        drop(tx);
+        assert_eq!(rx.recv().await, None);
+    }
+
+    #[monoio::test]
+    async fn wake_receiver() {
+        let (tx, rx) = channel(1);
+        let handle = monoio::spawn(async move { rx.recv().await });
+
+        monoio::spawn(async move {
+            tx.send("hello").unwrap();
+        })
+        .await;
+
+        assert_eq!(handle.await, Some("hello"));
+    }
+
+    #[test]
+    fn closed() {
+        let (tx, rx) = channel::<u8>(1);
+        assert!(!tx.is_closed());
+        assert!(tx.is_ready());
+        tx.send(1).unwrap();
+        assert!(!tx.is_ready());
+        assert_eq!(rx.try_recv(), Some(1));
+        assert_eq!(rx.try_recv(), None);
+
+        drop(rx);
+        assert!(tx.is_closed());
+        assert!(!tx.is_ready());
+        assert_eq!(tx.send(1), Err(SendError::Closed));
+    }
+}
@@ -0,0 +1,257 @@
+use super::channel;
+use std::collections::BTreeMap;
+use std::fmt::Write;
+
+/// Connected client, it is updated by the connection on every request.
+pub struct Client {
+    pub id: u64,
+    pub addr: String,
+    pub name: String,
+    pub created_at: std::time::Instant,
+    pub last_interaction: std::time::Instant,
+
+    /// Name of the last command executed, `NULL` before the first one like redis.
+    pub last_command: &'static str,
+
+    /// Protocol used by the last request, `unknown` before the first one.
+    pub protocol: &'static str,
+
+    /// Version of RESP selected with HELLO, responses to version 2 have no maps.
+    pub resp: u8,
+
+    /// Bytes of the last request and the capacity of the buffer used to read it.
+    pub query_buffer: usize,
+    pub query_buffer_capacity: usize,
+
+    /// Bytes of the last response written.
+    pub output_buffer: usize,
+
+    /// Set when the connection is in MONITOR mode.
+    pub monitor: bool,
+
+    /// User the client authenticated as with AUTH or HELLO, until then it is the default user when it does not
+    /// require a password.
+    pub user: Option<String>,
+
+    // NOTE: nothing is ever sent, dropping the sender wakes up the connection which closes itself.
+    _kill: channel::Sender<()>,
+}
+
+impl Client {
+    /// Line describing the client, it uses the same format of redis `CLIENT LIST`.
+    pub fn info(&self, now: std::time::Instant) -> String {
+        let mut out = String::new();
+        // NOTE: writing on a String never fails.
+        let _ = write!(
+            out,
+            "id={} addr={} name={} age={} idle={} flags={} db=0 qbuf={} qbuf-free={} obl={} cmd={} user={} proto={} resp={}",
+            self.id,
+            self.addr,
+            self.name,
+            now.saturating_duration_since(self.created_at).as_secs(),
+            now.saturating_duration_since(self.last_interaction)
+                .as_secs(),
+            if self.monitor { "O" } else { "N" },
+            self.query_buffer,
+            self.query_buffer_capacity
+                .saturating_sub(self.query_buffer),
+            self.output_buffer,
+            self.last_command,
+            self.username(),
+            self.protocol,
+            self.resp,
+        );
+        out
+    }
+
+    pub fn username(&self) -> &str {
+        self.user.as_deref().unwrap_or("default")
+    }
+}
+
+/// Filters of `CLIENT KILL`, a client is killed when it matches all of them.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct KillFilter {
+    pub id: Option<u64>,
+    pub addr: Option<String>,
+    pub user: Option<String>,
+
+    /// Client issuing the command, it is skipped unless `skip_me` is false.
+    pub me: u64,
+    pub skip_me: bool,
+}
+
+impl KillFilter {
+    fn matches(&self, c: &Client) -> bool {
+        self.id.map_or(true, |id| id == c.id)
+            && self.addr.as_ref().map_or(true, |addr| *addr == c.addr)
+            && self.user.as_ref().map_or(true, |user| user == c.username())
+            && !(self.skip_me && c.id == self.me)
+    }
+}
+
+#[derive(Clone, Copy)]
+struct Pause {
+    until: std::time::Instant,
+
+    /// When false only the writes are paused.
+    all: bool,
+}
+
+/// Registry of the connected clients.
+#[derive(Default)]
+pub struct Clients {
+    clients: BTreeMap<u64, Client>,
+    pause: Option<Pause>,
+}
+
+impl Clients {
+    /// Add the client, the returned receiver is closed when the client is killed.
+    pub fn register(
+        &mut self,
+        id: u64,
+        addr: String,
+        now: std::time::Instant,
+    ) -> channel::Receiver<()> {
+        let (tx, rx) = channel::channel(0);
+        self.clients.insert(
+            id,
+            Client {
+                id,
+                addr,
+                name: String::new(),
+                created_at: now,
+                last_interaction: now,
+                last_command: "NULL",
+                protocol: "unknown",
+                resp: 2,
+                query_buffer: 0,
+                query_buffer_capacity: 0,
+                output_buffer: 0,
+                monitor: false,
+                user: None,
+                _kill: tx,
+            },
+        );
+        rx
+    }
+
+    pub fn unregister(&mut self, id: u64) {
+        self.clients.remove(&id);
+    }
+
+    pub fn get(&self, id: u64) -> Option<&Client> {
+        self.clients.get(&id)
+    }
+
+    pub fn get_mut(&mut self, id: u64) -> Option<&mut Client> {
+        self.clients.get_mut(&id)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Client> {
+        self.clients.values()
+    }
+
+    /// Remove the clients matching the filter, their connections are closed, returns how many were killed.
+    pub fn kill(&mut self, filter: &KillFilter) -> usize {
+        let before = self.clients.len();
+        self.clients.retain(|_, c| !filter.matches(c));
+        before - self.clients.len()
+    }
+
+    /// Suspend the commands of every client, only the writes if `all` is false, until the given instant.
+    pub fn pause(&mut self, until: std::time::Instant, all: bool) {
+        self.pause = Some(Pause { until, all });
+    }
+
+    pub fn unpause(&mut self) {
+        self.pause = None;
+    }
+
+    /// Instant until which a command must wait, None if it can be executed now.
+    pub fn paused_until(&self, write: bool, now: std::time::Instant) -> Option<std::time::Instant> {
+        self.pause
+            .filter(|p| p.until > now && (p.all || write))
+            .map(|p| p.until)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[monoio::test]
+    async fn kill() {
+        let now = std::time::Instant::now();
+        let mut c = Clients::default();
+        let rx1 = c.register(1, "127.0.0.1:1".to_string(), now);
+        let _rx2 = c.register(2, "127.0.0.1:2".to_string(), now);
+
+        let filter = KillFilter {
+            me: 1,
+            skip_me: true,
+            ..Default::default()
+        };
+        assert_eq!(c.kill(&filter), 1);
+        assert!(c.get(2).is_none());
+
+        let filter = KillFilter {
+            addr: Some("127.0.0.1:1".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(c.kill(&filter), 1);
+        assert_eq!(rx1.recv().await, None);
+        assert_eq!(c.iter().count(), 0);
+
+        let _rx3 = c.register(3, "127.0.0.1:3".to_string(), now);
+        let _rx4 = c.register(4, "127.0.0.1:4".to_string(), now);
+        c.get_mut(3).unwrap().user = Some("alice".to_string());
+        let filter = KillFilter {
+            user: Some("default".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(c.kill(&filter), 1);
+        assert!(c.get(3).is_some());
+        assert!(c.get(4).is_none());
+    }
+
+    #[test]
+    fn info() {
+        let now = std::time::Instant::now();
+        let mut c = Clients::default();
+        let _rx = c.register(7, "127.0.0.1:1".to_string(), now);
+
+        let client = c.get_mut(7).unwrap();
+        client.name = "worker".to_string();
+        client.query_buffer = 10;
+        client.query_buffer_capacity = 4096;
+        client.last_command = "get";
+        client.protocol = "resp";
+
+        assert_eq!(
+            c.get(7)
+                .unwrap()
+                .info(now + std::time::Duration::from_secs(3)),
+            "id=7 addr=127.0.0.1:1 name=worker age=3 idle=3 flags=N db=0 qbuf=10 qbuf-free=4086 obl=0 cmd=get user=default proto=resp resp=2"
+        );
+    }
+
+    #[test]
+    fn pause() {
+        let now = std::time::Instant::now();
+        let until = now + std::time::Duration::from_secs(1);
+        let mut c = Clients::default();
+        assert_eq!(c.paused_until(true, now), None);
+
+        c.pause(until, false);
+        assert_eq!(c.paused_until(false, now), None);
+        assert_eq!(c.paused_until(true, now), Some(until));
+        assert_eq!(c.paused_until(true, until), None);
+
+        c.pause(until, true);
+        assert_eq!(c.paused_until(false, now), Some(until));
+
+        c.unpause();
+        assert_eq!(c.paused_until(true, now), None);
+    }
+}
@@ -0,0 +1,299 @@
+use super::Server;
+use crate::engine::db::HashMapDb;
+use std::fmt::Write;
+
+/// Every section known by INFO in the order they are printed.
+const SECTIONS: &[&str] = &[
+    "server",
+    "clients",
+    "memory",
+    "persistence",
+    "stats",
+    "commandstats",
+    "keyspace",
+];
+
+/// Sections printed when INFO is called without arguments, like redis `commandstats` is printed only on request.
+const DEFAULT_SECTIONS: &[&str] = &[
+    "server",
+    "clients",
+    "memory",
+    "persistence",
+    "stats",
+    "keyspace",
+];
+
+/// Version of redis we are compatible with, tools like `redis_exporter` rely on it.
+pub const REDIS_VERSION: &str = "7.2.0";
+
+/// Render the output of the [`Info`](crate::protocol::commands::Command::Info) command using the redis format,
+/// every section starts with `# Name` followed by `field:value` lines, sections are separated by an empty line.
+pub fn render(
+    sections: &[String],
+    server: &Server,
+    db: &HashMapDb,
+    now: std::time::Instant,
+) -> String {
+    let wanted = |s: &str| {
+        if sections.is_empty() || sections.iter().any(|w| w == "default") {
+            DEFAULT_SECTIONS.contains(&s)
+        } else {
+            sections
+                .iter()
+                .any(|w| w == s || w == "all" || w == "everything")
+        }
+    };
+
+    let mut out = String::new();
+    for section in SECTIONS.iter().filter(|s| wanted(s)) {
+        if !out.is_empty() {
+            out.push_str("\r\n");
+        }
+
+        match *section {
+            "server" => render_server(&mut out, server, now),
+            "clients" => render_clients(&mut out, server),
+            "memory" => render_memory(&mut out, db),
+            "persistence" => render_persistence(&mut out, server, db, now),
+            "stats" => render_stats(&mut out, server, db),
+            "commandstats" => render_commandstats(&mut out, server),
+            "keyspace" => render_keyspace(&mut out, db),
+            _ => unreachable!("section {section} is not handled"),
+        }
+    }
+
+    out
+}
+
+fn field(out: &mut String, name: &str, value: impl std::fmt::Display) {
+    // NOTE: writing on a String never fails.
+    let _ = write!(out, "{name}:{value}\r\n");
+}
+
+fn render_server(out: &mut String, server: &Server, now: std::time::Instant) {
+    let uptime = now.saturating_duration_since(server.started_at).as_secs();
+
+    out.push_str("# Server\r\n");
+    field(out, "redis_version", REDIS_VERSION);
+    field(out, "umd_version", env!("CARGO_PKG_VERSION"));
+    field(out, "redis_mode", "standalone");
+    field(
+        out,
+        "os",
+        format!("{} {}", std::env::consts::OS, std::env::consts::ARCH),
+    );
+    field(out, "arch_bits", usize::BITS);
+    field(out, "multiplexing_api", "io_uring");
+    field(out, "process_id", std::process::id());
+    field(out, "tcp_port", server.port);
+    field(out, "uptime_in_seconds", uptime);
+    field(out, "uptime_in_days", uptime / 86400);
+}
+
+fn render_clients(out: &mut String, server: &Server) {
+    out.push_str("# Clients\r\n");
+    field(out, "connected_clients", server.stats.connected_clients);
+    field(out, "blocked_clients", server.blocking.blocked_clients());
+}
+
+fn render_memory(out: &mut String, db: &HashMapDb) {
+    let used = db.estimated_memory();
+
+    out.push_str("# Memory\r\n");
+    field(out, "used_memory", used);
+    field(out, "used_memory_human", bytes_to_human(used));
+    field(out, "maxmemory", 0);
+    field(
+        out,
+        "maxmemory_policy",
+        if db.max_items().is_some() {
+            "allkeys-lru"
+        } else {
+            "noeviction"
+        },
+    );
+}
+
+fn render_persistence(out: &mut String, server: &Server, db: &HashMapDb, now: std::time::Instant) {
+    // NOTE: like redis, when the db has never been saved we report the start time of the server.
+    let last_save = db.stats().last_save_time.unwrap_or_else(|| {
+        std::time::SystemTime::now() - now.saturating_duration_since(server.started_at)
+    });
+    let last_save = last_save
+        .duration_since(std::time::UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs());
+
+    out.push_str("# Persistence\r\n");
+    field(out, "loading", 0);
+    field(
+        out,
+        "rdb_changes_since_last_save",
+        db.changes_since_last_save(),
+    );
+    field(out, "rdb_last_save_time", last_save);
+    field(
+        out,
+        "rdb_last_bgsave_status",
+        if db.stats().last_save_failed {
+            "err"
+        } else {
+            "ok"
+        },
+    );
+}
+
+fn render_stats(out: &mut String, server: &Server, db: &HashMapDb) {
+    let s = db.stats();
+
+    out.push_str("# Stats\r\n");
+    field(
+        out,
+        "total_connections_received",
+        server.stats.total_connections_received,
+    );
+    field(
+        out,
+        "total_commands_processed",
+        server.stats.total_commands_processed,
+    );
+    field(
+        out,
+        "rejected_connections",
+        server.stats.rejected_connections,
+    );
+    field(
+        out,
+        "total_net_input_bytes",
+        server.stats.total_net_input_bytes,
+    );
+    field(
+        out,
+        "total_net_output_bytes",
+        server.stats.total_net_output_bytes,
+    );
+    field(out, "expired_keys", s.expired_keys);
+    field(out, "evicted_keys", s.evicted_keys);
+    field(out, "keyspace_hits", s.keyspace_hits);
+    field(out, "keyspace_misses", s.keyspace_misses);
+}
+
+fn render_commandstats(out: &mut String, server: &Server) {
+    out.push_str("# Commandstats\r\n");
+    for (name, c) in &server.stats.commands {
+        #[allow(clippy::cast_precision_loss)]
+        let per_call = c.usec as f64 / c.calls as f64;
+        field(
+            out,
+            &format!("cmdstat_{name}"),
+            format!(
+                "calls={},usec={},usec_per_call={per_call:.2},rejected_calls=0,failed_calls=0",
+                c.calls, c.usec
+            ),
+        );
+    }
+}
+
+fn render_keyspace(out: &mut String, db: &HashMapDb) {
+    out.push_str("# Keyspace\r\n");
+    if db.keys_count() > 0 {
+        field(
+            out,
+            "db0",
+            format!(
+                "keys={},expires={},avg_ttl=0",
+                db.keys_count(),
+                db.expires_count()
+            ),
+        );
+    }
+}
+
+/// Same format used by redis for the `*_human` fields.
+#[allow(clippy::cast_precision_loss)]
+fn bytes_to_human(n: usize) -> String {
+    let n = n as f64;
+    match n {
+        n if n < 1024.0 => format!("{n}B"),
+        n if n < 1024.0 * 1024.0 => format!("{:.2}K", n / 1024.0),
+        n if n < 1024.0 * 1024.0 * 1024.0 => format!("{:.2}M", n / 1024.0 / 1024.0),
+        n => format!("{:.2}G", n / 1024.0 / 1024.0 / 1024.0),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config;
+
+    #[test]
+    fn default_sections() {
+        let server = Server::new(6379, &config::Config::default());
+        let db = HashMapDb::new(config::Engine::default());
+
+        let out = render(&[], &server, &db, std::time::Instant::now());
+        assert!(out.starts_with("# Server\r\nredis_version:"));
+        assert!(out.contains("\r\n\r\n# Clients\r\nconnected_clients:0\r\n"));
+        assert!(out.contains("tcp_port:6379\r\n"));
+        assert!(out.contains("rdb_last_bgsave_status:ok\r\n"));
+        assert!(!out.contains("# Commandstats"));
+        assert!(out.ends_with("# Keyspace\r\n"));
+    }
+
+    #[test]
+    fn single_section() {
+        let mut server = Server::new(6379, &config::Config::default());
+        server
+            .stats
+            .record_command("get", "resp", std::time::Duration::from_micros(3));
+        let mut db = HashMapDb::new(config::Engine::default());
+        db.set("foo", "bar".to_string(), None);
+        db.get("foo", std::time::Instant::now());
+        db.get("baz", std::time::Instant::now());
+
+        let out = render(
+            &["commandstats".to_string()],
+            &server,
+            &db,
+            std::time::Instant::now(),
+        );
+        assert_eq!(
+            out,
+            "# Commandstats\r\ncmdstat_get:calls=1,usec=3,usec_per_call=3.00,rejected_calls=0,failed_calls=0\r\n"
+        );
+
+        let out = render(
+            &["keyspace".to_string(), "stats".to_string()],
+            &server,
+            &db,
+            std::time::Instant::now(),
+        );
+        assert!(out.starts_with("# Stats\r\n"));
+        assert!(out.contains("keyspace_hits:1\r\nkeyspace_misses:1\r\n"));
+        assert!(out.ends_with("# Keyspace\r\ndb0:keys=1,expires=0,avg_ttl=0\r\n"));
+    }
+
+    #[test]
+    fn all_sections() {
+        let server = Server::new(6379, &config::Config::default());
+        let db = HashMapDb::new(config::Engine::default());
+
+        let out = render(
+            &["all".to_string()],
+            &server,
+            &db,
+            std::time::Instant::now(),
+        );
+        for s in SECTIONS {
+            let mut name = (*s).to_string();
+            name[..1].make_ascii_uppercase();
+            assert!(out.contains(&format!("# {name}\r\n")));
+        }
+    }
+
+    #[test]
+    fn human_bytes() {
+        assert_eq!(bytes_to_human(100), "100B");
+        assert_eq!(bytes_to_human(2048), "2.00K");
+        assert_eq!(bytes_to_human(3 * 1024 * 1024 + 512 * 1024), "3.50M");
+    }
+}
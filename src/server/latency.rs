@@ -0,0 +1,240 @@
+use crate::config;
+use std::collections::{BTreeMap, VecDeque};
+
+/// Values below `2 * SUB_BUCKETS` have their own bucket, bigger values share buckets whose width doubles every
+/// `SUB_BUCKETS` buckets, this keeps the relative error below 1/64 like an HDR histogram with 2 significant digits.
+const SUB_BUCKETS: u64 = 64;
+
+/// Number of samples kept for each event, the oldest are dropped first.
+const MAX_SAMPLES: usize = 160;
+
+/// Histogram with log-linear buckets of microseconds, it covers any value with a bounded relative error and a
+/// small memory footprint.
+#[derive(Default, Debug)]
+pub struct Histogram {
+    buckets: Vec<u64>,
+    count: u64,
+}
+
+impl Histogram {
+    pub fn record(&mut self, d: std::time::Duration) {
+        let usec = u64::try_from(d.as_micros()).unwrap_or(u64::MAX);
+        let i = bucket_index(usec);
+        if self.buckets.len() <= i {
+            self.buckets.resize(i + 1, 0);
+        }
+
+        self.buckets[i] += 1;
+        self.count += 1;
+    }
+
+    pub const fn count(&self) -> u64 {
+        self.count
+    }
+
+    /// Cumulative distribution sampled on powers of 2 microseconds, like redis only the points where the count
+    /// increases are returned as (usec, cumulative count).
+    pub fn cdf(&self) -> Vec<(u64, u64)> {
+        let mut out = vec![];
+        let mut cumulative = 0;
+        let mut buckets = self.buckets.iter().enumerate().peekable();
+
+        let mut boundary = 1u64;
+        while cumulative < self.count {
+            while let Some((_, n)) = buckets.next_if(|(i, _)| bucket_value(*i) <= boundary) {
+                cumulative += n;
+            }
+
+            if out.last().map_or(cumulative > 0, |(_, c)| cumulative > *c) {
+                out.push((boundary, cumulative));
+            }
+
+            boundary = boundary.saturating_mul(2);
+        }
+
+        out
+    }
+}
+
+#[allow(clippy::cast_possible_truncation)]
+const fn bucket_index(v: u64) -> usize {
+    if v < 2 * SUB_BUCKETS {
+        return v as usize;
+    }
+
+    let shift = (63 - v.leading_zeros()) as u64 - SUB_BUCKETS.trailing_zeros() as u64;
+    (SUB_BUCKETS * shift + (v >> shift)) as usize
+}
+
+/// Lowest value stored in the bucket.
+const fn bucket_value(i: usize) -> u64 {
+    let i = i as u64;
+    if i < 2 * SUB_BUCKETS {
+        return i;
+    }
+
+    let shift = i / SUB_BUCKETS - 1;
+    (i - SUB_BUCKETS * shift) << shift
+}
+
+/// Sample of an event, the latency is the highest seen in the same second.
+#[derive(Debug, PartialEq, Eq)]
+pub struct Sample {
+    /// Seconds since unix epoch.
+    pub timestamp: u64,
+    pub latency: std::time::Duration,
+}
+
+/// History of the latency spikes of an event.
+#[derive(Default, Debug)]
+pub struct Event {
+    pub samples: VecDeque<Sample>,
+    pub max: std::time::Duration,
+}
+
+/// Latency tracking: histograms per command and the monitor of events, like snapshot persistence or eviction,
+/// slower than the configured threshold.
+pub struct Latency {
+    histograms: BTreeMap<&'static str, Histogram>,
+    events: BTreeMap<&'static str, Event>,
+    config: config::Latency,
+}
+
+impl Latency {
+    pub fn new(config: config::Latency) -> Self {
+        Self {
+            histograms: BTreeMap::new(),
+            events: BTreeMap::new(),
+            config,
+        }
+    }
+
+    /// Record the time spent serving a command, from the decoding of the request to the response written.
+    pub fn record_command(&mut self, name: &'static str, d: std::time::Duration) {
+        if self.config.tracking {
+            self.histograms.entry(name).or_default().record(d);
+        }
+    }
+
+    /// Record the latency of an event if it is above the monitor threshold, a threshold of 0 disables the monitor.
+    pub fn record_event(&mut self, name: &'static str, latency: std::time::Duration) {
+        if self.config.monitor_threshold.is_zero() || latency < self.config.monitor_threshold {
+            return;
+        }
+
+        let timestamp = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map_or(0, |d| d.as_secs());
+
+        let e = self.events.entry(name).or_default();
+        e.max = e.max.max(latency);
+        match e.samples.back_mut() {
+            Some(s) if s.timestamp == timestamp => s.latency = s.latency.max(latency),
+            _ => {
+                e.samples.push_back(Sample { timestamp, latency });
+                if e.samples.len() > MAX_SAMPLES {
+                    e.samples.pop_front();
+                }
+            }
+        }
+    }
+
+    /// Histograms of the given commands, all of them if none is given.
+    pub fn histograms<'a>(
+        &'a self,
+        commands: &'a [String],
+    ) -> impl Iterator<Item = (&'static str, &'a Histogram)> + 'a {
+        self.histograms
+            .iter()
+            .filter(|(name, _)| commands.is_empty() || commands.iter().any(|c| c == *name))
+            .map(|(name, h)| (*name, h))
+    }
+
+    pub fn events(&self) -> impl Iterator<Item = (&'static str, &Event)> {
+        self.events.iter().map(|(name, e)| (*name, e))
+    }
+
+    pub fn event(&self, name: &str) -> Option<&Event> {
+        self.events.get(name)
+    }
+
+    /// Remove the samples of the given events, all of them if none is given, returns how many were removed.
+    pub fn reset(&mut self, events: &[String]) -> usize {
+        let before = self.events.len();
+        if events.is_empty() {
+            self.events.clear();
+        } else {
+            self.events
+                .retain(|name, _| !events.iter().any(|e| e == name));
+        }
+
+        before - self.events.len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn buckets() {
+        for v in [0, 1, 127, 128, 129, 255, 256, 1000, 123_456, u64::MAX] {
+            let i = bucket_index(v);
+            assert!(bucket_value(i) <= v, "value {v} in bucket {i}");
+            assert!(
+                v == u64::MAX || bucket_value(i + 1) > v,
+                "value {v} in bucket {i}"
+            );
+            // relative error is bounded
+            assert!(v - bucket_value(i) <= v / SUB_BUCKETS);
+        }
+    }
+
+    #[test]
+    fn cdf() {
+        let mut h = Histogram::default();
+        h.record(std::time::Duration::from_micros(1));
+        h.record(std::time::Duration::from_micros(3));
+        h.record(std::time::Duration::from_micros(3));
+        h.record(std::time::Duration::from_micros(300));
+
+        assert_eq!(h.count(), 4);
+        assert_eq!(h.cdf(), vec![(1, 1), (4, 3), (512, 4)]);
+        assert_eq!(Histogram::default().cdf(), vec![]);
+    }
+
+    #[test]
+    fn events() {
+        let mut l = Latency::new(config::Latency {
+            tracking: true,
+            monitor_threshold: std::time::Duration::from_millis(1),
+        });
+
+        l.record_event("expire-cycle", std::time::Duration::from_micros(10));
+        assert_eq!(l.events().count(), 0);
+
+        l.record_event("eviction-cycle", std::time::Duration::from_millis(2));
+        l.record_event("eviction-cycle", std::time::Duration::from_millis(5));
+        let e = l.event("eviction-cycle").unwrap();
+        assert_eq!(e.max, std::time::Duration::from_millis(5));
+        assert!(!e.samples.is_empty());
+
+        assert_eq!(l.reset(&["unknown".to_string()]), 0);
+        assert_eq!(l.reset(&[]), 1);
+        assert!(l.event("eviction-cycle").is_none());
+    }
+
+    #[test]
+    fn command_histograms() {
+        let mut l = Latency::new(config::Latency::default());
+        l.record_command("get", std::time::Duration::from_micros(10));
+        l.record_command("set", std::time::Duration::from_micros(10));
+
+        assert_eq!(l.histograms(&[]).count(), 2);
+        let filter = vec!["set".to_string()];
+        let h = l.histograms(&filter).collect::<Vec<_>>();
+        assert_eq!(h.len(), 1);
+        assert_eq!(h[0].0, "set");
+        assert_eq!(h[0].1.count(), 1);
+    }
+}
@@ -0,0 +1,306 @@
+use super::Server;
+use crate::engine::db::HashMapDb;
+use std::fmt::Write;
+
+/// Upper bounds, in seconds, of the buckets used for latency histograms, from 10us to 1s.
+const BUCKETS: [f64; 12] = [
+    0.000_01, 0.000_025, 0.000_05, 0.000_1, 0.000_25, 0.000_5, 0.001, 0.002_5, 0.005, 0.01, 0.1,
+    1.0,
+];
+
+/// Histogram with fixed buckets, following the prometheus semantic. Buckets are stored as non cumulative and
+/// accumulated only while rendering.
+#[derive(Default, Debug)]
+pub struct Histogram {
+    buckets: [u64; BUCKETS.len()],
+    count: u64,
+    sum: f64,
+}
+
+impl Histogram {
+    pub fn observe(&mut self, d: std::time::Duration) {
+        let secs = d.as_secs_f64();
+        if let Some(i) = BUCKETS.iter().position(|b| secs <= *b) {
+            self.buckets[i] += 1;
+        }
+
+        self.count += 1;
+        self.sum += secs;
+    }
+
+    pub const fn count(&self) -> u64 {
+        self.count
+    }
+}
+
+/// Render all metrics using the prometheus text format, it is the response of `GET /metrics` on the HTTP protocol.
+pub fn render(server: &Server, db: &HashMapDb, now: std::time::Instant) -> String {
+    let mut out = String::new();
+    render_commands(&mut out, server);
+    render_limits(&mut out, server);
+
+    for (name, kind, help, value) in scalars(server, db, now) {
+        header(&mut out, name, kind, help);
+        sample(&mut out, name, "", value);
+    }
+
+    out
+}
+
+fn render_commands(out: &mut String, server: &Server) {
+    let stats = &server.stats;
+
+    header(
+        out,
+        "umd_commands_total",
+        "counter",
+        "Total number of commands processed, by command and protocol.",
+    );
+    for ((command, protocol), n) in &stats.requests {
+        sample(
+            out,
+            "umd_commands_total",
+            &format!("command=\"{command}\",protocol=\"{protocol}\""),
+            n,
+        );
+    }
+
+    header(
+        out,
+        "umd_command_duration_seconds",
+        "histogram",
+        "Time spent executing commands, by command.",
+    );
+    for (command, c) in &stats.commands {
+        let h = &c.latency;
+        let mut cumulative = 0;
+        for (le, n) in BUCKETS.iter().zip(h.buckets) {
+            cumulative += n;
+            sample(
+                out,
+                "umd_command_duration_seconds_bucket",
+                &format!("command=\"{command}\",le=\"{le}\""),
+                cumulative,
+            );
+        }
+        sample(
+            out,
+            "umd_command_duration_seconds_bucket",
+            &format!("command=\"{command}\",le=\"+Inf\""),
+            h.count(),
+        );
+        sample(
+            out,
+            "umd_command_duration_seconds_sum",
+            &format!("command=\"{command}\""),
+            h.sum,
+        );
+        sample(
+            out,
+            "umd_command_duration_seconds_count",
+            &format!("command=\"{command}\""),
+            h.count(),
+        );
+    }
+}
+
+fn render_limits(out: &mut String, server: &Server) {
+    header(
+        out,
+        "umd_limits_exceeded_total",
+        "counter",
+        "Total requests rejected because of a limit, by limit.",
+    );
+    for (limit, n) in &server.stats.limits_exceeded {
+        sample(
+            out,
+            "umd_limits_exceeded_total",
+            &format!("limit=\"{limit}\""),
+            n,
+        );
+    }
+
+    header(
+        out,
+        "umd_rejected_connections_total",
+        "counter",
+        "Total connections refused by protected mode.",
+    );
+    sample(
+        out,
+        "umd_rejected_connections_total",
+        "",
+        server.stats.rejected_connections,
+    );
+}
+
+/// Metrics without labels as (name, type, help, value).
+fn scalars(
+    server: &Server,
+    db: &HashMapDb,
+    now: std::time::Instant,
+) -> [(&'static str, &'static str, &'static str, f64); 15] {
+    let stats = &server.stats;
+    let db_stats = db.stats();
+
+    [
+        (
+            "umd_uptime_seconds",
+            "gauge",
+            "Seconds since the server started.",
+            now.saturating_duration_since(server.started_at)
+                .as_secs_f64(),
+        ),
+        (
+            "umd_connected_clients",
+            "gauge",
+            "Number of active connections.",
+            as_f64(stats.connected_clients),
+        ),
+        (
+            "umd_connections_received_total",
+            "counter",
+            "Total number of accepted connections.",
+            as_f64(stats.total_connections_received),
+        ),
+        (
+            "umd_net_input_bytes_total",
+            "counter",
+            "Total bytes read from the network.",
+            as_f64(stats.total_net_input_bytes),
+        ),
+        (
+            "umd_net_output_bytes_total",
+            "counter",
+            "Total bytes written to the network.",
+            as_f64(stats.total_net_output_bytes),
+        ),
+        (
+            "umd_keys",
+            "gauge",
+            "Number of keys stored.",
+            as_f64(db.keys_count() as u64),
+        ),
+        (
+            "umd_keys_with_expiration",
+            "gauge",
+            "Number of keys with a TTL.",
+            as_f64(db.expires_count() as u64),
+        ),
+        (
+            "umd_memory_used_bytes",
+            "gauge",
+            "Estimation of the memory used by the stored data.",
+            as_f64(db.estimated_memory() as u64),
+        ),
+        (
+            "umd_keyspace_hits_total",
+            "counter",
+            "Total lookups of existing keys.",
+            as_f64(db_stats.keyspace_hits),
+        ),
+        (
+            "umd_keyspace_misses_total",
+            "counter",
+            "Total lookups of missing keys.",
+            as_f64(db_stats.keyspace_misses),
+        ),
+        (
+            "umd_evicted_keys_total",
+            "counter",
+            "Total keys evicted because of the max items limit.",
+            as_f64(db_stats.evicted_keys),
+        ),
+        (
+            "umd_expired_keys_total",
+            "counter",
+            "Total keys removed because their TTL expired.",
+            as_f64(db_stats.expired_keys),
+        ),
+        (
+            "umd_persistence_changes_since_last_save",
+            "gauge",
+            "Number of changes not yet persisted to disk.",
+            as_f64(db.changes_since_last_save()),
+        ),
+        (
+            "umd_persistence_last_duration_seconds",
+            "gauge",
+            "Time spent by the last attempt to persist the db.",
+            db_stats.last_save_duration.as_secs_f64(),
+        ),
+        (
+            "umd_persistence_failures_total",
+            "counter",
+            "Total attempts to persist the db that failed.",
+            as_f64(db_stats.save_failures),
+        ),
+    ]
+}
+
+#[allow(clippy::cast_precision_loss)]
+const fn as_f64(n: u64) -> f64 {
+    n as f64
+}
+
+fn header(out: &mut String, name: &str, kind: &str, help: &str) {
+    // NOTE: writing on a String never fails.
+    let _ = write!(out, "# HELP {name} {help}\n# TYPE {name} {kind}\n");
+}
+
+fn sample(out: &mut String, name: &str, labels: &str, value: impl std::fmt::Display) {
+    if labels.is_empty() {
+        let _ = writeln!(out, "{name} {value}");
+    } else {
+        let _ = writeln!(out, "{name}{{{labels}}} {value}");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config;
+
+    #[test]
+    fn histogram() {
+        let mut h = Histogram::default();
+        h.observe(std::time::Duration::from_micros(5));
+        h.observe(std::time::Duration::from_micros(10));
+        h.observe(std::time::Duration::from_millis(3));
+        h.observe(std::time::Duration::from_secs(2));
+
+        assert_eq!(h.count(), 4);
+        assert_eq!(h.buckets[0], 2);
+        assert_eq!(h.buckets[8], 1);
+        assert_eq!(h.buckets.iter().sum::<u64>(), 3);
+    }
+
+    #[test]
+    fn render_metrics() {
+        let mut server = Server::new(6379, &config::Config::default());
+        server.stats.connection_opened();
+        server
+            .stats
+            .record_command("get", "http", std::time::Duration::from_micros(30));
+        server.stats.limit_exceeded("max_commands_per_second");
+        let mut db = HashMapDb::new(config::Engine::default());
+        db.set("foo", "bar".to_string(), None);
+
+        let out = render(&server, &db, std::time::Instant::now());
+        assert!(out.contains("# TYPE umd_commands_total counter\n"));
+        assert!(out.contains("umd_commands_total{command=\"get\",protocol=\"http\"} 1\n"));
+        assert!(out
+            .contains("umd_command_duration_seconds_bucket{command=\"get\",le=\"0.000025\"} 0\n"));
+        assert!(
+            out.contains("umd_command_duration_seconds_bucket{command=\"get\",le=\"0.00005\"} 1\n")
+        );
+        assert!(
+            out.contains("umd_command_duration_seconds_bucket{command=\"get\",le=\"+Inf\"} 1\n")
+        );
+        assert!(out.contains("umd_command_duration_seconds_count{command=\"get\"} 1\n"));
+        assert!(out.contains("umd_limits_exceeded_total{limit=\"max_commands_per_second\"} 1\n"));
+        assert!(out.contains("umd_connected_clients 1\n"));
+        assert!(out.contains("umd_keys 1\n"));
+        assert!(out.contains("umd_persistence_failures_total 0\n"));
+    }
+}
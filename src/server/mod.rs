@@ -0,0 +1,90 @@
+pub mod acl;
+pub mod blocking;
+pub mod channel;
+pub mod clients;
+pub mod info;
+pub mod latency;
+pub mod metrics;
+pub mod monitor;
+pub mod ratelimit;
+pub mod sampler;
+pub mod slowlog;
+pub mod stats;
+pub mod tls;
+
+use crate::config;
+
+/// Server keeps the state shared by all the connections which is not part of the data itself, like the start time
+/// and the counters exposed by the [`Info`](crate::protocol::commands::Command::Info) command.
+pub struct Server {
+    pub started_at: std::time::Instant,
+    pub port: u16,
+    pub network: config::Network,
+    pub protected_mode: bool,
+    pub limits: config::Limits,
+    pub acl: acl::Acl,
+    pub stats: stats::Stats,
+    pub clients: clients::Clients,
+    pub slowlog: slowlog::SlowLog,
+    pub latency: latency::Latency,
+    pub monitors: monitor::Monitors,
+    pub blocking: blocking::Blocking,
+    pub rate_limiter: ratelimit::RateLimiter,
+
+    /// Picks the commands whose debug logs and spans are emitted.
+    pub log_sampler: sampler::Sampler,
+}
+
+impl Server {
+    pub fn new(port: u16, c: &config::Config) -> Self {
+        Self {
+            started_at: std::time::Instant::now(),
+            port,
+            network: c.network.clone(),
+            protected_mode: c.security.protected_mode,
+            limits: c.limits.clone(),
+            acl: acl::Acl::new(c.security.requirepass.as_deref()),
+            stats: stats::Stats::default(),
+            clients: clients::Clients::default(),
+            slowlog: slowlog::SlowLog::new(c.slowlog.clone()),
+            latency: latency::Latency::new(c.latency.clone()),
+            monitors: monitor::Monitors::default(),
+            blocking: blocking::Blocking::default(),
+            rate_limiter: ratelimit::RateLimiter::new(&c.limits),
+            log_sampler: sampler::Sampler::new(c.logger.sample_rate),
+        }
+    }
+
+    /// Returns if the client must be refused because of protected mode: the default user does not need a password
+    /// and the client is not on the loopback interface. Clients of the unix socket are always local.
+    pub fn is_protected_from(&self, addr: &str) -> bool {
+        self.protected_mode
+            && self.acl.is_open()
+            && addr
+                .parse::<std::net::SocketAddr>()
+                .is_ok_and(|a| !a.ip().is_loopback())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn protected_mode() {
+        let server = Server::new(6379, &config::Config::default());
+        assert!(server.is_protected_from("10.0.0.1:5000"));
+        assert!(server.is_protected_from("[2001:db8::1]:5000"));
+        assert!(!server.is_protected_from("127.0.0.1:5000"));
+        assert!(!server.is_protected_from("[::1]:5000"));
+        assert!(!server.is_protected_from("/tmp/umd.sock:0"));
+
+        let mut c = config::Config::default();
+        c.security.requirepass = Some("secret".to_string());
+        assert!(!Server::new(6379, &c).is_protected_from("10.0.0.1:5000"));
+
+        c.security.requirepass = None;
+        c.security.protected_mode = false;
+        assert!(!Server::new(6379, &c).is_protected_from("10.0.0.1:5000"));
+    }
+}
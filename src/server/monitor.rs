@@ -0,0 +1,114 @@
+use super::channel;
+use std::fmt::Write;
+
+/// Lines buffered for each monitor, clients which do not keep up with the traffic are disconnected.
+const QUEUE_CAPACITY: usize = 16 * 1024;
+
+/// Connections in MONITOR mode, every processed command is pushed to them using the redis format.
+#[derive(Default)]
+pub struct Monitors {
+    subscribers: Vec<channel::Sender<Vec<u8>>>,
+}
+
+impl Monitors {
+    pub fn subscribe(&mut self) -> channel::Receiver<Vec<u8>> {
+        let (tx, rx) = channel::channel(QUEUE_CAPACITY);
+        self.subscribers.push(tx);
+        rx
+    }
+
+    /// Push the command to every monitor, the ones gone or too slow are removed.
+    pub fn publish(&mut self, args: &[String], client_addr: &str) {
+        self.subscribers.retain(|s| !s.is_closed());
+        if self.subscribers.is_empty() {
+            return;
+        }
+
+        let line = format_line(std::time::SystemTime::now(), args, client_addr);
+        self.subscribers.retain(|s| match s.send(line.clone()) {
+            Ok(()) => true,
+            Err(e) => {
+                tracing::warn!(error = ?e, "monitor dropped");
+                false
+            }
+        });
+    }
+}
+
+/// Line in the same format used by redis: `+1339518083.107412 [0 127.0.0.1:60866] "keys" "*"`.
+fn format_line(at: std::time::SystemTime, args: &[String], client_addr: &str) -> Vec<u8> {
+    let at = at.duration_since(std::time::UNIX_EPOCH).unwrap_or_default();
+
+    let mut out = format!(
+        "+{}.{:06} [0 {client_addr}]",
+        at.as_secs(),
+        at.subsec_micros()
+    );
+    for a in args {
+        out.push(' ');
+        quote(&mut out, a);
+    }
+    out.push_str("\r\n");
+
+    out.into_bytes()
+}
+
+/// Quote the argument escaping the characters which are not printable, like `sdscatrepr` in redis.
+fn quote(out: &mut String, s: &str) {
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '\\' => out.push_str("\\\\"),
+            '"' => out.push_str("\\\""),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            '\x07' => out.push_str("\\a"),
+            '\x08' => out.push_str("\\b"),
+            c if c.is_control() => {
+                let mut buf = [0; 4];
+                for b in c.encode_utf8(&mut buf).bytes() {
+                    // NOTE: writing on a String never fails.
+                    let _ = write!(out, "\\x{b:02x}");
+                }
+            }
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn line() {
+        let at = std::time::UNIX_EPOCH + std::time::Duration::from_micros(1_339_518_083_107_412);
+        let args = vec![
+            "set".to_string(),
+            "k\"1".to_string(),
+            "a\r\n\x01".to_string(),
+        ];
+
+        assert_eq!(
+            String::from_utf8(format_line(at, &args, "127.0.0.1:60866")).unwrap(),
+            "+1339518083.107412 [0 127.0.0.1:60866] \"set\" \"k\\\"1\" \"a\\r\\n\\x01\"\r\n"
+        );
+    }
+
+    #[monoio::test]
+    async fn publish() {
+        let mut m = Monitors::default();
+        m.publish(&["ping".to_string()], "127.0.0.1:1");
+
+        let rx = m.subscribe();
+        m.publish(&["get".to_string(), "foo".to_string()], "127.0.0.1:1");
+        let line = String::from_utf8(rx.recv().await.unwrap()).unwrap();
+        assert!(line.ends_with(" [0 127.0.0.1:1] \"get\" \"foo\"\r\n"));
+
+        drop(rx);
+        m.publish(&["ping".to_string()], "127.0.0.1:1");
+        assert!(m.subscribers.is_empty());
+    }
+}
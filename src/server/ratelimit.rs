@@ -0,0 +1,135 @@
+use crate::config;
+use std::collections::HashMap;
+
+/// Limit which rejected a command.
+#[derive(Debug, PartialEq, Eq)]
+pub enum Exceeded {
+    Client(u64),
+    Ip(u64),
+}
+
+impl Exceeded {
+    /// Name of the limit in the config, it is used as label for the metrics.
+    pub const fn name(&self) -> &'static str {
+        match self {
+            Self::Client(_) => "max_commands_per_second",
+            Self::Ip(_) => "max_commands_per_second_per_ip",
+        }
+    }
+}
+
+impl std::fmt::Display for Exceeded {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Client(max) => write!(f, "max {max} commands per second per client"),
+            Self::Ip(max) => write!(f, "max {max} commands per second per address"),
+        }
+    }
+}
+
+/// Counts the commands of each client and address in windows of one second, the rejected ones are not counted.
+pub struct RateLimiter {
+    per_client: u64,
+    per_ip: u64,
+    window_start: Option<std::time::Instant>,
+    clients: HashMap<u64, u64>,
+    ips: HashMap<String, u64>,
+}
+
+impl RateLimiter {
+    pub fn new(c: &config::Limits) -> Self {
+        Self {
+            per_client: c.max_commands_per_second,
+            per_ip: c.max_commands_per_second_per_ip,
+            window_start: None,
+            clients: HashMap::new(),
+            ips: HashMap::new(),
+        }
+    }
+
+    /// Count the command of the client, it fails if the client or its address already reached the limit in the
+    /// current second.
+    pub fn check(
+        &mut self,
+        client_id: u64,
+        addr: &str,
+        now: std::time::Instant,
+    ) -> Result<(), Exceeded> {
+        if self.per_client == 0 && self.per_ip == 0 {
+            return Ok(());
+        }
+
+        // NOTE: the counters are dropped every second, so gone clients don't pile up.
+        if self
+            .window_start
+            .map_or(true, |s| now.saturating_duration_since(s).as_secs() >= 1)
+        {
+            self.window_start = Some(now);
+            self.clients.clear();
+            self.ips.clear();
+        }
+
+        let client = self.clients.get(&client_id).copied().unwrap_or_default();
+        if self.per_client > 0 && client >= self.per_client {
+            return Err(Exceeded::Client(self.per_client));
+        }
+
+        // NOTE: the port is not part of the address of the client, unix sockets use the path.
+        let ip = addr.rsplit_once(':').map_or(addr, |(ip, _)| ip);
+        let from_ip = self.ips.get(ip).copied().unwrap_or_default();
+        if self.per_ip > 0 && from_ip >= self.per_ip {
+            return Err(Exceeded::Ip(self.per_ip));
+        }
+
+        self.clients.insert(client_id, client + 1);
+        self.ips.insert(ip.to_string(), from_ip + 1);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn per_client() {
+        let now = std::time::Instant::now();
+        let mut r = RateLimiter::new(&config::Limits {
+            max_commands_per_second: 2,
+            ..Default::default()
+        });
+
+        assert_eq!(r.check(1, "127.0.0.1:1", now), Ok(()));
+        assert_eq!(r.check(1, "127.0.0.1:1", now), Ok(()));
+        assert_eq!(r.check(1, "127.0.0.1:1", now), Err(Exceeded::Client(2)));
+        assert_eq!(r.check(2, "127.0.0.1:2", now), Ok(()));
+
+        let later = now + std::time::Duration::from_secs(1);
+        assert_eq!(r.check(1, "127.0.0.1:1", later), Ok(()));
+    }
+
+    #[test]
+    fn per_ip() {
+        let now = std::time::Instant::now();
+        let mut r = RateLimiter::new(&config::Limits {
+            max_commands_per_second_per_ip: 2,
+            ..Default::default()
+        });
+
+        assert_eq!(r.check(1, "10.0.0.1:1", now), Ok(()));
+        assert_eq!(r.check(2, "10.0.0.1:2", now), Ok(()));
+        let err = r.check(3, "10.0.0.1:3", now).unwrap_err();
+        assert_eq!(err.name(), "max_commands_per_second_per_ip");
+        assert_eq!(r.check(4, "10.0.0.2:1", now), Ok(()));
+    }
+
+    #[test]
+    fn disabled() {
+        let now = std::time::Instant::now();
+        let mut r = RateLimiter::new(&config::Limits::default());
+        for _ in 0..1000 {
+            assert_eq!(r.check(1, "127.0.0.1:1", now), Ok(()));
+        }
+        assert!(r.clients.is_empty());
+    }
+}
@@ -0,0 +1,42 @@
+/// Sampler picks evenly spaced items given the rate, e.g. with 0.25 one every four items is picked. It is
+/// deterministic to avoid depending on a random generator in the hot path.
+pub struct Sampler {
+    rate: f64,
+    seen: u64,
+}
+
+impl Sampler {
+    pub fn new(rate: f64) -> Self {
+        Self {
+            rate: rate.clamp(0.0, 1.0),
+            seen: 0,
+        }
+    }
+
+    #[allow(clippy::cast_precision_loss, clippy::float_cmp)]
+    pub fn sample(&mut self) -> bool {
+        self.seen = self.seen.wrapping_add(1);
+
+        let now = (self.seen as f64 * self.rate).floor();
+        let before = ((self.seen - 1) as f64 * self.rate).floor();
+        now != before
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sample() {
+        let mut s = Sampler::new(1.0);
+        assert!((0..10).all(|_| s.sample()));
+
+        let mut s = Sampler::new(0.0);
+        assert!((0..10).all(|_| !s.sample()));
+
+        let mut s = Sampler::new(0.25);
+        let picked = (0..100).filter(|_| s.sample()).count();
+        assert_eq!(picked, 25);
+    }
+}
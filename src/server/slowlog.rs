@@ -0,0 +1,187 @@
+use crate::config;
+use std::collections::VecDeque;
+
+/// Maximum number of arguments kept for each entry, like redis the last one reports how many were dropped.
+const MAX_ARGS: usize = 32;
+
+/// Maximum length of each argument kept for each entry.
+const MAX_ARG_LEN: usize = 128;
+
+/// Entry of the slowlog, it describes a command which took more than the configured threshold.
+#[derive(Debug, PartialEq, Eq)]
+pub struct Entry {
+    pub id: u64,
+
+    /// Seconds since unix epoch when the command has been executed.
+    pub timestamp: u64,
+    pub duration: std::time::Duration,
+    pub args: Vec<String>,
+    pub client_addr: String,
+    pub client_name: String,
+}
+
+/// Bounded log of the slowest commands, the newest entries are at the front.
+pub struct SlowLog {
+    entries: VecDeque<Entry>,
+    next_id: u64,
+    config: config::Slowlog,
+}
+
+impl SlowLog {
+    pub fn new(config: config::Slowlog) -> Self {
+        Self {
+            entries: VecDeque::with_capacity(config.max_len),
+            next_id: 0,
+            config,
+        }
+    }
+
+    /// Record the command if it took more than the configured threshold.
+    pub fn record(
+        &mut self,
+        args: &[String],
+        duration: std::time::Duration,
+        client_addr: &str,
+        client_name: &str,
+    ) {
+        if !self.config.enabled || duration < self.config.log_slower_than {
+            return;
+        }
+
+        let timestamp = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map_or(0, |d| d.as_secs());
+
+        self.entries.push_front(Entry {
+            id: self.next_id,
+            timestamp,
+            duration,
+            args: truncate_args(args),
+            client_addr: client_addr.to_string(),
+            client_name: client_name.to_string(),
+        });
+        self.entries.truncate(self.config.max_len);
+        self.next_id += 1;
+    }
+
+    /// Newest `count` entries, all of them if count is None.
+    pub fn get(&self, count: Option<usize>) -> impl Iterator<Item = &Entry> {
+        self.entries.iter().take(count.unwrap_or(usize::MAX))
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn reset(&mut self) {
+        self.entries.clear();
+    }
+}
+
+fn truncate_args(args: &[String]) -> Vec<String> {
+    let mut out = args
+        .iter()
+        .take(if args.len() > MAX_ARGS {
+            MAX_ARGS - 1
+        } else {
+            MAX_ARGS
+        })
+        .map(|a| {
+            if a.len() > MAX_ARG_LEN {
+                let mut end = MAX_ARG_LEN;
+                while !a.is_char_boundary(end) {
+                    end -= 1;
+                }
+                format!("{}... ({} more bytes)", &a[..end], a.len() - end)
+            } else {
+                a.clone()
+            }
+        })
+        .collect::<Vec<String>>();
+
+    if args.len() > MAX_ARGS {
+        out.push(format!("... ({} more arguments)", args.len() - out.len()));
+    }
+
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn slowlog(max_len: usize) -> SlowLog {
+        SlowLog::new(config::Slowlog {
+            enabled: true,
+            log_slower_than: std::time::Duration::from_millis(1),
+            max_len,
+        })
+    }
+
+    #[test]
+    fn record() {
+        let mut s = slowlog(2);
+        let args = vec!["get".to_string(), "key".to_string()];
+
+        s.record(
+            &args,
+            std::time::Duration::from_micros(10),
+            "127.0.0.1:1",
+            "",
+        );
+        assert_eq!(s.len(), 0);
+
+        s.record(
+            &args,
+            std::time::Duration::from_millis(1),
+            "127.0.0.1:1",
+            "a",
+        );
+        s.record(
+            &args,
+            std::time::Duration::from_millis(2),
+            "127.0.0.1:2",
+            "b",
+        );
+        s.record(
+            &args,
+            std::time::Duration::from_millis(3),
+            "127.0.0.1:3",
+            "c",
+        );
+        assert_eq!(s.len(), 2);
+
+        let entries = s.get(None).collect::<Vec<&Entry>>();
+        assert_eq!(entries[0].id, 2);
+        assert_eq!(entries[0].client_name, "c");
+        assert_eq!(entries[0].args, args);
+        assert_eq!(entries[1].id, 1);
+        assert_eq!(s.get(Some(1)).count(), 1);
+
+        s.reset();
+        assert_eq!(s.len(), 0);
+    }
+
+    #[test]
+    fn disabled() {
+        let mut s = SlowLog::new(config::Slowlog {
+            enabled: false,
+            ..Default::default()
+        });
+
+        s.record(&[], std::time::Duration::from_secs(1), "127.0.0.1:1", "");
+        assert_eq!(s.len(), 0);
+    }
+
+    #[test]
+    fn truncate() {
+        let args = (0..40).map(|i| i.to_string()).collect::<Vec<String>>();
+        let out = truncate_args(&args);
+        assert_eq!(out.len(), MAX_ARGS);
+        assert_eq!(out[MAX_ARGS - 2], "30");
+        assert_eq!(out[MAX_ARGS - 1], "... (9 more arguments)");
+
+        let out = truncate_args(&["x".repeat(130)]);
+        assert_eq!(out[0], format!("{}... (2 more bytes)", "x".repeat(128)));
+    }
+}
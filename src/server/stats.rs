@@ -0,0 +1,96 @@
+use std::collections::BTreeMap;
+
+/// Counters about connections and processed commands, the engine keeps its own counters about keys.
+#[derive(Default)]
+pub struct Stats {
+    pub connected_clients: u64,
+    pub total_connections_received: u64,
+    pub total_commands_processed: u64,
+    pub total_net_input_bytes: u64,
+    pub total_net_output_bytes: u64,
+
+    /// Connections refused by protected mode.
+    pub rejected_connections: u64,
+
+    /// Calls and time spent per command, ordered by name to have a stable output.
+    pub commands: BTreeMap<&'static str, CommandStats>,
+
+    /// Calls per command and protocol used by the client, as (command, protocol).
+    pub requests: BTreeMap<(&'static str, &'static str), u64>,
+
+    /// Requests rejected per limit of the config, like `max_commands_per_second`.
+    pub limits_exceeded: BTreeMap<&'static str, u64>,
+}
+
+#[allow(clippy::module_name_repetitions)]
+#[derive(Default, Debug)]
+pub struct CommandStats {
+    pub calls: u64,
+    pub usec: u64,
+    pub latency: super::metrics::Histogram,
+}
+
+impl Stats {
+    /// Returns the id of the new connection, it is unique for the lifetime of the server.
+    pub fn connection_opened(&mut self) -> u64 {
+        self.connected_clients += 1;
+        self.total_connections_received += 1;
+        self.total_connections_received
+    }
+
+    pub fn connection_closed(&mut self) {
+        self.connected_clients = self.connected_clients.saturating_sub(1);
+    }
+
+    pub fn limit_exceeded(&mut self, limit: &'static str) {
+        *self.limits_exceeded.entry(limit).or_default() += 1;
+    }
+
+    pub fn record_command(
+        &mut self,
+        name: &'static str,
+        protocol: &'static str,
+        elapsed: std::time::Duration,
+    ) {
+        self.total_commands_processed += 1;
+
+        let c = self.commands.entry(name).or_default();
+        c.calls += 1;
+        c.usec += u64::try_from(elapsed.as_micros()).unwrap_or(u64::MAX);
+        c.latency.observe(elapsed);
+
+        *self.requests.entry((name, protocol)).or_default() += 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn record_command() {
+        let mut s = Stats::default();
+        s.record_command("get", "resp", std::time::Duration::from_micros(10));
+        s.record_command("get", "http", std::time::Duration::from_micros(5));
+        s.record_command("set", "resp", std::time::Duration::from_micros(1));
+
+        assert_eq!(s.total_commands_processed, 3);
+        let get = s.commands.get("get").unwrap();
+        assert_eq!((get.calls, get.usec, get.latency.count()), (2, 15, 2));
+        let set = s.commands.get("set").unwrap();
+        assert_eq!((set.calls, set.usec, set.latency.count()), (1, 1, 1));
+        assert_eq!(s.requests.get(&("get", "resp")), Some(&1));
+        assert_eq!(s.requests.get(&("get", "http")), Some(&1));
+    }
+
+    #[test]
+    fn connections() {
+        let mut s = Stats::default();
+        assert_eq!(s.connection_opened(), 1);
+        assert_eq!(s.connection_opened(), 2);
+        s.connection_closed();
+
+        assert_eq!(s.connected_clients, 1);
+        assert_eq!(s.total_connections_received, 2);
+    }
+}
@@ -0,0 +1,232 @@
+use crate::config;
+use rustls::pki_types::pem::PemObject;
+use rustls::pki_types::{CertificateDer, PrivateKeyDer};
+use std::sync::Arc;
+
+#[allow(clippy::module_name_repetitions)]
+#[derive(thiserror::Error, Debug)]
+pub enum TlsError {
+    /// The file can't be read or it does not contain valid PEM items.
+    #[error("invalid pem file {0}: {1}")]
+    Pem(String, rustls::pki_types::pem::Error),
+
+    /// The file does not contain any certificate.
+    #[error("no certificate found in {0}")]
+    NoCertificate(String),
+
+    #[error("invalid client verifier: {0}")]
+    Verifier(#[from] rustls::server::VerifierBuilderError),
+
+    #[error("invalid tls config: {0}")]
+    Rustls(#[from] rustls::Error),
+}
+
+/// Build the acceptor of the TLS listener from the config, client certificates are verified against the CA when
+/// one is given.
+pub fn acceptor(c: &config::Tls) -> Result<monoio_rustls::TlsAcceptor, TlsError> {
+    let provider = Arc::new(rustls::crypto::ring::default_provider());
+    let builder = rustls::ServerConfig::builder_with_provider(Arc::clone(&provider))
+        .with_safe_default_protocol_versions()?;
+
+    let builder = match &c.ca_file {
+        Some(ca_file) => {
+            let mut roots = rustls::RootCertStore::empty();
+            for cert in load_certs(ca_file)? {
+                roots.add(cert)?;
+            }
+
+            let verifier = rustls::server::WebPkiClientVerifier::builder_with_provider(
+                Arc::new(roots),
+                provider,
+            );
+            let verifier = if c.auth_clients {
+                verifier.build()?
+            } else {
+                verifier.allow_unauthenticated().build()?
+            };
+            builder.with_client_cert_verifier(verifier)
+        }
+        None => builder.with_no_client_auth(),
+    };
+
+    let certs = load_certs(&c.cert_file)?;
+    let key = PrivateKeyDer::from_pem_file(&c.key_file)
+        .map_err(|e| TlsError::Pem(c.key_file.clone(), e))?;
+    let config = builder.with_single_cert(certs, key)?;
+
+    Ok(monoio_rustls::TlsAcceptor::from(config))
+}
+
+fn load_certs(file: &str) -> Result<Vec<CertificateDer<'static>>, TlsError> {
+    let certs = CertificateDer::pem_file_iter(file)
+        .and_then(Iterator::collect::<Result<Vec<_>, _>>)
+        .map_err(|e| TlsError::Pem(file.to_string(), e))?;
+
+    if certs.is_empty() {
+        return Err(TlsError::NoCertificate(file.to_string()));
+    }
+
+    Ok(certs)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use monoio::io::{AsyncReadRent, AsyncWriteRentExt};
+
+    struct Pki {
+        dir: tempfile::TempDir,
+        ca: rcgen::Certificate,
+        ca_key: rcgen::KeyPair,
+    }
+
+    impl Pki {
+        fn new() -> Self {
+            let mut params = rcgen::CertificateParams::new(vec![]).unwrap();
+            params.is_ca = rcgen::IsCa::Ca(rcgen::BasicConstraints::Unconstrained);
+            params
+                .distinguished_name
+                .push(rcgen::DnType::CommonName, "umd test ca");
+            let ca_key = rcgen::KeyPair::generate().unwrap();
+            let ca = params.self_signed(&ca_key).unwrap();
+
+            let pki = Self {
+                dir: tempfile::tempdir().unwrap(),
+                ca,
+                ca_key,
+            };
+            std::fs::write(pki.path("ca.pem"), pki.ca.pem()).unwrap();
+            pki
+        }
+
+        fn path(&self, name: &str) -> String {
+            self.dir.path().join(name).to_string_lossy().to_string()
+        }
+
+        /// Certificate signed by the CA, written to `name.pem` and `name.key`.
+        fn issue(
+            &self,
+            name: &str,
+            usage: rcgen::ExtendedKeyUsagePurpose,
+        ) -> (Vec<CertificateDer<'static>>, PrivateKeyDer<'static>) {
+            let mut params = rcgen::CertificateParams::new(vec!["localhost".to_string()]).unwrap();
+            params.extended_key_usages = vec![usage];
+            let key = rcgen::KeyPair::generate().unwrap();
+            let cert = params.signed_by(&key, &self.ca, &self.ca_key).unwrap();
+
+            std::fs::write(self.path(&format!("{name}.pem")), cert.pem()).unwrap();
+            std::fs::write(self.path(&format!("{name}.key")), key.serialize_pem()).unwrap();
+            (
+                vec![cert.der().clone()],
+                PrivateKeyDer::try_from(key.serialize_der()).unwrap(),
+            )
+        }
+
+        fn config(&self, ca_file: bool) -> config::Tls {
+            config::Tls {
+                enabled: true,
+                addr: "127.0.0.1:0".to_string(),
+                cert_file: self.path("server.pem"),
+                key_file: self.path("server.key"),
+                ca_file: ca_file.then(|| self.path("ca.pem")),
+                auth_clients: true,
+            }
+        }
+
+        fn connector(
+            &self,
+            client_cert: Option<(Vec<CertificateDer<'static>>, PrivateKeyDer<'static>)>,
+        ) -> monoio_rustls::TlsConnector {
+            let mut roots = rustls::RootCertStore::empty();
+            roots.add(self.ca.der().clone()).unwrap();
+
+            let builder = rustls::ClientConfig::builder_with_provider(Arc::new(
+                rustls::crypto::ring::default_provider(),
+            ))
+            .with_safe_default_protocol_versions()
+            .unwrap()
+            .with_root_certificates(roots);
+            let config = match client_cert {
+                Some((certs, key)) => builder.with_client_auth_cert(certs, key).unwrap(),
+                None => builder.with_no_client_auth(),
+            };
+
+            monoio_rustls::TlsConnector::from(config)
+        }
+    }
+
+    /// Send PING through a TLS connection, returns the result of the handshake on the server side and the answer.
+    // NOTE: the test runtime is single threaded like the server one.
+    #[allow(clippy::future_not_send)]
+    async fn ping(
+        acceptor: monoio_rustls::TlsAcceptor,
+        connector: monoio_rustls::TlsConnector,
+    ) -> (bool, Vec<u8>) {
+        let listener = monoio::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+
+        let server = monoio::spawn(async move {
+            let (stream, _) = listener.accept().await.unwrap();
+            let Ok(mut stream) = acceptor.accept(stream).await else {
+                return false;
+            };
+            let (res, _) = stream.read(vec![0; 64]).await;
+            res.unwrap();
+            let (res, _) = stream.write_all(b"+PONG\r\n").await;
+            res.unwrap();
+            true
+        });
+
+        let stream = monoio::net::TcpStream::connect(addr).await.unwrap();
+        let domain = rustls::pki_types::ServerName::try_from("localhost").unwrap();
+        let mut answer = vec![];
+        if let Ok(mut stream) = connector.connect(domain, stream).await {
+            let _ = stream.write_all(b"*1\r\n$4\r\nPING\r\n").await;
+            let (res, buf) = stream.read(vec![0; 64]).await;
+            if res.is_ok() {
+                answer = buf;
+            }
+        }
+
+        (server.await, answer)
+    }
+
+    #[monoio::test]
+    async fn handshake() {
+        let pki = Pki::new();
+        pki.issue("server", rcgen::ExtendedKeyUsagePurpose::ServerAuth);
+
+        let acceptor = acceptor(&pki.config(false)).unwrap();
+        let (accepted, answer) = ping(acceptor, pki.connector(None)).await;
+        assert!(accepted);
+        assert_eq!(answer, b"+PONG\r\n");
+    }
+
+    #[monoio::test]
+    async fn client_certificate() {
+        let pki = Pki::new();
+        pki.issue("server", rcgen::ExtendedKeyUsagePurpose::ServerAuth);
+        let client = pki.issue("client", rcgen::ExtendedKeyUsagePurpose::ClientAuth);
+
+        let acceptor = acceptor(&pki.config(true)).unwrap();
+        let (accepted, answer) = ping(acceptor.clone(), pki.connector(Some(client))).await;
+        assert!(accepted);
+        assert_eq!(answer, b"+PONG\r\n");
+
+        let (accepted, answer) = ping(acceptor, pki.connector(None)).await;
+        assert!(!accepted);
+        assert!(answer.is_empty());
+    }
+
+    #[test]
+    fn invalid_files() {
+        let pki = Pki::new();
+        let err = acceptor(&pki.config(false)).err().unwrap();
+        assert!(matches!(err, TlsError::Pem(..)), "{err:?}");
+
+        std::fs::write(pki.path("server.pem"), "").unwrap();
+        std::fs::write(pki.path("server.key"), "").unwrap();
+        let err = acceptor(&pki.config(false)).err().unwrap();
+        assert!(matches!(err, TlsError::NoCertificate(..)));
+    }
+}